@@ -0,0 +1,740 @@
+//! Auto-vacuum: pointer-map maintenance and page relocation (§11.6).
+//!
+//! An auto-vacuum database stores a non-zero "largest root page" at header
+//! offset 52 and reserves every `usable_size / 5 + 1`-th page (starting at
+//! page 2) as a pointer-map page. Each 5-byte entry records what kind of page
+//! its target is and which page points at it, which is what lets a page be
+//! moved without scanning the whole file:
+//!
+//! ```text
+//! page 1 │ page 2 (ptrmap) │ page 3 .. page 2+E │ page 3+E (ptrmap) │ ...
+//!          entries for ───────────┘
+//! ```
+//!
+//! Entries are derived from the dirty page images at commit rather than being
+//! threaded through every balance, overflow and freelist call site: any page
+//! whose parent changed is reachable from a dirty parent, so walking the
+//! dirty set from its roots visits every entry that can have changed.
+//!
+//! In `FULL` mode [`prepare_commit`] then moves the pages at the end of the
+//! file into free slots and asks the pager to truncate, exactly like SQLite's
+//! `autoVacuumCommit`. In `INCREMENTAL` mode the same relocation runs only
+//! from [`incremental_vacuum`].
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_pager::TransactionHandle;
+use fsqlite_types::cx::Cx;
+use fsqlite_types::{DATABASE_HEADER_MAGIC, PageNumber};
+use tracing::debug;
+
+use crate::cell::{
+    BtreePageHeader, BtreePageType, CellRef, header_offset_for_page, read_cell_pointers,
+};
+use crate::freelist::{
+    PENDING_BYTE_OFFSET, PTRMAP_ENTRY_SIZE_BYTES, PtrMapEntry, PtrMapType, compute_ptrmap_page,
+    is_ptrmap_page, ptrmap_entry_offset, ptrmap_page_for,
+};
+
+/// Header offset of the "largest root page" field (non-zero = auto-vacuum).
+const LARGEST_ROOT_PAGE_OFFSET: usize = 52;
+/// Header offset of the incremental-vacuum flag.
+const INCREMENTAL_VACUUM_OFFSET: usize = 64;
+/// Header offset of the reserved-bytes-per-page field.
+const RESERVED_SPACE_OFFSET: usize = 20;
+
+/// `PRAGMA auto_vacuum` setting as stored in the database header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoVacuumMode {
+    /// No pointer map; freed pages stay on the freelist.
+    None,
+    /// Relocate and truncate on every commit.
+    Full,
+    /// Keep the pointer map but only shrink on `PRAGMA incremental_vacuum`.
+    Incremental,
+}
+
+impl AutoVacuumMode {
+    /// Mode for a `PRAGMA auto_vacuum` integer (0, 1 or 2).
+    #[must_use]
+    pub const fn from_pragma(value: i64) -> Option<Self> {
+        match value {
+            0 => Some(Self::None),
+            1 => Some(Self::Full),
+            2 => Some(Self::Incremental),
+            _ => None,
+        }
+    }
+
+    /// Integer reported by `PRAGMA auto_vacuum`.
+    #[must_use]
+    pub const fn as_pragma(self) -> i64 {
+        match self {
+            Self::None => 0,
+            Self::Full => 1,
+            Self::Incremental => 2,
+        }
+    }
+
+    /// Mode encoded by the header's largest-root-page and incremental fields.
+    #[must_use]
+    pub const fn from_header(largest_root_page: u32, incremental: u32) -> Self {
+        if largest_root_page == 0 {
+            Self::None
+        } else if incremental == 0 {
+            Self::Full
+        } else {
+            Self::Incremental
+        }
+    }
+}
+
+/// Auto-vacuum parameters read from the pending page 1.
+#[derive(Debug, Clone, Copy)]
+struct VacuumHeader {
+    mode: AutoVacuumMode,
+    largest_root_page: u32,
+    page_size: u32,
+    usable_size: u32,
+}
+
+fn read_header_u32(page1: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        page1[offset],
+        page1[offset + 1],
+        page1[offset + 2],
+        page1[offset + 3],
+    ])
+}
+
+/// Parse the auto-vacuum fields of page 1, or `None` for a database that is
+/// not yet initialized or has auto-vacuum off.
+#[allow(clippy::cast_possible_truncation)]
+fn read_vacuum_header<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &T,
+) -> Result<Option<VacuumHeader>> {
+    let page1 = txn.get_page(cx, PageNumber::ONE)?;
+    let page1 = page1.as_bytes();
+    if page1.len() < 100 || !page1.starts_with(DATABASE_HEADER_MAGIC) {
+        return Ok(None);
+    }
+    let largest_root_page = read_header_u32(page1, LARGEST_ROOT_PAGE_OFFSET);
+    if largest_root_page == 0 {
+        return Ok(None);
+    }
+    let incremental = read_header_u32(page1, INCREMENTAL_VACUUM_OFFSET);
+    let page_size = page1.len() as u32;
+    Ok(Some(VacuumHeader {
+        mode: AutoVacuumMode::from_header(largest_root_page, incremental),
+        largest_root_page,
+        page_size,
+        usable_size: page_size - u32::from(page1[RESERVED_SPACE_OFFSET]),
+    }))
+}
+
+/// Write-back cache of pointer-map pages touched during one commit.
+struct PtrMapPages {
+    page_size: u32,
+    usable_size: u32,
+    pages: HashMap<PageNumber, (Vec<u8>, bool)>,
+}
+
+impl PtrMapPages {
+    fn new(header: &VacuumHeader) -> Self {
+        Self {
+            page_size: header.page_size,
+            usable_size: header.usable_size,
+            pages: HashMap::new(),
+        }
+    }
+
+    fn locate(&self, pgno: PageNumber) -> Option<(PageNumber, usize)> {
+        let ptrmap = ptrmap_page_for(pgno, self.usable_size, self.page_size)?;
+        let offset = ptrmap_entry_offset(pgno, self.usable_size, self.page_size)?;
+        Some((ptrmap, offset as usize))
+    }
+
+    fn load<T: TransactionHandle + ?Sized>(
+        &mut self,
+        cx: &Cx,
+        txn: &T,
+        ptrmap: PageNumber,
+    ) -> Result<&mut (Vec<u8>, bool)> {
+        match self.pages.entry(ptrmap) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let bytes = txn.get_page(cx, ptrmap)?.as_bytes().to_vec();
+                Ok(entry.insert((bytes, false)))
+            }
+        }
+    }
+
+    /// Entry for `pgno`, or `None` when the slot is empty or unreadable.
+    fn get<T: TransactionHandle + ?Sized>(
+        &mut self,
+        cx: &Cx,
+        txn: &T,
+        pgno: PageNumber,
+    ) -> Result<Option<PtrMapEntry>> {
+        let Some((ptrmap, offset)) = self.locate(pgno) else {
+            return Ok(None);
+        };
+        let (bytes, _) = self.load(cx, txn, ptrmap)?;
+        let end = offset + PTRMAP_ENTRY_SIZE_BYTES as usize;
+        if end > bytes.len() || bytes[offset] == 0 {
+            return Ok(None);
+        }
+        Ok(PtrMapEntry::decode(&bytes[offset..end]).ok())
+    }
+
+    fn set<T: TransactionHandle + ?Sized>(
+        &mut self,
+        cx: &Cx,
+        txn: &T,
+        pgno: PageNumber,
+        kind: PtrMapType,
+        parent: Option<PageNumber>,
+    ) -> Result<()> {
+        let Some((ptrmap, offset)) = self.locate(pgno) else {
+            return Ok(());
+        };
+        let encoded = PtrMapEntry { kind, parent }.encode();
+        let (bytes, dirty) = self.load(cx, txn, ptrmap)?;
+        let end = offset + encoded.len();
+        if end <= bytes.len() && bytes[offset..end] != encoded {
+            bytes[offset..end].copy_from_slice(&encoded);
+            *dirty = true;
+        }
+        Ok(())
+    }
+
+    /// Write every modified pointer-map page at or below `db_size`; pages
+    /// past a pending truncation are dropped with the rest of the tail.
+    fn flush<T: TransactionHandle + ?Sized>(
+        &mut self,
+        cx: &Cx,
+        txn: &mut T,
+        db_size: u32,
+    ) -> Result<()> {
+        let mut dirty: Vec<PageNumber> = self
+            .pages
+            .iter()
+            .filter(|(page_no, (_, dirty))| *dirty && page_no.get() <= db_size)
+            .map(|(page_no, _)| *page_no)
+            .collect();
+        dirty.sort_unstable();
+        for page_no in dirty {
+            if let Some((bytes, dirty)) = self.pages.get_mut(&page_no) {
+                txn.write_page(cx, page_no, bytes)?;
+                *dirty = false;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a page's outgoing pointers should be interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageRole {
+    Btree,
+    Overflow,
+}
+
+/// One outgoing pointer of a page: the child and the entry it should carry.
+fn child_pointers(
+    page_no: PageNumber,
+    page: &[u8],
+    role: PageRole,
+    usable_size: u32,
+) -> Result<Vec<(PageNumber, PtrMapType)>> {
+    let mut children = Vec::new();
+    match role {
+        PageRole::Overflow => {
+            if page.len() >= 4 {
+                let next = read_header_u32(page, 0);
+                if let Some(next) = PageNumber::new(next) {
+                    children.push((next, PtrMapType::Overflow2));
+                }
+            }
+        }
+        PageRole::Btree => {
+            let header_offset = header_offset_for_page(page_no);
+            if page.len() <= header_offset
+                || BtreePageType::from_flag(page[header_offset]).is_none()
+            {
+                return Ok(children);
+            }
+            let header = BtreePageHeader::parse(page, header_offset)?;
+            for offset in read_cell_pointers(page, &header, header_offset)? {
+                let cell =
+                    CellRef::parse(page, usize::from(offset), header.page_type, usable_size)?;
+                if let Some(left) = cell.left_child {
+                    children.push((left, PtrMapType::Btree));
+                }
+                if let Some(overflow) = cell.overflow_page {
+                    children.push((overflow, PtrMapType::Overflow1));
+                }
+            }
+            if let Some(right) = header.right_child {
+                children.push((right, PtrMapType::Btree));
+            }
+        }
+    }
+    Ok(children)
+}
+
+/// Bring the pointer map in line with the pages this transaction dirtied.
+fn update_ptrmap<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &T,
+    header: &VacuumHeader,
+    map: &mut PtrMapPages,
+) -> Result<()> {
+    let free: HashSet<PageNumber> = txn.freelist_pages()?.into_iter().collect();
+    let dirty: HashSet<PageNumber> = txn
+        .pending_commit_pages()?
+        .into_iter()
+        .filter(|page_no| {
+            !free.contains(page_no)
+                && !is_ptrmap_page(*page_no, header.usable_size, header.page_size)
+        })
+        .collect();
+
+    let mut worklist = vec![(PageNumber::ONE, PageRole::Btree)];
+    let mut seeds: Vec<PageNumber> = dirty.iter().copied().collect();
+    seeds.sort_unstable();
+    for page_no in seeds {
+        let role = match map.get(cx, txn, page_no)? {
+            Some(entry) if matches!(entry.kind, PtrMapType::RootPage | PtrMapType::Btree) => {
+                PageRole::Btree
+            }
+            Some(entry) if matches!(entry.kind, PtrMapType::Overflow1 | PtrMapType::Overflow2) => {
+                PageRole::Overflow
+            }
+            _ => continue,
+        };
+        worklist.push((page_no, role));
+    }
+
+    let mut visited = HashSet::new();
+    while let Some((page_no, role)) = worklist.pop() {
+        if !visited.insert(page_no) {
+            continue;
+        }
+        let page = txn.get_page(cx, page_no)?;
+        for (child, kind) in child_pointers(page_no, page.as_bytes(), role, header.usable_size)? {
+            map.set(cx, txn, child, kind, Some(page_no))?;
+            if dirty.contains(&child) && !visited.contains(&child) {
+                let child_role = if kind == PtrMapType::Btree {
+                    PageRole::Btree
+                } else {
+                    PageRole::Overflow
+                };
+                worklist.push((child, child_role));
+            }
+        }
+    }
+
+    for page_no in free {
+        map.set(cx, txn, page_no, PtrMapType::FreePage, None)?;
+    }
+    Ok(())
+}
+
+/// Rewrite the pointer from `parent` to `old` so it refers to `new`.
+fn patch_parent_pointer<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+    header: &VacuumHeader,
+    parent: PageNumber,
+    kind: PtrMapType,
+    old: PageNumber,
+    new: PageNumber,
+) -> Result<()> {
+    let mut page = txn.get_page(cx, parent)?.as_bytes().to_vec();
+    let old_bytes = old.get().to_be_bytes();
+    let new_bytes = new.get().to_be_bytes();
+    let mut patched = false;
+
+    if kind == PtrMapType::Overflow2 {
+        if page.len() >= 4 && page[0..4] == old_bytes {
+            page[0..4].copy_from_slice(&new_bytes);
+            patched = true;
+        }
+    } else {
+        let header_offset = header_offset_for_page(parent);
+        let btree_header = BtreePageHeader::parse(&page, header_offset)?;
+        for offset in read_cell_pointers(&page, &btree_header, header_offset)? {
+            let offset = usize::from(offset);
+            let cell = CellRef::parse(&page, offset, btree_header.page_type, header.usable_size)?;
+            if kind == PtrMapType::Btree && cell.left_child == Some(old) {
+                page[offset..offset + 4].copy_from_slice(&new_bytes);
+                patched = true;
+                break;
+            }
+            if kind == PtrMapType::Overflow1 && cell.overflow_page == Some(old) {
+                let at = cell.payload_offset + cell.local_size as usize;
+                page[at..at + 4].copy_from_slice(&new_bytes);
+                patched = true;
+                break;
+            }
+        }
+        if !patched && kind == PtrMapType::Btree && btree_header.right_child == Some(old) {
+            let at = header_offset + 8;
+            page[at..at + 4].copy_from_slice(&new_bytes);
+            patched = true;
+        }
+    }
+
+    if !patched {
+        return Err(FrankenError::DatabaseCorrupt {
+            detail: format!(
+                "pointer map names page {} as parent of page {} but it holds no such pointer",
+                parent.get(),
+                old.get()
+            ),
+        });
+    }
+    txn.write_page(cx, parent, &page)
+}
+
+/// Move the content of `src` to the already-claimed page `dst`, fixing up
+/// the parent pointer and every child's pointer-map entry.
+fn relocate_page<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+    header: &VacuumHeader,
+    map: &mut PtrMapPages,
+    src: PageNumber,
+    dst: PageNumber,
+) -> Result<()> {
+    let entry = map
+        .get(cx, txn, src)?
+        .ok_or_else(|| FrankenError::DatabaseCorrupt {
+            detail: format!("page {} has no pointer-map entry", src.get()),
+        })?;
+    let role = match entry.kind {
+        PtrMapType::Btree => PageRole::Btree,
+        PtrMapType::Overflow1 | PtrMapType::Overflow2 => PageRole::Overflow,
+        PtrMapType::RootPage | PtrMapType::FreePage => {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "cannot relocate page {} of type {:?}",
+                    src.get(),
+                    entry.kind
+                ),
+            });
+        }
+    };
+    let parent = entry.parent.ok_or_else(|| FrankenError::DatabaseCorrupt {
+        detail: format!("page {} has no parent in the pointer map", src.get()),
+    })?;
+    debug!(
+        src = src.get(),
+        dst = dst.get(),
+        kind = ?entry.kind,
+        "auto-vacuum relocating page"
+    );
+
+    let content = txn.get_page(cx, src)?.as_bytes().to_vec();
+    txn.write_page(cx, dst, &content)?;
+    for (child, kind) in child_pointers(dst, &content, role, header.usable_size)? {
+        map.set(cx, txn, child, kind, Some(dst))?;
+    }
+    patch_parent_pointer(cx, txn, header, parent, entry.kind, src, dst)?;
+    map.set(cx, txn, dst, entry.kind, Some(parent))
+}
+
+fn is_skipped_page(pgno: u32, header: &VacuumHeader) -> bool {
+    pgno == PENDING_BYTE_OFFSET / header.page_size + 1
+        || PageNumber::new(pgno)
+            .is_some_and(|page_no| is_ptrmap_page(page_no, header.usable_size, header.page_size))
+}
+
+/// Database size after every free page is removed (SQLite's `finalDbSize`).
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn final_db_size(n_orig: u32, n_free: u32, usable_size: u32, page_size: u32) -> u32 {
+    let n_entry = i64::from(usable_size / PTRMAP_ENTRY_SIZE_BYTES);
+    let ptrmap_of_last = i64::from(compute_ptrmap_page(n_orig, usable_size, page_size));
+    let n_ptrmap = (i64::from(n_free) - i64::from(n_orig) + ptrmap_of_last + n_entry) / n_entry;
+    let mut n_fin = i64::from(n_orig) - i64::from(n_free) - n_ptrmap;
+    let pending_byte_page = i64::from(PENDING_BYTE_OFFSET / page_size + 1);
+    if i64::from(n_orig) > pending_byte_page && n_fin < pending_byte_page {
+        n_fin -= 1;
+    }
+    while n_fin > 1
+        && (n_fin == pending_byte_page
+            || compute_ptrmap_page(n_fin as u32, usable_size, page_size) == n_fin as u32)
+    {
+        n_fin -= 1;
+    }
+    n_fin.max(1) as u32
+}
+
+/// Move live pages off the end of the file and truncate, releasing up to
+/// `limit` pages (all free pages when `None`). Returns the number of pages
+/// the file shrinks by.
+fn vacuum<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+    header: &VacuumHeader,
+    map: &mut PtrMapPages,
+    limit: Option<u32>,
+) -> Result<u32> {
+    let n_orig = txn.database_size()?;
+    let mut free: BTreeSet<PageNumber> = txn.freelist_pages()?.into_iter().collect();
+    if free.is_empty() {
+        return Ok(0);
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    let n_fin = final_db_size(
+        n_orig,
+        free.len() as u32,
+        header.usable_size,
+        header.page_size,
+    );
+    let mut budget = limit.unwrap_or(u32::MAX);
+    let mut moved = Vec::new();
+    let mut last = n_orig;
+
+    while last > n_fin && budget > 0 {
+        if is_skipped_page(last, header) {
+            last -= 1;
+            continue;
+        }
+        let Some(src) = PageNumber::new(last) else {
+            break;
+        };
+        if !free.remove(&src) {
+            let movable = map
+                .get(cx, txn, src)?
+                .is_some_and(|entry| !matches!(entry.kind, PtrMapType::RootPage));
+            if !movable {
+                break;
+            }
+            let Some(dst) = free.pop_first() else {
+                break;
+            };
+            if !txn.allocate_page_at(cx, dst)? {
+                break;
+            }
+            relocate_page(cx, txn, header, map, src, dst)?;
+            moved.push(src);
+        }
+        budget -= 1;
+        last -= 1;
+    }
+    while last > 1 && is_skipped_page(last, header) {
+        last -= 1;
+    }
+    if last >= n_orig {
+        return Ok(0);
+    }
+
+    if !txn.truncate_database(cx, last)? {
+        // The pager cannot shrink right now (another snapshot may still read
+        // the tail); the relocated sources simply become free pages.
+        for page_no in moved {
+            txn.free_page(cx, page_no)?;
+        }
+        for page_no in txn.freelist_pages()? {
+            map.set(cx, txn, page_no, PtrMapType::FreePage, None)?;
+        }
+        return Ok(0);
+    }
+    for page_no in txn.freelist_pages()? {
+        map.set(cx, txn, page_no, PtrMapType::FreePage, None)?;
+    }
+    Ok(n_orig - last)
+}
+
+/// Maintain the pointer map, and in `FULL` mode vacuum, before `txn` commits.
+///
+/// A no-op for transactions without pending writes and for databases with
+/// auto-vacuum off.
+pub fn prepare_commit<T: TransactionHandle + ?Sized>(cx: &Cx, txn: &mut T) -> Result<()> {
+    if !txn.has_pending_writes() {
+        return Ok(());
+    }
+    let Some(header) = read_vacuum_header(cx, txn)? else {
+        return Ok(());
+    };
+    let mut map = PtrMapPages::new(&header);
+    update_ptrmap(cx, txn, &header, &mut map)?;
+    if header.mode == AutoVacuumMode::Full {
+        vacuum(cx, txn, &header, &mut map, None)?;
+    }
+    let db_size = txn.database_size()?;
+    map.flush(cx, txn, db_size)
+}
+
+/// `PRAGMA incremental_vacuum(N)`: release up to `limit` free pages from the
+/// end of an `INCREMENTAL` database. Returns the number of pages removed.
+pub fn incremental_vacuum<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+    limit: Option<u32>,
+) -> Result<u32> {
+    let Some(header) = read_vacuum_header(cx, txn)? else {
+        return Ok(0);
+    };
+    if header.mode != AutoVacuumMode::Incremental {
+        return Ok(0);
+    }
+    let mut map = PtrMapPages::new(&header);
+    update_ptrmap(cx, txn, &header, &mut map)?;
+    let removed = vacuum(cx, txn, &header, &mut map, limit)?;
+    let db_size = txn.database_size()?;
+    map.flush(cx, txn, db_size)?;
+    Ok(removed)
+}
+
+/// Allocate a root page for a new table or index.
+///
+/// Auto-vacuum databases keep root pages packed directly after page 1 so
+/// they never need to move; whatever currently occupies the next root slot
+/// is relocated first. Other databases just take any free page.
+pub fn allocate_root_page<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+) -> Result<PageNumber> {
+    let Some(header) = read_vacuum_header(cx, txn)? else {
+        return txn.allocate_page(cx);
+    };
+    let mut raw = header.largest_root_page.saturating_add(1);
+    while is_skipped_page(raw, &header) {
+        raw = raw.saturating_add(1);
+    }
+    let root = PageNumber::new(raw).ok_or_else(|| FrankenError::OutOfRange {
+        what: "root page number".to_owned(),
+        value: raw.to_string(),
+    })?;
+
+    let mut map = PtrMapPages::new(&header);
+    if !txn.allocate_page_at(cx, root)? {
+        update_ptrmap(cx, txn, &header, &mut map)?;
+        if map
+            .get(cx, txn, root)?
+            .is_some_and(|entry| entry.kind == PtrMapType::FreePage)
+        {
+            // Free but still visible to another snapshot.
+            return Err(FrankenError::Busy);
+        }
+        let dst = txn.allocate_page(cx)?;
+        relocate_page(cx, txn, &header, &mut map, root, dst)?;
+    }
+    map.set(cx, txn, root, PtrMapType::RootPage, None)?;
+    // The caller writes the root page itself after this returns, so the
+    // pointer-map page covering it must be written even though it may lie
+    // past the current end of file.
+    map.flush(cx, txn, u32::MAX)?;
+
+    let mut page1 = txn.get_page(cx, PageNumber::ONE)?.as_bytes().to_vec();
+    page1[LARGEST_ROOT_PAGE_OFFSET..LARGEST_ROOT_PAGE_OFFSET + 4]
+        .copy_from_slice(&root.get().to_be_bytes());
+    txn.write_page(cx, PageNumber::ONE, &page1)?;
+    Ok(root)
+}
+
+/// Apply `PRAGMA auto_vacuum = mode` and return the mode now in effect.
+///
+/// Switching between `FULL` and `INCREMENTAL` is always allowed. Turning
+/// auto-vacuum on or off needs a pointer map built from scratch, so as in
+/// SQLite it only takes effect while the database has no tables yet;
+/// otherwise the current mode is returned unchanged.
+pub fn set_auto_vacuum_mode<T: TransactionHandle + ?Sized>(
+    cx: &Cx,
+    txn: &mut T,
+    mode: AutoVacuumMode,
+) -> Result<AutoVacuumMode> {
+    let mut page1 = txn.get_page(cx, PageNumber::ONE)?.as_bytes().to_vec();
+    if page1.len() < 108 || !page1.starts_with(DATABASE_HEADER_MAGIC) {
+        return Ok(AutoVacuumMode::None);
+    }
+    let largest_root_page = read_header_u32(&page1, LARGEST_ROOT_PAGE_OFFSET);
+    let current = AutoVacuumMode::from_header(
+        largest_root_page,
+        read_header_u32(&page1, INCREMENTAL_VACUUM_OFFSET),
+    );
+    if current == mode {
+        return Ok(current);
+    }
+    let toggles_pointer_map = (current == AutoVacuumMode::None) != (mode == AutoVacuumMode::None);
+    if toggles_pointer_map {
+        let cell_count = u16::from_be_bytes([page1[103], page1[104]]);
+        if txn.database_size()? > 1 || cell_count != 0 {
+            return Ok(current);
+        }
+    }
+
+    let largest_root_page = match mode {
+        AutoVacuumMode::None => 0,
+        AutoVacuumMode::Full | AutoVacuumMode::Incremental => largest_root_page.max(1),
+    };
+    let incremental = u32::from(mode == AutoVacuumMode::Incremental);
+    page1[LARGEST_ROOT_PAGE_OFFSET..LARGEST_ROOT_PAGE_OFFSET + 4]
+        .copy_from_slice(&largest_root_page.to_be_bytes());
+    page1[INCREMENTAL_VACUUM_OFFSET..INCREMENTAL_VACUUM_OFFSET + 4]
+        .copy_from_slice(&incremental.to_be_bytes());
+    txn.write_page(cx, PageNumber::ONE, &page1)?;
+    Ok(mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_vacuum_mode_round_trip() {
+        for mode in [
+            AutoVacuumMode::None,
+            AutoVacuumMode::Full,
+            AutoVacuumMode::Incremental,
+        ] {
+            assert_eq!(AutoVacuumMode::from_pragma(mode.as_pragma()), Some(mode));
+        }
+        assert_eq!(AutoVacuumMode::from_pragma(3), None);
+        assert_eq!(AutoVacuumMode::from_header(0, 1), AutoVacuumMode::None);
+        assert_eq!(AutoVacuumMode::from_header(3, 0), AutoVacuumMode::Full);
+        assert_eq!(
+            AutoVacuumMode::from_header(3, 1),
+            AutoVacuumMode::Incremental
+        );
+    }
+
+    #[test]
+    fn test_final_db_size_matches_sqlite() {
+        // No free pages: size is unchanged.
+        assert_eq!(final_db_size(10, 0, 4096, 4096), 10);
+        // Freeing the tail of the first pointer-map group.
+        assert_eq!(final_db_size(10, 4, 4096, 4096), 6);
+        // Everything but page 1 and its pointer map free: only page 1 stays
+        // once page 2 (the pointer map) is trimmed.
+        assert_eq!(final_db_size(10, 8, 4096, 4096), 1);
+        // Crossing a pointer-map page boundary drops that page too.
+        assert_eq!(final_db_size(830, 8, 4096, 4096), 821);
+    }
+
+    #[test]
+    fn test_child_pointers_follow_overflow_chain() {
+        let mut page = vec![0u8; 512];
+        page[0..4].copy_from_slice(&7u32.to_be_bytes());
+        let children =
+            child_pointers(PageNumber::new(5).unwrap(), &page, PageRole::Overflow, 512).unwrap();
+        assert_eq!(
+            children,
+            vec![(PageNumber::new(7).unwrap(), PtrMapType::Overflow2)]
+        );
+
+        page[0..4].copy_from_slice(&0u32.to_be_bytes());
+        assert!(
+            child_pointers(PageNumber::new(5).unwrap(), &page, PageRole::Overflow, 512)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
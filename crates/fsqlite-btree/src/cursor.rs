@@ -30,7 +30,10 @@ use fsqlite_types::cx::Cx;
 use fsqlite_types::limits::BTREE_MAX_DEPTH;
 use fsqlite_types::record::parse_record;
 use fsqlite_types::serial_type::{read_varint, write_varint};
-use fsqlite_types::{PageData, PageNumber, WitnessKey};
+use fsqlite_types::{PageData, PageNumber, SqliteValue, WitnessKey};
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
 use tracing::{Level, debug, trace, warn};
 
 #[inline]
@@ -286,6 +289,58 @@ struct StackEntry {
     cell_idx: u16,
 }
 
+// ---------------------------------------------------------------------------
+// Index key collation
+// ---------------------------------------------------------------------------
+
+/// Byte-level comparator behind an [`IndexKeyCollation`].
+pub type CollationCompare = Arc<dyn Fn(&[u8], &[u8]) -> Ordering + Send + Sync>;
+
+/// Collating sequence applied to one index key column.
+///
+/// Index B-trees are ordered by each key column's collation, so a cursor
+/// over an index declared with (or inheriting) a non-binary collation must
+/// compare TEXT keys with the same function that ordered them on insert.
+/// Non-TEXT pairs always use the standard value ordering.
+#[derive(Clone)]
+pub struct IndexKeyCollation {
+    name: String,
+    compare: CollationCompare,
+}
+
+impl IndexKeyCollation {
+    /// Wrap a byte-level text comparator under the given collation name.
+    pub fn new(
+        name: impl Into<String>,
+        compare: impl Fn(&[u8], &[u8]) -> Ordering + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            compare: Arc::new(compare),
+        }
+    }
+
+    /// The collation name this comparator was registered under.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Compare two UTF-8 encoded TEXT keys.
+    #[must_use]
+    pub fn compare(&self, left: &[u8], right: &[u8]) -> Ordering {
+        (self.compare)(left, right)
+    }
+}
+
+impl fmt::Debug for IndexKeyCollation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IndexKeyCollation")
+            .field(&self.name)
+            .finish()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // BtCursor
 // ---------------------------------------------------------------------------
@@ -308,6 +363,8 @@ pub struct BtCursor<P> {
     /// The implicit trailing rowid suffix always sorts ascending, so this
     /// vector covers only the logical key terms before the rowid.
     index_desc_flags: Vec<bool>,
+    /// Per-key collating sequences for index cursors (`None` = BINARY).
+    index_collations: Vec<Option<IndexKeyCollation>>,
     /// Page stack from root to current leaf.
     stack: Vec<StackEntry>,
     /// Whether the cursor is at EOF (past the last entry).
@@ -360,6 +417,14 @@ impl<P> BtCursor<P> {
     pub fn usable_size(&self) -> u32 {
         self.usable_size
     }
+
+    /// Install per-key collating sequences for an index cursor.
+    ///
+    /// Like the descending flags, the vector covers only the logical key
+    /// terms; the trailing rowid always compares numerically.
+    pub fn set_index_collations(&mut self, collations: Vec<Option<IndexKeyCollation>>) {
+        self.index_collations = collations;
+    }
 }
 
 impl<P: PageReader> BtCursor<P> {
@@ -384,6 +449,7 @@ impl<P: PageReader> BtCursor<P> {
             usable_size,
             is_table,
             index_desc_flags,
+            index_collations: Vec::new(),
             stack: Vec::with_capacity(BTREE_MAX_DEPTH as usize),
             at_eof: true,
             read_witnesses: Vec::new(),
@@ -421,6 +487,53 @@ impl<P: PageReader> BtCursor<P> {
         self.issue_prefetch_hint(cx, page_no);
     }
 
+    /// Total payload size (local + overflow) of the current cell.
+    pub fn payload_size(&self) -> Result<u32> {
+        let (_, cell) = self.current_cell()?;
+        Ok(cell.payload_size)
+    }
+
+    /// Read `buf.len()` bytes of the current cell's payload starting at
+    /// `offset`, without reassembling the whole payload.
+    ///
+    /// Only the local area and the overflow pages overlapping the range are
+    /// read, which keeps random access into large BLOBs proportional to the
+    /// range rather than the value.
+    pub fn read_payload_at(&self, cx: &Cx, offset: usize, buf: &mut [u8]) -> Result<()> {
+        let (top, cell) = self.current_cell()?;
+        check_payload_range(&cell, offset, buf.len())?;
+        let local = cell.local_payload(&top.page_data);
+        let mut done = 0;
+        if offset < local.len() {
+            done = buf.len().min(local.len() - offset);
+            buf[..done].copy_from_slice(&local[offset..offset + done]);
+        }
+        if done < buf.len() {
+            let first_overflow = cell.overflow_page.ok_or_else(missing_overflow_chain)?;
+            overflow::read_overflow_range(
+                first_overflow,
+                offset + done - local.len(),
+                self.usable_size,
+                &mut buf[done..],
+                &mut |pgno| self.pager.read_page(cx, pgno),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The top-of-stack entry and the cell the cursor is positioned on.
+    fn current_cell(&self) -> Result<(&StackEntry, CellRef)> {
+        if self.at_eof || self.stack.is_empty() {
+            return Err(FrankenError::internal("cursor at EOF"));
+        }
+        let top = self
+            .stack
+            .last()
+            .ok_or_else(|| FrankenError::internal("cursor stack empty"))?;
+        let cell = self.parse_cell_at(top, top.cell_idx)?;
+        Ok((top, cell))
+    }
+
     #[inline]
     fn cell_tag_from_rowid(rowid: i64) -> u64 {
         u64::from_ne_bytes(rowid.to_ne_bytes())
@@ -1228,7 +1341,13 @@ impl<P: PageReader> BtCursor<P> {
     ) -> Option<std::cmp::Ordering> {
         let shared_len = lhs.len().min(rhs.len());
         for idx in 0..shared_len {
-            let mut ord = lhs[idx].partial_cmp(&rhs[idx])?;
+            let collation = self.index_collations.get(idx).and_then(Option::as_ref);
+            let mut ord = match (&lhs[idx], &rhs[idx], collation) {
+                (SqliteValue::Text(l), SqliteValue::Text(r), Some(coll)) => {
+                    coll.compare(l.as_bytes(), r.as_bytes())
+                }
+                (l, r, _) => l.partial_cmp(r)?,
+            };
             if self.index_desc_flags.get(idx).copied().unwrap_or(false) {
                 ord = ord.reverse();
             }
@@ -1424,6 +1543,24 @@ impl<P: PageReader> BtCursor<P> {
     }
 }
 
+/// Reject payload ranges that extend past the end of `cell`'s payload.
+fn check_payload_range(cell: &CellRef, offset: usize, len: usize) -> Result<()> {
+    let end = offset.checked_add(len).ok_or(FrankenError::TooBig)?;
+    if end > cell.payload_size as usize {
+        return Err(FrankenError::OutOfRange {
+            what: "payload range".to_owned(),
+            value: format!("{offset}..{end} of {}", cell.payload_size),
+        });
+    }
+    Ok(())
+}
+
+fn missing_overflow_chain() -> FrankenError {
+    FrankenError::DatabaseCorrupt {
+        detail: "payload extends past local data without an overflow chain".to_owned(),
+    }
+}
+
 /// Result of a binary search within a page.
 enum BinarySearchResult {
     /// Exact match found at this cell index.
@@ -1433,6 +1570,48 @@ enum BinarySearchResult {
 }
 
 impl<P: PageWriter> BtCursor<P> {
+    /// Overwrite part of the current cell's payload in place.
+    ///
+    /// The payload keeps its size, so no cell moves and no page is
+    /// rebalanced: bytes in the local area are patched on the leaf page and
+    /// the rest are written straight to the overflow pages that hold them.
+    pub fn write_payload_at(&mut self, cx: &Cx, offset: usize, data: &[u8]) -> Result<()> {
+        let (page_no, cell_idx, cell) = {
+            let (top, cell) = self.current_cell()?;
+            (top.page_no, top.cell_idx, cell)
+        };
+        check_payload_range(&cell, offset, data.len())?;
+        let local_len = cell.local_size as usize;
+        let mut done = 0;
+        if offset < local_len {
+            done = data.len().min(local_len - offset);
+            let mut page_data = self.pager.read_page(cx, page_no)?;
+            let start = cell.payload_offset + offset;
+            page_data[start..start + done].copy_from_slice(&data[..done]);
+            self.pager
+                .write_page_data(cx, page_no, PageData::from_vec(page_data))?;
+
+            let depth = self.stack.len();
+            let mut refreshed = self.reload_page_fresh(cx, page_no)?;
+            refreshed.cell_idx = cell_idx;
+            self.stack[depth - 1] = refreshed;
+        }
+        if done < data.len() {
+            let first_overflow = cell.overflow_page.ok_or_else(missing_overflow_chain)?;
+            let usable_size = self.usable_size;
+            let pager = std::cell::RefCell::new(&mut self.pager);
+            overflow::write_overflow_range(
+                first_overflow,
+                offset + done - local_len,
+                usable_size,
+                &data[done..],
+                &mut |pgno| pager.borrow().read_page(cx, pgno),
+                &mut |pgno, page| pager.borrow_mut().write_page(cx, pgno, page),
+            )?;
+        }
+        Ok(())
+    }
+
     fn write_overflow_chain_for_insert(
         &mut self,
         cx: &Cx,
@@ -3513,6 +3692,36 @@ mod tests {
         assert_eq!(cursor.payload(&cx).unwrap(), payload);
     }
 
+    #[test]
+    fn test_cursor_payload_range_read_write_in_place() {
+        let mut store = MemPageStore::new(USABLE);
+        store.pages.insert(2, build_leaf_table(&[]));
+        let mut payload: Vec<u8> = (0u8..=255).cycle().take(5000).collect();
+
+        let cx = Cx::new();
+        let mut cursor = BtCursor::new(store, pn(2), USABLE, true);
+        cursor.table_insert(&cx, 7, &payload).unwrap();
+        assert!(cursor.table_move_to(&cx, 7).unwrap().is_found());
+        assert_eq!(cursor.payload_size().unwrap(), 5000);
+
+        let mut buf = vec![0u8; 1500];
+        cursor.read_payload_at(&cx, 3000, &mut buf).unwrap();
+        assert_eq!(buf, payload[3000..4500]);
+
+        // One write spanning the local area and the overflow chain.
+        cursor.write_payload_at(&cx, 10, &[0xEE; 4000]).unwrap();
+        payload[10..4010].fill(0xEE);
+        assert!(cursor.table_move_to(&cx, 7).unwrap().is_found());
+        assert_eq!(cursor.payload(&cx).unwrap(), payload);
+
+        let mut past_end = [0u8; 2];
+        assert!(matches!(
+            cursor.read_payload_at(&cx, 4999, &mut past_end),
+            Err(FrankenError::OutOfRange { .. })
+        ));
+        assert!(cursor.write_payload_at(&cx, 5000, &[1]).is_err());
+    }
+
     #[test]
     fn test_cursor_table_seek_past_end_then_insert() {
         let mut store = MemPageStore::new(USABLE);
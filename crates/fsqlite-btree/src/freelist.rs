@@ -284,7 +284,7 @@ impl PtrMapEntry {
 }
 
 /// Byte offset of the 1 GiB pending byte region.
-const PENDING_BYTE_OFFSET: u32 = 0x4000_0000;
+pub(crate) const PENDING_BYTE_OFFSET: u32 = 0x4000_0000;
 
 /// Number of pointer-map entries per pointer-map page for a usable page size.
 #[must_use]
@@ -300,7 +300,7 @@ pub const fn ptrmap_group_size(usable_size: u32) -> u32 {
 
 /// Compute the logical pointer map page for a given `pgno`.
 #[must_use]
-const fn compute_ptrmap_page(pgno: u32, usable_size: u32, page_size: u32) -> u32 {
+pub(crate) const fn compute_ptrmap_page(pgno: u32, usable_size: u32, page_size: u32) -> u32 {
     if pgno < 2 {
         return 0;
     }
@@ -1,5 +1,6 @@
 use std::cmp::Ordering;
 
+pub mod autovacuum;
 pub mod balance;
 pub mod be_tree;
 pub mod cell;
@@ -33,7 +34,10 @@ pub use cooling::{
 pub use cracking::{
     CrackedColumn, CrackingMetricsSnapshot, cracking_metrics_snapshot, reset_cracking_metrics,
 };
-pub use cursor::{BtCursor, MemPageStore, PageReader, PageWriter, TransactionPageIo};
+pub use cursor::{
+    BtCursor, CollationCompare, IndexKeyCollation, MemPageStore, PageReader, PageWriter,
+    TransactionPageIo,
+};
 pub use instrumentation::{
     BtreeMetricsSnapshot, BtreeOpType, BtreeOperationTotals, btree_metrics_snapshot,
     reset_btree_metrics,
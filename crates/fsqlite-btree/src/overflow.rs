@@ -136,6 +136,144 @@ where
     Ok(())
 }
 
+/// Read part of the overflow portion of a payload without reassembling it.
+///
+/// `offset` is measured from the first overflow byte (payload offset minus
+/// the local size) and `buf.len()` bytes are copied out. Pages before the
+/// range are read only for their next-page pointer and pages after it are
+/// never touched.
+pub fn read_overflow_range<F>(
+    first_overflow: PageNumber,
+    offset: usize,
+    usable_size: u32,
+    buf: &mut [u8],
+    read_page: &mut F,
+) -> Result<()>
+where
+    F: FnMut(PageNumber) -> Result<Vec<u8>>,
+{
+    let len = buf.len();
+    walk_overflow_range(
+        first_overflow,
+        offset,
+        len,
+        usable_size,
+        read_page,
+        &mut |_, page_data, page_range, out_pos| {
+            let n = page_range.len();
+            buf[out_pos..out_pos + n].copy_from_slice(&page_data[page_range]);
+            Ok(())
+        },
+    )
+}
+
+/// Overwrite part of the overflow portion of a payload in place.
+///
+/// `offset` is measured as in [`read_overflow_range`]. The chain is never
+/// extended: the range must lie within the existing payload. Only pages
+/// that overlap the range are rewritten.
+pub fn write_overflow_range<F, W>(
+    first_overflow: PageNumber,
+    offset: usize,
+    usable_size: u32,
+    data: &[u8],
+    read_page: &mut F,
+    write_page: &mut W,
+) -> Result<()>
+where
+    F: FnMut(PageNumber) -> Result<Vec<u8>>,
+    W: FnMut(PageNumber, &[u8]) -> Result<()>,
+{
+    walk_overflow_range(
+        first_overflow,
+        offset,
+        data.len(),
+        usable_size,
+        read_page,
+        &mut |pgno, page_data, page_range, in_pos| {
+            let n = page_range.len();
+            let mut page = page_data.to_vec();
+            page[page_range].copy_from_slice(&data[in_pos..in_pos + n]);
+            write_page(pgno, &page)
+        },
+    )
+}
+
+/// Visit every overflow page overlapping `[offset, offset + len)` of the
+/// overflow data, passing the page, the overlapping byte range within the
+/// raw page, and the position of that range relative to `offset`.
+fn walk_overflow_range<F, V>(
+    first_overflow: PageNumber,
+    offset: usize,
+    len: usize,
+    usable_size: u32,
+    read_page: &mut F,
+    visit: &mut V,
+) -> Result<()>
+where
+    F: FnMut(PageNumber) -> Result<Vec<u8>>,
+    V: FnMut(PageNumber, &[u8], std::ops::Range<usize>, usize) -> Result<()>,
+{
+    if len == 0 {
+        return Ok(());
+    }
+    if usable_size <= 4 {
+        return Err(FrankenError::DatabaseCorrupt {
+            detail: format!(
+                "invalid usable page size {} for overflow chain",
+                usable_size
+            ),
+        });
+    }
+
+    let bytes_per_overflow = (usable_size - 4) as usize;
+    let end = offset.checked_add(len).ok_or(FrankenError::TooBig)?;
+    let mut current_page = first_overflow;
+    let mut page_start = 0_usize;
+    let mut chain_length = 0;
+
+    loop {
+        chain_length += 1;
+        if chain_length > MAX_OVERFLOW_CHAIN {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "overflow chain exceeds maximum length of {}",
+                    MAX_OVERFLOW_CHAIN
+                ),
+            });
+        }
+
+        let page_data = read_page(current_page)?;
+        if page_data.len() <= 4 {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: "overflow page too small or empty".to_owned(),
+            });
+        }
+        let next_raw = u32::from_be_bytes([page_data[0], page_data[1], page_data[2], page_data[3]]);
+
+        let page_end = page_start + bytes_per_overflow;
+        if page_end > offset {
+            let from = offset.max(page_start);
+            let to = end.min(page_end);
+            let page_range = (4 + from - page_start)..(4 + to - page_start);
+            if page_range.end > page_data.len() {
+                return Err(FrankenError::DatabaseCorrupt {
+                    detail: "overflow page too small or empty".to_owned(),
+                });
+            }
+            visit(current_page, &page_data, page_range, from - offset)?;
+            if to == end {
+                return Ok(());
+            }
+        }
+
+        current_page = PageNumber::new(next_raw).ok_or_else(|| FrankenError::DatabaseCorrupt {
+            detail: "unexpected end of overflow chain".to_owned(),
+        })?;
+        page_start = page_end;
+    }
+}
+
 /// Write a payload to an overflow chain, allocating pages as needed.
 ///
 /// `overflow_data` is the portion of the payload that doesn't fit locally.
@@ -368,6 +506,67 @@ mod tests {
         assert_eq!(&result[6..], &overflow_data[..]);
     }
 
+    #[test]
+    fn test_overflow_range_read_write_in_place() {
+        let usable = 20u32; // 16 bytes of data per overflow page.
+        let overflow_data: Vec<u8> = (0..50).collect();
+        let pages: std::cell::RefCell<HashMap<u32, Vec<u8>>> = Default::default();
+        let mut next_page = 100u32;
+        let first = write_overflow_chain(
+            &overflow_data,
+            usable,
+            &mut || {
+                let pgno = PageNumber::new(next_page).unwrap();
+                next_page += 1;
+                Ok(pgno)
+            },
+            &mut |pgno, data| {
+                pages.borrow_mut().insert(pgno.get(), data.to_vec());
+                Ok(())
+            },
+        )
+        .unwrap();
+        let mut read_page = |pgno: PageNumber| {
+            pages
+                .borrow()
+                .get(&pgno.get())
+                .cloned()
+                .ok_or_else(|| FrankenError::internal("page not found"))
+        };
+
+        // A range straddling the first page boundary.
+        let mut buf = [0u8; 10];
+        read_overflow_range(first, 12, usable, &mut buf, &mut read_page).unwrap();
+        assert_eq!(&buf, &overflow_data[12..22]);
+
+        let mut rewritten = Vec::new();
+        write_overflow_range(
+            first,
+            30,
+            usable,
+            &[0xAA; 4],
+            &mut read_page,
+            &mut |pgno, data| {
+                rewritten.push(pgno.get());
+                pages.borrow_mut().insert(pgno.get(), data.to_vec());
+                Ok(())
+            },
+        )
+        .unwrap();
+        // Bytes 30..34 live on the second and third overflow pages only.
+        assert_eq!(rewritten, vec![101, 102]);
+
+        let mut all = vec![0u8; overflow_data.len()];
+        read_overflow_range(first, 0, usable, &mut all, &mut read_page).unwrap();
+        assert_eq!(&all[..30], &overflow_data[..30]);
+        assert_eq!(&all[30..34], &[0xAA; 4]);
+        assert_eq!(&all[34..], &overflow_data[34..]);
+
+        // Reading past the end of the chain is corruption, not a short read.
+        let mut past_end = [0u8; 8];
+        assert!(read_overflow_range(first, 60, usable, &mut past_end, &mut read_page).is_err());
+    }
+
     #[test]
     fn test_overflow_chain_premature_end() {
         // Use small pages so one overflow page can't satisfy the full payload.
@@ -2,7 +2,12 @@
 //
 // Drop-in replacement for sqlite3_open/close/exec/prepare/step/finalize/column_*
 // via C FFI.  Read-only compat is the first milestone; writes via execute() are
-// included but the step-based iteration model is the primary focus.
+// included but the step-based iteration model is the primary focus.  Parameter
+// binding, result-column metadata and the connection-level counters cover the
+// entry points that common C/Python/Go drivers link against.  User-defined
+// functions and collations registered through sqlite3_create_function_v2 /
+// sqlite3_create_window_function / sqlite3_create_collation_v2 bridge onto the
+// Connection registration APIs.
 //
 // Tracing: span 'compat_api' with fields api_func, duration_us.
 // Log level: INFO API calls via compat layer, WARN for unsupported features.
@@ -17,17 +22,20 @@
     clippy::cast_possible_wrap
 )]
 
+use std::cell::{OnceCell, RefCell};
 use std::ffi::{CStr, CString};
 use std::fmt::Write as _;
 use std::os::raw::{c_char, c_double, c_int, c_void};
-use std::sync::LazyLock;
 use std::sync::Mutex;
-use std::sync::atomic::{AtomicI32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock};
 
-use fsqlite::Connection;
-use fsqlite_ast::Statement;
+use fsqlite::{Connection, InterruptHandle};
+use fsqlite_ast::{Expr, ResultColumn, SelectCore, Statement, TableOrSubquery};
 use fsqlite_error::{ErrorCode, FrankenError};
-use fsqlite_parser::parse_first_statement_with_tail;
+use fsqlite_func::{AggregateFunction, ScalarFunction, WindowFunction};
+use fsqlite_parser::{Lexer, TokenKind, parse_first_statement_with_tail};
+use fsqlite_types::flags::OpenFlags;
 use fsqlite_types::value::SqliteValue;
 
 // ── SQLite result codes ─────────────────────────────────────────────
@@ -35,9 +43,12 @@ use fsqlite_types::value::SqliteValue;
 pub const SQLITE_OK: c_int = ErrorCode::Ok as c_int;
 pub const SQLITE_ERROR: c_int = ErrorCode::Error as c_int;
 pub const SQLITE_INTERNAL: c_int = ErrorCode::Internal as c_int;
+pub const SQLITE_PERM: c_int = ErrorCode::Perm as c_int;
 pub const SQLITE_BUSY: c_int = ErrorCode::Busy as c_int;
+pub const SQLITE_LOCKED: c_int = ErrorCode::Locked as c_int;
 pub const SQLITE_NOMEM: c_int = ErrorCode::NoMem as c_int;
 pub const SQLITE_READONLY: c_int = ErrorCode::ReadOnly as c_int;
+pub const SQLITE_INTERRUPT: c_int = ErrorCode::Interrupt as c_int;
 pub const SQLITE_IOERR: c_int = ErrorCode::IoErr as c_int;
 pub const SQLITE_CORRUPT: c_int = ErrorCode::Corrupt as c_int;
 pub const SQLITE_FULL: c_int = ErrorCode::Full as c_int;
@@ -54,6 +65,20 @@ pub const SQLITE_ROW: c_int = ErrorCode::Row as c_int;
 pub const SQLITE_DONE: c_int = ErrorCode::Done as c_int;
 pub const SQLITE_ABORT: c_int = ErrorCode::Abort as c_int;
 
+// ── Extended result codes ───────────────────────────────────────────
+
+pub const SQLITE_IOERR_READ: c_int = SQLITE_IOERR | (1 << 8);
+pub const SQLITE_IOERR_SHORT_READ: c_int = SQLITE_IOERR | (2 << 8);
+pub const SQLITE_IOERR_WRITE: c_int = SQLITE_IOERR | (3 << 8);
+pub const SQLITE_BUSY_RECOVERY: c_int = SQLITE_BUSY | (1 << 8);
+pub const SQLITE_BUSY_SNAPSHOT: c_int = SQLITE_BUSY | (2 << 8);
+pub const SQLITE_CONSTRAINT_CHECK: c_int = SQLITE_CONSTRAINT | (1 << 8);
+pub const SQLITE_CONSTRAINT_FOREIGNKEY: c_int = SQLITE_CONSTRAINT | (3 << 8);
+pub const SQLITE_CONSTRAINT_NOTNULL: c_int = SQLITE_CONSTRAINT | (5 << 8);
+pub const SQLITE_CONSTRAINT_PRIMARYKEY: c_int = SQLITE_CONSTRAINT | (6 << 8);
+pub const SQLITE_CONSTRAINT_UNIQUE: c_int = SQLITE_CONSTRAINT | (8 << 8);
+pub const SQLITE_CONSTRAINT_DATATYPE: c_int = SQLITE_CONSTRAINT | (12 << 8);
+
 // ── Column type constants ───────────────────────────────────────────
 
 pub const SQLITE_INTEGER: c_int = 1;
@@ -62,6 +87,39 @@ pub const SQLITE_TEXT: c_int = 3;
 pub const SQLITE_BLOB: c_int = 4;
 pub const SQLITE_NULL: c_int = 5;
 
+// ── Text encodings and open flags ───────────────────────────────────
+
+pub const SQLITE_UTF8: c_int = 1;
+pub const SQLITE_ANY: c_int = 5;
+
+// ── Function flags (`eTextRep` bits of `sqlite3_create_function_v2`) ──
+
+pub const SQLITE_DETERMINISTIC: c_int = 0x0000_0800;
+pub const SQLITE_DIRECTONLY: c_int = 0x0008_0000;
+pub const SQLITE_INNOCUOUS: c_int = 0x0020_0000;
+
+pub const SQLITE_OPEN_READONLY: c_int = OpenFlags::READONLY.bits() as c_int;
+pub const SQLITE_OPEN_READWRITE: c_int = OpenFlags::READWRITE.bits() as c_int;
+pub const SQLITE_OPEN_CREATE: c_int = OpenFlags::CREATE.bits() as c_int;
+pub const SQLITE_OPEN_URI: c_int = OpenFlags::URI.bits() as c_int;
+pub const SQLITE_OPEN_MEMORY: c_int = OpenFlags::MEMORY.bits() as c_int;
+pub const SQLITE_OPEN_NOMUTEX: c_int = OpenFlags::NOMUTEX.bits() as c_int;
+pub const SQLITE_OPEN_FULLMUTEX: c_int = OpenFlags::FULLMUTEX.bits() as c_int;
+
+// ── Library version ─────────────────────────────────────────────────
+
+pub const SQLITE_VERSION_NUMBER: c_int = 3_052_000;
+const SQLITE_VERSION: &CStr = c"3.52.0";
+const SQLITE_SOURCE_ID: &CStr = c"FrankenSQLite 0.1.0 (compatible with SQLite 3.52.0)";
+
+/// Destructor argument of `sqlite3_bind_text`/`sqlite3_bind_blob`:
+/// `SQLITE_STATIC` (null), `SQLITE_TRANSIENT` (`-1`) or a function that
+/// releases the caller's buffer.
+pub type Sqlite3Destructor = Option<unsafe extern "C" fn(*mut c_void)>;
+
+/// Address of the `SQLITE_TRANSIENT` sentinel, `(sqlite3_destructor_type)-1`.
+const SQLITE_TRANSIENT_ADDR: usize = usize::MAX;
+
 // ── Metrics ─────────────────────────────────────────────────────────
 
 static COMPAT_OPEN: AtomicU64 = AtomicU64::new(0);
@@ -72,6 +130,8 @@ static COMPAT_STEP: AtomicU64 = AtomicU64::new(0);
 static COMPAT_FINALIZE: AtomicU64 = AtomicU64::new(0);
 static COMPAT_COLUMN: AtomicU64 = AtomicU64::new(0);
 static COMPAT_ERRMSG: AtomicU64 = AtomicU64::new(0);
+static COMPAT_BIND: AtomicU64 = AtomicU64::new(0);
+static COMPAT_CREATE_FUNCTION: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Clone)]
 pub struct CompatMetricsSnapshot {
@@ -83,6 +143,8 @@ pub struct CompatMetricsSnapshot {
     pub finalize: u64,
     pub column: u64,
     pub errmsg: u64,
+    pub bind: u64,
+    pub create_function: u64,
 }
 
 impl CompatMetricsSnapshot {
@@ -95,6 +157,8 @@ impl CompatMetricsSnapshot {
             + self.finalize
             + self.column
             + self.errmsg
+            + self.bind
+            + self.create_function
     }
 }
 
@@ -108,6 +172,8 @@ pub fn compat_metrics_snapshot() -> CompatMetricsSnapshot {
         finalize: COMPAT_FINALIZE.load(Ordering::Relaxed),
         column: COMPAT_COLUMN.load(Ordering::Relaxed),
         errmsg: COMPAT_ERRMSG.load(Ordering::Relaxed),
+        bind: COMPAT_BIND.load(Ordering::Relaxed),
+        create_function: COMPAT_CREATE_FUNCTION.load(Ordering::Relaxed),
     }
 }
 
@@ -120,6 +186,8 @@ pub fn reset_compat_metrics() {
     COMPAT_FINALIZE.store(0, Ordering::Relaxed);
     COMPAT_COLUMN.store(0, Ordering::Relaxed);
     COMPAT_ERRMSG.store(0, Ordering::Relaxed);
+    COMPAT_BIND.store(0, Ordering::Relaxed);
+    COMPAT_CREATE_FUNCTION.store(0, Ordering::Relaxed);
 }
 
 // ── Opaque handle types ─────────────────────────────────────────────
@@ -131,34 +199,69 @@ const DEFAULT_ERROR_MESSAGE: &str = "not an error";
 /// Wraps a `Connection` plus the last error message for `sqlite3_errmsg`.
 pub struct Sqlite3 {
     conn: Connection,
+    interrupt: InterruptHandle,
+    filename: CString,
     last_error: Mutex<CString>,
+    /// Extended result code of the most recent error.
     last_error_code: AtomicI32,
-    last_changes: AtomicI32,
+    /// Set by `sqlite3_extended_result_codes`: API calls return extended
+    /// result codes instead of the primary code in the low byte.
+    extended_result_codes: AtomicBool,
+    last_changes: AtomicI64,
+    last_insert_rowid: AtomicI64,
+    total_changes: AtomicI64,
     active_statements: AtomicUsize,
+    /// Set by `sqlite3_close_v2` while statements are still open; the last
+    /// `sqlite3_finalize` then closes the connection.
+    zombie: AtomicBool,
 }
 
 impl Sqlite3 {
     fn new(conn: Connection) -> Self {
+        let filename = if conn.path() == ":memory:" {
+            CString::default()
+        } else {
+            CString::new(conn.path()).unwrap_or_default()
+        };
         Self {
+            interrupt: conn.interrupt_handle(),
             conn,
+            filename,
             last_error: Mutex::new(CString::new(DEFAULT_ERROR_MESSAGE).expect("static")),
             last_error_code: AtomicI32::new(SQLITE_OK),
-            last_changes: AtomicI32::new(0),
+            extended_result_codes: AtomicBool::new(false),
+            last_changes: AtomicI64::new(0),
+            last_insert_rowid: AtomicI64::new(0),
+            total_changes: AtomicI64::new(0),
             active_statements: AtomicUsize::new(0),
+            zombie: AtomicBool::new(false),
         }
     }
 
-    fn set_error(&self, err: &FrankenError) {
-        self.set_error_message_and_code(&err.to_string(), error_to_code(err));
+    /// Record `err` for `sqlite3_errmsg`/`sqlite3_extended_errcode` and
+    /// return the result code the failing API call should report.
+    fn set_error(&self, err: &FrankenError) -> c_int {
+        self.set_error_message_and_code(&err.to_string(), err.extended_error_code())
     }
 
-    fn set_error_message_and_code(&self, message: &str, code: c_int) {
+    fn set_error_message_and_code(&self, message: &str, code: c_int) -> c_int {
         if let Ok(c) = CString::new(message) {
             if let Ok(mut guard) = self.last_error.lock() {
                 *guard = c;
             }
         }
         self.last_error_code.store(code, Ordering::Relaxed);
+        self.result_code(code)
+    }
+
+    /// Mask an extended result code down to the primary code unless
+    /// extended result codes are enabled on this connection.
+    fn result_code(&self, code: c_int) -> c_int {
+        if self.extended_result_codes.load(Ordering::Relaxed) {
+            code
+        } else {
+            code & 0xff
+        }
     }
 
     fn clear_error(&self) {
@@ -169,16 +272,20 @@ impl Sqlite3 {
     }
 
     fn refresh_last_changes(&self) {
-        let changes = self
+        let Ok(row) = self
             .conn
-            .query_row("SELECT changes();")
-            .ok()
-            .and_then(|row| match row.get(0) {
-                Some(SqliteValue::Integer(n)) => Some(i64_to_c_int_saturating(*n)),
-                _ => None,
-            })
-            .unwrap_or(0);
-        self.last_changes.store(changes, Ordering::Relaxed);
+            .query_row("SELECT changes(), last_insert_rowid(), total_changes();")
+        else {
+            self.last_changes.store(0, Ordering::Relaxed);
+            return;
+        };
+        let counter = |idx: usize| match row.get(idx) {
+            Some(SqliteValue::Integer(n)) => *n,
+            _ => 0,
+        };
+        self.last_changes.store(counter(0), Ordering::Relaxed);
+        self.last_insert_rowid.store(counter(1), Ordering::Relaxed);
+        self.total_changes.store(counter(2), Ordering::Relaxed);
     }
 
     fn register_statement(&self) {
@@ -211,18 +318,99 @@ enum PreparedStepMode {
 pub struct Sqlite3Stmt {
     db: *mut Sqlite3,
     sql: String,
+    /// NUL-terminated copy of `sql` for `sqlite3_sql`.
+    sql_cstr: CString,
     step_mode: PreparedStepMode,
+    readonly: bool,
     /// Cached rows from last execution.  `None` means not yet stepped.
     rows: Option<Vec<fsqlite::Row>>,
     /// Current row index (0-based, incremented by each `sqlite3_step`).
     cursor: usize,
+    /// Set once `sqlite3_step` has returned `SQLITE_DONE`.
+    finished: bool,
     /// Column count from the most recent result set.
     column_count: c_int,
+    /// Names and declared origins of the result columns.
+    columns: Vec<ColumnMetadata>,
+    /// Bind parameters: values by index, names for `sqlite3_bind_parameter_name`.
+    params: BindParameters,
+    bindings: Vec<SqliteValue>,
     /// Cached CString values for text column accessors (kept alive until
     /// next step or finalize to satisfy C lifetime expectations).
     text_cache: Vec<Option<CString>>,
 }
 
+/// Metadata for one result column, as reported by `sqlite3_column_name`,
+/// `sqlite3_column_decltype` and the `SQLITE_ENABLE_COLUMN_METADATA`
+/// accessors.
+#[derive(Debug, Clone, Default)]
+struct ColumnMetadata {
+    name: CString,
+    decltype: Option<CString>,
+    database: Option<CString>,
+    table: Option<CString>,
+    origin: Option<CString>,
+}
+
+impl ColumnMetadata {
+    fn unnamed(idx: usize) -> Self {
+        Self {
+            name: CString::new(format!("column{idx}")).expect("no interior NUL"),
+            ..Self::default()
+        }
+    }
+}
+
+/// Bind parameters of a statement, numbered the way SQLite numbers them:
+/// `?NNN` takes slot NNN, while `?` and each distinct `:name`/`@name`/`$name`
+/// take the slot after the largest one used so far.
+#[derive(Debug, Default)]
+struct BindParameters {
+    /// Parameter name by slot (`None` for anonymous `?`).
+    names: Vec<Option<CString>>,
+    /// Byte range of each parameter token in the SQL and its slot.
+    sites: Vec<(std::ops::Range<usize>, usize)>,
+}
+
+impl BindParameters {
+    fn scan(sql: &str) -> Self {
+        let mut params = Self::default();
+        for token in Lexer::tokenize(sql) {
+            let slot = match &token.kind {
+                TokenKind::Question => params.names.len(),
+                TokenKind::QuestionNum(n) => *n as usize - 1,
+                TokenKind::ColonParam(_) | TokenKind::AtParam(_) | TokenKind::DollarParam(_) => {
+                    let name = token.kind.to_sql();
+                    params
+                        .names
+                        .iter()
+                        .position(|existing| {
+                            existing
+                                .as_ref()
+                                .is_some_and(|c| c.as_bytes() == name.as_bytes())
+                        })
+                        .unwrap_or(params.names.len())
+                }
+                _ => continue,
+            };
+            if params.names.len() <= slot {
+                params.names.resize(slot + 1, None);
+            }
+            if !matches!(token.kind, TokenKind::Question) && params.names[slot].is_none() {
+                params.names[slot] = CString::new(token.kind.to_sql()).ok();
+            }
+            params
+                .sites
+                .push((token.span.start as usize..token.span.end as usize, slot));
+        }
+        params
+    }
+
+    fn count(&self) -> usize {
+        self.names.len()
+    }
+}
+
 // ── Helper: convert FrankenError → c_int ────────────────────────────
 
 fn error_to_code(err: &FrankenError) -> c_int {
@@ -239,6 +427,172 @@ fn i64_to_c_int_saturating(value: i64) -> c_int {
     }
 }
 
+/// Render a bound value as an SQL literal for `sqlite3_expanded_sql`.
+fn sql_literal(value: &SqliteValue) -> String {
+    match value {
+        SqliteValue::Null => "NULL".to_owned(),
+        SqliteValue::Integer(n) => n.to_string(),
+        v @ SqliteValue::Float(_) => v.to_text(),
+        SqliteValue::Text(s) => format!("'{}'", s.replace('\'', "''")),
+        SqliteValue::Blob(b) => {
+            let mut hex = String::with_capacity(3 + b.len() * 2);
+            hex.push_str("X'");
+            for byte in b {
+                let _ = write!(hex, "{byte:02X}");
+            }
+            hex.push('\'');
+            hex
+        }
+    }
+}
+
+fn is_readonly_statement(statement: &Statement) -> bool {
+    matches!(
+        statement,
+        Statement::Select(_)
+            | Statement::Explain { .. }
+            | Statement::Begin(_)
+            | Statement::Commit
+            | Statement::Rollback(_)
+            | Statement::Savepoint(_)
+            | Statement::Release(_)
+    )
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A named table in the FROM clause: `(schema, table, alias-or-name)`.
+type FromTable = (String, String, String);
+
+/// Declared `(name, type)` pairs of a `main` table's columns.
+fn declared_columns(conn: &Connection, table: &str) -> Vec<(String, String)> {
+    let sql = format!("PRAGMA table_info({});", quote_identifier(table));
+    conn.query(&sql)
+        .map(|rows| {
+            rows.iter()
+                .filter_map(|row| match (row.get(1), row.get(2)) {
+                    (Some(SqliteValue::Text(name)), Some(SqliteValue::Text(decl))) => {
+                        Some((name.clone(), decl.clone()))
+                    }
+                    (Some(SqliteValue::Text(name)), _) => Some((name.clone(), String::new())),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn column_origin(schema: &str, table: &str, column: &str, decltype: &str) -> ColumnMetadata {
+    ColumnMetadata {
+        name: CString::default(),
+        decltype: CString::new(decltype).ok().filter(|c| !c.is_empty()),
+        database: CString::new(schema).ok(),
+        table: CString::new(table).ok(),
+        origin: CString::new(column).ok(),
+    }
+}
+
+/// Resolve the table column behind each result column of a simple SELECT.
+///
+/// Returns `None` for columns that are expressions, come from subqueries or
+/// compound SELECTs, or whenever the result shape cannot be matched up.
+fn result_column_origins(
+    conn: &Connection,
+    statement: &Statement,
+    column_count: usize,
+) -> Vec<Option<ColumnMetadata>> {
+    let unresolved = vec![None; column_count];
+    let Statement::Select(select) = statement else {
+        return unresolved;
+    };
+    if !select.body.compounds.is_empty() {
+        return unresolved;
+    }
+    let SelectCore::Select { columns, from, .. } = &select.body.select else {
+        return unresolved;
+    };
+
+    let mut tables: Vec<FromTable> = Vec::new();
+    if let Some(from) = from {
+        for source in std::iter::once(&from.source).chain(from.joins.iter().map(|j| &j.table)) {
+            let TableOrSubquery::Table { name, alias, .. } = source else {
+                // Subquery columns have no declared origin; bail out on `*`.
+                if columns
+                    .iter()
+                    .any(|c| !matches!(c, ResultColumn::Expr { .. }))
+                {
+                    return unresolved;
+                }
+                continue;
+            };
+            let schema = name.schema.clone().unwrap_or_else(|| "main".to_owned());
+            if !schema.eq_ignore_ascii_case("main") {
+                return unresolved;
+            }
+            let visible = alias.clone().unwrap_or_else(|| name.name.clone());
+            tables.push((schema, name.name.clone(), visible));
+        }
+    }
+    let declared: Vec<Vec<(String, String)>> = tables
+        .iter()
+        .map(|(_, table, _)| declared_columns(conn, table))
+        .collect();
+
+    let mut origins = Vec::with_capacity(column_count);
+    for column in columns {
+        match column {
+            ResultColumn::Star => {
+                for ((schema, table, _), cols) in tables.iter().zip(&declared) {
+                    origins.extend(
+                        cols.iter()
+                            .map(|(name, decl)| Some(column_origin(schema, table, name, decl))),
+                    );
+                }
+            }
+            ResultColumn::TableStar(qualifier) => {
+                for ((schema, table, visible), cols) in tables.iter().zip(&declared) {
+                    if visible.eq_ignore_ascii_case(qualifier) {
+                        origins
+                            .extend(cols.iter().map(|(name, decl)| {
+                                Some(column_origin(schema, table, name, decl))
+                            }));
+                    }
+                }
+            }
+            ResultColumn::Expr {
+                expr: Expr::Column(column_ref, _),
+                ..
+            } => {
+                let origin =
+                    tables
+                        .iter()
+                        .zip(&declared)
+                        .find_map(|((schema, table, visible), cols)| {
+                            if column_ref
+                                .table
+                                .as_ref()
+                                .is_some_and(|q| !visible.eq_ignore_ascii_case(q))
+                            {
+                                return None;
+                            }
+                            cols.iter()
+                                .find(|(name, _)| name.eq_ignore_ascii_case(&column_ref.column))
+                                .map(|(name, decl)| column_origin(schema, table, name, decl))
+                        });
+                origins.push(origin);
+            }
+            ResultColumn::Expr { .. } => origins.push(None),
+        }
+    }
+    if origins.len() == column_count {
+        origins
+    } else {
+        unresolved
+    }
+}
+
 fn can_prepare_statement(statement: &Statement) -> bool {
     matches!(
         statement,
@@ -259,7 +613,10 @@ struct PreparedSqlInfo {
     consumed_sql: String,
     tail_offset: usize,
     step_mode: PreparedStepMode,
+    readonly: bool,
     column_count: c_int,
+    columns: Vec<ColumnMetadata>,
+    params: BindParameters,
 }
 
 fn validate_and_classify_prepared_sql(
@@ -276,18 +633,35 @@ fn validate_and_classify_prepared_sql(
     };
     let consumed_sql = &sql[..tail_offset];
     let step_mode = prepared_step_mode(&statement);
-    let column_count = if can_prepare_statement(&statement) {
+    let (column_count, columns) = if can_prepare_statement(&statement) {
         let prepared = conn.prepare(consumed_sql)?;
-        c_int::try_from(prepared.column_count()).unwrap_or(c_int::MAX)
+        let count = prepared.column_count();
+        let names = prepared.column_names();
+        let columns = result_column_origins(conn, &statement, count)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, origin)| {
+                let mut column = origin.unwrap_or_default();
+                column.name = names
+                    .get(idx)
+                    .and_then(|name| CString::new(name.as_str()).ok())
+                    .unwrap_or_else(|| ColumnMetadata::unnamed(idx).name);
+                column
+            })
+            .collect();
+        (c_int::try_from(count).unwrap_or(c_int::MAX), columns)
     } else {
-        0
+        (0, Vec::new())
     };
 
     Ok(Some(PreparedSqlInfo {
         consumed_sql: consumed_sql.to_owned(),
         tail_offset,
         step_mode,
+        readonly: is_readonly_statement(&statement),
         column_count,
+        columns,
+        params: BindParameters::scan(consumed_sql),
     }))
 }
 
@@ -317,11 +691,47 @@ pub unsafe extern "C" fn sqlite3_open(filename: *const c_char, pp_db: *mut *mut
     COMPAT_OPEN.fetch_add(1, Ordering::Relaxed);
     let _span = tracing::info_span!("compat_api", api_func = "open").entered();
 
+    open_handle(filename, pp_db, None)
+}
+
+/// Open a new database connection with `SQLITE_OPEN_*` flags.
+///
+/// `z_vfs` is accepted for signature compatibility; the connection always
+/// uses the default VFS.
+///
+/// # Safety
+/// Same as [`sqlite3_open`]; `z_vfs` may be null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_open_v2(
+    filename: *const c_char,
+    pp_db: *mut *mut Sqlite3,
+    flags: c_int,
+    z_vfs: *const c_char,
+) -> c_int {
+    COMPAT_OPEN.fetch_add(1, Ordering::Relaxed);
+    let _span = tracing::info_span!("compat_api", api_func = "open_v2").entered();
+
+    if !z_vfs.is_null() {
+        tracing::warn!(target: "fsqlite.compat", "sqlite3_open_v2: zVfs ignored, using default VFS");
+    }
+    open_handle(
+        filename,
+        pp_db,
+        Some(OpenFlags::from_bits_truncate(flags as u32)),
+    )
+}
+
+unsafe fn open_handle(
+    filename: *const c_char,
+    pp_db: *mut *mut Sqlite3,
+    flags: Option<OpenFlags>,
+) -> c_int {
     if pp_db.is_null() {
         return SQLITE_MISUSE;
     }
 
-    let path = if filename.is_null() {
+    let in_memory = flags.is_some_and(|f| f.contains(OpenFlags::MEMORY));
+    let path = if filename.is_null() || in_memory {
         ":memory:".to_owned()
     } else if let Ok(s) = CStr::from_ptr(filename).to_str() {
         if s.is_empty() {
@@ -336,8 +746,14 @@ pub unsafe extern "C" fn sqlite3_open(filename: *const c_char, pp_db: *mut *mut
 
     tracing::info!(target: "fsqlite.compat", path = %path, "sqlite3_open");
 
-    let open_result =
-        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| Connection::open(&path)));
+    let open_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match flags {
+        Some(flags) => Connection::open_with_flags(
+            &path,
+            flags
+                & (OpenFlags::READONLY | OpenFlags::READWRITE | OpenFlags::CREATE | OpenFlags::URI),
+        ),
+        None => Connection::open(&path),
+    }));
 
     match open_result {
         Ok(Ok(conn)) => {
@@ -394,8 +810,7 @@ pub unsafe extern "C" fn sqlite3_close(db: *mut Sqlite3) -> c_int {
         Ok(Ok(())) => SQLITE_OK,
         Ok(Err(e)) => {
             tracing::warn!(target: "fsqlite.compat", error = %e, "sqlite3_close failed");
-            handle.set_error(&e);
-            let code = error_to_code(&e);
+            let code = handle.set_error(&e);
             let _ = Box::into_raw(handle);
             code
         }
@@ -407,6 +822,26 @@ pub unsafe extern "C" fn sqlite3_close(db: *mut Sqlite3) -> c_int {
     }
 }
 
+/// Close a database connection, deferring the close until the last
+/// outstanding statement is finalized.
+///
+/// # Safety
+/// Same as [`sqlite3_close`]; the handle must not be used for anything but
+/// finalizing its remaining statements after this call.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_close_v2(db: *mut Sqlite3) -> c_int {
+    if db.is_null() {
+        return SQLITE_OK;
+    }
+    let handle = &*db;
+    if handle.active_statement_count() != 0 {
+        tracing::info!(target: "fsqlite.compat", "sqlite3_close_v2 deferred until finalize");
+        handle.zombie.store(true, Ordering::Relaxed);
+        return SQLITE_OK;
+    }
+    sqlite3_close(db)
+}
+
 // ── sqlite3_exec ────────────────────────────────────────────────────
 
 /// Execute one or more SQL statements.
@@ -449,9 +884,9 @@ pub unsafe extern "C" fn sqlite3_exec(
             offset: 0,
             detail: "SQL text is not valid UTF-8".to_owned(),
         };
-        handle.set_error(&err);
+        let code = handle.set_error(&err);
         write_error_message(errmsg, &err.to_string());
-        return error_to_code(&err);
+        return code;
     };
 
     tracing::info!(target: "fsqlite.compat", sql = %sql_str, "sqlite3_exec");
@@ -528,16 +963,16 @@ pub unsafe extern "C" fn sqlite3_exec(
         }
         Ok(Err(e)) => {
             tracing::warn!(target: "fsqlite.compat", error = %e, "sqlite3_exec failed");
-            handle.set_error(&e);
+            let code = handle.set_error(&e);
             write_error_message(errmsg, &e.to_string());
-            error_to_code(&e)
+            code
         }
         Err(_) => {
             let e = FrankenError::Internal("Rust panic during sqlite3_exec".to_owned());
             tracing::error!(target: "fsqlite.compat", error = %e, "sqlite3_exec panicked");
-            handle.set_error(&e);
+            let code = handle.set_error(&e);
             write_error_message(errmsg, &e.to_string());
-            error_to_code(&e)
+            code
         }
     }
 }
@@ -578,14 +1013,20 @@ unsafe fn libc_malloc(size: usize) -> *mut u8 {
     ptr.add(ALLOC_HEADER_SIZE)
 }
 
+/// Usable size of an allocation made by `libc_malloc`.
+unsafe fn allocation_size(ptr: *mut c_void) -> usize {
+    let real_ptr = ptr.cast::<u8>().sub(ALLOC_HEADER_SIZE);
+    let mut size_bytes = [0_u8; ALLOC_HEADER_SIZE];
+    std::ptr::copy_nonoverlapping(real_ptr, size_bytes.as_mut_ptr(), ALLOC_HEADER_SIZE);
+    usize::from_ne_bytes(size_bytes)
+}
+
 unsafe fn libc_free(ptr: *mut c_void) {
     if ptr.is_null() {
         return;
     }
     let real_ptr = ptr.cast::<u8>().sub(ALLOC_HEADER_SIZE);
-    let mut size_bytes = [0_u8; ALLOC_HEADER_SIZE];
-    std::ptr::copy_nonoverlapping(real_ptr, size_bytes.as_mut_ptr(), ALLOC_HEADER_SIZE);
-    let size = usize::from_ne_bytes(size_bytes);
+    let size = allocation_size(ptr);
     if let Ok(layout) =
         std::alloc::Layout::from_size_align(size + ALLOC_HEADER_SIZE, ALLOC_HEADER_ALIGN)
     {
@@ -632,8 +1073,7 @@ pub unsafe extern "C" fn sqlite3_prepare_v2(
                 offset: 0,
                 detail: "SQL text is not valid UTF-8".to_owned(),
             };
-            handle.set_error(&err);
-            return error_to_code(&err);
+            return handle.set_error(&err);
         }
     } else {
         let slice = std::slice::from_raw_parts(sql.cast::<u8>(), n_byte as usize);
@@ -645,8 +1085,7 @@ pub unsafe extern "C" fn sqlite3_prepare_v2(
                 offset: 0,
                 detail: "SQL text is not valid UTF-8".to_owned(),
             };
-            handle.set_error(&err);
-            return error_to_code(&err);
+            return handle.set_error(&err);
         }
     };
 
@@ -666,11 +1105,17 @@ pub unsafe extern "C" fn sqlite3_prepare_v2(
             handle.clear_error();
             let stmt = Box::new(Sqlite3Stmt {
                 db,
+                sql_cstr: CString::new(info.consumed_sql.as_str()).unwrap_or_default(),
                 sql: info.consumed_sql,
                 step_mode: info.step_mode,
+                readonly: info.readonly,
                 rows: None,
                 cursor: 0,
+                finished: false,
                 column_count: info.column_count,
+                columns: info.columns,
+                bindings: vec![SqliteValue::Null; info.params.count()],
+                params: info.params,
                 text_cache: Vec::new(),
             });
             handle.register_statement();
@@ -689,14 +1134,12 @@ pub unsafe extern "C" fn sqlite3_prepare_v2(
         }
         Ok(Err(e)) => {
             tracing::warn!(target: "fsqlite.compat", error = %e, "sqlite3_prepare_v2 failed");
-            handle.set_error(&e);
-            error_to_code(&e)
+            handle.set_error(&e)
         }
         Err(_) => {
             let e = FrankenError::Internal("Rust panic during sqlite3_prepare_v2".to_owned());
             tracing::error!(target: "fsqlite.compat", error = %e, "sqlite3_prepare_v2 panicked");
-            handle.set_error(&e);
-            error_to_code(&e)
+            handle.set_error(&e)
         }
     }
 }
@@ -726,11 +1169,19 @@ pub unsafe extern "C" fn sqlite3_step(stmt: *mut Sqlite3Stmt) -> c_int {
     if s.rows.is_none() {
         tracing::info!(target: "fsqlite.compat", sql = %s.sql, "sqlite3_step (first call)");
 
-        let execute_result =
-            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| match s.step_mode {
-                PreparedStepMode::Query => db.conn.query(&s.sql).map(Some),
-                PreparedStepMode::Execute => db.conn.execute(&s.sql).map(|_| None),
-            }));
+        let execute_result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            match (s.step_mode, s.bindings.is_empty()) {
+                (PreparedStepMode::Query, true) => db.conn.query(&s.sql).map(Some),
+                (PreparedStepMode::Query, false) => {
+                    db.conn.query_with_params(&s.sql, &s.bindings).map(Some)
+                }
+                (PreparedStepMode::Execute, true) => db.conn.execute(&s.sql).map(|_| None),
+                (PreparedStepMode::Execute, false) => db
+                    .conn
+                    .execute_with_params(&s.sql, &s.bindings)
+                    .map(|_| None),
+            }
+        }));
 
         match execute_result {
             Ok(Ok(Some(rows))) => {
@@ -739,6 +1190,9 @@ pub unsafe extern "C" fn sqlite3_step(stmt: *mut Sqlite3Stmt) -> c_int {
                 if s.column_count == 0 {
                     if let Some(first) = rows.first() {
                         s.column_count = first.values().len() as c_int;
+                        s.columns = (0..first.values().len())
+                            .map(ColumnMetadata::unnamed)
+                            .collect();
                     }
                 }
                 s.rows = Some(rows);
@@ -759,14 +1213,12 @@ pub unsafe extern "C" fn sqlite3_step(stmt: *mut Sqlite3Stmt) -> c_int {
             }
             Ok(Err(e)) => {
                 tracing::warn!(target: "fsqlite.compat", error = %e, "sqlite3_step failed");
-                db.set_error(&e);
-                return error_to_code(&e);
+                return db.set_error(&e);
             }
             Err(_) => {
                 let e = FrankenError::Internal("Rust panic during statement execution".to_owned());
                 tracing::error!(target: "fsqlite.compat", error = %e, "sqlite3_step panicked");
-                db.set_error(&e);
-                return error_to_code(&e);
+                return db.set_error(&e);
             }
         }
     }
@@ -785,9 +1237,11 @@ pub unsafe extern "C" fn sqlite3_step(stmt: *mut Sqlite3Stmt) -> c_int {
             s.cursor += 1;
             SQLITE_ROW
         } else {
+            s.finished = true;
             SQLITE_DONE
         }
     } else {
+        s.finished = true;
         SQLITE_DONE
     }
 }
@@ -811,10 +1265,15 @@ pub unsafe extern "C" fn sqlite3_finalize(stmt: *mut Sqlite3Stmt) -> c_int {
     tracing::info!(target: "fsqlite.compat", "sqlite3_finalize");
 
     let stmt = Box::from_raw(stmt);
-    if !stmt.db.is_null() {
-        (&*stmt.db).release_statement();
-    }
+    let db = stmt.db;
     drop(stmt);
+    if !db.is_null() {
+        let handle = &*db;
+        handle.release_statement();
+        if handle.zombie.load(Ordering::Relaxed) && handle.active_statement_count() == 0 {
+            return sqlite3_close(db);
+        }
+    }
     SQLITE_OK
 }
 
@@ -833,6 +1292,7 @@ pub unsafe extern "C" fn sqlite3_reset(stmt: *mut Sqlite3Stmt) -> c_int {
     let s = &mut *stmt;
     s.rows = None;
     s.cursor = 0;
+    s.finished = false;
     s.text_cache.clear();
     SQLITE_OK
 }
@@ -1077,16 +1537,49 @@ pub unsafe extern "C" fn sqlite3_errmsg(db: *mut Sqlite3) -> *const c_char {
 
 /// Get the most recent error code.
 ///
+/// This is the primary result code unless extended result codes were
+/// enabled with `sqlite3_extended_result_codes`.
+///
 /// # Safety
 /// `db` must be a valid handle.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn sqlite3_errcode(db: *mut Sqlite3) -> c_int {
+    if db.is_null() {
+        return SQLITE_OK;
+    }
+    let handle = &*db;
+    handle.result_code(handle.last_error_code.load(Ordering::Relaxed))
+}
+
+/// Get the extended result code of the most recent error, e.g.
+/// `SQLITE_CONSTRAINT_UNIQUE` rather than `SQLITE_CONSTRAINT`.
+///
+/// # Safety
+/// `db` must be a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_extended_errcode(db: *mut Sqlite3) -> c_int {
     if db.is_null() {
         return SQLITE_OK;
     }
     (&*db).last_error_code.load(Ordering::Relaxed)
 }
 
+/// Enable (`onoff != 0`) or disable extended result codes in the values
+/// returned by API calls on this connection.
+///
+/// # Safety
+/// `db` must be a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_extended_result_codes(db: *mut Sqlite3, onoff: c_int) -> c_int {
+    if db.is_null() {
+        return SQLITE_MISUSE;
+    }
+    (&*db)
+        .extended_result_codes
+        .store(onoff != 0, Ordering::Relaxed);
+    SQLITE_OK
+}
+
 // ── sqlite3_changes ─────────────────────────────────────────────────
 
 /// Return the number of rows modified by the most recent INSERT/UPDATE/DELETE.
@@ -1098,89 +1591,1920 @@ pub unsafe extern "C" fn sqlite3_changes(db: *mut Sqlite3) -> c_int {
     if db.is_null() {
         return 0;
     }
-    (&*db).last_changes.load(Ordering::Relaxed)
+    i64_to_c_int_saturating((&*db).last_changes.load(Ordering::Relaxed))
 }
 
-// ── Tests ───────────────────────────────────────────────────────────
+// ── sqlite3_bind_* ──────────────────────────────────────────────────
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::ffi::CString;
-    use std::ptr;
+/// Largest string or BLOB a binding may hold (`SQLITE_MAX_LENGTH`).
+const MAX_BINDING_LEN: u64 = 1_000_000_000;
 
-    /// Helper: open an in-memory database via C API.
-    unsafe fn open_memory() -> *mut Sqlite3 {
-        let mut db: *mut Sqlite3 = ptr::null_mut();
-        let path = CString::new(":memory:").unwrap();
-        let rc = sqlite3_open(path.as_ptr(), &mut db);
-        assert_eq!(rc, SQLITE_OK);
-        assert!(!db.is_null());
-        db
-    }
+/// Store `value` into bind slot `index` (1-based).
+unsafe fn store_binding(stmt: *mut Sqlite3Stmt, index: c_int, value: SqliteValue) -> c_int {
+    COMPAT_BIND.fetch_add(1, Ordering::Relaxed);
 
-    #[test]
-    fn test_open_close_memory() {
-        unsafe {
-            let db = open_memory();
-            let rc = sqlite3_close(db);
-            assert_eq!(rc, SQLITE_OK);
-        }
+    if stmt.is_null() {
+        return SQLITE_MISUSE;
+    }
+    let s = &mut *stmt;
+    let db = &*s.db;
+    if s.rows.is_some() {
+        db.set_error_message_and_code("bad parameter or other API misuse", SQLITE_MISUSE);
+        return SQLITE_MISUSE;
     }
+    let slot = usize::try_from(index)
+        .ok()
+        .and_then(|idx| idx.checked_sub(1))
+        .and_then(|idx| s.bindings.get_mut(idx));
+    let Some(slot) = slot else {
+        db.set_error_message_and_code("column index out of range", SQLITE_RANGE);
+        return SQLITE_RANGE;
+    };
+    *slot = value;
+    SQLITE_OK
+}
 
-    #[test]
-    fn test_open_null_filename() {
-        unsafe {
-            let mut db: *mut Sqlite3 = ptr::null_mut();
-            let rc = sqlite3_open(ptr::null(), &mut db);
-            assert_eq!(rc, SQLITE_OK);
-            assert!(!db.is_null());
-            sqlite3_close(db);
+/// Hand a caller buffer back to its destructor once it is no longer needed.
+unsafe fn release_bound_memory(ptr: *const c_void, destructor: Sqlite3Destructor) {
+    if let Some(destructor) = destructor {
+        if !ptr.is_null() && destructor as usize != SQLITE_TRANSIENT_ADDR {
+            destructor(ptr.cast_mut());
         }
     }
+}
 
-    #[test]
-    fn test_open_empty_filename() {
-        unsafe {
-            let mut db: *mut Sqlite3 = ptr::null_mut();
-            let path = CString::new("").unwrap();
-            let rc = sqlite3_open(path.as_ptr(), &mut db);
-            assert_eq!(rc, SQLITE_OK);
-            assert!(!db.is_null());
-            sqlite3_close(db);
-        }
+/// Copy `n` bytes (or up to the NUL terminator when `n` is negative) out of
+/// a caller buffer, then release the buffer.
+unsafe fn take_bound_bytes(
+    ptr: *const c_void,
+    n: i64,
+    destructor: Sqlite3Destructor,
+) -> Option<Vec<u8>> {
+    if ptr.is_null() {
+        return None;
     }
+    let bytes = if n < 0 {
+        CStr::from_ptr(ptr.cast()).to_bytes().to_vec()
+    } else {
+        std::slice::from_raw_parts(ptr.cast::<u8>(), n as usize).to_vec()
+    };
+    release_bound_memory(ptr, destructor);
+    Some(bytes)
+}
 
-    #[test]
-    fn test_close_null() {
-        unsafe {
-            let rc = sqlite3_close(ptr::null_mut());
-            assert_eq!(rc, SQLITE_OK);
-        }
+unsafe fn bind_bytes(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    ptr: *const c_void,
+    n: i64,
+    destructor: Sqlite3Destructor,
+    as_text: bool,
+) -> c_int {
+    if n > 0 && n as u64 > MAX_BINDING_LEN {
+        release_bound_memory(ptr, destructor);
+        return SQLITE_TOOBIG;
     }
+    let value = match take_bound_bytes(ptr, n, destructor) {
+        None => SqliteValue::Null,
+        Some(bytes) if as_text => SqliteValue::Text(String::from_utf8_lossy(&bytes).into_owned()),
+        Some(bytes) => SqliteValue::Blob(bytes),
+    };
+    store_binding(stmt, index, value)
+}
 
-    #[test]
-    fn test_exec_create_insert_select() {
-        unsafe {
-            unsafe extern "C" fn count_cb(
-                parg: *mut c_void,
-                _ncols: c_int,
-                _values: *mut *mut c_char,
-                _names: *mut *mut c_char,
-            ) -> c_int {
-                let counter = &*(parg.cast::<AtomicU64>());
-                counter.fetch_add(1, Ordering::Relaxed);
-                0
-            }
+/// Bind NULL to parameter `index` (1-based).
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_null(stmt: *mut Sqlite3Stmt, index: c_int) -> c_int {
+    store_binding(stmt, index, SqliteValue::Null)
+}
 
-            let db = open_memory();
+/// Bind a 32-bit integer to parameter `index`.
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_int(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    value: c_int,
+) -> c_int {
+    store_binding(stmt, index, SqliteValue::Integer(i64::from(value)))
+}
 
-            let sql = CString::new("CREATE TABLE t1(id INTEGER PRIMARY KEY, name TEXT);").unwrap();
-            let rc = sqlite3_exec(db, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
-            assert_eq!(rc, SQLITE_OK);
+/// Bind a 64-bit integer to parameter `index`.
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_int64(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    value: i64,
+) -> c_int {
+    store_binding(stmt, index, SqliteValue::Integer(value))
+}
 
-            let sql = CString::new("INSERT INTO t1 VALUES(1, 'alice');").unwrap();
-            let rc = sqlite3_exec(db, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+/// Bind a double to parameter `index`.
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_double(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    value: c_double,
+) -> c_int {
+    store_binding(stmt, index, SqliteValue::Float(value))
+}
+
+/// Bind UTF-8 text to parameter `index`.
+///
+/// The text is copied immediately, so `destructor` (unless it is
+/// `SQLITE_STATIC` or `SQLITE_TRANSIENT`) runs before this call returns.
+///
+/// # Safety
+/// `stmt` must be a valid handle. `text` must be null, NUL-terminated when
+/// `n` is negative, or point to at least `n` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_text(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    text: *const c_char,
+    n: c_int,
+    destructor: Sqlite3Destructor,
+) -> c_int {
+    bind_bytes(stmt, index, text.cast(), i64::from(n), destructor, true)
+}
+
+/// Bind text with a 64-bit length and explicit encoding to parameter `index`.
+///
+/// Only `SQLITE_UTF8` is supported.
+///
+/// # Safety
+/// Same as [`sqlite3_bind_text`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_text64(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    text: *const c_char,
+    n: u64,
+    destructor: Sqlite3Destructor,
+    encoding: u8,
+) -> c_int {
+    if c_int::from(encoding) != SQLITE_UTF8 {
+        tracing::warn!(target: "fsqlite.compat", encoding, "sqlite3_bind_text64: only UTF-8 is supported");
+        release_bound_memory(text.cast(), destructor);
+        return SQLITE_MISUSE;
+    }
+    let n = i64::try_from(n).unwrap_or(i64::MAX);
+    bind_bytes(stmt, index, text.cast(), n, destructor, true)
+}
+
+/// Bind a BLOB to parameter `index`. A null `blob` binds NULL.
+///
+/// # Safety
+/// `stmt` must be a valid handle. `blob` must be null or point to at least
+/// `n` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_blob(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    blob: *const c_void,
+    n: c_int,
+    destructor: Sqlite3Destructor,
+) -> c_int {
+    if n < 0 {
+        release_bound_memory(blob, destructor);
+        return SQLITE_MISUSE;
+    }
+    bind_bytes(stmt, index, blob, i64::from(n), destructor, false)
+}
+
+/// Bind a BLOB with a 64-bit length to parameter `index`.
+///
+/// # Safety
+/// Same as [`sqlite3_bind_blob`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_blob64(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    blob: *const c_void,
+    n: u64,
+    destructor: Sqlite3Destructor,
+) -> c_int {
+    let n = i64::try_from(n).unwrap_or(i64::MAX);
+    bind_bytes(stmt, index, blob, n, destructor, false)
+}
+
+/// Bind a BLOB of `n` zero bytes to parameter `index`.
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_zeroblob(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    n: c_int,
+) -> c_int {
+    sqlite3_bind_zeroblob64(stmt, index, u64::try_from(n).unwrap_or(0))
+}
+
+/// Bind a BLOB of `n` zero bytes (64-bit length) to parameter `index`.
+///
+/// # Safety
+/// `stmt` must be a valid handle from `sqlite3_prepare_v2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_zeroblob64(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+    n: u64,
+) -> c_int {
+    if n > MAX_BINDING_LEN {
+        return SQLITE_TOOBIG;
+    }
+    store_binding(stmt, index, SqliteValue::Blob(vec![0; n as usize]))
+}
+
+/// Number of the largest bind parameter in the statement.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_parameter_count(stmt: *mut Sqlite3Stmt) -> c_int {
+    if stmt.is_null() {
+        return 0;
+    }
+    c_int::try_from((*stmt).params.count()).unwrap_or(c_int::MAX)
+}
+
+/// Index of the parameter named `name` (including its `:`/`@`/`$`/`?`
+/// prefix), or 0 when there is no such parameter.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null); `name` must be a valid C string
+/// (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_parameter_index(
+    stmt: *mut Sqlite3Stmt,
+    name: *const c_char,
+) -> c_int {
+    if stmt.is_null() || name.is_null() {
+        return 0;
+    }
+    let wanted = CStr::from_ptr(name);
+    (*stmt)
+        .params
+        .names
+        .iter()
+        .position(|n| n.as_deref() == Some(wanted))
+        .map_or(0, |idx| c_int::try_from(idx + 1).unwrap_or(0))
+}
+
+/// Name of parameter `index` (1-based), or null for anonymous `?`
+/// parameters and out-of-range indexes.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_bind_parameter_name(
+    stmt: *mut Sqlite3Stmt,
+    index: c_int,
+) -> *const c_char {
+    if stmt.is_null() {
+        return std::ptr::null();
+    }
+    let s = &*stmt;
+    usize::try_from(index)
+        .ok()
+        .and_then(|idx| idx.checked_sub(1))
+        .and_then(|idx| s.params.names.get(idx))
+        .and_then(Option::as_ref)
+        .map_or(std::ptr::null(), |name| name.as_ptr())
+}
+
+/// Reset every bind parameter of the statement to NULL.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_clear_bindings(stmt: *mut Sqlite3Stmt) -> c_int {
+    if stmt.is_null() {
+        return SQLITE_OK;
+    }
+    for value in &mut (*stmt).bindings {
+        *value = SqliteValue::Null;
+    }
+    SQLITE_OK
+}
+
+// ── Column metadata ─────────────────────────────────────────────────
+
+unsafe fn column_metadata<'a>(
+    stmt: *const Sqlite3Stmt,
+    i_col: c_int,
+) -> Option<&'a ColumnMetadata> {
+    if stmt.is_null() {
+        return None;
+    }
+    let s = &*stmt;
+    s.columns.get(usize::try_from(i_col).ok()?)
+}
+
+/// Name of result column `i_col` (its alias, column name or expression text).
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_column_name(
+    stmt: *mut Sqlite3Stmt,
+    i_col: c_int,
+) -> *const c_char {
+    COMPAT_COLUMN.fetch_add(1, Ordering::Relaxed);
+
+    column_metadata(stmt, i_col).map_or(std::ptr::null(), |c| c.name.as_ptr())
+}
+
+/// Declared type of the table column behind result column `i_col`, or null
+/// for expressions and untyped columns.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_column_decltype(
+    stmt: *mut Sqlite3Stmt,
+    i_col: c_int,
+) -> *const c_char {
+    COMPAT_COLUMN.fetch_add(1, Ordering::Relaxed);
+
+    column_metadata(stmt, i_col)
+        .and_then(|c| c.decltype.as_ref())
+        .map_or(std::ptr::null(), |d| d.as_ptr())
+}
+
+/// Schema name of the table behind result column `i_col`.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_column_database_name(
+    stmt: *mut Sqlite3Stmt,
+    i_col: c_int,
+) -> *const c_char {
+    column_metadata(stmt, i_col)
+        .and_then(|c| c.database.as_ref())
+        .map_or(std::ptr::null(), |d| d.as_ptr())
+}
+
+/// Name of the table behind result column `i_col`.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_column_table_name(
+    stmt: *mut Sqlite3Stmt,
+    i_col: c_int,
+) -> *const c_char {
+    column_metadata(stmt, i_col)
+        .and_then(|c| c.table.as_ref())
+        .map_or(std::ptr::null(), |t| t.as_ptr())
+}
+
+/// Name of the table column behind result column `i_col`.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_column_origin_name(
+    stmt: *mut Sqlite3Stmt,
+    i_col: c_int,
+) -> *const c_char {
+    column_metadata(stmt, i_col)
+        .and_then(|c| c.origin.as_ref())
+        .map_or(std::ptr::null(), |o| o.as_ptr())
+}
+
+/// Number of columns in the current row (0 when no row is available).
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_data_count(stmt: *mut Sqlite3Stmt) -> c_int {
+    if stmt.is_null() {
+        return 0;
+    }
+    let s = &*stmt;
+    s.rows
+        .as_ref()
+        .zip(s.cursor.checked_sub(1))
+        .and_then(|(rows, idx)| rows.get(idx))
+        .map_or(0, |row| {
+            c_int::try_from(row.values().len()).unwrap_or(c_int::MAX)
+        })
+}
+
+// ── Statement introspection ─────────────────────────────────────────
+
+/// Compile an SQL statement (legacy interface, same as `sqlite3_prepare_v2`).
+///
+/// # Safety
+/// Same as [`sqlite3_prepare_v2`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_prepare(
+    db: *mut Sqlite3,
+    sql: *const c_char,
+    n_byte: c_int,
+    pp_stmt: *mut *mut Sqlite3Stmt,
+    pz_tail: *mut *const c_char,
+) -> c_int {
+    sqlite3_prepare_v2(db, sql, n_byte, pp_stmt, pz_tail)
+}
+
+/// Compile an SQL statement with `SQLITE_PREPARE_*` flags, which are
+/// accepted and ignored.
+///
+/// # Safety
+/// Same as [`sqlite3_prepare_v2`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_prepare_v3(
+    db: *mut Sqlite3,
+    sql: *const c_char,
+    n_byte: c_int,
+    _prep_flags: u32,
+    pp_stmt: *mut *mut Sqlite3Stmt,
+    pz_tail: *mut *const c_char,
+) -> c_int {
+    sqlite3_prepare_v2(db, sql, n_byte, pp_stmt, pz_tail)
+}
+
+/// SQL text the statement was prepared from.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_sql(stmt: *mut Sqlite3Stmt) -> *const c_char {
+    if stmt.is_null() {
+        return std::ptr::null();
+    }
+    (*stmt).sql_cstr.as_ptr()
+}
+
+/// SQL text with the bound parameter values substituted as literals.
+///
+/// The result is allocated with `sqlite3_malloc` and must be released with
+/// `sqlite3_free`.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_expanded_sql(stmt: *mut Sqlite3Stmt) -> *mut c_char {
+    if stmt.is_null() {
+        return std::ptr::null_mut();
+    }
+    let s = &*stmt;
+    let mut expanded = String::with_capacity(s.sql.len());
+    let mut copied = 0;
+    for (range, slot) in &s.params.sites {
+        expanded.push_str(&s.sql[copied..range.start]);
+        let value = s.bindings.get(*slot).unwrap_or(&SqliteValue::Null);
+        expanded.push_str(&sql_literal(value));
+        copied = range.end;
+    }
+    expanded.push_str(&s.sql[copied..]);
+    malloc_c_string(&expanded)
+}
+
+/// Whether the statement leaves the database file unchanged.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_stmt_readonly(stmt: *mut Sqlite3Stmt) -> c_int {
+    if stmt.is_null() {
+        return 1;
+    }
+    c_int::from((*stmt).readonly)
+}
+
+/// Whether the statement has been stepped but not yet run to completion
+/// or reset.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_stmt_busy(stmt: *mut Sqlite3Stmt) -> c_int {
+    if stmt.is_null() {
+        return 0;
+    }
+    let s = &*stmt;
+    c_int::from(s.rows.is_some() && !s.finished)
+}
+
+/// Connection a statement belongs to.
+///
+/// # Safety
+/// `stmt` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_db_handle(stmt: *mut Sqlite3Stmt) -> *mut Sqlite3 {
+    if stmt.is_null() {
+        return std::ptr::null_mut();
+    }
+    (*stmt).db
+}
+
+// ── Connection state ────────────────────────────────────────────────
+
+/// Rowid of the most recent successful INSERT on this connection.
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_last_insert_rowid(db: *mut Sqlite3) -> i64 {
+    if db.is_null() {
+        return 0;
+    }
+    (&*db).last_insert_rowid.load(Ordering::Relaxed)
+}
+
+/// 64-bit variant of [`sqlite3_changes`].
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_changes64(db: *mut Sqlite3) -> i64 {
+    if db.is_null() {
+        return 0;
+    }
+    (&*db).last_changes.load(Ordering::Relaxed)
+}
+
+/// Total rows modified by INSERT/UPDATE/DELETE since the connection opened.
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_total_changes(db: *mut Sqlite3) -> c_int {
+    i64_to_c_int_saturating(sqlite3_total_changes64(db))
+}
+
+/// 64-bit variant of [`sqlite3_total_changes`].
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_total_changes64(db: *mut Sqlite3) -> i64 {
+    if db.is_null() {
+        return 0;
+    }
+    (&*db).total_changes.load(Ordering::Relaxed)
+}
+
+/// Set the busy timeout in milliseconds (`PRAGMA busy_timeout`). A value of
+/// zero or less disables waiting.
+///
+/// # Safety
+/// `db` must be a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_busy_timeout(db: *mut Sqlite3, ms: c_int) -> c_int {
+    if db.is_null() {
+        return SQLITE_MISUSE;
+    }
+    let handle = &*db;
+    let sql = format!("PRAGMA busy_timeout={};", ms.max(0));
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handle.conn.execute(&sql))) {
+        Ok(Ok(_)) => SQLITE_OK,
+        Ok(Err(e)) => handle.set_error(&e),
+        Err(_) => SQLITE_ERROR,
+    }
+}
+
+/// Whether the connection is in autocommit mode (no explicit transaction).
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_get_autocommit(db: *mut Sqlite3) -> c_int {
+    if db.is_null() {
+        return 1;
+    }
+    c_int::from(!(&*db).conn.in_transaction())
+}
+
+/// Interrupt statements running on the connection. Safe to call from any
+/// thread while the connection is in use.
+///
+/// # Safety
+/// `db` must be a valid handle (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_interrupt(db: *mut Sqlite3) {
+    if db.is_null() {
+        return;
+    }
+    (&*db).interrupt.interrupt();
+}
+
+/// Filename of database `z_db_name` (`"main"` or null), or null for other
+/// schemas. In-memory databases report an empty string.
+///
+/// # Safety
+/// `db` must be a valid handle (or null); `z_db_name` must be a valid C
+/// string (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_db_filename(
+    db: *mut Sqlite3,
+    z_db_name: *const c_char,
+) -> *const c_char {
+    if db.is_null() || !is_main_schema(z_db_name) {
+        return std::ptr::null();
+    }
+    (&*db).filename.as_ptr()
+}
+
+/// 1 if database `z_db_name` is read-only, 0 if writable, -1 if it is not
+/// `"main"`.
+///
+/// # Safety
+/// `db` must be a valid handle (or null); `z_db_name` must be a valid C
+/// string (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_db_readonly(db: *mut Sqlite3, z_db_name: *const c_char) -> c_int {
+    if db.is_null() || !is_main_schema(z_db_name) {
+        return -1;
+    }
+    c_int::from((&*db).conn.is_read_only())
+}
+
+unsafe fn is_main_schema(z_db_name: *const c_char) -> bool {
+    z_db_name.is_null()
+        || CStr::from_ptr(z_db_name)
+            .to_bytes()
+            .eq_ignore_ascii_case(b"main")
+}
+
+/// English description of a result code.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_errstr(code: c_int) -> *const c_char {
+    let message: &'static CStr = match code & 0xff {
+        0 => c"not an error",
+        1 => c"SQL logic error",
+        2 => c"internal logic error",
+        3 => c"access permission denied",
+        4 => c"query aborted",
+        5 => c"database is locked",
+        6 => c"database table is locked",
+        7 => c"out of memory",
+        8 => c"attempt to write a readonly database",
+        9 => c"interrupted",
+        10 => c"disk I/O error",
+        11 => c"database disk image is malformed",
+        12 => c"unknown operation",
+        13 => c"database or disk is full",
+        14 => c"unable to open database file",
+        15 => c"locking protocol",
+        17 => c"database schema has changed",
+        18 => c"string or blob too big",
+        19 => c"constraint failed",
+        20 => c"datatype mismatch",
+        21 => c"bad parameter or other API misuse",
+        22 => c"large file support is disabled",
+        23 => c"authorization denied",
+        25 => c"column index out of range",
+        26 => c"file is not a database",
+        27 => c"notification message",
+        28 => c"warning message",
+        100 => c"another row available",
+        101 => c"no more rows available",
+        _ => c"unknown error",
+    };
+    message.as_ptr()
+}
+
+// ── User-defined functions and collations ───────────────────────────
+
+/// Callback type of `xFunc`, `xStep` and `xInverse`.
+pub type Sqlite3FunctionCallback =
+    Option<unsafe extern "C" fn(*mut Sqlite3Context, c_int, *mut *mut Sqlite3Value)>;
+
+/// Callback type of `xFinal` and `xValue`.
+pub type Sqlite3FinalCallback = Option<unsafe extern "C" fn(*mut Sqlite3Context)>;
+
+/// Callback type of the `xCompare` argument of `sqlite3_create_collation_v2`.
+pub type Sqlite3CollationCallback =
+    Option<unsafe extern "C" fn(*mut c_void, c_int, *const c_void, c_int, *const c_void) -> c_int>;
+
+/// Largest `nArg` accepted by `sqlite3_create_function_v2`
+/// (`SQLITE_MAX_FUNCTION_ARG`).
+const MAX_FUNCTION_ARG: c_int = 127;
+
+/// Opaque `sqlite3_context` handed to user function callbacks.
+///
+/// Collects the result set through `sqlite3_result_*` and exposes the
+/// function's user data and per-group aggregate memory.
+pub struct Sqlite3Context {
+    function: *const CFunction,
+    aggregate: *const AggregateState,
+    result: SqliteValue,
+    error: Option<(c_int, String)>,
+}
+
+impl Sqlite3Context {
+    fn new(function: &CFunction, aggregate: Option<&AggregateState>) -> Self {
+        Self {
+            function,
+            aggregate: aggregate.map_or(std::ptr::null(), std::ptr::from_ref),
+            result: SqliteValue::Null,
+            error: None,
+        }
+    }
+
+    fn into_result(self) -> fsqlite_error::Result<SqliteValue> {
+        match self.error {
+            None => Ok(self.result),
+            Some((code, message)) => Err(callback_error(code, message)),
+        }
+    }
+}
+
+/// Map a code reported through `sqlite3_result_error_code` onto the
+/// matching `FrankenError`; anything else surfaces as a function error.
+fn callback_error(code: c_int, message: String) -> FrankenError {
+    match code & 0xff {
+        SQLITE_NOMEM => FrankenError::OutOfMemory,
+        SQLITE_TOOBIG => FrankenError::TooBig,
+        SQLITE_ABORT => FrankenError::Abort,
+        SQLITE_INTERRUPT => FrankenError::Interrupt,
+        SQLITE_BUSY => FrankenError::Busy,
+        SQLITE_READONLY => FrankenError::ReadOnly,
+        SQLITE_AUTH => FrankenError::AuthDenied,
+        _ => FrankenError::FunctionError(message),
+    }
+}
+
+/// Opaque `sqlite3_value`: a function argument, or a copy made by
+/// `sqlite3_value_dup`.
+pub struct Sqlite3Value {
+    value: SqliteValue,
+    /// Text form with a trailing NUL, built by the first
+    /// `sqlite3_value_text`. Embedded NULs are kept, so the length before the
+    /// terminator always matches `sqlite3_value_bytes`.
+    text: OnceCell<Option<Vec<u8>>>,
+}
+
+impl Sqlite3Value {
+    fn new(value: SqliteValue) -> Self {
+        Self {
+            value,
+            text: OnceCell::new(),
+        }
+    }
+
+    fn text(&self) -> Option<&[u8]> {
+        self.text
+            .get_or_init(|| {
+                let mut bytes = match &self.value {
+                    SqliteValue::Null => return None,
+                    SqliteValue::Text(s) => s.clone().into_bytes(),
+                    SqliteValue::Blob(b) => b.clone(),
+                    v => v.to_text().into_bytes(),
+                };
+                bytes.push(0);
+                Some(bytes)
+            })
+            .as_deref()
+    }
+}
+
+/// Per-group memory behind `sqlite3_aggregate_context`: zeroed, allocated
+/// on the first request and released after the final callback.
+#[derive(Default)]
+struct AggregateState(RefCell<Vec<u64>>);
+
+impl AggregateState {
+    fn memory(&self, n_bytes: c_int) -> *mut c_void {
+        let mut words = self.0.borrow_mut();
+        if words.is_empty() {
+            match usize::try_from(n_bytes) {
+                Ok(n) if n > 0 => *words = vec![0; n.div_ceil(8)],
+                _ => return std::ptr::null_mut(),
+            }
+        }
+        words.as_mut_ptr().cast()
+    }
+}
+
+/// The callbacks and user data of one `sqlite3_create_function_v2` or
+/// `sqlite3_create_window_function` registration.
+///
+/// Shared by the scalar, aggregate and window adapters registered on the
+/// connection; `x_destroy` runs once the last of them is dropped, i.e. when
+/// the function is replaced, deleted, or the connection closes.
+struct CFunction {
+    name: String,
+    num_args: i32,
+    deterministic: bool,
+    db: *mut Sqlite3,
+    user_data: *mut c_void,
+    x_func: Sqlite3FunctionCallback,
+    x_step: Sqlite3FunctionCallback,
+    x_final: Sqlite3FinalCallback,
+    x_value: Sqlite3FinalCallback,
+    x_inverse: Sqlite3FunctionCallback,
+    x_destroy: Sqlite3Destructor,
+}
+
+// The function registry requires `Send + Sync`. Connection handles are not
+// shared across threads (`sqlite3_threadsafe` reports 0), so the callbacks
+// only ever run on the thread driving `db`.
+unsafe impl Send for CFunction {}
+unsafe impl Sync for CFunction {}
+
+impl Drop for CFunction {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.x_destroy {
+            unsafe { destroy(self.user_data) };
+        }
+    }
+}
+
+impl CFunction {
+    /// Run `xFunc`/`xStep`/`xInverse` and collect what it reported through
+    /// the context.
+    fn call(
+        &self,
+        callback: Sqlite3FunctionCallback,
+        aggregate: Option<&AggregateState>,
+        args: &[SqliteValue],
+    ) -> fsqlite_error::Result<SqliteValue> {
+        let Some(callback) = callback else {
+            return Ok(SqliteValue::Null);
+        };
+        let mut values: Vec<Sqlite3Value> = args.iter().cloned().map(Sqlite3Value::new).collect();
+        let mut arg_ptrs: Vec<*mut Sqlite3Value> =
+            values.iter_mut().map(std::ptr::from_mut).collect();
+        let mut ctx = Sqlite3Context::new(self, aggregate);
+        unsafe { callback(&mut ctx, arg_ptrs.len() as c_int, arg_ptrs.as_mut_ptr()) };
+        ctx.into_result()
+    }
+
+    /// Run `xFinal`/`xValue` against the group's aggregate memory.
+    fn call_final(
+        &self,
+        callback: Sqlite3FinalCallback,
+        aggregate: &AggregateState,
+    ) -> fsqlite_error::Result<SqliteValue> {
+        let Some(callback) = callback else {
+            return Ok(SqliteValue::Null);
+        };
+        let mut ctx = Sqlite3Context::new(self, Some(aggregate));
+        unsafe { callback(&mut ctx) };
+        ctx.into_result()
+    }
+}
+
+struct CScalarFunction(Arc<CFunction>);
+
+impl ScalarFunction for CScalarFunction {
+    fn invoke(&self, args: &[SqliteValue]) -> fsqlite_error::Result<SqliteValue> {
+        self.0.call(self.0.x_func, None, args)
+    }
+
+    fn is_deterministic(&self) -> bool {
+        self.0.deterministic
+    }
+
+    fn num_args(&self) -> i32 {
+        self.0.num_args
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+}
+
+struct CAggregateFunction(Arc<CFunction>);
+
+impl AggregateFunction for CAggregateFunction {
+    type State = AggregateState;
+
+    fn initial_state(&self) -> AggregateState {
+        AggregateState::default()
+    }
+
+    fn step(&self, state: &mut AggregateState, args: &[SqliteValue]) -> fsqlite_error::Result<()> {
+        self.0.call(self.0.x_step, Some(state), args).map(drop)
+    }
+
+    fn finalize(&self, state: AggregateState) -> fsqlite_error::Result<SqliteValue> {
+        self.0.call_final(self.0.x_final, &state)
+    }
+
+    fn num_args(&self) -> i32 {
+        self.0.num_args
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+}
+
+struct CWindowFunction(Arc<CFunction>);
+
+impl WindowFunction for CWindowFunction {
+    type State = AggregateState;
+
+    fn initial_state(&self) -> AggregateState {
+        AggregateState::default()
+    }
+
+    fn step(&self, state: &mut AggregateState, args: &[SqliteValue]) -> fsqlite_error::Result<()> {
+        self.0.call(self.0.x_step, Some(state), args).map(drop)
+    }
+
+    fn inverse(
+        &self,
+        state: &mut AggregateState,
+        args: &[SqliteValue],
+    ) -> fsqlite_error::Result<()> {
+        self.0.call(self.0.x_inverse, Some(state), args).map(drop)
+    }
+
+    fn value(&self, state: &AggregateState) -> fsqlite_error::Result<SqliteValue> {
+        self.0.call_final(self.0.x_value, state)
+    }
+
+    fn finalize(&self, state: AggregateState) -> fsqlite_error::Result<SqliteValue> {
+        self.0.call_final(self.0.x_final, &state)
+    }
+
+    fn num_args(&self) -> i32 {
+        self.0.num_args
+    }
+
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+}
+
+/// Copy a C string argument, or `None` when it is null or not UTF-8.
+unsafe fn c_str_arg(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok().map(str::to_owned)
+}
+
+/// Validate and apply one function registration.
+///
+/// Null callbacks across the board delete the function. Every previous
+/// scalar, aggregate or window definition of `(name, nArg)` is replaced.
+/// `function` is dropped (running its destructor) when registration fails,
+/// matching C SQLite.
+unsafe fn register_c_function(db: *mut Sqlite3, function: CFunction) -> c_int {
+    COMPAT_CREATE_FUNCTION.fetch_add(1, Ordering::Relaxed);
+
+    if db.is_null() {
+        return SQLITE_MISUSE;
+    }
+    let handle = &*db;
+    let scalar = function.x_func.is_some();
+    let aggregate = function.x_step.is_some() && function.x_final.is_some();
+    let window_callbacks = [function.x_value.is_some(), function.x_inverse.is_some()];
+    let window = window_callbacks == [true, true];
+    let deleting = !scalar
+        && function.x_step.is_none()
+        && function.x_final.is_none()
+        && window_callbacks == [false, false];
+    let well_formed = if scalar {
+        function.x_step.is_none()
+            && function.x_final.is_none()
+            && window_callbacks == [false, false]
+    } else {
+        deleting || (aggregate && (window || window_callbacks == [false, false]))
+    };
+    if !well_formed
+        || function.name.is_empty()
+        || function.name.len() > 255
+        || !(-1..=MAX_FUNCTION_ARG).contains(&function.num_args)
+    {
+        return handle
+            .set_error_message_and_code("bad parameter or other API misuse", SQLITE_MISUSE);
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        handle
+            .conn
+            .unregister_function(&function.name, function.num_args);
+        if deleting {
+            return;
+        }
+        tracing::info!(
+            target: "fsqlite.compat",
+            func_name = %function.name,
+            num_args = function.num_args,
+            window,
+            "registering C function"
+        );
+        let function = Arc::new(function);
+        if scalar {
+            handle
+                .conn
+                .register_scalar_function(CScalarFunction(function));
+        } else {
+            handle
+                .conn
+                .register_aggregate_function(CAggregateFunction(Arc::clone(&function)));
+            if window {
+                handle
+                    .conn
+                    .register_window_function(CWindowFunction(function));
+            }
+        }
+    }));
+    if result.is_err() {
+        let e = FrankenError::Internal("Rust panic during function registration".to_owned());
+        tracing::error!(target: "fsqlite.compat", error = %e, "sqlite3_create_function panicked");
+        return handle.set_error(&e);
+    }
+    handle.clear_error();
+    SQLITE_OK
+}
+
+/// Register, replace or delete a scalar (`x_func`) or aggregate
+/// (`x_step` + `x_final`) SQL function.
+///
+/// `e_text_rep` may carry `SQLITE_DETERMINISTIC`; arguments and results are
+/// always exchanged as UTF-8. `x_destroy(p_app)` runs when the function is
+/// replaced, deleted or the connection closes, or immediately if the call
+/// fails.
+///
+/// # Safety
+/// `db` must be a valid handle and `z_function_name` a NUL-terminated string.
+/// The callbacks must be safe to call with `p_app` for as long as the
+/// function stays registered.
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn sqlite3_create_function_v2(
+    db: *mut Sqlite3,
+    z_function_name: *const c_char,
+    n_arg: c_int,
+    e_text_rep: c_int,
+    p_app: *mut c_void,
+    x_func: Sqlite3FunctionCallback,
+    x_step: Sqlite3FunctionCallback,
+    x_final: Sqlite3FinalCallback,
+    x_destroy: Sqlite3Destructor,
+) -> c_int {
+    let _span = tracing::info_span!("compat_api", api_func = "create_function_v2").entered();
+
+    register_c_function(
+        db,
+        CFunction {
+            name: c_str_arg(z_function_name).unwrap_or_default(),
+            num_args: n_arg,
+            deterministic: e_text_rep & SQLITE_DETERMINISTIC != 0,
+            db,
+            user_data: p_app,
+            x_func,
+            x_step,
+            x_final,
+            x_value: None,
+            x_inverse: None,
+            x_destroy,
+        },
+    )
+}
+
+/// `sqlite3_create_function_v2` without a destructor.
+///
+/// # Safety
+/// Same as [`sqlite3_create_function_v2`].
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn sqlite3_create_function(
+    db: *mut Sqlite3,
+    z_function_name: *const c_char,
+    n_arg: c_int,
+    e_text_rep: c_int,
+    p_app: *mut c_void,
+    x_func: Sqlite3FunctionCallback,
+    x_step: Sqlite3FunctionCallback,
+    x_final: Sqlite3FinalCallback,
+) -> c_int {
+    sqlite3_create_function_v2(
+        db,
+        z_function_name,
+        n_arg,
+        e_text_rep,
+        p_app,
+        x_func,
+        x_step,
+        x_final,
+        None,
+    )
+}
+
+/// Register, replace or delete an aggregate window function.
+///
+/// `x_step`, `x_final`, `x_value` and `x_inverse` must all be set, or all
+/// null to delete the function. The function is also usable as a plain
+/// aggregate without an `OVER` clause.
+///
+/// # Safety
+/// Same as [`sqlite3_create_function_v2`].
+#[unsafe(no_mangle)]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn sqlite3_create_window_function(
+    db: *mut Sqlite3,
+    z_function_name: *const c_char,
+    n_arg: c_int,
+    e_text_rep: c_int,
+    p_app: *mut c_void,
+    x_step: Sqlite3FunctionCallback,
+    x_final: Sqlite3FinalCallback,
+    x_value: Sqlite3FinalCallback,
+    x_inverse: Sqlite3FunctionCallback,
+    x_destroy: Sqlite3Destructor,
+) -> c_int {
+    let _span = tracing::info_span!("compat_api", api_func = "create_window_function").entered();
+
+    let function = CFunction {
+        name: c_str_arg(z_function_name).unwrap_or_default(),
+        num_args: n_arg,
+        deterministic: e_text_rep & SQLITE_DETERMINISTIC != 0,
+        db,
+        user_data: p_app,
+        x_func: None,
+        x_step,
+        x_final,
+        x_value,
+        x_inverse,
+        x_destroy,
+    };
+    let callbacks = [
+        x_step.is_some(),
+        x_final.is_some(),
+        x_value.is_some(),
+        x_inverse.is_some(),
+    ];
+    if callbacks.contains(&true) && callbacks.contains(&false) {
+        // Without xValue/xInverse this would register as a plain aggregate.
+        drop(function);
+        return SQLITE_MISUSE;
+    }
+    register_c_function(db, function)
+}
+
+/// Register, replace or delete (`x_compare` null) a collation sequence.
+///
+/// `x_compare(p_arg, n1, s1, n2, s2)` receives two UTF-8 strings that are
+/// not NUL-terminated and returns a negative, zero or positive number.
+/// `x_destroy(p_arg)` runs when the collation is replaced, deleted or the
+/// connection closes.
+///
+/// # Safety
+/// `db` must be a valid handle and `z_name` a NUL-terminated string.
+/// `x_compare` must be safe to call with `p_arg` while the collation stays
+/// registered.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_create_collation_v2(
+    db: *mut Sqlite3,
+    z_name: *const c_char,
+    e_text_rep: c_int,
+    p_arg: *mut c_void,
+    x_compare: Sqlite3CollationCallback,
+    x_destroy: Sqlite3Destructor,
+) -> c_int {
+    let _span = tracing::info_span!("compat_api", api_func = "create_collation_v2").entered();
+    COMPAT_CREATE_FUNCTION.fetch_add(1, Ordering::Relaxed);
+
+    if db.is_null() {
+        return SQLITE_MISUSE;
+    }
+    let handle = &*db;
+    let Some(name) = c_str_arg(z_name).filter(|name| !name.is_empty()) else {
+        return handle
+            .set_error_message_and_code("bad parameter or other API misuse", SQLITE_MISUSE);
+    };
+    if !matches!(e_text_rep & 0x0f, SQLITE_UTF8 | SQLITE_ANY) {
+        tracing::warn!(
+            target: "fsqlite.compat",
+            e_text_rep,
+            "sqlite3_create_collation_v2: strings are always passed as UTF-8"
+        );
+    }
+
+    // Built only once the arguments are known good: a rejected call must not
+    // run `x_destroy`, as SQLite leaves `p_arg` with the caller.
+    let collation = CCollation {
+        user_data: p_arg,
+        x_compare,
+        x_destroy,
+    };
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        if collation.x_compare.is_none() {
+            handle.conn.unregister_collation(&name);
+            return;
+        }
+        let collation = Arc::new(collation);
+        handle
+            .conn
+            .register_collation(&name, move |a, b| collation.compare(a, b));
+    }));
+    if result.is_err() {
+        let e = FrankenError::Internal("Rust panic during collation registration".to_owned());
+        tracing::error!(target: "fsqlite.compat", error = %e, "sqlite3_create_collation_v2 panicked");
+        return handle.set_error(&e);
+    }
+    handle.clear_error();
+    SQLITE_OK
+}
+
+/// `sqlite3_create_collation_v2` without a destructor.
+///
+/// # Safety
+/// Same as [`sqlite3_create_collation_v2`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_create_collation(
+    db: *mut Sqlite3,
+    z_name: *const c_char,
+    e_text_rep: c_int,
+    p_arg: *mut c_void,
+    x_compare: Sqlite3CollationCallback,
+) -> c_int {
+    sqlite3_create_collation_v2(db, z_name, e_text_rep, p_arg, x_compare, None)
+}
+
+/// The comparator and user data of one `sqlite3_create_collation_v2` call.
+struct CCollation {
+    user_data: *mut c_void,
+    x_compare: Sqlite3CollationCallback,
+    x_destroy: Sqlite3Destructor,
+}
+
+// See `CFunction`: collations run on the thread driving the connection.
+unsafe impl Send for CCollation {}
+unsafe impl Sync for CCollation {}
+
+impl Drop for CCollation {
+    fn drop(&mut self) {
+        if let Some(destroy) = self.x_destroy {
+            unsafe { destroy(self.user_data) };
+        }
+    }
+}
+
+impl CCollation {
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let Some(compare) = self.x_compare else {
+            return a.cmp(b);
+        };
+        let len = |s: &str| c_int::try_from(s.len()).unwrap_or(c_int::MAX);
+        let rc = unsafe {
+            compare(
+                self.user_data,
+                len(a),
+                a.as_ptr().cast(),
+                len(b),
+                b.as_ptr().cast(),
+            )
+        };
+        rc.cmp(&0)
+    }
+}
+
+// ── sqlite3_context accessors ───────────────────────────────────────
+
+/// The `p_app` pointer the running function was registered with.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_user_data(ctx: *mut Sqlite3Context) -> *mut c_void {
+    if ctx.is_null() {
+        return std::ptr::null_mut();
+    }
+    (*(*ctx).function).user_data
+}
+
+/// The connection the running function was registered on.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_context_db_handle(ctx: *mut Sqlite3Context) -> *mut Sqlite3 {
+    if ctx.is_null() {
+        return std::ptr::null_mut();
+    }
+    (*(*ctx).function).db
+}
+
+/// Per-group memory of an aggregate or window function.
+///
+/// The first call in a group allocates `n_bytes` zeroed bytes; later calls
+/// return the same block regardless of `n_bytes`. Returns null when
+/// nothing was allocated and `n_bytes <= 0`, or outside an aggregate.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_aggregate_context(
+    ctx: *mut Sqlite3Context,
+    n_bytes: c_int,
+) -> *mut c_void {
+    if ctx.is_null() || (*ctx).aggregate.is_null() {
+        return std::ptr::null_mut();
+    }
+    (*(*ctx).aggregate).memory(n_bytes)
+}
+
+// ── sqlite3_value_* ─────────────────────────────────────────────────
+
+unsafe fn value_ref<'a>(value: *mut Sqlite3Value) -> Option<&'a SqliteValue> {
+    value.as_ref().map(|v| &v.value)
+}
+
+/// Datatype of a function argument (`SQLITE_INTEGER` … `SQLITE_NULL`).
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_type(value: *mut Sqlite3Value) -> c_int {
+    match value_ref(value) {
+        Some(SqliteValue::Integer(_)) => SQLITE_INTEGER,
+        Some(SqliteValue::Float(_)) => SQLITE_FLOAT,
+        Some(SqliteValue::Text(_)) => SQLITE_TEXT,
+        Some(SqliteValue::Blob(_)) => SQLITE_BLOB,
+        Some(SqliteValue::Null) | None => SQLITE_NULL,
+    }
+}
+
+/// Integer value of a function argument, with SQLite's conversions.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_int64(value: *mut Sqlite3Value) -> i64 {
+    value_ref(value).map_or(0, SqliteValue::to_integer)
+}
+
+/// 32-bit integer value of a function argument.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_int(value: *mut Sqlite3Value) -> c_int {
+    sqlite3_value_int64(value) as c_int
+}
+
+/// Floating-point value of a function argument.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_double(value: *mut Sqlite3Value) -> c_double {
+    value_ref(value).map_or(0.0, SqliteValue::to_float)
+}
+
+/// NUL-terminated UTF-8 text of a function argument, or null for NULL.
+///
+/// The pointer stays valid while the value does.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_text(value: *mut Sqlite3Value) -> *const c_char {
+    value
+        .as_ref()
+        .and_then(Sqlite3Value::text)
+        .map_or(std::ptr::null(), |text| text.as_ptr().cast())
+}
+
+/// Bytes of a BLOB (or text) function argument; null for an empty value.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_blob(value: *mut Sqlite3Value) -> *const c_void {
+    let Some(v) = value.as_ref() else {
+        return std::ptr::null();
+    };
+    match &v.value {
+        SqliteValue::Blob(b) if !b.is_empty() => b.as_ptr().cast(),
+        SqliteValue::Text(s) if !s.is_empty() => s.as_ptr().cast(),
+        SqliteValue::Integer(_) | SqliteValue::Float(_) => v
+            .text()
+            .map_or(std::ptr::null(), |text| text.as_ptr().cast()),
+        _ => std::ptr::null(),
+    }
+}
+
+/// Size in bytes of the BLOB or text form of a function argument.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_bytes(value: *mut Sqlite3Value) -> c_int {
+    let len = match value_ref(value) {
+        Some(SqliteValue::Blob(b)) => b.len(),
+        Some(SqliteValue::Text(s)) => s.len(),
+        Some(v @ (SqliteValue::Integer(_) | SqliteValue::Float(_))) => v.to_text().len(),
+        Some(SqliteValue::Null) | None => 0,
+    };
+    c_int::try_from(len).unwrap_or(c_int::MAX)
+}
+
+/// Copy a value so it outlives the callback; release with
+/// `sqlite3_value_free`.
+///
+/// # Safety
+/// `value` must be null or a valid `sqlite3_value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_dup(value: *const Sqlite3Value) -> *mut Sqlite3Value {
+    match value.as_ref() {
+        Some(v) => Box::into_raw(Box::new(Sqlite3Value::new(v.value.clone()))),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Release a value returned by `sqlite3_value_dup`.
+///
+/// # Safety
+/// `value` must be null or come from `sqlite3_value_dup`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_value_free(value: *mut Sqlite3Value) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+// ── sqlite3_result_* ────────────────────────────────────────────────
+
+unsafe fn set_result(ctx: *mut Sqlite3Context, value: SqliteValue) {
+    if let Some(ctx) = ctx.as_mut() {
+        ctx.result = value;
+    }
+}
+
+unsafe fn set_result_error(ctx: *mut Sqlite3Context, code: c_int, message: String) {
+    if let Some(ctx) = ctx.as_mut() {
+        ctx.error = Some((code, message));
+    }
+}
+
+unsafe fn result_bytes(
+    ctx: *mut Sqlite3Context,
+    ptr: *const c_void,
+    n: i64,
+    destructor: Sqlite3Destructor,
+    as_text: bool,
+) {
+    if n > 0 && n as u64 > MAX_BINDING_LEN {
+        release_bound_memory(ptr, destructor);
+        sqlite3_result_error_toobig(ctx);
+        return;
+    }
+    let value = match take_bound_bytes(ptr, n, destructor) {
+        None => SqliteValue::Null,
+        Some(bytes) if as_text => SqliteValue::Text(String::from_utf8_lossy(&bytes).into_owned()),
+        Some(bytes) => SqliteValue::Blob(bytes),
+    };
+    set_result(ctx, value);
+}
+
+/// Return NULL from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_null(ctx: *mut Sqlite3Context) {
+    set_result(ctx, SqliteValue::Null);
+}
+
+/// Return a 32-bit integer from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_int(ctx: *mut Sqlite3Context, value: c_int) {
+    set_result(ctx, SqliteValue::Integer(i64::from(value)));
+}
+
+/// Return a 64-bit integer from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_int64(ctx: *mut Sqlite3Context, value: i64) {
+    set_result(ctx, SqliteValue::Integer(value));
+}
+
+/// Return a floating-point number from the function; NaN becomes NULL.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_double(ctx: *mut Sqlite3Context, value: c_double) {
+    let value = if value.is_nan() {
+        SqliteValue::Null
+    } else {
+        SqliteValue::Float(value)
+    };
+    set_result(ctx, value);
+}
+
+/// Return text from the function. The text is copied before this call
+/// returns, as for `sqlite3_bind_text`.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback. `text` must be
+/// null, NUL-terminated when `n` is negative, or point to `n` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_text(
+    ctx: *mut Sqlite3Context,
+    text: *const c_char,
+    n: c_int,
+    destructor: Sqlite3Destructor,
+) {
+    result_bytes(ctx, text.cast(), i64::from(n), destructor, true);
+}
+
+/// Return text with a 64-bit length; only `SQLITE_UTF8` is supported.
+///
+/// # Safety
+/// Same as [`sqlite3_result_text`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_text64(
+    ctx: *mut Sqlite3Context,
+    text: *const c_char,
+    n: u64,
+    destructor: Sqlite3Destructor,
+    encoding: u8,
+) {
+    if c_int::from(encoding) != SQLITE_UTF8 {
+        tracing::warn!(target: "fsqlite.compat", encoding, "sqlite3_result_text64: only UTF-8 is supported");
+        release_bound_memory(text.cast(), destructor);
+        set_result_error(ctx, SQLITE_MISUSE, "unsupported text encoding".to_owned());
+        return;
+    }
+    let n = i64::try_from(n).unwrap_or(i64::MAX);
+    result_bytes(ctx, text.cast(), n, destructor, true);
+}
+
+/// Return a BLOB from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback. `blob` must be
+/// null or point to `n` readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_blob(
+    ctx: *mut Sqlite3Context,
+    blob: *const c_void,
+    n: c_int,
+    destructor: Sqlite3Destructor,
+) {
+    result_bytes(ctx, blob, i64::from(n.max(0)), destructor, false);
+}
+
+/// Return a BLOB with a 64-bit length from the function.
+///
+/// # Safety
+/// Same as [`sqlite3_result_blob`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_blob64(
+    ctx: *mut Sqlite3Context,
+    blob: *const c_void,
+    n: u64,
+    destructor: Sqlite3Destructor,
+) {
+    let n = i64::try_from(n).unwrap_or(i64::MAX);
+    result_bytes(ctx, blob, n, destructor, false);
+}
+
+/// Return a BLOB of `n` zero bytes from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_zeroblob(ctx: *mut Sqlite3Context, n: c_int) {
+    sqlite3_result_zeroblob64(ctx, u64::try_from(n).unwrap_or(0));
+}
+
+/// Return a BLOB of `n` zero bytes (64-bit length) from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_zeroblob64(ctx: *mut Sqlite3Context, n: u64) -> c_int {
+    if n > MAX_BINDING_LEN {
+        sqlite3_result_error_toobig(ctx);
+        return SQLITE_TOOBIG;
+    }
+    set_result(ctx, SqliteValue::Blob(vec![0; n as usize]));
+    SQLITE_OK
+}
+
+/// Return a copy of `value` from the function.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback and `value` a
+/// valid `sqlite3_value` (or null for NULL).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_value(ctx: *mut Sqlite3Context, value: *mut Sqlite3Value) {
+    set_result(ctx, value_ref(value).cloned().unwrap_or(SqliteValue::Null));
+}
+
+/// Make the function fail with `message` (`n` bytes, or up to the NUL
+/// terminator when negative) and `SQLITE_ERROR`.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback and `message`
+/// null or a valid string as described.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_error(
+    ctx: *mut Sqlite3Context,
+    message: *const c_char,
+    n: c_int,
+) {
+    let message = take_bound_bytes(message.cast(), i64::from(n), None)
+        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default();
+    set_result_error(ctx, SQLITE_ERROR, message);
+}
+
+/// Make the function fail with result code `code`, keeping any message
+/// set by `sqlite3_result_error`.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_error_code(ctx: *mut Sqlite3Context, code: c_int) {
+    let message = match ctx.as_ref().and_then(|ctx| ctx.error.as_ref()) {
+        Some((_, message)) => message.clone(),
+        None => CStr::from_ptr(sqlite3_errstr(code))
+            .to_string_lossy()
+            .into_owned(),
+    };
+    set_result_error(ctx, code, message);
+}
+
+/// Make the function fail with `SQLITE_NOMEM`.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_error_nomem(ctx: *mut Sqlite3Context) {
+    set_result_error(ctx, SQLITE_NOMEM, FrankenError::OutOfMemory.to_string());
+}
+
+/// Make the function fail with `SQLITE_TOOBIG`.
+///
+/// # Safety
+/// `ctx` must be the context passed to the running callback.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_result_error_toobig(ctx: *mut Sqlite3Context) {
+    set_result_error(ctx, SQLITE_TOOBIG, FrankenError::TooBig.to_string());
+}
+
+// ── Library ─────────────────────────────────────────────────────────
+
+/// SQLite version string this library is compatible with.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_libversion() -> *const c_char {
+    SQLITE_VERSION.as_ptr()
+}
+
+/// SQLite version number (`SQLITE_VERSION_NUMBER`) this library is
+/// compatible with.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_libversion_number() -> c_int {
+    SQLITE_VERSION_NUMBER
+}
+
+/// Source identifier of this build.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_sourceid() -> *const c_char {
+    SQLITE_SOURCE_ID.as_ptr()
+}
+
+/// Threading mode: 0, since a connection handle must not be used from two
+/// threads at once (`sqlite3_interrupt` excepted).
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_threadsafe() -> c_int {
+    0
+}
+
+/// Library initialization; nothing to do, always `SQLITE_OK`.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_initialize() -> c_int {
+    SQLITE_OK
+}
+
+/// Library shutdown; nothing to do, always `SQLITE_OK`.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_shutdown() -> c_int {
+    SQLITE_OK
+}
+
+/// Whether `sql` ends with a complete SQL statement (a `;` outside any
+/// string, comment or `CREATE TRIGGER` body).
+///
+/// # Safety
+/// `sql` must be a valid null-terminated C string (or null).
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_complete(sql: *const c_char) -> c_int {
+    if sql.is_null() {
+        return 0;
+    }
+    let Ok(sql) = CStr::from_ptr(sql).to_str() else {
+        return 0;
+    };
+    c_int::from(is_complete_sql(sql))
+}
+
+fn is_complete_sql(sql: &str) -> bool {
+    let mut complete = false;
+    let mut statement: Vec<TokenKind> = Vec::new();
+    for token in Lexer::tokenize(sql) {
+        match token.kind {
+            TokenKind::Eof => break,
+            TokenKind::Error(_) => return false,
+            TokenKind::Semicolon => {
+                let in_trigger = matches!(
+                    statement.as_slice(),
+                    [TokenKind::KwCreate, TokenKind::KwTrigger, ..]
+                        | [
+                            TokenKind::KwCreate,
+                            TokenKind::KwTemp | TokenKind::KwTemporary,
+                            TokenKind::KwTrigger,
+                            ..
+                        ]
+                );
+                if in_trigger && statement.last() != Some(&TokenKind::KwEnd) {
+                    statement.push(TokenKind::Semicolon);
+                    complete = false;
+                } else {
+                    statement.clear();
+                    complete = true;
+                }
+            }
+            kind => {
+                statement.push(kind);
+                complete = false;
+            }
+        }
+    }
+    complete
+}
+
+/// Allocate `n` bytes, releasable with `sqlite3_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_malloc(n: c_int) -> *mut c_void {
+    sqlite3_malloc64(u64::try_from(n).unwrap_or(0))
+}
+
+/// Allocate `n` bytes (64-bit size), releasable with `sqlite3_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn sqlite3_malloc64(n: u64) -> *mut c_void {
+    let Ok(n) = usize::try_from(n) else {
+        return std::ptr::null_mut();
+    };
+    if n == 0 {
+        return std::ptr::null_mut();
+    }
+    unsafe { libc_malloc(n).cast() }
+}
+
+/// Resize an allocation from `sqlite3_malloc`, preserving its contents.
+///
+/// # Safety
+/// `ptr` must be null or a live allocation from this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_realloc(ptr: *mut c_void, n: c_int) -> *mut c_void {
+    sqlite3_realloc64(ptr, u64::try_from(n).unwrap_or(0))
+}
+
+/// 64-bit variant of [`sqlite3_realloc`].
+///
+/// # Safety
+/// `ptr` must be null or a live allocation from this library.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sqlite3_realloc64(ptr: *mut c_void, n: u64) -> *mut c_void {
+    if ptr.is_null() {
+        return sqlite3_malloc64(n);
+    }
+    if n == 0 {
+        libc_free(ptr);
+        return std::ptr::null_mut();
+    }
+    let new_ptr = sqlite3_malloc64(n);
+    if new_ptr.is_null() {
+        return new_ptr;
+    }
+    let old_size = allocation_size(ptr);
+    std::ptr::copy_nonoverlapping(
+        ptr.cast::<u8>(),
+        new_ptr.cast::<u8>(),
+        old_size.min(n as usize),
+    );
+    libc_free(ptr);
+    new_ptr
+}
+
+/// Copy `text` into a NUL-terminated `sqlite3_malloc` allocation.
+unsafe fn malloc_c_string(text: &str) -> *mut c_char {
+    let Ok(ctext) = CString::new(text) else {
+        return std::ptr::null_mut();
+    };
+    let bytes = ctext.as_bytes_with_nul();
+    let buf = libc_malloc(bytes.len());
+    if !buf.is_null() {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+    }
+    buf.cast()
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    /// Helper: open an in-memory database via C API.
+    unsafe fn open_memory() -> *mut Sqlite3 {
+        let mut db: *mut Sqlite3 = ptr::null_mut();
+        let path = CString::new(":memory:").unwrap();
+        let rc = sqlite3_open(path.as_ptr(), &mut db);
+        assert_eq!(rc, SQLITE_OK);
+        assert!(!db.is_null());
+        db
+    }
+
+    #[test]
+    fn test_open_close_memory() {
+        unsafe {
+            let db = open_memory();
+            let rc = sqlite3_close(db);
+            assert_eq!(rc, SQLITE_OK);
+        }
+    }
+
+    #[test]
+    fn test_open_null_filename() {
+        unsafe {
+            let mut db: *mut Sqlite3 = ptr::null_mut();
+            let rc = sqlite3_open(ptr::null(), &mut db);
+            assert_eq!(rc, SQLITE_OK);
+            assert!(!db.is_null());
+            sqlite3_close(db);
+        }
+    }
+
+    #[test]
+    fn test_open_empty_filename() {
+        unsafe {
+            let mut db: *mut Sqlite3 = ptr::null_mut();
+            let path = CString::new("").unwrap();
+            let rc = sqlite3_open(path.as_ptr(), &mut db);
+            assert_eq!(rc, SQLITE_OK);
+            assert!(!db.is_null());
+            sqlite3_close(db);
+        }
+    }
+
+    #[test]
+    fn test_close_null() {
+        unsafe {
+            let rc = sqlite3_close(ptr::null_mut());
+            assert_eq!(rc, SQLITE_OK);
+        }
+    }
+
+    #[test]
+    fn test_exec_create_insert_select() {
+        unsafe {
+            unsafe extern "C" fn count_cb(
+                parg: *mut c_void,
+                _ncols: c_int,
+                _values: *mut *mut c_char,
+                _names: *mut *mut c_char,
+            ) -> c_int {
+                let counter = &*(parg.cast::<AtomicU64>());
+                counter.fetch_add(1, Ordering::Relaxed);
+                0
+            }
+
+            let db = open_memory();
+
+            let sql = CString::new("CREATE TABLE t1(id INTEGER PRIMARY KEY, name TEXT);").unwrap();
+            let rc = sqlite3_exec(db, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(rc, SQLITE_OK);
+
+            let sql = CString::new("INSERT INTO t1 VALUES(1, 'alice');").unwrap();
+            let rc = sqlite3_exec(db, sql.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
             assert_eq!(rc, SQLITE_OK);
 
             let sql = CString::new("INSERT INTO t1 VALUES(2, 'bob');").unwrap();
@@ -1867,6 +4191,237 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bind_parameters_by_index_and_name() {
+        unsafe {
+            let db = open_memory();
+
+            let setup = CString::new(
+                "CREATE TABLE t(id INTEGER PRIMARY KEY, name TEXT, data BLOB, score REAL);",
+            )
+            .unwrap();
+            assert_eq!(
+                sqlite3_exec(db, setup.as_ptr(), None, ptr::null_mut(), ptr::null_mut()),
+                SQLITE_OK
+            );
+
+            let sql = CString::new("INSERT INTO t VALUES(?1, :name, @data, ?);").unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            assert_eq!(
+                sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut()),
+                SQLITE_OK
+            );
+            assert_eq!(sqlite3_bind_parameter_count(stmt), 4);
+            assert_eq!(
+                CStr::from_ptr(sqlite3_bind_parameter_name(stmt, 1))
+                    .to_str()
+                    .unwrap(),
+                "?1"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_bind_parameter_name(stmt, 2))
+                    .to_str()
+                    .unwrap(),
+                ":name"
+            );
+            assert!(sqlite3_bind_parameter_name(stmt, 4).is_null());
+            let name = CString::new("@data").unwrap();
+            assert_eq!(sqlite3_bind_parameter_index(stmt, name.as_ptr()), 3);
+            let missing = CString::new(":missing").unwrap();
+            assert_eq!(sqlite3_bind_parameter_index(stmt, missing.as_ptr()), 0);
+
+            let text = CString::new("alice").unwrap();
+            let blob = [0xCA_u8, 0xFE];
+            assert_eq!(sqlite3_bind_int64(stmt, 1, 7), SQLITE_OK);
+            assert_eq!(
+                sqlite3_bind_text(stmt, 2, text.as_ptr(), -1, None),
+                SQLITE_OK
+            );
+            assert_eq!(
+                sqlite3_bind_blob(stmt, 3, blob.as_ptr().cast(), 2, None),
+                SQLITE_OK
+            );
+            assert_eq!(sqlite3_bind_double(stmt, 4, 2.5), SQLITE_OK);
+            assert_eq!(sqlite3_bind_int(stmt, 5, 1), SQLITE_RANGE);
+            assert_eq!(sqlite3_errcode(db), SQLITE_RANGE);
+
+            let expanded = sqlite3_expanded_sql(stmt);
+            assert_eq!(
+                CStr::from_ptr(expanded).to_str().unwrap(),
+                "INSERT INTO t VALUES(7, 'alice', X'CAFE', 2.5);"
+            );
+            sqlite3_free(expanded.cast());
+
+            assert_eq!(sqlite3_step(stmt), SQLITE_DONE);
+            assert_eq!(sqlite3_bind_int(stmt, 1, 8), SQLITE_MISUSE);
+            assert_eq!(sqlite3_last_insert_rowid(db), 7);
+
+            assert_eq!(sqlite3_reset(stmt), SQLITE_OK);
+            assert_eq!(sqlite3_clear_bindings(stmt), SQLITE_OK);
+            assert_eq!(sqlite3_bind_int(stmt, 1, 8), SQLITE_OK);
+            assert_eq!(sqlite3_step(stmt), SQLITE_DONE);
+            assert_eq!(sqlite3_total_changes(db), 2);
+            sqlite3_finalize(stmt);
+
+            let sql = CString::new("SELECT name, data FROM t WHERE id = ?;").unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut());
+            assert_eq!(sqlite3_stmt_readonly(stmt), 1);
+            sqlite3_bind_int(stmt, 1, 8);
+            assert_eq!(sqlite3_step(stmt), SQLITE_ROW);
+            assert_eq!(sqlite3_stmt_busy(stmt), 1);
+            assert_eq!(sqlite3_column_type(stmt, 0), SQLITE_NULL);
+            assert_eq!(sqlite3_column_type(stmt, 1), SQLITE_NULL);
+            assert_eq!(sqlite3_step(stmt), SQLITE_DONE);
+            assert_eq!(sqlite3_stmt_busy(stmt), 0);
+
+            sqlite3_finalize(stmt);
+            sqlite3_close(db);
+        }
+    }
+
+    #[test]
+    fn test_column_name_and_decltype_metadata() {
+        unsafe {
+            let db = open_memory();
+
+            let setup = CString::new(
+                "CREATE TABLE people(id INTEGER PRIMARY KEY, name VARCHAR(20), note);",
+            )
+            .unwrap();
+            sqlite3_exec(db, setup.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+
+            let sql = CString::new("SELECT p.name AS who, note, id + 1 FROM people AS p;").unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            assert_eq!(
+                sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut()),
+                SQLITE_OK
+            );
+            assert_eq!(sqlite3_column_count(stmt), 3);
+            assert_eq!(
+                CStr::from_ptr(sqlite3_column_name(stmt, 0))
+                    .to_str()
+                    .unwrap(),
+                "who"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_column_decltype(stmt, 0))
+                    .to_str()
+                    .unwrap(),
+                "VARCHAR(20)"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_column_table_name(stmt, 0))
+                    .to_str()
+                    .unwrap(),
+                "people"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_column_origin_name(stmt, 0))
+                    .to_str()
+                    .unwrap(),
+                "name"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_column_database_name(stmt, 0))
+                    .to_str()
+                    .unwrap(),
+                "main"
+            );
+            assert!(sqlite3_column_decltype(stmt, 1).is_null());
+            assert!(!sqlite3_column_table_name(stmt, 1).is_null());
+            assert!(sqlite3_column_decltype(stmt, 2).is_null());
+            assert!(sqlite3_column_table_name(stmt, 2).is_null());
+            assert!(sqlite3_column_name(stmt, 3).is_null());
+            assert_eq!(sqlite3_data_count(stmt), 0);
+
+            sqlite3_finalize(stmt);
+            sqlite3_close(db);
+        }
+    }
+
+    #[test]
+    fn test_close_v2_defers_until_last_finalize() {
+        unsafe {
+            let db = open_memory();
+
+            let sql = CString::new("SELECT 1;").unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut());
+
+            assert_eq!(sqlite3_close_v2(db), SQLITE_OK);
+            assert_eq!(sqlite3_db_handle(stmt), db);
+            assert_eq!(sqlite3_step(stmt), SQLITE_ROW);
+            assert_eq!(sqlite3_finalize(stmt), SQLITE_OK);
+        }
+    }
+
+    #[test]
+    fn test_library_and_connection_introspection() {
+        unsafe {
+            assert_eq!(sqlite3_libversion_number(), SQLITE_VERSION_NUMBER);
+            assert_eq!(
+                CStr::from_ptr(sqlite3_libversion()).to_str().unwrap(),
+                "3.52.0"
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_errstr(SQLITE_RANGE))
+                    .to_str()
+                    .unwrap(),
+                "column index out of range"
+            );
+
+            for (sql, complete) in [
+                ("SELECT 1", 0),
+                ("SELECT 1;", 1),
+                ("SELECT ';'", 0),
+                ("CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1;", 0),
+                (
+                    "CREATE TRIGGER tr AFTER INSERT ON t BEGIN SELECT 1; END;",
+                    1,
+                ),
+            ] {
+                let sql = CString::new(sql).unwrap();
+                assert_eq!(sqlite3_complete(sql.as_ptr()), complete);
+            }
+
+            let mut db: *mut Sqlite3 = ptr::null_mut();
+            assert_eq!(
+                sqlite3_open_v2(
+                    ptr::null(),
+                    &mut db,
+                    SQLITE_OPEN_READWRITE | SQLITE_OPEN_CREATE,
+                    ptr::null(),
+                ),
+                SQLITE_OK
+            );
+            assert_eq!(
+                CStr::from_ptr(sqlite3_db_filename(db, ptr::null())).to_bytes(),
+                b""
+            );
+            assert_eq!(sqlite3_db_readonly(db, ptr::null()), 0);
+            let other = CString::new("aux").unwrap();
+            assert_eq!(sqlite3_db_readonly(db, other.as_ptr()), -1);
+            assert_eq!(sqlite3_busy_timeout(db, 250), SQLITE_OK);
+
+            assert_eq!(sqlite3_get_autocommit(db), 1);
+            let begin = CString::new("BEGIN;").unwrap();
+            sqlite3_exec(db, begin.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(sqlite3_get_autocommit(db), 0);
+            let commit = CString::new("COMMIT;").unwrap();
+            sqlite3_exec(db, commit.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(sqlite3_get_autocommit(db), 1);
+
+            let buf = sqlite3_malloc(4).cast::<u8>();
+            buf.copy_from_nonoverlapping([1_u8, 2, 3, 4].as_ptr(), 4);
+            let grown = sqlite3_realloc(buf.cast(), 16).cast::<u8>();
+            assert_eq!(std::slice::from_raw_parts(grown, 4), &[1, 2, 3, 4]);
+            sqlite3_free(grown.cast());
+
+            sqlite3_close(db);
+        }
+    }
+
     #[test]
     fn test_changes_tracks_last_dml_and_survives_select() {
         unsafe {
@@ -1903,4 +4458,365 @@ mod tests {
             sqlite3_close(db);
         }
     }
+
+    /// Helper: run a single-value query and return the value as an integer.
+    unsafe fn query_i64(db: *mut Sqlite3, sql: &str) -> (c_int, i64) {
+        let sql = CString::new(sql).unwrap();
+        let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+        let rc = sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut());
+        if rc != SQLITE_OK {
+            return (rc, 0);
+        }
+        let rc = sqlite3_step(stmt);
+        let value = sqlite3_column_int64(stmt, 0);
+        sqlite3_finalize(stmt);
+        (rc, value)
+    }
+
+    static SCALE_DESTROYED: AtomicUsize = AtomicUsize::new(0);
+
+    #[allow(clippy::similar_names)]
+    unsafe extern "C" fn scale(
+        ctx: *mut Sqlite3Context,
+        argc: c_int,
+        argv: *mut *mut Sqlite3Value,
+    ) {
+        assert_eq!(argc, 1);
+        let arg = *argv;
+        if sqlite3_value_type(arg) == SQLITE_NULL {
+            sqlite3_result_error(ctx, c"scale() needs a value".as_ptr(), -1);
+            return;
+        }
+        let factor = *sqlite3_user_data(ctx).cast::<i64>();
+        sqlite3_result_int64(ctx, sqlite3_value_int64(arg) * factor);
+    }
+
+    unsafe extern "C" fn destroy_scale(p_app: *mut c_void) {
+        drop(Box::from_raw(p_app.cast::<i64>()));
+        SCALE_DESTROYED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_create_function_v2_scalar_user_data_errors_and_destroy() {
+        unsafe {
+            let db = open_memory();
+            let name = CString::new("scale").unwrap();
+
+            let factor = Box::into_raw(Box::new(3_i64)).cast::<c_void>();
+            let rc = sqlite3_create_function_v2(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8 | SQLITE_DETERMINISTIC,
+                factor,
+                Some(scale),
+                None,
+                None,
+                Some(destroy_scale),
+            );
+            assert_eq!(rc, SQLITE_OK);
+            assert_eq!(query_i64(db, "SELECT scale(14);"), (SQLITE_ROW, 42));
+
+            let (rc, _) = query_i64(db, "SELECT scale(NULL);");
+            assert_eq!(rc, SQLITE_ERROR);
+            assert_eq!(
+                CStr::from_ptr(sqlite3_errmsg(db)).to_str().unwrap(),
+                "scale() needs a value"
+            );
+
+            // Replacing the definition swaps the user data.
+            let factor = Box::into_raw(Box::new(10_i64)).cast::<c_void>();
+            sqlite3_create_function_v2(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                factor,
+                Some(scale),
+                None,
+                None,
+                Some(destroy_scale),
+            );
+            assert_eq!(query_i64(db, "SELECT scale(4);"), (SQLITE_ROW, 40));
+
+            // xFunc together with xStep is rejected.
+            let rc = sqlite3_create_function_v2(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                Some(scale),
+                Some(scale),
+                None,
+                None,
+            );
+            assert_eq!(rc, SQLITE_MISUSE);
+
+            // Null callbacks delete the function.
+            let rc = sqlite3_create_function(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(rc, SQLITE_OK);
+            let (rc, _) = query_i64(db, "SELECT scale(4);");
+            assert_eq!(rc, SQLITE_ERROR);
+
+            sqlite3_close(db);
+            assert_eq!(SCALE_DESTROYED.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[test]
+    fn test_value_text_keeps_embedded_nul() {
+        unsafe {
+            let mut value = Sqlite3Value::new(SqliteValue::Text("a\0b".to_owned()));
+            let value: *mut Sqlite3Value = &raw mut value;
+            let len = sqlite3_value_bytes(value);
+            assert_eq!(len, 3);
+            let text = sqlite3_value_text(value);
+            assert_eq!(std::slice::from_raw_parts(text.cast::<u8>(), 4), b"a\0b\0");
+        }
+    }
+
+    #[test]
+    fn test_deleting_udf_restores_overridden_builtin() {
+        unsafe {
+            let db = open_memory();
+            let name = CString::new("upper").unwrap();
+            let mut factor = 2_i64;
+            let rc = sqlite3_create_function(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                (&raw mut factor).cast(),
+                Some(scale),
+                None,
+                None,
+            );
+            assert_eq!(rc, SQLITE_OK);
+            assert_eq!(query_i64(db, "SELECT upper(21);"), (SQLITE_ROW, 42));
+
+            let rc = sqlite3_create_function(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(rc, SQLITE_OK);
+            assert_eq!(query_i64(db, "SELECT upper('a') = 'A';"), (SQLITE_ROW, 1));
+            sqlite3_close(db);
+        }
+    }
+
+    unsafe extern "C" fn running_sum_step(
+        ctx: *mut Sqlite3Context,
+        _argc: c_int,
+        argv: *mut *mut Sqlite3Value,
+    ) {
+        let total = sqlite3_aggregate_context(ctx, 8).cast::<i64>();
+        *total += sqlite3_value_int64(*argv);
+    }
+
+    unsafe extern "C" fn running_sum_inverse(
+        ctx: *mut Sqlite3Context,
+        _argc: c_int,
+        argv: *mut *mut Sqlite3Value,
+    ) {
+        let total = sqlite3_aggregate_context(ctx, 8).cast::<i64>();
+        *total -= sqlite3_value_int64(*argv);
+    }
+
+    unsafe extern "C" fn running_sum_value(ctx: *mut Sqlite3Context) {
+        let total = sqlite3_aggregate_context(ctx, 0).cast::<i64>();
+        if total.is_null() {
+            sqlite3_result_null(ctx);
+        } else {
+            sqlite3_result_int64(ctx, *total);
+        }
+    }
+
+    #[test]
+    fn test_create_window_function_runs_as_aggregate_and_window() {
+        unsafe {
+            let db = open_memory();
+            let setup =
+                CString::new("CREATE TABLE t(x INTEGER); INSERT INTO t VALUES (1), (2), (3), (4);")
+                    .unwrap();
+            sqlite3_exec(db, setup.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+
+            let name = CString::new("csum").unwrap();
+            let rc = sqlite3_create_window_function(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                Some(running_sum_step),
+                Some(running_sum_value),
+                Some(running_sum_value),
+                Some(running_sum_inverse),
+                None,
+            );
+            assert_eq!(rc, SQLITE_OK);
+            assert_eq!(query_i64(db, "SELECT csum(x) FROM t;"), (SQLITE_ROW, 10));
+
+            let sql = CString::new(
+                "SELECT csum(x) OVER (ORDER BY x ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) FROM t;",
+            )
+            .unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            assert_eq!(
+                sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut()),
+                SQLITE_OK
+            );
+            let mut sums = Vec::new();
+            while sqlite3_step(stmt) == SQLITE_ROW {
+                sums.push(sqlite3_column_int64(stmt, 0));
+            }
+            assert_eq!(sums, vec![1, 3, 5, 7]);
+            sqlite3_finalize(stmt);
+
+            // A window function needs all four callbacks.
+            let rc = sqlite3_create_window_function(
+                db,
+                name.as_ptr(),
+                1,
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                Some(running_sum_step),
+                Some(running_sum_value),
+                None,
+                None,
+                None,
+            );
+            assert_eq!(rc, SQLITE_MISUSE);
+
+            sqlite3_close(db);
+        }
+    }
+
+    static COLLATION_ARG_DESTROYED: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn destroy_collation_arg(p_app: *mut c_void) {
+        drop(Box::from_raw(p_app.cast::<i64>()));
+        COLLATION_ARG_DESTROYED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe extern "C" fn reverse_compare(
+        _p_arg: *mut c_void,
+        n1: c_int,
+        s1: *const c_void,
+        n2: c_int,
+        s2: *const c_void,
+    ) -> c_int {
+        let a = std::slice::from_raw_parts(s1.cast::<u8>(), n1 as usize);
+        let b = std::slice::from_raw_parts(s2.cast::<u8>(), n2 as usize);
+        match b.cmp(a) {
+            std::cmp::Ordering::Less => -1,
+            std::cmp::Ordering::Equal => 0,
+            std::cmp::Ordering::Greater => 1,
+        }
+    }
+
+    #[test]
+    fn test_create_collation_v2_orders_with_c_comparator() {
+        unsafe {
+            let db = open_memory();
+            let setup = CString::new(
+                "CREATE TABLE words(w TEXT); INSERT INTO words VALUES ('b'), ('a'), ('c');",
+            )
+            .unwrap();
+            sqlite3_exec(db, setup.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+
+            let name = CString::new("reverse").unwrap();
+            let rc = sqlite3_create_collation_v2(
+                db,
+                name.as_ptr(),
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                Some(reverse_compare),
+                None,
+            );
+            assert_eq!(rc, SQLITE_OK);
+
+            let sql = CString::new("SELECT w FROM words ORDER BY w COLLATE reverse;").unwrap();
+            let mut stmt: *mut Sqlite3Stmt = ptr::null_mut();
+            sqlite3_prepare_v2(db, sql.as_ptr(), -1, &mut stmt, ptr::null_mut());
+            let mut words = Vec::new();
+            while sqlite3_step(stmt) == SQLITE_ROW {
+                words.push(
+                    CStr::from_ptr(sqlite3_column_text(stmt, 0))
+                        .to_str()
+                        .unwrap()
+                        .to_owned(),
+                );
+            }
+            assert_eq!(words, ["c", "b", "a"]);
+            sqlite3_finalize(stmt);
+
+            assert_eq!(
+                sqlite3_create_collation(db, ptr::null(), SQLITE_UTF8, ptr::null_mut(), None),
+                SQLITE_MISUSE
+            );
+            // A rejected call leaves the user data with the caller.
+            let factor = Box::into_raw(Box::new(1_i64)).cast::<c_void>();
+            assert_eq!(
+                sqlite3_create_collation_v2(
+                    db,
+                    ptr::null(),
+                    SQLITE_UTF8,
+                    factor,
+                    Some(reverse_compare),
+                    Some(destroy_collation_arg),
+                ),
+                SQLITE_MISUSE
+            );
+            assert_eq!(COLLATION_ARG_DESTROYED.load(Ordering::SeqCst), 0);
+            drop(Box::from_raw(factor.cast::<i64>()));
+            sqlite3_close(db);
+        }
+    }
+
+    #[test]
+    fn test_extended_errcode_and_extended_result_codes() {
+        unsafe {
+            let db = open_memory();
+            let setup =
+                CString::new("CREATE TABLE u(id INTEGER PRIMARY KEY, v TEXT UNIQUE);").unwrap();
+            sqlite3_exec(db, setup.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            let insert = CString::new("INSERT INTO u(v) VALUES ('x');").unwrap();
+            assert_eq!(
+                sqlite3_exec(db, insert.as_ptr(), None, ptr::null_mut(), ptr::null_mut()),
+                SQLITE_OK
+            );
+
+            let rc = sqlite3_exec(db, insert.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(rc, SQLITE_CONSTRAINT);
+            assert_eq!(sqlite3_errcode(db), SQLITE_CONSTRAINT);
+            assert_eq!(sqlite3_extended_errcode(db), SQLITE_CONSTRAINT_UNIQUE);
+
+            assert_eq!(sqlite3_extended_result_codes(db, 1), SQLITE_OK);
+            let rc = sqlite3_exec(db, insert.as_ptr(), None, ptr::null_mut(), ptr::null_mut());
+            assert_eq!(rc, SQLITE_CONSTRAINT_UNIQUE);
+            assert_eq!(sqlite3_errcode(db), SQLITE_CONSTRAINT_UNIQUE);
+            assert_eq!(
+                CStr::from_ptr(sqlite3_errstr(rc)).to_str().unwrap(),
+                "constraint failed"
+            );
+
+            sqlite3_close(db);
+        }
+    }
 }
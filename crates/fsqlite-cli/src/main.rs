@@ -1,3 +1,5 @@
+mod snapshot;
+
 use std::ffi::OsString;
 use std::fmt::Write as _;
 use std::io::{self, BufRead, ErrorKind, IsTerminal, Write};
@@ -128,6 +130,11 @@ where
     W: Write,
     E: Write,
 {
+    let args: Vec<OsString> = args.into_iter().collect();
+    if args.get(1).is_some_and(|arg| arg == "snapshot") {
+        return snapshot::run(&args[2..], out, err);
+    }
+
     let options = match parse_args(args) {
         Ok(options) => options,
         Err(message) => {
@@ -1093,6 +1100,10 @@ where
          Verify decode proof JSON:\n\
          fsqlite --verify-proof proof.json [--verify-policy-id N] [--verify-slack N]\n\
          \n\
+         Ship a database as fountain-coded symbols (see `fsqlite snapshot --help`):\n\
+         fsqlite snapshot send DB --to ADDR|FILE\n\
+         fsqlite snapshot recv --from ADDR|FILE OUT.db\n\
+         \n\
          Examples:\n\
          \n\
          fsqlite\n\
@@ -0,0 +1,855 @@
+//! `fsqlite snapshot send|recv`: fountain-coded snapshot shipping.
+//!
+//! A snapshot stream is a sequence of frames. Over UDP each datagram is one
+//! frame; in a file each frame is prefixed by its length (`u32` LE). A frame
+//! starts with a tag byte: `M` carries a [`SnapshotManifest`], `S` one
+//! replication packet. The sender repeats the manifest between symbols so a
+//! receiver that joins late or loses it still learns the image geometry.
+//!
+//! The receiver spools every useful frame to `OUT.db.fsnap-partial`.
+//! Rerunning `recv` with the same output replays the spool first, so a
+//! transfer interrupted by loss, a timeout or a crash resumes from where it
+//! stopped instead of starting over.
+
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use fsqlite::Connection;
+use fsqlite_core::replication_sender::{
+    MAX_UDP_PAYLOAD, MTU_SAFE_SYMBOL_SIZE, ReplicationPacket, SenderConfig,
+};
+use fsqlite_core::snapshot_shipping::{
+    DecodedBlock, SnapshotManifest, SnapshotPacketResult, SnapshotReceiver, SnapshotReceiverState,
+    SnapshotSender, assemble_snapshot_image,
+};
+
+const FRAME_MANIFEST: u8 = b'M';
+const FRAME_SYMBOL: u8 = b'S';
+const SPOOL_SUFFIX: &str = ".fsnap-partial";
+const MANIFEST_EVERY: usize = 64;
+const DEFAULT_RECV_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_SOCKET_PACE_MICROS: u64 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Endpoint {
+    Socket(SocketAddr),
+    File(PathBuf),
+}
+
+impl Endpoint {
+    fn parse(raw: &str) -> Self {
+        raw.parse()
+            .map_or_else(|_| Self::File(PathBuf::from(raw)), Self::Socket)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SendOptions {
+    db_path: String,
+    to: Endpoint,
+    passes: u32,
+    symbol_size: u16,
+    loss_percent: u32,
+    shuffle: bool,
+    seed: u64,
+    pace_micros: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecvOptions {
+    from: Endpoint,
+    out_path: PathBuf,
+    timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SnapshotCommand {
+    Send(SendOptions),
+    Recv(RecvOptions),
+}
+
+/// Run `fsqlite snapshot ARGS...`; `args` excludes `fsqlite snapshot`.
+pub fn run<W, E>(args: &[OsString], out: &mut W, err: &mut E) -> i32
+where
+    W: Write,
+    E: Write,
+{
+    if args
+        .first()
+        .is_some_and(|arg| arg == "-h" || arg == "--help")
+    {
+        return i32::from(write_snapshot_usage(out).is_err());
+    }
+    let command = match parse_snapshot_args(args) {
+        Ok(command) => command,
+        Err(message) => {
+            let _ = writeln!(err, "error: {message}");
+            let _ = write_snapshot_usage(err);
+            return 2;
+        }
+    };
+    let result = match &command {
+        SnapshotCommand::Send(options) => run_send(options),
+        SnapshotCommand::Recv(options) => run_recv(options),
+    };
+    match result {
+        Ok(summary) => {
+            if writeln!(out, "{summary}").is_err() {
+                return 1;
+            }
+            0
+        }
+        Err(message) => {
+            let _ = writeln!(err, "error: {message}");
+            1
+        }
+    }
+}
+
+fn write_snapshot_usage<W>(out: &mut W) -> io::Result<()>
+where
+    W: Write,
+{
+    writeln!(
+        out,
+        "Usage: fsqlite snapshot send DB --to ADDR|FILE [--passes N] [--symbol-size N]\n\
+         \x20                             [--loss PCT] [--shuffle] [--seed N] [--pace-us N]\n\
+         \x20      fsqlite snapshot recv --from ADDR|FILE OUT.db [--timeout SECS]\n\
+         \n\
+         ADDR is a UDP socket address such as 127.0.0.1:7000; anything else is a file.\n\
+         `--loss` and `--shuffle` simulate a lossy, reordering link. An incomplete\n\
+         `recv` keeps its progress in OUT.db{SPOOL_SUFFIX}; rerun it to resume.\n",
+    )
+}
+
+fn parse_snapshot_args(args: &[OsString]) -> Result<SnapshotCommand, String> {
+    let mut iter = args.iter().map(|arg| arg.to_string_lossy().into_owned());
+    let subcommand = iter
+        .next()
+        .ok_or_else(|| String::from("missing snapshot subcommand (`send` or `recv`)"))?;
+    let mut positional = Vec::new();
+    let mut endpoint = None;
+    let mut passes = 1;
+    let mut symbol_size = MTU_SAFE_SYMBOL_SIZE;
+    let mut loss_percent = 0;
+    let mut shuffle = false;
+    let mut seed = 0;
+    let mut pace_micros = None;
+    let mut timeout_secs = DEFAULT_RECV_TIMEOUT_SECS;
+    let endpoint_flag = match subcommand.as_str() {
+        "send" => "--to",
+        "recv" => "--from",
+        other => return Err(format!("unknown snapshot subcommand `{other}`")),
+    };
+
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_owned(), Some(value.to_owned()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| iter.next())
+                .ok_or_else(|| format!("missing argument for `{flag}`"))
+        };
+        match (subcommand.as_str(), flag.as_str()) {
+            (_, f) if f == endpoint_flag => {
+                if endpoint.is_some() {
+                    return Err(format!("`{endpoint_flag}` may only be provided once"));
+                }
+                endpoint = Some(Endpoint::parse(&value()?));
+            }
+            ("send", "--passes") => {
+                passes = parse_number(&value()?, "--passes")?;
+                if passes == 0 {
+                    return Err(String::from("`--passes` must be at least 1"));
+                }
+            }
+            ("send", "--symbol-size") => symbol_size = parse_number(&value()?, "--symbol-size")?,
+            ("send", "--loss") => {
+                loss_percent = parse_number(&value()?, "--loss")?;
+                if loss_percent >= 100 {
+                    return Err(String::from("`--loss` must be below 100"));
+                }
+            }
+            ("send", "--shuffle") => shuffle = true,
+            ("send", "--seed") => seed = parse_number(&value()?, "--seed")?,
+            ("send", "--pace-us") => pace_micros = Some(parse_number(&value()?, "--pace-us")?),
+            ("recv", "--timeout") => timeout_secs = parse_number(&value()?, "--timeout")?,
+            (_, f) if f.starts_with('-') => {
+                return Err(format!("unknown option `{f}` for `snapshot {subcommand}`"));
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let endpoint = endpoint.ok_or_else(|| format!("missing `{endpoint_flag}` argument"))?;
+    let [path] = <[String; 1]>::try_from(positional).map_err(|positional| {
+        format!(
+            "`snapshot {subcommand}` expects exactly one {} path, got {}",
+            if subcommand == "send" { "DB" } else { "output" },
+            positional.len()
+        )
+    })?;
+    Ok(if subcommand == "send" {
+        SnapshotCommand::Send(SendOptions {
+            db_path: path,
+            to: endpoint,
+            passes,
+            symbol_size,
+            loss_percent,
+            shuffle,
+            seed,
+            pace_micros,
+        })
+    } else {
+        SnapshotCommand::Recv(RecvOptions {
+            from: endpoint,
+            out_path: PathBuf::from(path),
+            timeout_secs,
+        })
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid integer for `{flag}`: `{value}`"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut acc, byte| {
+        let _ = write!(acc, "{byte:02x}");
+        acc
+    })
+}
+
+fn frame(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(body.len() + 1);
+    frame.push(tag);
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Deterministic xorshift generator for simulated loss and reordering.
+#[derive(Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    const fn new(seed: u64) -> Self {
+        Self((seed ^ 0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    const fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Send
+// ---------------------------------------------------------------------------
+
+enum FrameSink {
+    Socket {
+        socket: UdpSocket,
+        addr: SocketAddr,
+        pace: Duration,
+    },
+    File(BufWriter<File>),
+}
+
+impl FrameSink {
+    fn open(endpoint: &Endpoint, pace_micros: Option<u64>) -> Result<Self, String> {
+        match endpoint {
+            Endpoint::Socket(addr) => {
+                let bind: SocketAddr = if addr.is_ipv4() {
+                    ([0, 0, 0, 0], 0).into()
+                } else {
+                    ([0_u16; 8], 0).into()
+                };
+                let socket = UdpSocket::bind(bind)
+                    .map_err(|error| format!("failed binding UDP socket: {error}"))?;
+                Ok(Self::Socket {
+                    socket,
+                    addr: *addr,
+                    pace: Duration::from_micros(pace_micros.unwrap_or(DEFAULT_SOCKET_PACE_MICROS)),
+                })
+            }
+            Endpoint::File(path) => File::create(path)
+                .map(|file| Self::File(BufWriter::new(file)))
+                .map_err(|error| format!("failed creating `{}`: {error}", path.display())),
+        }
+    }
+
+    fn write(&mut self, frame: &[u8]) -> Result<(), String> {
+        match self {
+            Self::Socket { socket, addr, pace } => {
+                if frame.len() > MAX_UDP_PAYLOAD {
+                    return Err(format!(
+                        "snapshot frame of {} bytes exceeds a UDP datagram; ship to a file instead",
+                        frame.len()
+                    ));
+                }
+                socket
+                    .send_to(frame, *addr)
+                    .map_err(|error| format!("failed sending to {addr}: {error}"))?;
+                if !pace.is_zero() {
+                    std::thread::sleep(*pace);
+                }
+                Ok(())
+            }
+            Self::File(writer) => write_file_frame(writer, frame)
+                .map_err(|error| format!("failed writing snapshot stream: {error}")),
+        }
+    }
+
+    fn finish(self) -> Result<(), String> {
+        match self {
+            Self::Socket { .. } => Ok(()),
+            Self::File(mut writer) => writer
+                .flush()
+                .map_err(|error| format!("failed writing snapshot stream: {error}")),
+        }
+    }
+}
+
+fn write_file_frame<W: Write>(writer: &mut W, frame: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(frame.len())
+        .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "snapshot frame too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(frame)
+}
+
+/// Read the next length-prefixed frame; a missing or truncated frame ends
+/// the stream.
+fn read_file_frame<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0_u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+    let mut frame = vec![0_u8; u32::from_le_bytes(len) as usize];
+    match reader.read_exact(&mut frame) {
+        Ok(()) => Ok(Some(frame)),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn run_send(options: &SendOptions) -> Result<String, String> {
+    let image = Connection::open(&options.db_path)
+        .and_then(|connection| connection.serialize("main"))
+        .map_err(|error| format!("failed reading `{}`: {error}", options.db_path))?;
+    if image.len() < 100 {
+        return Err(format!("`{}` is not a database image", options.db_path));
+    }
+    let raw_page_size = u16::from_be_bytes([image[16], image[17]]);
+    let page_size = if raw_page_size == 1 {
+        65_536
+    } else {
+        u32::from(raw_page_size)
+    };
+    let config = SenderConfig {
+        symbol_size: options.symbol_size,
+        ..SenderConfig::default()
+    };
+    let (mut sender, manifest) =
+        SnapshotSender::from_image(&image, page_size, config).map_err(|error| error.to_string())?;
+    let mut packets = Vec::new();
+    while let Some(packet) = sender.next_packet() {
+        packets.push(frame(
+            FRAME_SYMBOL,
+            &packet.to_bytes().map_err(|error| error.to_string())?,
+        ));
+    }
+
+    let manifest_frame = frame(FRAME_MANIFEST, &manifest.to_bytes());
+    let mut sink = FrameSink::open(&options.to, options.pace_micros)?;
+    let mut rng = XorShift::new(options.seed);
+    let mut sent = 0_u64;
+    let mut dropped = 0_u64;
+    for _ in 0..options.passes {
+        let mut order: Vec<usize> = (0..packets.len()).collect();
+        if options.shuffle {
+            rng.shuffle(&mut order);
+        }
+        sink.write(&manifest_frame)?;
+        for (n, &index) in order.iter().enumerate() {
+            if n > 0 && n % MANIFEST_EVERY == 0 {
+                sink.write(&manifest_frame)?;
+            }
+            if rng.next_u64() % 100 < u64::from(options.loss_percent) {
+                dropped += 1;
+                continue;
+            }
+            sink.write(&packets[index])?;
+            sent += 1;
+        }
+    }
+    sink.finish()?;
+
+    Ok(format!(
+        "sent snapshot of {} page(s) in {} block(s): {sent} symbol(s) over {} pass(es), \
+         {dropped} dropped, digest {}",
+        manifest.page_count,
+        manifest.num_blocks,
+        options.passes,
+        hex(&manifest.digest)
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Receive
+// ---------------------------------------------------------------------------
+
+enum FrameSource {
+    Socket(UdpSocket),
+    File(BufReader<File>),
+}
+
+/// Receiver-side state shared by live streams and spool replay.
+#[derive(Default)]
+struct SnapshotAssembler {
+    manifest: Option<SnapshotManifest>,
+    receiver: Option<SnapshotReceiver>,
+    early_symbols: Vec<Vec<u8>>,
+    blocks: Vec<DecodedBlock>,
+    spool: Option<BufWriter<File>>,
+    accepted: u64,
+    duplicates: u64,
+    rejected: u64,
+}
+
+impl SnapshotAssembler {
+    fn is_complete(&self) -> bool {
+        self.receiver
+            .as_ref()
+            .is_some_and(|receiver| receiver.state() == SnapshotReceiverState::Complete)
+    }
+
+    fn blocks_decoded(&self) -> u32 {
+        self.receiver
+            .as_ref()
+            .map_or(0, |receiver| receiver.resume_state().decoded_count())
+    }
+
+    fn ingest(&mut self, frame: &[u8]) -> Result<(), String> {
+        match frame.split_first() {
+            Some((&FRAME_MANIFEST, body)) => {
+                let Ok(manifest) = SnapshotManifest::from_bytes(body) else {
+                    self.rejected += 1;
+                    return Ok(());
+                };
+                match self.manifest {
+                    Some(existing) if existing != manifest => Err(String::from(
+                        "stream carries a different snapshot than the saved progress",
+                    )),
+                    Some(_) => Ok(()),
+                    None => {
+                        self.manifest = Some(manifest);
+                        self.receiver = Some(SnapshotReceiver::new(
+                            manifest.num_blocks as usize,
+                            manifest.page_size,
+                        ));
+                        self.spool_frame(frame)?;
+                        for symbol in std::mem::take(&mut self.early_symbols) {
+                            self.ingest_symbol(&symbol)?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            Some((&FRAME_SYMBOL, _)) if self.receiver.is_none() => {
+                self.early_symbols.push(frame.to_vec());
+                Ok(())
+            }
+            Some((&FRAME_SYMBOL, _)) => self.ingest_symbol(frame),
+            _ => {
+                self.rejected += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn ingest_symbol(&mut self, frame: &[u8]) -> Result<(), String> {
+        let Some(receiver) = self.receiver.as_mut() else {
+            return Ok(());
+        };
+        let packet = match ReplicationPacket::from_bytes(&frame[1..]) {
+            Ok(packet) if packet.verify_integrity(None) => packet,
+            _ => {
+                self.rejected += 1;
+                return Ok(());
+            }
+        };
+        match receiver.process_packet(&packet) {
+            Ok(SnapshotPacketResult::Accepted) => {}
+            Ok(SnapshotPacketResult::BlockDecoded(_)) => {
+                self.blocks.extend(receiver.take_decoded_blocks());
+            }
+            Ok(
+                SnapshotPacketResult::Duplicate
+                | SnapshotPacketResult::BlockAlreadyDecoded
+                | SnapshotPacketResult::AlreadyComplete,
+            ) => {
+                self.duplicates += 1;
+                return Ok(());
+            }
+            Ok(SnapshotPacketResult::Rejected) | Err(_) => {
+                self.rejected += 1;
+                return Ok(());
+            }
+        }
+        self.accepted += 1;
+        self.spool_frame(frame)
+    }
+
+    fn spool_frame(&mut self, frame: &[u8]) -> Result<(), String> {
+        match self.spool.as_mut() {
+            Some(spool) => write_file_frame(spool, frame)
+                .map_err(|error| format!("failed saving receive progress: {error}")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn spool_path(out_path: &Path) -> PathBuf {
+    let mut name = out_path.as_os_str().to_owned();
+    name.push(SPOOL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Replay saved progress into `assembler` and reopen the spool for append,
+/// dropping a torn trailing frame.
+fn resume_from_spool(assembler: &mut SnapshotAssembler, path: &Path) -> Result<u64, String> {
+    let io_error = |error: io::Error| format!("failed reading `{}`: {error}", path.display());
+    let mut replayed = 0;
+    let mut good_len = 0_u64;
+    if path.exists() {
+        let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+        while let Some(frame) = read_file_frame(&mut reader).map_err(io_error)? {
+            assembler.ingest(&frame)?;
+            replayed += 1;
+            good_len += 4 + frame.len() as u64;
+        }
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_error)?;
+    file.set_len(good_len).map_err(io_error)?;
+    assembler.spool = Some(BufWriter::new(file));
+    Ok(replayed)
+}
+
+fn run_recv(options: &RecvOptions) -> Result<String, String> {
+    let source = match &options.from {
+        Endpoint::Socket(addr) => {
+            let socket =
+                UdpSocket::bind(addr).map_err(|error| format!("failed binding {addr}: {error}"))?;
+            FrameSource::Socket(socket)
+        }
+        Endpoint::File(path) => File::open(path)
+            .map(|file| FrameSource::File(BufReader::new(file)))
+            .map_err(|error| format!("failed opening `{}`: {error}", path.display()))?,
+    };
+    receive_snapshot(source, options)
+}
+
+fn receive_snapshot(source: FrameSource, options: &RecvOptions) -> Result<String, String> {
+    let out_path = &options.out_path;
+    if out_path.exists() {
+        return Err(format!(
+            "refusing to overwrite existing `{}`",
+            out_path.display()
+        ));
+    }
+    let spool = spool_path(out_path);
+    let mut assembler = SnapshotAssembler::default();
+    let replayed = resume_from_spool(&mut assembler, &spool)?;
+
+    match source {
+        FrameSource::Socket(socket) => {
+            socket
+                .set_read_timeout(Some(Duration::from_secs(options.timeout_secs.max(1))))
+                .map_err(|error| format!("failed configuring socket: {error}"))?;
+            let mut buf = vec![0_u8; MAX_UDP_PAYLOAD];
+            while !assembler.is_complete() {
+                match socket.recv_from(&mut buf) {
+                    Ok((len, _)) => assembler.ingest(&buf[..len])?,
+                    Err(error)
+                        if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                    {
+                        break;
+                    }
+                    Err(error) => return Err(format!("failed receiving snapshot: {error}")),
+                }
+            }
+        }
+        FrameSource::File(mut reader) => {
+            while !assembler.is_complete() {
+                let Some(frame) = read_file_frame(&mut reader)
+                    .map_err(|error| format!("failed reading snapshot stream: {error}"))?
+                else {
+                    break;
+                };
+                assembler.ingest(&frame)?;
+            }
+        }
+    }
+    if let Some(writer) = assembler.spool.as_mut() {
+        writer
+            .flush()
+            .map_err(|error| format!("failed saving receive progress: {error}"))?;
+    }
+
+    let Some(manifest) = assembler.manifest else {
+        return Err(String::from(
+            "no snapshot manifest received; is the sender running?",
+        ));
+    };
+    if !assembler.is_complete() {
+        return Err(format!(
+            "snapshot incomplete: {}/{} block(s) decoded; progress saved to `{}`, rerun to resume",
+            assembler.blocks_decoded(),
+            manifest.num_blocks,
+            spool.display()
+        ));
+    }
+
+    let image = assemble_snapshot_image(&manifest, &assembler.blocks).map_err(|error| {
+        format!(
+            "{error}; delete `{}` and receive the snapshot again",
+            spool.display()
+        )
+    })?;
+    let mut staging = out_path.as_os_str().to_owned();
+    staging.push(".tmp");
+    let staging = PathBuf::from(staging);
+    fs::write(&staging, &image)
+        .and_then(|()| fs::rename(&staging, out_path))
+        .map_err(|error| format!("failed writing `{}`: {error}", out_path.display()))?;
+    drop(assembler.spool.take());
+    let _ = fs::remove_file(&spool);
+
+    Ok(format!(
+        "received snapshot of {} page(s) in {} block(s): {} symbol(s) used, {} duplicate(s), \
+         {} rejected, {replayed} frame(s) resumed; digest {} verified",
+        manifest.page_count,
+        manifest.num_blocks,
+        assembler.accepted,
+        assembler.duplicates,
+        assembler.rejected,
+        hex(&manifest.digest)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::net::UdpSocket;
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use fsqlite::Connection;
+
+    use super::{
+        Endpoint, FrameSource, RecvOptions, SnapshotCommand, parse_snapshot_args, receive_snapshot,
+        run, spool_path,
+    };
+
+    fn unique_temp_path(prefix: &str, extension: &str) -> PathBuf {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock should be after UNIX_EPOCH");
+        std::env::temp_dir().join(format!(
+            "{prefix}_{}_{}.{extension}",
+            std::process::id(),
+            now.as_nanos()
+        ))
+    }
+
+    fn run_args(args: &[&str]) -> (i32, String, String) {
+        let args: Vec<OsString> = args.iter().map(OsString::from).collect();
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let code = run(&args, &mut out, &mut err);
+        (
+            code,
+            String::from_utf8(out).expect("utf8 stdout"),
+            String::from_utf8(err).expect("utf8 stderr"),
+        )
+    }
+
+    fn seed_database(prefix: &str) -> PathBuf {
+        let path = unique_temp_path(prefix, "db");
+        let connection = Connection::open(path.to_string_lossy()).expect("open source db");
+        connection
+            .execute("CREATE TABLE edge (id INTEGER PRIMARY KEY, note TEXT);")
+            .expect("create table");
+        for id in 0..200 {
+            connection
+                .execute(&format!(
+                    "INSERT INTO edge VALUES ({id}, 'node-{id}-{}');",
+                    "x".repeat(40)
+                ))
+                .expect("insert row");
+        }
+        path
+    }
+
+    fn row_count(path: &Path) -> usize {
+        Connection::open(path.to_string_lossy())
+            .expect("open rebuilt db")
+            .query("SELECT id FROM edge;")
+            .expect("query rebuilt db")
+            .len()
+    }
+
+    #[test]
+    fn test_parse_snapshot_send_and_recv() {
+        let args: Vec<OsString> = ["send", "app.db", "--to=127.0.0.1:7000", "--passes", "3"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let SnapshotCommand::Send(send) = parse_snapshot_args(&args).expect("send args") else {
+            panic!("expected send");
+        };
+        assert_eq!(send.db_path, "app.db");
+        assert_eq!(send.to, Endpoint::Socket("127.0.0.1:7000".parse().unwrap()));
+        assert_eq!(send.passes, 3);
+
+        let args: Vec<OsString> = ["recv", "--from", "stream.fsnap", "out.db"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        let SnapshotCommand::Recv(recv) = parse_snapshot_args(&args).expect("recv args") else {
+            panic!("expected recv");
+        };
+        assert_eq!(recv.from, Endpoint::File(PathBuf::from("stream.fsnap")));
+        assert_eq!(recv.out_path, PathBuf::from("out.db"));
+
+        for bad in [
+            &["send", "app.db"][..],
+            &["recv", "--from", "x", "a.db", "b.db"],
+            &["send", "app.db", "--to", "x", "--loss", "100"],
+            &["send", "app.db", "--to", "x", "--timeout", "3"],
+            &["push"],
+        ] {
+            let args: Vec<OsString> = bad.iter().map(OsString::from).collect();
+            assert!(parse_snapshot_args(&args).is_err(), "{bad:?} should fail");
+        }
+    }
+
+    #[test]
+    fn test_snapshot_file_transfer_survives_loss_and_resumes() {
+        let source = seed_database("fsqlite_cli_snapshot_src");
+        let lossy = unique_temp_path("fsqlite_cli_snapshot_lossy", "fsnap");
+        let clean = unique_temp_path("fsqlite_cli_snapshot_clean", "fsnap");
+        let out = unique_temp_path("fsqlite_cli_snapshot_out", "db");
+        let source_arg = source.to_string_lossy().into_owned();
+        let out_arg = out.to_string_lossy().into_owned();
+
+        let (code, stdout, _) = run_args(&[
+            "send",
+            &source_arg,
+            "--to",
+            &lossy.to_string_lossy(),
+            "--loss",
+            "90",
+            "--seed",
+            "7",
+        ]);
+        assert_eq!(code, 0);
+        assert!(stdout.contains("dropped"));
+        let (code, _, stderr) = run_args(&["recv", "--from", &lossy.to_string_lossy(), &out_arg]);
+        assert_eq!(code, 1);
+        assert!(stderr.contains("rerun to resume"), "{stderr}");
+        assert!(spool_path(&out).exists());
+        assert!(!out.exists());
+
+        let (code, _, _) = run_args(&[
+            "send",
+            &source_arg,
+            "--to",
+            &clean.to_string_lossy(),
+            "--shuffle",
+            "--seed",
+            "3",
+        ]);
+        assert_eq!(code, 0);
+        let (code, stdout, stderr) =
+            run_args(&["recv", "--from", &clean.to_string_lossy(), &out_arg]);
+        assert_eq!(code, 0, "{stderr}");
+        assert!(stdout.contains("verified"));
+        assert!(!stdout.contains(" 0 frame(s) resumed"), "{stdout}");
+        assert!(!spool_path(&out).exists());
+        assert_eq!(row_count(&out), 200);
+
+        let (code, _, stderr) = run_args(&["recv", "--from", &clean.to_string_lossy(), &out_arg]);
+        assert_eq!(code, 1);
+        assert!(stderr.contains("refusing to overwrite"));
+
+        for path in [&source, &lossy, &clean, &out] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_loopback_socket_transfer() {
+        let source = seed_database("fsqlite_cli_snapshot_udp_src");
+        let out = unique_temp_path("fsqlite_cli_snapshot_udp_out", "db");
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("bind receiver");
+        let addr = socket.local_addr().expect("receiver addr");
+        let options = RecvOptions {
+            from: Endpoint::Socket(addr),
+            out_path: out.clone(),
+            timeout_secs: 5,
+        };
+        let receiver =
+            std::thread::spawn(move || receive_snapshot(FrameSource::Socket(socket), &options));
+
+        let (code, _, stderr) = run_args(&[
+            "send",
+            &source.to_string_lossy(),
+            "--to",
+            &addr.to_string(),
+            "--passes",
+            "3",
+            "--shuffle",
+        ]);
+        assert_eq!(code, 0, "{stderr}");
+        let summary = receiver
+            .join()
+            .expect("receiver thread")
+            .expect("snapshot received");
+        assert!(summary.contains("verified"));
+        assert_eq!(row_count(&out), 200);
+
+        for path in [&source, &out] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,980 @@
+//! Compile-time statement authorization (§9.4).
+//!
+//! [`StatementAuthorizer`] walks a parsed [`Statement`] before it is compiled
+//! and reports every table/column read, write, DDL operation, PRAGMA,
+//! ATTACH/DETACH, transaction-control statement and function call to a
+//! registered [`Authorizer`], in the spirit of `sqlite3_set_authorizer`.
+//!
+//! A `Deny` answer fails compilation with [`FrankenError::AuthDenied`]. An
+//! `Ignore` answer replaces column reads and function calls with `NULL`,
+//! drops ignored `UPDATE` assignments, and turns any other ignored statement
+//! into a no-op.
+//!
+//! Views are expanded where they are used: reads inside a view body are
+//! reported against its base tables, and a view whose body had reads masked
+//! is replaced by the masked query as a subquery. Trigger bodies are stored
+//! as written and authorized each time the trigger fires (see
+//! [`StatementAuthorizer::in_trigger`]).
+
+use fsqlite_ast::{
+    Assignment, AssignmentTarget, ColumnRef, CreateTableBody, DeleteStatement, DropObjectType,
+    Expr, FrameBound, FromClause, FunctionArgs, InSet, InsertSource, InsertStatement,
+    JoinConstraint, LimitClause, Literal, OrderingTerm, PragmaValue, QualifiedName, ResultColumn,
+    SelectCore, SelectStatement, Span, Statement, TableOrSubquery, UpdateStatement, UpsertAction,
+    WindowSpec, WithClause,
+};
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_func::{AuthAction, AuthResult, Authorizer};
+
+/// Outcome of authorizing a single statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthorizedStatement {
+    /// Every action was allowed; run the statement as parsed.
+    Unchanged,
+    /// Some column reads, function calls or assignments were ignored; run
+    /// this rewritten statement instead.
+    Rewritten(Box<Statement>),
+    /// A statement-level action was ignored; the statement does nothing.
+    Skipped,
+}
+
+/// A table source visible to column references at one query level.
+#[derive(Debug)]
+struct Source {
+    /// Alias, or the table name when unaliased.
+    visible: String,
+    /// Underlying table and database for real tables; `None` for subqueries,
+    /// CTEs, table-valued functions and pseudo-tables such as `excluded`.
+    table: Option<(String, String)>,
+    /// Declared column names, when known.
+    columns: Option<Vec<String>>,
+}
+
+/// Declared column names of a main/temp table, or `None` when unknown.
+pub type TableColumns<'a> = &'a dyn Fn(&str) -> Option<Vec<String>>;
+
+/// Stored query of a main/temp view, or `None` when the name is not a view.
+pub type ViewQuery<'a> = &'a dyn Fn(&str) -> Option<SelectStatement>;
+
+/// Walks parsed statements and consults an [`Authorizer`] for each action.
+pub struct StatementAuthorizer<'a> {
+    authorizer: &'a dyn Authorizer,
+    table_columns: TableColumns<'a>,
+    view_query: ViewQuery<'a>,
+    trigger: Option<String>,
+    /// Table whose `OLD`/`NEW` rows a trigger body can read.
+    trigger_table: Option<String>,
+    scopes: Vec<Vec<Source>>,
+    ctes: Vec<String>,
+    /// Views whose bodies are being walked, innermost last.
+    views: Vec<String>,
+    rewritten: bool,
+}
+
+impl<'a> StatementAuthorizer<'a> {
+    /// Create a walker that resolves bare column names via `table_columns`
+    /// and expands the views `view_query` knows about.
+    pub fn new(
+        authorizer: &'a dyn Authorizer,
+        table_columns: TableColumns<'a>,
+        view_query: ViewQuery<'a>,
+    ) -> Self {
+        Self {
+            authorizer,
+            table_columns,
+            view_query,
+            trigger: None,
+            trigger_table: None,
+            scopes: Vec::new(),
+            ctes: Vec::new(),
+            views: Vec::new(),
+            rewritten: false,
+        }
+    }
+
+    /// Attribute every action to the trigger `name` on `table`, for
+    /// authorizing a trigger body statement before it is bound to a row.
+    /// `OLD`/`NEW` references and bare `table` columns count as reads of
+    /// `table`.
+    #[must_use]
+    pub fn in_trigger(mut self, name: &str, table: &str) -> Self {
+        self.trigger = Some(name.to_owned());
+        self.trigger_table = Some(table.to_owned());
+        self
+    }
+
+    /// Authorize `statement`, returning how it should be executed.
+    ///
+    /// # Errors
+    /// Returns [`FrankenError::AuthDenied`] when any action is denied.
+    pub fn authorize(&mut self, statement: &Statement) -> Result<AuthorizedStatement> {
+        self.scopes.clear();
+        self.ctes.clear();
+        self.views.clear();
+        self.rewritten = false;
+        if let Some(table) = self.trigger_table.clone() {
+            let name = QualifiedName::bare(table);
+            let pseudo = ["old", "new"].map(|alias| self.table_source(&name, Some(alias)));
+            let own = self.table_source(&name, None);
+            self.scopes.push(pseudo.into_iter().chain([own]).collect());
+        }
+        let mut statement = statement.clone();
+        if !self.statement(&mut statement)? {
+            return Ok(AuthorizedStatement::Skipped);
+        }
+        Ok(if self.rewritten {
+            AuthorizedStatement::Rewritten(Box::new(statement))
+        } else {
+            AuthorizedStatement::Unchanged
+        })
+    }
+
+    /// Ask the authorizer about one action: `Ok(false)` means ignore.
+    fn check(
+        &self,
+        action: AuthAction,
+        arg1: Option<&str>,
+        arg2: Option<&str>,
+        db_name: Option<&str>,
+    ) -> Result<bool> {
+        match self
+            .authorizer
+            .authorize(action, arg1, arg2, db_name, self.trigger.as_deref())
+        {
+            AuthResult::Ok => Ok(true),
+            AuthResult::Ignore => Ok(false),
+            AuthResult::Deny => Err(FrankenError::AuthDenied),
+        }
+    }
+
+    /// Authorize one statement; `Ok(false)` means it should be skipped.
+    fn statement(&mut self, statement: &mut Statement) -> Result<bool> {
+        match statement {
+            Statement::Select(select) => {
+                if !self.check(AuthAction::Select, None, None, None)? {
+                    return Ok(false);
+                }
+                self.select(select)?;
+                Ok(true)
+            }
+            Statement::Insert(insert) => self.insert(insert),
+            Statement::Update(update) => self.update(update),
+            Statement::Delete(delete) => self.delete(delete),
+            Statement::CreateTable(create) => {
+                let action = if create.temporary {
+                    AuthAction::CreateTempTable
+                } else {
+                    AuthAction::CreateTable
+                };
+                let db = db_name(&create.name, create.temporary);
+                if !self.check(action, Some(&create.name.name), None, Some(db))? {
+                    return Ok(false);
+                }
+                if let CreateTableBody::AsSelect(select) = &mut create.body {
+                    if !self.check(AuthAction::Select, None, None, None)? {
+                        return Ok(false);
+                    }
+                    self.select(select)?;
+                }
+                Ok(true)
+            }
+            Statement::CreateIndex(create) => {
+                let temporary = is_temp(&create.name);
+                let action = if temporary {
+                    AuthAction::CreateTempIndex
+                } else {
+                    AuthAction::CreateIndex
+                };
+                let db = db_name(&create.name, temporary);
+                self.check(
+                    action,
+                    Some(&create.name.name),
+                    Some(&create.table),
+                    Some(db),
+                )
+            }
+            Statement::CreateView(create) => {
+                let action = if create.temporary {
+                    AuthAction::CreateTempView
+                } else {
+                    AuthAction::CreateView
+                };
+                let db = db_name(&create.name, create.temporary);
+                self.check(action, Some(&create.name.name), None, Some(db))
+            }
+            Statement::CreateTrigger(create) => {
+                let action = if create.temporary {
+                    AuthAction::CreateTempTrigger
+                } else {
+                    AuthAction::CreateTrigger
+                };
+                // The body is authorized each time the trigger fires.
+                let db = db_name(&create.name, create.temporary);
+                self.check(
+                    action,
+                    Some(&create.name.name),
+                    Some(&create.table),
+                    Some(db),
+                )
+            }
+            Statement::CreateVirtualTable(create) => {
+                let db = db_name(&create.name, false);
+                self.check(
+                    AuthAction::CreateVtable,
+                    Some(&create.name.name),
+                    Some(&create.module),
+                    Some(db),
+                )
+            }
+            Statement::Drop(drop) => {
+                let temporary = is_temp(&drop.name);
+                let action = match (drop.object_type, temporary) {
+                    (DropObjectType::Table, false) => AuthAction::DropTable,
+                    (DropObjectType::Table, true) => AuthAction::DropTempTable,
+                    (DropObjectType::View, false) => AuthAction::DropView,
+                    (DropObjectType::View, true) => AuthAction::DropTempView,
+                    (DropObjectType::Index, false) => AuthAction::DropIndex,
+                    (DropObjectType::Index, true) => AuthAction::DropTempIndex,
+                    (DropObjectType::Trigger, false) => AuthAction::DropTrigger,
+                    (DropObjectType::Trigger, true) => AuthAction::DropTempTrigger,
+                };
+                let db = db_name(&drop.name, temporary);
+                self.check(action, Some(&drop.name.name), None, Some(db))
+            }
+            Statement::AlterTable(alter) => {
+                let db = db_name(&alter.table, false);
+                self.check(
+                    AuthAction::AlterTable,
+                    Some(db),
+                    Some(&alter.table.name),
+                    Some(db),
+                )
+            }
+            Statement::Begin(_) => self.check(AuthAction::Transaction, Some("BEGIN"), None, None),
+            Statement::Commit => self.check(AuthAction::Transaction, Some("COMMIT"), None, None),
+            Statement::Rollback(rollback) => match &rollback.to_savepoint {
+                Some(name) => self.check(AuthAction::Savepoint, Some("ROLLBACK"), Some(name), None),
+                None => self.check(AuthAction::Transaction, Some("ROLLBACK"), None, None),
+            },
+            Statement::Savepoint(name) => {
+                self.check(AuthAction::Savepoint, Some("BEGIN"), Some(name), None)
+            }
+            Statement::Release(name) => {
+                self.check(AuthAction::Savepoint, Some("RELEASE"), Some(name), None)
+            }
+            Statement::Attach(attach) => {
+                let filename = expr_text(&attach.expr);
+                if !self.check(AuthAction::Attach, Some(&filename), None, None)? {
+                    return Ok(false);
+                }
+                self.expr(&mut attach.expr)?;
+                Ok(true)
+            }
+            Statement::Detach(name) => self.check(AuthAction::Detach, Some(name), None, None),
+            Statement::Pragma(pragma) => {
+                let arg = pragma.value.as_ref().map(|value| match value {
+                    PragmaValue::Assign(expr) | PragmaValue::Call(expr) => expr_text(expr),
+                });
+                self.check(
+                    AuthAction::Pragma,
+                    Some(&pragma.name.name),
+                    arg.as_deref(),
+                    pragma.name.schema.as_deref(),
+                )
+            }
+            Statement::Reindex(name) => self.check(
+                AuthAction::Reindex,
+                name.as_ref().map(|name| name.name.as_str()),
+                None,
+                name.as_ref().and_then(|name| name.schema.as_deref()),
+            ),
+            Statement::Analyze(name) => self.check(
+                AuthAction::Analyze,
+                name.as_ref().map(|name| name.name.as_str()),
+                None,
+                name.as_ref().and_then(|name| name.schema.as_deref()),
+            ),
+            Statement::Explain { stmt, .. } => self.statement(stmt),
+            Statement::Vacuum(vacuum) => {
+                let schema = vacuum.schema.as_deref().unwrap_or("main");
+                let into = vacuum.into.as_ref().map(expr_text);
+                if !self.check(
+                    AuthAction::Vacuum,
+                    Some(schema),
+                    into.as_deref(),
+                    Some(schema),
+                )? {
+                    return Ok(false);
+                }
+                if let Some(into) = &mut vacuum.into {
+                    self.expr(into)?;
+                }
+                Ok(true)
+            }
+        }
+    }
+
+    fn insert(&mut self, insert: &mut InsertStatement) -> Result<bool> {
+        self.with_clause(insert.with.as_mut())?;
+        let table = insert.table.name.clone();
+        let db = db_name(&insert.table, false).to_owned();
+        if !self.check(AuthAction::Insert, Some(&table), None, Some(&db))? {
+            return Ok(false);
+        }
+        match &mut insert.source {
+            InsertSource::Values(rows) => {
+                self.scopes.push(Vec::new());
+                for expr in rows.iter_mut().flatten() {
+                    self.expr(expr)?;
+                }
+                self.scopes.pop();
+            }
+            InsertSource::Select(select) => self.select(select)?,
+            InsertSource::DefaultValues => {}
+        }
+        let target = self.table_source(&insert.table, insert.alias.as_deref());
+        let excluded = Source {
+            visible: "excluded".to_owned(),
+            table: None,
+            columns: target.columns.clone(),
+        };
+        self.scopes.push(vec![target, excluded]);
+        for upsert in &mut insert.upsert {
+            if let Some(target) = &mut upsert.target
+                && let Some(where_clause) = &mut target.where_clause
+            {
+                self.expr(where_clause)?;
+            }
+            let keep = match &mut upsert.action {
+                UpsertAction::Update {
+                    assignments,
+                    where_clause,
+                } => {
+                    let keep = self.assignments(&table, &db, assignments)?;
+                    if keep && let Some(where_clause) = where_clause {
+                        self.expr(where_clause)?;
+                    }
+                    keep
+                }
+                UpsertAction::Nothing => true,
+            };
+            if !keep {
+                upsert.action = UpsertAction::Nothing;
+            }
+        }
+        self.result_columns(&mut insert.returning)?;
+        self.scopes.pop();
+        Ok(true)
+    }
+
+    fn update(&mut self, update: &mut UpdateStatement) -> Result<bool> {
+        self.with_clause(update.with.as_mut())?;
+        let table = update.table.name.name.clone();
+        let db = db_name(&update.table.name, false).to_owned();
+        let target = self.table_source(&update.table.name, update.table.alias.as_deref());
+        self.scopes.push(vec![target]);
+        if let Some(from) = &mut update.from {
+            self.from(from)?;
+        }
+        if !self.assignments(&table, &db, &mut update.assignments)? {
+            return Ok(false);
+        }
+        if let Some(where_clause) = &mut update.where_clause {
+            self.expr(where_clause)?;
+        }
+        self.result_columns(&mut update.returning)?;
+        self.order_and_limit(&mut update.order_by, update.limit.as_mut())?;
+        self.scopes.pop();
+        Ok(true)
+    }
+
+    fn delete(&mut self, delete: &mut DeleteStatement) -> Result<bool> {
+        self.with_clause(delete.with.as_mut())?;
+        let db = db_name(&delete.table.name, false);
+        if !self.check(
+            AuthAction::Delete,
+            Some(&delete.table.name.name),
+            None,
+            Some(db),
+        )? {
+            return Ok(false);
+        }
+        let target = self.table_source(&delete.table.name, delete.table.alias.as_deref());
+        self.scopes.push(vec![target]);
+        if let Some(where_clause) = &mut delete.where_clause {
+            self.expr(where_clause)?;
+        }
+        self.result_columns(&mut delete.returning)?;
+        self.order_and_limit(&mut delete.order_by, delete.limit.as_mut())?;
+        self.scopes.pop();
+        Ok(true)
+    }
+
+    /// Authorize `UPDATE` assignments, dropping those whose columns are all
+    /// ignored. Returns `false` when no assignment is left to perform.
+    fn assignments(
+        &mut self,
+        table: &str,
+        db: &str,
+        assignments: &mut Vec<Assignment>,
+    ) -> Result<bool> {
+        if assignments.is_empty() {
+            return Ok(true);
+        }
+        let mut kept = Vec::with_capacity(assignments.len());
+        for mut assignment in std::mem::take(assignments) {
+            let columns: Vec<&str> = match &assignment.target {
+                AssignmentTarget::Column(column) => vec![column.as_str()],
+                AssignmentTarget::ColumnList(columns) => {
+                    columns.iter().map(String::as_str).collect()
+                }
+            };
+            let mut ignored = 0;
+            for column in &columns {
+                if !self.check(AuthAction::Update, Some(table), Some(column), Some(db))? {
+                    ignored += 1;
+                }
+            }
+            if ignored == columns.len() {
+                self.rewritten = true;
+                continue;
+            }
+            // A row-value assignment cannot drop only some of its targets.
+            if ignored > 0 {
+                return Err(FrankenError::AuthDenied);
+            }
+            self.expr(&mut assignment.value)?;
+            kept.push(assignment);
+        }
+        *assignments = kept;
+        Ok(!assignments.is_empty())
+    }
+
+    fn with_clause(&mut self, with: Option<&mut WithClause>) -> Result<()> {
+        let Some(with) = with else {
+            return Ok(());
+        };
+        if with.recursive {
+            self.check(AuthAction::Recursive, None, None, None)?;
+        }
+        for cte in &mut with.ctes {
+            self.ctes.push(cte.name.clone());
+            self.select(&mut cte.query)?;
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, select: &mut SelectStatement) -> Result<()> {
+        let ctes = self.ctes.len();
+        self.with_clause(select.with.as_mut())?;
+        self.core(&mut select.body.select)?;
+        if !select.body.compounds.is_empty() {
+            // ORDER BY on a compound refers to its result columns only.
+            self.scopes.pop();
+            for (_, core) in &mut select.body.compounds {
+                self.core(core)?;
+                self.scopes.pop();
+            }
+            self.scopes.push(Vec::new());
+        }
+        self.order_and_limit(&mut select.order_by, select.limit.as_mut())?;
+        self.scopes.pop();
+        self.ctes.truncate(ctes);
+        Ok(())
+    }
+
+    /// Authorize one SELECT core, leaving its FROM scope pushed.
+    fn core(&mut self, core: &mut SelectCore) -> Result<()> {
+        self.scopes.push(Vec::new());
+        match core {
+            SelectCore::Values(rows) => {
+                for expr in rows.iter_mut().flatten() {
+                    self.expr(expr)?;
+                }
+            }
+            SelectCore::Select {
+                columns,
+                from,
+                where_clause,
+                group_by,
+                having,
+                windows,
+                ..
+            } => {
+                if let Some(from) = from {
+                    self.from(from)?;
+                }
+                self.result_columns(columns)?;
+                if let Some(where_clause) = where_clause {
+                    self.expr(where_clause)?;
+                }
+                for expr in group_by {
+                    self.expr(expr)?;
+                }
+                if let Some(having) = having {
+                    self.expr(having)?;
+                }
+                for window in windows {
+                    self.window(&mut window.spec)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn from(&mut self, from: &mut FromClause) -> Result<()> {
+        self.table_or_subquery(&mut from.source)?;
+        for join in &mut from.joins {
+            self.table_or_subquery(&mut join.table)?;
+            if let Some(JoinConstraint::On(expr)) = &mut join.constraint {
+                self.expr(expr)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn table_or_subquery(&mut self, source: &mut TableOrSubquery) -> Result<()> {
+        let source = match source {
+            TableOrSubquery::Table { name, alias, .. } => {
+                let visible = alias.clone().unwrap_or_else(|| name.name.clone());
+                match self.view(name)? {
+                    Some((query, masked)) => {
+                        if masked {
+                            *source = TableOrSubquery::Subquery {
+                                query: Box::new(query),
+                                alias: Some(visible.clone()),
+                            };
+                        }
+                        Source {
+                            visible,
+                            table: None,
+                            columns: None,
+                        }
+                    }
+                    None => self.table_source(name, alias.as_deref()),
+                }
+            }
+            TableOrSubquery::Subquery { query, alias } => {
+                self.select(query)?;
+                Source {
+                    visible: alias.clone().unwrap_or_default(),
+                    table: None,
+                    columns: None,
+                }
+            }
+            TableOrSubquery::TableFunction { name, args, alias } => {
+                for arg in args {
+                    self.expr(arg)?;
+                }
+                Source {
+                    visible: alias.clone().unwrap_or_else(|| name.clone()),
+                    table: None,
+                    columns: None,
+                }
+            }
+            TableOrSubquery::ParenJoin(from) => return self.from(from),
+        };
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(source);
+        }
+        Ok(())
+    }
+
+    /// Walk the body of the view `name` refers to, if it is one.
+    ///
+    /// Reads inside the view are reported against its base tables, with the
+    /// view as the inner-most trigger or view. Returns the view's query with
+    /// ignored reads masked, and whether anything was masked.
+    fn view(&mut self, name: &QualifiedName) -> Result<Option<(SelectStatement, bool)>> {
+        let db = db_name(name, false);
+        if self.is_cte(name)
+            || !(db.eq_ignore_ascii_case("main") || db.eq_ignore_ascii_case("temp"))
+        {
+            return Ok(None);
+        }
+        let Some(mut query) = (self.view_query)(&name.name) else {
+            return Ok(None);
+        };
+        if self
+            .views
+            .iter()
+            .any(|view| view.eq_ignore_ascii_case(&name.name))
+        {
+            return Err(FrankenError::function_error(format!(
+                "view {} is circularly defined",
+                name.name
+            )));
+        }
+        // The view body sees neither the outer query's tables nor its CTEs.
+        let scopes = std::mem::take(&mut self.scopes);
+        let ctes = std::mem::take(&mut self.ctes);
+        let rewritten = std::mem::replace(&mut self.rewritten, false);
+        let trigger = self.trigger.replace(name.name.clone());
+        self.views.push(name.name.clone());
+        let outcome = self.select(&mut query);
+        self.views.pop();
+        self.trigger = trigger;
+        self.ctes = ctes;
+        self.scopes = scopes;
+        let masked = std::mem::replace(&mut self.rewritten, rewritten);
+        outcome?;
+        self.rewritten |= masked;
+        Ok(Some((query, masked)))
+    }
+
+    fn is_cte(&self, name: &QualifiedName) -> bool {
+        name.schema.is_none()
+            && self
+                .ctes
+                .iter()
+                .any(|cte| cte.eq_ignore_ascii_case(&name.name))
+    }
+
+    fn table_source(&self, name: &QualifiedName, alias: Option<&str>) -> Source {
+        let visible = alias.unwrap_or(&name.name).to_owned();
+        if self.is_cte(name) {
+            return Source {
+                visible,
+                table: None,
+                columns: None,
+            };
+        }
+        let db = db_name(name, false);
+        let columns = if db.eq_ignore_ascii_case("main") || db.eq_ignore_ascii_case("temp") {
+            (self.table_columns)(&name.name)
+        } else {
+            None
+        };
+        Source {
+            visible,
+            table: Some((name.name.clone(), db.to_owned())),
+            columns,
+        }
+    }
+
+    fn result_columns(&mut self, columns: &mut Vec<ResultColumn>) -> Result<()> {
+        let mut authorized = Vec::with_capacity(columns.len());
+        for column in std::mem::take(columns) {
+            match column {
+                ResultColumn::Star => match self.star(None)? {
+                    Some(expanded) => authorized.extend(expanded),
+                    None => authorized.push(ResultColumn::Star),
+                },
+                ResultColumn::TableStar(table) => match self.star(Some(&table))? {
+                    Some(expanded) => authorized.extend(expanded),
+                    None => authorized.push(ResultColumn::TableStar(table)),
+                },
+                ResultColumn::Expr {
+                    mut expr,
+                    mut alias,
+                } => {
+                    let name = match &expr {
+                        Expr::Column(column, _) => Some(column.column.clone()),
+                        _ => None,
+                    };
+                    self.expr(&mut expr)?;
+                    // A masked column keeps the name it would have had.
+                    if alias.is_none() && matches!(expr, Expr::Literal(Literal::Null, _)) {
+                        alias = name;
+                    }
+                    authorized.push(ResultColumn::Expr { expr, alias });
+                }
+            }
+        }
+        *columns = authorized;
+        Ok(())
+    }
+
+    /// Authorize the columns behind `*` / `table.*`. Returns an explicit
+    /// column list when some of them were ignored, `None` otherwise.
+    fn star(&mut self, qualifier: Option<&str>) -> Result<Option<Vec<ResultColumn>>> {
+        let Some(scope) = self.scopes.last() else {
+            return Ok(None);
+        };
+        let mut expanded = Vec::new();
+        let mut any_ignored = false;
+        let mut unexpandable = false;
+        for source in scope
+            .iter()
+            .filter(|source| qualifier.is_none_or(|q| source.visible.eq_ignore_ascii_case(q)))
+        {
+            match (&source.table, &source.columns) {
+                (Some((table, db)), Some(columns)) => {
+                    for column in columns {
+                        let allowed =
+                            self.check(AuthAction::Read, Some(table), Some(column), Some(db))?;
+                        any_ignored |= !allowed;
+                        let expr = if allowed {
+                            Expr::Column(
+                                ColumnRef::qualified(source.visible.clone(), column.clone()),
+                                Span::ZERO,
+                            )
+                        } else {
+                            Expr::Literal(Literal::Null, Span::ZERO)
+                        };
+                        expanded.push(ResultColumn::Expr {
+                            expr,
+                            alias: Some(column.clone()),
+                        });
+                    }
+                }
+                _ if !source.visible.is_empty() => {
+                    expanded.push(ResultColumn::TableStar(source.visible.clone()));
+                }
+                _ => unexpandable = true,
+            }
+        }
+        if !any_ignored {
+            return Ok(None);
+        }
+        // Hidden columns cannot be masked next to an unnamed subquery.
+        if unexpandable {
+            return Err(FrankenError::AuthDenied);
+        }
+        self.rewritten = true;
+        Ok(Some(expanded))
+    }
+
+    fn order_and_limit(
+        &mut self,
+        order_by: &mut [OrderingTerm],
+        limit: Option<&mut LimitClause>,
+    ) -> Result<()> {
+        for term in order_by {
+            self.expr(&mut term.expr)?;
+        }
+        if let Some(limit) = limit {
+            self.expr(&mut limit.limit)?;
+            if let Some(offset) = &mut limit.offset {
+                self.expr(offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn window(&mut self, spec: &mut WindowSpec) -> Result<()> {
+        for expr in &mut spec.partition_by {
+            self.expr(expr)?;
+        }
+        for term in &mut spec.order_by {
+            self.expr(&mut term.expr)?;
+        }
+        if let Some(frame) = &mut spec.frame {
+            for bound in std::iter::once(&mut frame.start).chain(frame.end.as_mut()) {
+                if let FrameBound::Preceding(expr) | FrameBound::Following(expr) = bound {
+                    self.expr(expr)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self, expr: &mut Expr) -> Result<()> {
+        if let Some(span) = self.ignored_leaf(expr)? {
+            *expr = Expr::Literal(Literal::Null, span);
+            self.rewritten = true;
+            return Ok(());
+        }
+        match expr {
+            Expr::Literal(..) | Expr::Column(..) | Expr::Raise { .. } | Expr::Placeholder(..) => {}
+            Expr::BinaryOp { left, right, .. } => {
+                self.expr(left)?;
+                self.expr(right)?;
+            }
+            Expr::UnaryOp { expr, .. }
+            | Expr::Cast { expr, .. }
+            | Expr::Collate { expr, .. }
+            | Expr::IsNull { expr, .. } => self.expr(expr)?,
+            Expr::Between {
+                expr, low, high, ..
+            } => {
+                self.expr(expr)?;
+                self.expr(low)?;
+                self.expr(high)?;
+            }
+            Expr::In { expr, set, .. } => {
+                self.expr(expr)?;
+                match set {
+                    InSet::List(items) => {
+                        for item in items {
+                            self.expr(item)?;
+                        }
+                    }
+                    InSet::Subquery(select) => self.select(select)?,
+                    InSet::Table(name) => self.in_table(name)?,
+                }
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                ..
+            } => {
+                self.expr(expr)?;
+                self.expr(pattern)?;
+                if let Some(escape) = escape {
+                    self.expr(escape)?;
+                }
+            }
+            Expr::Case {
+                operand,
+                whens,
+                else_expr,
+                ..
+            } => {
+                if let Some(operand) = operand {
+                    self.expr(operand)?;
+                }
+                for (when, then) in whens {
+                    self.expr(when)?;
+                    self.expr(then)?;
+                }
+                if let Some(else_expr) = else_expr {
+                    self.expr(else_expr)?;
+                }
+            }
+            Expr::Exists { subquery, .. } | Expr::Subquery(subquery, _) => self.select(subquery)?,
+            Expr::FunctionCall {
+                args,
+                order_by,
+                filter,
+                over,
+                ..
+            } => {
+                if let FunctionArgs::List(args) = args {
+                    for arg in args {
+                        self.expr(arg)?;
+                    }
+                }
+                for term in order_by {
+                    self.expr(&mut term.expr)?;
+                }
+                if let Some(filter) = filter {
+                    self.expr(filter)?;
+                }
+                if let Some(over) = over {
+                    self.window(over)?;
+                }
+            }
+            Expr::JsonAccess { expr, path, .. } => {
+                self.expr(expr)?;
+                self.expr(path)?;
+            }
+            Expr::RowValue(items, _) => {
+                for item in items {
+                    self.expr(item)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a column read or function call; returns its span when the
+    /// authorizer asked for it to be ignored.
+    fn ignored_leaf(&self, expr: &Expr) -> Result<Option<Span>> {
+        match expr {
+            Expr::Column(column, span) => {
+                let Some((table, db)) = self.resolve(column) else {
+                    return Ok(None);
+                };
+                let allowed = self.check(
+                    AuthAction::Read,
+                    Some(table),
+                    Some(&column.column),
+                    Some(db),
+                )?;
+                Ok((!allowed).then_some(*span))
+            }
+            Expr::FunctionCall { name, span, .. } => {
+                let allowed = self.check(AuthAction::Function, None, Some(name), None)?;
+                Ok((!allowed).then_some(*span))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// `x IN table` reads every column of `table`; it cannot be masked, so
+    /// an ignored column denies the statement.
+    fn in_table(&mut self, name: &QualifiedName) -> Result<()> {
+        if let Some((_, masked)) = self.view(name)? {
+            return if masked {
+                Err(FrankenError::AuthDenied)
+            } else {
+                Ok(())
+            };
+        }
+        let source = self.table_source(name, None);
+        if let (Some((table, db)), Some(columns)) = (&source.table, &source.columns) {
+            for column in columns {
+                if !self.check(AuthAction::Read, Some(table), Some(column), Some(db))? {
+                    return Err(FrankenError::AuthDenied);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve a column reference to the real table and database it reads,
+    /// searching from the innermost query level outwards.
+    fn resolve(&self, column: &ColumnRef) -> Option<(&str, &str)> {
+        fn real(source: &Source) -> Option<(&str, &str)> {
+            source
+                .table
+                .as_ref()
+                .map(|(table, db)| (table.as_str(), db.as_str()))
+        }
+        for scope in self.scopes.iter().rev() {
+            if let Some(qualifier) = &column.table {
+                if let Some(source) = scope
+                    .iter()
+                    .find(|source| source.visible.eq_ignore_ascii_case(qualifier))
+                {
+                    return real(source);
+                }
+                continue;
+            }
+            if let Some(source) = scope.iter().find(|source| {
+                source.columns.as_ref().is_some_and(|columns| {
+                    columns
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(&column.column))
+                })
+            }) {
+                return real(source);
+            }
+            // A lone source whose columns are unknown owns every bare name.
+            if let [source] = scope.as_slice()
+                && source.columns.is_none()
+            {
+                return real(source);
+            }
+        }
+        None
+    }
+}
+
+/// Database name reported for an object: `temp` for temporary objects,
+/// otherwise its schema qualifier or `main`.
+fn db_name(name: &QualifiedName, temporary: bool) -> &str {
+    if temporary {
+        "temp"
+    } else {
+        name.schema.as_deref().unwrap_or("main")
+    }
+}
+
+fn is_temp(name: &QualifiedName) -> bool {
+    name.schema
+        .as_deref()
+        .is_some_and(|schema| schema.eq_ignore_ascii_case("temp"))
+}
+
+/// Text of an ATTACH filename or PRAGMA argument: string literals unquoted,
+/// anything else as written.
+fn expr_text(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(Literal::String(text), _) => text.clone(),
+        Expr::Column(column, _) if column.table.is_none() => column.column.clone(),
+        other => other.to_string(),
+    }
+}
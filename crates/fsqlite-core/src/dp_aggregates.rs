@@ -0,0 +1,814 @@
+//! SQL surface for differentially private aggregates (bd-19u.7, §12.5).
+//!
+//! Exposes the [`DpEngine`] from `fsqlite-mvcc` to SQL through three
+//! aggregate functions registered in the connection's function registry:
+//!
+//! - `dp_count(*)`, `dp_count(x)` and `dp_count(x, epsilon)`: noisy count of
+//!   rows (or non-NULL values), sensitivity 1.
+//! - `dp_sum(x, max_contribution)` and `dp_sum(x, max_contribution, epsilon)`:
+//!   noisy sum with each value clamped to `[-max_contribution,
+//!   max_contribution]`, sensitivity `max_contribution`.
+//! - `dp_avg(x, max_contribution)` and `dp_avg(x, max_contribution, epsilon)`:
+//!   a noisy clamped sum divided by a noisy count, each released with half of
+//!   the epsilon.
+//!
+//! The contribution bound and epsilon must be numeric literals, the same for
+//! every call of one overload within a statement, so an empty group can be
+//! released with noise calibrated exactly like a non-empty one: whether a
+//! group has rows is never visible as an exact NULL.
+//!
+//! Every finalized aggregate (one per group) charges its epsilon against the
+//! connection's [`PrivacyBudget`] via the Laplace mechanism, empty groups
+//! included. When the budget cannot cover the charge the aggregate fails and
+//! the statement is refused. The budget is set once per connection with
+//! `PRAGMA fsqlite.dp_budget = <ε>` and cannot be reset after any epsilon
+//! has been spent; the
+//! default per-aggregate epsilon with `PRAGMA fsqlite.dp_epsilon = <ε>`, and
+//! its state is visible through the `fsqlite_dp_budget()` table-valued
+//! function.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_func::vtab::{
+    ColumnContext, ErasedVtabInstance, IndexInfo, VirtualTable, VirtualTableCursor,
+    VtabModuleFactory,
+};
+use fsqlite_func::{AggregateFunction, FunctionRegistry};
+use fsqlite_mvcc::{DpEngine, DpError, PrivacyBudget, sensitivity};
+use fsqlite_parser::TokenKind;
+use fsqlite_parser::lexer::Lexer;
+use fsqlite_types::SqliteValue;
+use fsqlite_types::cx::Cx;
+
+/// Epsilon charged by a DP aggregate that does not pass one explicitly.
+pub const DEFAULT_DP_QUERY_EPSILON: f64 = 0.1;
+
+/// Name of the table-valued function reporting the session budget.
+pub const DP_BUDGET_TABLE_NAME: &str = "fsqlite_dp_budget";
+
+/// Result columns of `fsqlite_dp_budget()`.
+pub const DP_BUDGET_TABLE_COLUMN_NAMES: [&str; 5] = [
+    "total_epsilon",
+    "spent_epsilon",
+    "remaining_epsilon",
+    "queries_charged",
+    "query_epsilon",
+];
+
+#[derive(Debug)]
+struct DpSession {
+    engine: Option<DpEngine>,
+    query_epsilon: f64,
+    /// Constant arguments of the DP calls in the running statement.
+    statement: DpStatementArgs,
+}
+
+/// Shared handle to one connection's differential-privacy session.
+///
+/// The aggregates and the budget virtual table hold clones of the handle, so
+/// every DP query issued on a connection draws from the same budget.
+#[derive(Debug, Clone)]
+pub struct DpSessionHandle {
+    inner: Arc<Mutex<DpSession>>,
+}
+
+impl Default for DpSessionHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DpSessionHandle {
+    /// Create a session with no budget configured.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(DpSession {
+                engine: None,
+                query_epsilon: DEFAULT_DP_QUERY_EPSILON,
+                statement: DpStatementArgs::default(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DpSession> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Configure the session budget as `total_epsilon`.
+    ///
+    /// The budget may be replaced only until the first aggregate charges it;
+    /// after that, resetting it would hand out fresh epsilon over the same
+    /// data. Noise is drawn from a PRNG seeded with `seed`, or from the
+    /// operating system's CSPRNG when `None`.
+    ///
+    /// # Errors
+    /// Returns [`FrankenError::FunctionError`] if `total_epsilon` is not
+    /// positive and finite, if epsilon has already been spent, or if the OS
+    /// random source fails.
+    pub fn configure_budget(&self, total_epsilon: f64, seed: Option<u64>) -> Result<()> {
+        let mut session = self.lock();
+        if let Some(engine) = &session.engine
+            && engine.budget().spent() > 0.0
+        {
+            return Err(FrankenError::function_error(format!(
+                "differential privacy budget already charged ({} of {} epsilon spent); \
+                 it cannot be reset on this connection",
+                engine.budget().spent(),
+                engine.budget().total()
+            )));
+        }
+        let seed = match seed {
+            Some(seed) => seed,
+            None => os_random_seed()?,
+        };
+        session.engine = Some(DpEngine::new(total_epsilon, seed).map_err(dp_error)?);
+        Ok(())
+    }
+
+    /// Snapshot of the configured budget, or `None` if none is configured.
+    #[must_use]
+    pub fn budget(&self) -> Option<PrivacyBudget> {
+        self.lock()
+            .engine
+            .as_ref()
+            .map(|engine| engine.budget().clone())
+    }
+
+    /// Epsilon charged by aggregates that do not pass one explicitly.
+    #[must_use]
+    pub fn query_epsilon(&self) -> f64 {
+        self.lock().query_epsilon
+    }
+
+    /// Set the epsilon charged by aggregates that do not pass one explicitly.
+    ///
+    /// # Errors
+    /// Returns [`FrankenError::FunctionError`] if `epsilon` is not positive
+    /// and finite.
+    pub fn set_query_epsilon(&self, epsilon: f64) -> Result<()> {
+        validate_positive("epsilon", epsilon)?;
+        self.lock().query_epsilon = epsilon;
+        Ok(())
+    }
+
+    /// Bind the constant arguments of the statement about to run, replacing
+    /// those of the previous one.
+    pub fn begin_statement(&self, args: DpStatementArgs) {
+        self.lock().statement = args;
+    }
+
+    /// Constants the running statement passes to `(kind, explicit_epsilon)`.
+    fn statement_call(&self, kind: DpKind, explicit_epsilon: bool) -> Option<DpCall> {
+        self.lock()
+            .statement
+            .find(kind, explicit_epsilon)
+            .map(|call| call.args)
+    }
+
+    /// Check the constants one group stepped with against the statement's,
+    /// recording them for calls the statement text did not show (views and
+    /// trigger bodies).
+    fn bind_call(&self, call: DpStatementCall) -> Result<()> {
+        self.lock().statement.bind(call)
+    }
+
+    /// Charge `epsilon` and return `true_value` plus calibrated Laplace noise.
+    fn release(&self, true_value: f64, sensitivity: f64, epsilon: f64) -> Result<f64> {
+        let mut session = self.lock();
+        let engine = session.engine.as_mut().ok_or_else(|| {
+            FrankenError::function_error(
+                "no differential privacy budget configured; set PRAGMA fsqlite.dp_budget",
+            )
+        })?;
+        engine
+            .laplace(true_value, sensitivity, epsilon)
+            .map(|result| result.noisy_value)
+            .map_err(dp_error)
+    }
+}
+
+/// Noise seed from the OS CSPRNG; predictable noise would void the guarantee.
+fn os_random_seed() -> Result<u64> {
+    let mut seed = [0_u8; 8];
+    getrandom::getrandom(&mut seed).map_err(|err| {
+        FrankenError::function_error(format!("cannot seed differential privacy noise: {err}"))
+    })?;
+    Ok(u64::from_le_bytes(seed))
+}
+
+fn dp_error(err: DpError) -> FrankenError {
+    FrankenError::function_error(err.to_string())
+}
+
+fn validate_positive(what: &str, value: f64) -> Result<()> {
+    if value > 0.0 && value.is_finite() {
+        Ok(())
+    } else {
+        Err(FrankenError::function_error(format!(
+            "{what} must be positive and finite, got {value}"
+        )))
+    }
+}
+
+/// Register `dp_count`, `dp_sum` and `dp_avg` bound to `session`.
+pub fn register_dp_aggregates(registry: &mut FunctionRegistry, session: &DpSessionHandle) {
+    for kind in [DpKind::Count, DpKind::Sum, DpKind::Avg] {
+        for explicit_epsilon in [false, true] {
+            registry.register_aggregate(DpAggregate {
+                kind,
+                explicit_epsilon,
+                star: false,
+                session: session.clone(),
+            });
+        }
+    }
+    registry.register_aggregate(DpAggregate {
+        kind: DpKind::Count,
+        explicit_epsilon: false,
+        star: true,
+        session: session.clone(),
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DpKind {
+    Count,
+    Sum,
+    Avg,
+}
+
+impl DpKind {
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Count, Self::Sum, Self::Avg]
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Count => "dp_count",
+            Self::Sum => "dp_sum",
+            Self::Avg => "dp_avg",
+        }
+    }
+
+    /// Position of the contribution bound among the call arguments.
+    const fn bound_arg(self) -> Option<usize> {
+        match self {
+            Self::Count => None,
+            Self::Sum | Self::Avg => Some(1),
+        }
+    }
+}
+
+/// Contribution bound and explicit epsilon of one DP call.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct DpCall {
+    max_contribution: Option<f64>,
+    epsilon: Option<f64>,
+}
+
+/// The constants passed to one `(name, arity)` overload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DpStatementCall {
+    kind: DpKind,
+    explicit_epsilon: bool,
+    args: DpCall,
+}
+
+/// Constant arguments of the DP aggregate calls in one statement.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DpStatementArgs {
+    calls: Vec<DpStatementCall>,
+}
+
+/// Cheap pre-check: whether `sql` can call a DP aggregate at all.
+#[must_use]
+pub fn may_call_dp_aggregate(sql: &str) -> bool {
+    sql.as_bytes()
+        .windows(3)
+        .any(|window| window.eq_ignore_ascii_case(b"dp_"))
+}
+
+impl DpStatementArgs {
+    /// Collect the bound and epsilon of every `dp_*` call in `sql`.
+    ///
+    /// # Errors
+    /// Returns [`FrankenError::FunctionError`] if one of them is not a
+    /// numeric literal, or if one overload is called with different values.
+    pub fn scan(sql: &str) -> Result<Self> {
+        let mut args = Self::default();
+        if !may_call_dp_aggregate(sql) {
+            return Ok(args);
+        }
+        let tokens = Lexer::tokenize(sql);
+        for (pos, token) in tokens.iter().enumerate() {
+            let TokenKind::Id(name) = &token.kind else {
+                continue;
+            };
+            let Some(kind) = DpKind::from_name(name) else {
+                continue;
+            };
+            let qualified = pos > 0 && matches!(tokens[pos - 1].kind, TokenKind::Dot);
+            if qualified
+                || !matches!(
+                    tokens.get(pos + 1).map(|t| &t.kind),
+                    Some(TokenKind::LeftParen)
+                )
+            {
+                continue;
+            }
+            let call_args = split_call_args(&tokens[pos + 2..]);
+            let (value_args, explicit_epsilon) = match (kind, call_args.len()) {
+                (DpKind::Count, 2) | (DpKind::Sum | DpKind::Avg, 3) => (call_args.len() - 1, true),
+                (DpKind::Sum | DpKind::Avg, 2) => (2, false),
+                _ => continue,
+            };
+            let literal = |index: usize, what: &str| {
+                literal_number(call_args[index]).ok_or_else(|| {
+                    FrankenError::function_error(format!(
+                        "{}: {what} must be a numeric literal",
+                        kind.name()
+                    ))
+                })
+            };
+            let call = DpStatementCall {
+                kind,
+                explicit_epsilon,
+                args: DpCall {
+                    max_contribution: kind
+                        .bound_arg()
+                        .map(|index| literal(index, "max_contribution"))
+                        .transpose()?,
+                    epsilon: explicit_epsilon
+                        .then(|| literal(value_args, "epsilon"))
+                        .transpose()?,
+                },
+            };
+            args.bind(call)?;
+        }
+        Ok(args)
+    }
+
+    fn find(&self, kind: DpKind, explicit_epsilon: bool) -> Option<&DpStatementCall> {
+        self.calls
+            .iter()
+            .find(|call| call.kind == kind && call.explicit_epsilon == explicit_epsilon)
+    }
+
+    fn bind(&mut self, call: DpStatementCall) -> Result<()> {
+        match self.find(call.kind, call.explicit_epsilon) {
+            Some(bound) if bound.args != call.args => Err(FrankenError::function_error(format!(
+                "{}: max_contribution and epsilon must be the same constants for every call \
+                 in a statement",
+                call.kind.name()
+            ))),
+            Some(_) => Ok(()),
+            None => {
+                self.calls.push(call);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Split the tokens after a call's `(` into its top-level arguments.
+fn split_call_args(tokens: &[fsqlite_parser::Token]) -> Vec<&[fsqlite_parser::Token]> {
+    let mut args = Vec::new();
+    let mut depth = 0_usize;
+    let mut start = 0;
+    for (pos, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::LeftParen => depth += 1,
+            TokenKind::RightParen if depth == 0 => {
+                if pos > start {
+                    args.push(&tokens[start..pos]);
+                }
+                return args;
+            }
+            TokenKind::RightParen => depth -= 1,
+            TokenKind::Comma if depth == 0 => {
+                args.push(&tokens[start..pos]);
+                start = pos + 1;
+            }
+            TokenKind::Eof => break,
+            _ => {}
+        }
+    }
+    Vec::new()
+}
+
+/// Value of an optionally signed numeric literal.
+#[allow(clippy::cast_precision_loss)]
+fn literal_number(tokens: &[fsqlite_parser::Token]) -> Option<f64> {
+    let (sign, literal) = match tokens {
+        [sign, literal] if matches!(sign.kind, TokenKind::Minus) => (-1.0, literal),
+        [sign, literal] if matches!(sign.kind, TokenKind::Plus) => (1.0, literal),
+        [literal] => (1.0, literal),
+        _ => return None,
+    };
+    match literal.kind {
+        TokenKind::Integer(value) => Some(sign * value as f64),
+        TokenKind::Float(value) => Some(sign * value),
+        _ => None,
+    }
+}
+
+/// One `(name, arity)` overload of a DP aggregate.
+struct DpAggregate {
+    kind: DpKind,
+    explicit_epsilon: bool,
+    /// `dp_count(*)`: count every row rather than non-NULL values.
+    star: bool,
+    session: DpSessionHandle,
+}
+
+/// Accumulator shared by all DP aggregates.
+#[derive(Debug, Default)]
+struct DpAggregateState {
+    rows: u64,
+    sum: f64,
+    /// Constants of the first row, which every later row must repeat.
+    call: Option<DpCall>,
+}
+
+impl DpAggregate {
+    const fn value_args(&self) -> usize {
+        match (self.kind, self.star) {
+            (DpKind::Count, true) => 0,
+            (DpKind::Count, false) => 1,
+            (DpKind::Sum | DpKind::Avg, _) => 2,
+        }
+    }
+
+    fn numeric_arg(&self, value: &SqliteValue, what: &str) -> Result<f64> {
+        let number = match value {
+            SqliteValue::Integer(_) | SqliteValue::Float(_) => value.to_float(),
+            _ => {
+                return Err(FrankenError::function_error(format!(
+                    "{}: {what} must be numeric",
+                    self.name()
+                )));
+            }
+        };
+        validate_positive(&format!("{}: {what}", self.name()), number)?;
+        Ok(number)
+    }
+}
+
+impl DpAggregate {
+    /// Constants passed on one row.
+    fn row_call(&self, args: &[SqliteValue]) -> Result<DpCall> {
+        Ok(DpCall {
+            max_contribution: self
+                .kind
+                .bound_arg()
+                .map(|index| self.numeric_arg(&args[index], "max_contribution"))
+                .transpose()?,
+            epsilon: self
+                .explicit_epsilon
+                .then(|| self.numeric_arg(&args[self.value_args()], "epsilon"))
+                .transpose()?,
+        })
+    }
+
+    /// Constants to finalize `state` with. A group that never stepped takes
+    /// them from the statement, so it is released like any other group.
+    fn finalize_call(&self, state: &DpAggregateState) -> Result<DpCall> {
+        let call = state
+            .call
+            .or_else(|| {
+                self.session
+                    .statement_call(self.kind, self.explicit_epsilon)
+            })
+            .unwrap_or_default();
+        let unknown = |what: &str| {
+            FrankenError::function_error(format!(
+                "{}: {what} of an empty group is unknown; pass it as a literal",
+                self.name()
+            ))
+        };
+        if self.kind.bound_arg().is_some() && call.max_contribution.is_none() {
+            return Err(unknown("max_contribution"));
+        }
+        if self.explicit_epsilon && call.epsilon.is_none() {
+            return Err(unknown("epsilon"));
+        }
+        Ok(call)
+    }
+}
+
+impl AggregateFunction for DpAggregate {
+    type State = DpAggregateState;
+
+    fn initial_state(&self) -> Self::State {
+        DpAggregateState::default()
+    }
+
+    fn step(&self, state: &mut Self::State, args: &[SqliteValue]) -> Result<()> {
+        let call = self.row_call(args)?;
+        match state.call {
+            Some(first) if first != call => {
+                return Err(FrankenError::function_error(format!(
+                    "{}: max_contribution and epsilon must be constant",
+                    self.name()
+                )));
+            }
+            Some(_) => {}
+            None => {
+                self.session.bind_call(DpStatementCall {
+                    kind: self.kind,
+                    explicit_epsilon: self.explicit_epsilon,
+                    args: call,
+                })?;
+                state.call = Some(call);
+            }
+        }
+        match self.kind {
+            DpKind::Count => {
+                if self.star || !args[0].is_null() {
+                    state.rows += 1;
+                }
+            }
+            DpKind::Sum | DpKind::Avg => {
+                let bound = call.max_contribution.unwrap_or(0.0);
+                if !args[0].is_null() {
+                    state.sum += args[0].to_float().clamp(-bound, bound);
+                    state.rows += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn finalize(&self, state: Self::State) -> Result<SqliteValue> {
+        let call = self.finalize_call(&state)?;
+        let epsilon = call.epsilon.unwrap_or_else(|| self.session.query_epsilon());
+        let bound = call.max_contribution.unwrap_or(0.0);
+        match self.kind {
+            DpKind::Count => {
+                let noisy = self
+                    .session
+                    .release(state.rows as f64, sensitivity::COUNT, epsilon)?;
+                Ok(SqliteValue::Integer(noisy.round().max(0.0) as i64))
+            }
+            DpKind::Sum => {
+                let noisy = self
+                    .session
+                    .release(state.sum, sensitivity::sum(bound), epsilon)?;
+                Ok(SqliteValue::Float(noisy))
+            }
+            // Dividing by the true count would make the sensitivity depend on
+            // the data, so both halves are released independently.
+            DpKind::Avg => {
+                let half = epsilon / 2.0;
+                let noisy_sum = self
+                    .session
+                    .release(state.sum, sensitivity::sum(bound), half)?;
+                let noisy_rows =
+                    self.session
+                        .release(state.rows as f64, sensitivity::COUNT, half)?;
+                Ok(SqliteValue::Float(
+                    (noisy_sum / noisy_rows.max(1.0)).clamp(-bound, bound),
+                ))
+            }
+        }
+    }
+
+    fn num_args(&self) -> i32 {
+        let args = self.value_args() + usize::from(self.explicit_epsilon);
+        i32::try_from(args).unwrap_or(i32::MAX)
+    }
+
+    fn name(&self) -> &str {
+        self.kind.name()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// fsqlite_dp_budget() virtual table
+// ---------------------------------------------------------------------------
+
+/// Module factory for `fsqlite_dp_budget()`, bound to one connection's session.
+pub struct DpBudgetModuleFactory {
+    session: DpSessionHandle,
+}
+
+impl DpBudgetModuleFactory {
+    /// Create a factory reporting on `session`.
+    #[must_use]
+    pub const fn new(session: DpSessionHandle) -> Self {
+        Self { session }
+    }
+}
+
+impl VtabModuleFactory for DpBudgetModuleFactory {
+    fn create(&self, _cx: &Cx, _args: &[&str]) -> Result<Box<dyn ErasedVtabInstance>> {
+        Ok(Box::new(DpBudgetTable {
+            session: Some(self.session.clone()),
+        }))
+    }
+
+    fn column_info(&self, _args: &[&str]) -> Vec<(String, char)> {
+        DP_BUDGET_TABLE_COLUMN_NAMES
+            .iter()
+            .map(|name| {
+                let affinity = if *name == "queries_charged" { 'D' } else { 'E' };
+                ((*name).to_owned(), affinity)
+            })
+            .collect()
+    }
+}
+
+/// Read-only, single-row view of the session privacy budget.
+///
+/// Produces no rows while no budget is configured.
+pub struct DpBudgetTable {
+    session: Option<DpSessionHandle>,
+}
+
+impl VirtualTable for DpBudgetTable {
+    type Cursor = DpBudgetCursor;
+
+    /// Instances opened without [`DpBudgetModuleFactory`] are not bound to a
+    /// session and report no budget.
+    fn connect(_cx: &Cx, _args: &[&str]) -> Result<Self> {
+        Ok(Self { session: None })
+    }
+
+    fn best_index(&self, info: &mut IndexInfo) -> Result<()> {
+        info.estimated_cost = 1.0;
+        info.estimated_rows = 1;
+        Ok(())
+    }
+
+    fn open(&self) -> Result<Self::Cursor> {
+        Ok(DpBudgetCursor {
+            session: self.session.clone(),
+            row: None,
+        })
+    }
+}
+
+/// Cursor over the budget snapshot taken at `filter` time.
+pub struct DpBudgetCursor {
+    session: Option<DpSessionHandle>,
+    row: Option<(PrivacyBudget, f64)>,
+}
+
+impl VirtualTableCursor for DpBudgetCursor {
+    fn filter(
+        &mut self,
+        _cx: &Cx,
+        _idx_num: i32,
+        _idx_str: Option<&str>,
+        _args: &[SqliteValue],
+    ) -> Result<()> {
+        self.row = self.session.as_ref().and_then(|session| {
+            session
+                .budget()
+                .map(|budget| (budget, session.query_epsilon()))
+        });
+        Ok(())
+    }
+
+    fn next(&mut self, _cx: &Cx) -> Result<()> {
+        self.row = None;
+        Ok(())
+    }
+
+    fn eof(&self) -> bool {
+        self.row.is_none()
+    }
+
+    fn column(&self, ctx: &mut ColumnContext, col: i32) -> Result<()> {
+        let value =
+            self.row
+                .as_ref()
+                .map_or(SqliteValue::Null, |(budget, query_epsilon)| match col {
+                    0 => SqliteValue::Float(budget.total()),
+                    1 => SqliteValue::Float(budget.spent()),
+                    2 => SqliteValue::Float(budget.remaining()),
+                    3 => SqliteValue::Integer(
+                        i64::try_from(budget.queries_charged()).unwrap_or(i64::MAX),
+                    ),
+                    4 => SqliteValue::Float(*query_epsilon),
+                    _ => SqliteValue::Null,
+                });
+        ctx.set_value(value);
+        Ok(())
+    }
+
+    fn rowid(&self) -> Result<i64> {
+        Ok(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_agg(session: &DpSessionHandle) -> DpAggregate {
+        DpAggregate {
+            kind: DpKind::Count,
+            explicit_epsilon: true,
+            star: false,
+            session: session.clone(),
+        }
+    }
+
+    #[test]
+    fn test_dp_aggregate_requires_configured_budget() {
+        let session = DpSessionHandle::new();
+        let agg = count_agg(&session);
+        let mut state = agg.initial_state();
+        agg.step(
+            &mut state,
+            &[SqliteValue::Integer(1), SqliteValue::Float(0.5)],
+        )
+        .unwrap();
+        let err = agg.finalize(state).unwrap_err();
+        assert!(err.to_string().contains("fsqlite.dp_budget"), "{err}");
+    }
+
+    #[test]
+    fn test_dp_aggregate_charges_budget_until_exhausted() {
+        let session = DpSessionHandle::new();
+        session.configure_budget(1.0, Some(7)).unwrap();
+        let agg = count_agg(&session);
+        for _ in 0..2 {
+            let mut state = agg.initial_state();
+            agg.step(
+                &mut state,
+                &[SqliteValue::Integer(1), SqliteValue::Float(0.5)],
+            )
+            .unwrap();
+            agg.finalize(state).unwrap();
+        }
+        let budget = session.budget().unwrap();
+        assert_eq!(budget.queries_charged(), 2);
+        assert!(budget.remaining().abs() < 1e-12);
+
+        let mut state = agg.initial_state();
+        agg.step(
+            &mut state,
+            &[SqliteValue::Integer(1), SqliteValue::Float(0.5)],
+        )
+        .unwrap();
+        let err = agg.finalize(state).unwrap_err();
+        assert!(err.to_string().contains("budget exhausted"), "{err}");
+        assert_eq!(session.budget().unwrap().queries_charged(), 2);
+    }
+
+    #[test]
+    fn test_dp_sum_clamps_contributions_to_bound() {
+        let session = DpSessionHandle::new();
+        session.configure_budget(1e9, Some(11)).unwrap();
+        let agg = DpAggregate {
+            kind: DpKind::Sum,
+            explicit_epsilon: true,
+            star: false,
+            session: session.clone(),
+        };
+        let mut state = agg.initial_state();
+        for value in [5, 1_000, -1_000, 2] {
+            agg.step(
+                &mut state,
+                &[
+                    SqliteValue::Integer(value),
+                    SqliteValue::Integer(10),
+                    SqliteValue::Float(1e8),
+                ],
+            )
+            .unwrap();
+        }
+        let SqliteValue::Float(noisy) = agg.finalize(state).unwrap() else {
+            panic!("dp_sum should produce a REAL");
+        };
+        assert!((noisy - 7.0).abs() < 1e-3, "noisy sum {noisy}");
+    }
+
+    #[test]
+    fn test_dp_budget_cursor_reports_session_snapshot() {
+        let session = DpSessionHandle::new();
+        let cx = Cx::new();
+        let table = DpBudgetTable {
+            session: Some(session.clone()),
+        };
+        let mut cursor = table.open().unwrap();
+        cursor.filter(&cx, 0, None, &[]).unwrap();
+        assert!(cursor.eof(), "no budget configured yet");
+
+        session.configure_budget(2.0, Some(1)).unwrap();
+        cursor.filter(&cx, 0, None, &[]).unwrap();
+        assert!(!cursor.eof());
+        let mut ctx = ColumnContext::new();
+        cursor.column(&mut ctx, 2).unwrap();
+        assert_eq!(ctx.take_value(), Some(SqliteValue::Float(2.0)));
+        cursor.next(&cx).unwrap();
+        assert!(cursor.eof());
+    }
+}
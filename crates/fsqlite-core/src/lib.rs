@@ -5,6 +5,7 @@
 //! (`FrankenError::Busy`) instead of queue-and-wait semantics.
 
 pub mod attach;
+pub mod authorizer;
 pub mod commit_marker;
 pub mod commit_repair;
 pub mod compat_persist;
@@ -12,6 +13,7 @@ pub mod connection;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod db_fec;
 pub mod decode_proofs;
+pub mod dp_aggregates;
 pub mod ecs_replication;
 pub mod epoch;
 pub mod explain;
@@ -20,22 +22,28 @@ pub mod lrc;
 pub mod native_index;
 pub mod permeation_map;
 pub mod por;
+pub mod provenance_sql;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod raptorq_codec;
 pub mod raptorq_integration;
 pub mod region;
 pub mod remote_effects;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod remote_tier;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod repair_engine;
 pub mod repair_symbols;
 pub mod replication_receiver;
 pub mod replication_sender;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replication_service;
 pub mod snapshot_shipping;
 pub mod source_block_partition;
 pub mod symbol_log;
 pub mod symbol_size_policy;
 pub mod tiered_storage;
 pub mod transaction;
+pub mod uri;
 pub mod wal_adapter;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod wal_fec_adapter;
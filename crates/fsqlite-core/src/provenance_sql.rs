@@ -0,0 +1,466 @@
+//! SQL surface for query lineage via provenance semirings (bd-2j365, §12.6).
+//!
+//! With `PRAGMA fsqlite.provenance = why|how`, every top-level SELECT is
+//! rewritten so that each output row carries a trailing
+//! [`PROVENANCE_COLUMN_NAME`] column describing the base tuples it was
+//! derived from. Annotations follow the N\[X\] semiring of
+//! [`ProvenanceToken`]:
+//!
+//! - a scanned row of table `t` is the generator `t:rowid`;
+//! - a joined row is the product (`*`) of its sources' generators, with the
+//!   NULL side of an outer join contributing `1`;
+//! - WHERE/ON filters keep or drop annotated rows unchanged;
+//! - a GROUP BY group (or DISTINCT row) is the sum (`+`) of its members.
+//!
+//! The rewrite appends `fsqlite_provenance(mode, raw, root, name, ...)`, where
+//! `raw` is a compact sum-of-products string built in SQL from each source's
+//! rowid (`root:rowid` factors joined by `*`, terms joined by `+`). The scalar
+//! function folds it back into a [`ProvenanceToken`] and renders why-provenance
+//! as a JSON array of `{"table", "rowid"}` objects or how-provenance as the
+//! semiring expression. Tuples read only by subqueries are not tracked.
+
+use std::collections::BTreeMap;
+
+use fsqlite_ast::{
+    BinaryOp, ColumnRef, CompoundOp, Distinctness, Expr, FromClause, FunctionArgs, Literal,
+    QualifiedName, ResultColumn, SelectCore, SelectStatement, Span, TableOrSubquery,
+};
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_func::{FunctionRegistry, ScalarFunction};
+use fsqlite_mvcc::{ProvenanceMode, ProvenanceToken};
+use fsqlite_types::SqliteValue;
+
+/// Name of the trailing result column added in provenance mode.
+pub const PROVENANCE_COLUMN_NAME: &str = "fsqlite_provenance";
+
+/// Scalar function that renders a raw provenance encoding.
+const PROVENANCE_FUNCTION_NAME: &str = "fsqlite_provenance";
+
+/// A base table that can contribute tuples to a provenance annotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceTable {
+    /// Table name as recorded in the schema.
+    pub name: String,
+    /// Root page of the table B-tree; identifies the table in [`ProvenanceToken`]s.
+    pub root_page: u32,
+    /// Column name that reaches the rowid (`rowid`, `_rowid_` or `oid`).
+    pub rowid_column: String,
+}
+
+/// Parse a `PRAGMA fsqlite.provenance` value.
+pub fn parse_provenance_mode(value: &str) -> Result<ProvenanceMode> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "off" | "none" | "0" | "false" => Ok(ProvenanceMode::Disabled),
+        "why" | "on" | "1" | "true" => Ok(ProvenanceMode::Why),
+        "how" => Ok(ProvenanceMode::How),
+        other => Err(FrankenError::Internal(format!(
+            "PRAGMA provenance must be OFF, WHY or HOW, got `{other}`"
+        ))),
+    }
+}
+
+/// The `PRAGMA fsqlite.provenance` spelling of `mode`.
+#[must_use]
+pub const fn provenance_mode_label(mode: ProvenanceMode) -> &'static str {
+    match mode {
+        ProvenanceMode::Disabled => "off",
+        ProvenanceMode::Why => "why",
+        ProvenanceMode::How => "how",
+    }
+}
+
+/// Register the `fsqlite_provenance` rendering function.
+pub fn register_provenance_functions(registry: &mut FunctionRegistry) {
+    registry.register_scalar(ProvenanceRenderFunc);
+}
+
+/// Rewrite `select` so each output row carries a provenance column.
+///
+/// `lookup_table` resolves a FROM-clause table to its rowid base table and
+/// `is_aggregate_call` reports whether `name(argc)` is an aggregate.
+///
+/// # Errors
+/// Returns [`FrankenError::NotImplemented`] for shapes whose provenance is
+/// not tracked: WITH clauses, compound operators other than UNION ALL,
+/// VALUES, subqueries and table-valued functions in FROM, views and
+/// WITHOUT ROWID tables, and `SELECT DISTINCT *`.
+pub fn rewrite_select_for_provenance(
+    select: &SelectStatement,
+    mode: ProvenanceMode,
+    lookup_table: &dyn Fn(&QualifiedName) -> Option<ProvenanceTable>,
+    is_aggregate_call: &dyn Fn(&str, usize) -> bool,
+) -> Result<SelectStatement> {
+    if select.with.is_some() {
+        return Err(unsupported("WITH clauses"));
+    }
+    if select
+        .body
+        .compounds
+        .iter()
+        .any(|(op, _)| *op != CompoundOp::UnionAll)
+    {
+        return Err(unsupported("compound operators other than UNION ALL"));
+    }
+    let mut rewritten = select.clone();
+    annotate_core(
+        &mut rewritten.body.select,
+        mode,
+        lookup_table,
+        is_aggregate_call,
+    )?;
+    for (_, core) in &mut rewritten.body.compounds {
+        annotate_core(core, mode, lookup_table, is_aggregate_call)?;
+    }
+    Ok(rewritten)
+}
+
+fn unsupported(what: &str) -> FrankenError {
+    FrankenError::NotImplemented(format!("provenance tracking does not support {what}"))
+}
+
+fn annotate_core(
+    core: &mut SelectCore,
+    mode: ProvenanceMode,
+    lookup_table: &dyn Fn(&QualifiedName) -> Option<ProvenanceTable>,
+    is_aggregate_call: &dyn Fn(&str, usize) -> bool,
+) -> Result<()> {
+    let SelectCore::Select {
+        distinct,
+        columns,
+        from,
+        group_by,
+        having,
+        ..
+    } = core
+    else {
+        return Err(unsupported("VALUES"));
+    };
+
+    let mut sources = Vec::new();
+    if let Some(from) = from.as_ref() {
+        collect_from_sources(from, lookup_table, &mut sources)?;
+    }
+
+    let mut grouped = !group_by.is_empty()
+        || having.is_some()
+        || columns.iter().any(|column| match column {
+            ResultColumn::Expr { expr, .. } => expr_calls_aggregate(expr, is_aggregate_call),
+            ResultColumn::Star | ResultColumn::TableStar(_) => false,
+        });
+    if *distinct == Distinctness::Distinct && !grouped {
+        // DISTINCT merges duplicate rows, i.e. groups by every result column.
+        let mut keys = Vec::with_capacity(columns.len());
+        for column in columns.iter() {
+            match column {
+                ResultColumn::Expr { expr, .. } => keys.push(expr.clone()),
+                ResultColumn::Star | ResultColumn::TableStar(_) => {
+                    return Err(unsupported("SELECT DISTINCT with `*`"));
+                }
+            }
+        }
+        *distinct = Distinctness::All;
+        *group_by = keys;
+        grouped = true;
+    }
+
+    let mut raw = row_token_expr(&sources);
+    if grouped {
+        raw = function_call("group_concat", vec![raw, string_literal("+")]);
+    }
+    let mut args = vec![string_literal(provenance_mode_label(mode)), raw];
+    let mut seen_roots = Vec::new();
+    for (_, table) in &sources {
+        if !seen_roots.contains(&table.root_page) {
+            seen_roots.push(table.root_page);
+            args.push(Expr::Literal(
+                Literal::Integer(i64::from(table.root_page)),
+                Span::ZERO,
+            ));
+            args.push(string_literal(&table.name));
+        }
+    }
+    columns.push(ResultColumn::Expr {
+        expr: function_call(PROVENANCE_FUNCTION_NAME, args),
+        alias: Some(PROVENANCE_COLUMN_NAME.to_owned()),
+    });
+    Ok(())
+}
+
+/// Collect `(qualifier, table)` for every base table joined in `from`.
+fn collect_from_sources(
+    from: &FromClause,
+    lookup_table: &dyn Fn(&QualifiedName) -> Option<ProvenanceTable>,
+    out: &mut Vec<(String, ProvenanceTable)>,
+) -> Result<()> {
+    collect_source(&from.source, lookup_table, out)?;
+    for join in &from.joins {
+        collect_source(&join.table, lookup_table, out)?;
+    }
+    Ok(())
+}
+
+fn collect_source(
+    source: &TableOrSubquery,
+    lookup_table: &dyn Fn(&QualifiedName) -> Option<ProvenanceTable>,
+    out: &mut Vec<(String, ProvenanceTable)>,
+) -> Result<()> {
+    match source {
+        TableOrSubquery::Table { name, alias, .. } => {
+            let table = lookup_table(name).ok_or_else(|| {
+                unsupported(&format!(
+                    "`{}`: only rowid base tables can contribute tuples",
+                    name.name
+                ))
+            })?;
+            let qualifier = alias.clone().unwrap_or_else(|| name.name.clone());
+            out.push((qualifier, table));
+            Ok(())
+        }
+        TableOrSubquery::ParenJoin(from) => collect_from_sources(from, lookup_table, out),
+        TableOrSubquery::Subquery { .. } => Err(unsupported("subqueries in FROM")),
+        TableOrSubquery::TableFunction { name, .. } => Err(unsupported(&format!(
+            "table-valued function `{name}` in FROM"
+        ))),
+    }
+}
+
+/// `coalesce('root:' || q.rowid, '') || coalesce('*root:' || q.rowid, '') ...`
+///
+/// The coalesce keeps the NULL side of an outer join from erasing the term.
+fn row_token_expr(sources: &[(String, ProvenanceTable)]) -> Expr {
+    let mut factors = sources.iter().enumerate().map(|(idx, (qualifier, table))| {
+        let separator = if idx == 0 { "" } else { "*" };
+        let rowid = Expr::Column(
+            ColumnRef::qualified(qualifier.clone(), table.rowid_column.clone()),
+            Span::ZERO,
+        );
+        let factor = concat(
+            string_literal(&format!("{separator}{}:", table.root_page)),
+            rowid,
+        );
+        function_call("coalesce", vec![factor, string_literal("")])
+    });
+    let Some(first) = factors.next() else {
+        return string_literal("");
+    };
+    factors.fold(first, concat)
+}
+
+fn expr_calls_aggregate(expr: &Expr, is_aggregate_call: &dyn Fn(&str, usize) -> bool) -> bool {
+    let recurse = |inner: &Expr| expr_calls_aggregate(inner, is_aggregate_call);
+    match expr {
+        Expr::FunctionCall {
+            name,
+            args,
+            over: None,
+            ..
+        } => match args {
+            FunctionArgs::Star => is_aggregate_call(name, 0),
+            FunctionArgs::List(list) => {
+                is_aggregate_call(name, list.len()) || list.iter().any(recurse)
+            }
+        },
+        Expr::BinaryOp { left, right, .. } => recurse(left) || recurse(right),
+        Expr::UnaryOp { expr: inner, .. }
+        | Expr::Cast { expr: inner, .. }
+        | Expr::Collate { expr: inner, .. } => recurse(inner),
+        Expr::Between {
+            expr: inner,
+            low,
+            high,
+            ..
+        } => recurse(inner) || recurse(low) || recurse(high),
+        Expr::Case {
+            operand,
+            whens,
+            else_expr,
+            ..
+        } => {
+            operand.as_deref().is_some_and(recurse)
+                || whens
+                    .iter()
+                    .any(|(when, then)| recurse(when) || recurse(then))
+                || else_expr.as_deref().is_some_and(recurse)
+        }
+        _ => false,
+    }
+}
+
+fn string_literal(value: &str) -> Expr {
+    Expr::Literal(Literal::String(value.to_owned()), Span::ZERO)
+}
+
+fn concat(left: Expr, right: Expr) -> Expr {
+    Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOp::Concat,
+        right: Box::new(right),
+        span: Span::ZERO,
+    }
+}
+
+fn function_call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::FunctionCall {
+        name: name.to_owned(),
+        args: FunctionArgs::List(args),
+        distinct: false,
+        order_by: Vec::new(),
+        filter: None,
+        over: None,
+        span: Span::ZERO,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Rendering
+// ---------------------------------------------------------------------------
+
+/// Fold a raw `root:rowid*root:rowid+...` encoding into a semiring token.
+///
+/// `NULL` (an empty group) is `0`; an empty product is `1`.
+fn parse_raw_token(raw: Option<&str>) -> Result<ProvenanceToken> {
+    let Some(raw) = raw else {
+        return Ok(ProvenanceToken::Zero);
+    };
+    let mut sum = ProvenanceToken::Zero;
+    for term in raw.split('+') {
+        let mut product = ProvenanceToken::One;
+        for factor in term.split('*').filter(|factor| !factor.is_empty()) {
+            let parsed = factor.split_once(':').and_then(|(root, rowid)| {
+                Some((root.parse::<u32>().ok()?, rowid.parse::<i64>().ok()?))
+            });
+            let Some((root, rowid)) = parsed else {
+                return Err(FrankenError::function_error(format!(
+                    "{PROVENANCE_FUNCTION_NAME}: malformed tuple `{factor}`"
+                )));
+            };
+            product = product.times(ProvenanceToken::base(root, rowid));
+        }
+        sum = sum.plus(product);
+    }
+    Ok(sum)
+}
+
+fn render_how(token: &ProvenanceToken, names: &BTreeMap<u32, String>) -> String {
+    match token {
+        ProvenanceToken::Zero => "0".to_owned(),
+        ProvenanceToken::One => "1".to_owned(),
+        ProvenanceToken::Base(id) => format!("{}:{}", table_label(id.table_root, names), id.rowid),
+        ProvenanceToken::Plus(a, b) => {
+            format!("({} + {})", render_how(a, names), render_how(b, names))
+        }
+        ProvenanceToken::Times(a, b) => {
+            format!("({} * {})", render_how(a, names), render_how(b, names))
+        }
+    }
+}
+
+fn table_label(root: u32, names: &BTreeMap<u32, String>) -> String {
+    names
+        .get(&root)
+        .cloned()
+        .unwrap_or_else(|| format!("t{root}"))
+}
+
+/// `fsqlite_provenance(mode, raw, root, name, ...)`.
+struct ProvenanceRenderFunc;
+
+impl ScalarFunction for ProvenanceRenderFunc {
+    fn invoke(&self, args: &[SqliteValue]) -> Result<SqliteValue> {
+        let mode = args
+            .first()
+            .and_then(SqliteValue::as_text)
+            .map_or(Ok(ProvenanceMode::Why), parse_provenance_mode)?;
+        let token = parse_raw_token(args.get(1).and_then(SqliteValue::as_text))?;
+        let names = args
+            .get(2..)
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .filter_map(|pair| {
+                let root = u32::try_from(pair[0].as_integer()?).ok()?;
+                Some((root, pair[1].as_text()?.to_owned()))
+            })
+            .collect::<BTreeMap<_, _>>();
+        let rendered = match mode {
+            ProvenanceMode::How => render_how(&token, &names),
+            ProvenanceMode::Why | ProvenanceMode::Disabled => {
+                let tuples = token
+                    .why_provenance()
+                    .into_iter()
+                    .map(|id| {
+                        serde_json::json!({
+                            "table": table_label(id.table_root, &names),
+                            "rowid": id.rowid,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                serde_json::Value::Array(tuples).to_string()
+            }
+        };
+        Ok(SqliteValue::Text(rendered))
+    }
+
+    fn is_deterministic(&self) -> bool {
+        true
+    }
+
+    fn num_args(&self) -> i32 {
+        -1
+    }
+
+    fn name(&self) -> &str {
+        PROVENANCE_FUNCTION_NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_raw_token_builds_sum_of_products() {
+        let token = parse_raw_token(Some("2:1*3:10+2:1*3:11")).unwrap();
+        let names = BTreeMap::from([(2, "users".to_owned()), (3, "orders".to_owned())]);
+        assert_eq!(
+            render_how(&token, &names),
+            "((users:1 * orders:10) + (users:1 * orders:11))"
+        );
+        assert_eq!(token.why_provenance().len(), 3);
+
+        assert!(parse_raw_token(None).unwrap().is_zero());
+        assert!(parse_raw_token(Some("")).unwrap().is_one());
+        assert!(parse_raw_token(Some("2:x")).is_err());
+    }
+
+    #[test]
+    fn test_render_function_emits_why_json() {
+        let rendered = ProvenanceRenderFunc
+            .invoke(&[
+                SqliteValue::Text("why".into()),
+                SqliteValue::Text("3:10*2:1+3:11*2:1".into()),
+                SqliteValue::Integer(2),
+                SqliteValue::Text("users".into()),
+                SqliteValue::Integer(3),
+                SqliteValue::Text("orders".into()),
+            ])
+            .unwrap();
+        assert_eq!(
+            rendered,
+            SqliteValue::Text(
+                r#"[{"table":"users","rowid":1},{"table":"orders","rowid":10},{"table":"orders","rowid":11}]"#
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_provenance_mode_accepts_pragma_spellings() {
+        assert_eq!(parse_provenance_mode("WHY").unwrap(), ProvenanceMode::Why);
+        assert_eq!(parse_provenance_mode("how").unwrap(), ProvenanceMode::How);
+        assert_eq!(
+            parse_provenance_mode("off").unwrap(),
+            ProvenanceMode::Disabled
+        );
+        assert!(parse_provenance_mode("maybe").is_err());
+    }
+}
@@ -0,0 +1,1416 @@
+//! Concrete L3 remote tiers for tiered storage (§3.5.11, `bd-1hi.29`).
+//!
+//! [`BlobRemoteTier`] implements [`RemoteTier`] over one or more
+//! [`BlobStore`] replicas:
+//! - [`DirectoryBlobStore`]: a local or mounted filesystem directory
+//! - [`S3BlobStore`]: an S3-API-compatible bucket (path-style requests,
+//!   SigV4-signed, plain HTTP) such as a local MinIO
+//!
+//! Every replica uses the same key layout:
+//! - `segments/{segment_id:016x}`: the records of one uploaded segment
+//! - `objects/{object_id}`: every symbol uploaded so far for one object
+//! - `receipts/{segment_id:016x}-{idempotency_key}`: upload dedup markers
+//!
+//! An upload ACKs once per replica that stored it. A segment counts as
+//! recoverable when enough replicas can decode every object it touches to
+//! satisfy the configured durability mode.
+//!
+//! [`TieredSpillSession`] is the connection-scoped driver behind
+//! `PRAGMA fsqlite.tiered_storage`, `PRAGMA fsqlite.durability` and
+//! `PRAGMA fsqlite.tiered_spill`: it moves rotated `ecs/symbols` segments to
+//! the remote tier and deletes the local copy once the eviction saga retires.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_types::cx::{Cx, cap};
+use fsqlite_types::{ObjectId, Oti, RemoteCap, SymbolRecord, source_symbol_count};
+use sha2::{Digest, Sha256};
+use tracing::{debug, info, warn};
+
+use crate::symbol_log::{scan_symbol_segment, symbol_segment_path};
+use crate::tiered_storage::{
+    DurabilityMode, EvictionPhase, FetchOutcome, FetchSymbolsRequest, RemoteTier, TieredStorage,
+    UploadSegmentReceipt, UploadSegmentRequest,
+};
+
+const BEAD_ID: &str = "bd-1hi.29";
+
+/// Magic bytes for an encoded record set blob (`"FSTR"`).
+const RECORD_SET_MAGIC: [u8; 4] = *b"FSTR";
+/// Separator between replica targets in a tiering spec.
+const SPEC_SEPARATOR: char = ';';
+const S3_SCHEME: &str = "s3://";
+const DEFAULT_S3_REGION: &str = "us-east-1";
+const DEFAULT_S3_TIMEOUT: Duration = Duration::from_secs(30);
+const REMOTE_CAP_DOMAIN: &[u8] = b"fsqlite:tiered:remote_cap:v1";
+
+// ---------------------------------------------------------------------------
+// Blob stores
+// ---------------------------------------------------------------------------
+
+/// Key/value blob contract shared by the concrete remote tiers.
+///
+/// Keys are `/`-separated ASCII paths built from the fixed prefixes in the
+/// module docs, lowercase hex digits and `-`, so they never need escaping.
+pub trait BlobStore: fmt::Debug {
+    /// Human-readable location used in logs and PRAGMA output.
+    fn location(&self) -> String;
+
+    /// Read one blob; `Ok(None)` when the key does not exist.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Create or replace one blob.
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()>;
+}
+
+/// Blob store rooted at a filesystem directory (local disk or a mount).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryBlobStore {
+    root: PathBuf,
+}
+
+impl DirectoryBlobStore {
+    /// Open (creating if needed) a directory-backed store.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Root directory of the store.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl BlobStore for DirectoryBlobStore {
+    fn location(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match fs::read(self.root.join(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        // Write-then-rename so readers never observe a torn blob.
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let staging = path.with_extension("tmp");
+        let mut file = fs::File::create(&staging)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&staging, &path)?;
+        Ok(())
+    }
+}
+
+/// Connection settings for an S3-API-compatible bucket.
+#[derive(Clone, PartialEq, Eq)]
+pub struct S3Config {
+    /// `host:port` of the endpoint. Only plain HTTP is spoken.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    /// Empty access key sends unsigned (anonymous) requests.
+    pub access_key: String,
+    pub secret_key: String,
+}
+
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key", &self.access_key)
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+impl S3Config {
+    /// Parse `s3://[ACCESS:SECRET@]HOST:PORT/BUCKET[?region=REGION]`.
+    pub fn parse(url: &str) -> Result<Self> {
+        let invalid = |detail: &str| FrankenError::OutOfRange {
+            what: "tiered_storage s3 target".to_owned(),
+            value: detail.to_owned(),
+        };
+        let rest = url
+            .strip_prefix(S3_SCHEME)
+            .ok_or_else(|| invalid("missing s3:// scheme"))?;
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        let (credentials, rest) = match rest.rsplit_once('@') {
+            Some((credentials, rest)) => (Some(credentials), rest),
+            None => (None, rest),
+        };
+        let (endpoint, bucket) = rest
+            .split_once('/')
+            .ok_or_else(|| invalid("missing bucket"))?;
+        let bucket = bucket.trim_end_matches('/');
+        if endpoint.is_empty() {
+            return Err(invalid("missing endpoint"));
+        }
+        if !valid_bucket_name(bucket) {
+            return Err(invalid(&format!("invalid bucket name {bucket:?}")));
+        }
+        let (access_key, secret_key) = match credentials {
+            Some(credentials) => credentials
+                .split_once(':')
+                .ok_or_else(|| invalid("credentials must be ACCESS:SECRET"))?,
+            None => ("", ""),
+        };
+        let mut region = DEFAULT_S3_REGION.to_owned();
+        for pair in query.into_iter().flat_map(|query| query.split('&')) {
+            match pair.split_once('=') {
+                Some(("region", value)) if !value.is_empty() => value.clone_into(&mut region),
+                _ => return Err(invalid(&format!("unsupported query parameter {pair:?}"))),
+            }
+        }
+        Ok(Self {
+            endpoint: endpoint.to_owned(),
+            bucket: bucket.to_owned(),
+            region,
+            access_key: access_key.to_owned(),
+            secret_key: secret_key.to_owned(),
+        })
+    }
+}
+
+fn valid_bucket_name(bucket: &str) -> bool {
+    (3..=63).contains(&bucket.len())
+        && bucket.bytes().all(|byte| {
+            byte.is_ascii_lowercase() || byte.is_ascii_digit() || matches!(byte, b'-' | b'.')
+        })
+}
+
+/// Blob store backed by one S3-API-compatible bucket.
+#[derive(Debug, Clone)]
+pub struct S3BlobStore {
+    config: S3Config,
+    timeout: Duration,
+}
+
+struct HttpResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl S3BlobStore {
+    /// Create a store for `config`; no request is made until first use.
+    #[must_use]
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            config,
+            timeout: DEFAULT_S3_TIMEOUT,
+        }
+    }
+
+    /// Override the per-request connect/read/write timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Store configuration.
+    #[must_use]
+    pub const fn config(&self) -> &S3Config {
+        &self.config
+    }
+
+    fn request(&self, method: &str, key: &str, body: &[u8]) -> Result<HttpResponse> {
+        let canonical_uri = format!("/{}/{key}", self.config.bucket);
+        let payload_hash = hex_lower(&Sha256::digest(body));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let request_date = amz_date(now);
+
+        let mut head = format!(
+            "{method} {canonical_uri} HTTP/1.1\r\nHost: {}\r\nx-amz-date: {request_date}\r\nx-amz-content-sha256: {payload_hash}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.config.endpoint,
+            body.len()
+        );
+        if !self.config.access_key.is_empty() {
+            let authorization = s3_authorization(
+                &self.config,
+                method,
+                &canonical_uri,
+                &payload_hash,
+                &request_date,
+            );
+            head.push_str(&format!("Authorization: {authorization}\r\n"));
+        }
+        head.push_str("\r\n");
+
+        let addr = self
+            .config
+            .endpoint
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| {
+                FrankenError::Internal(format!(
+                    "S3 endpoint {} did not resolve",
+                    self.config.endpoint
+                ))
+            })?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(body)?;
+        stream.flush()?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        parse_http_response(&raw)
+    }
+}
+
+impl BlobStore for S3BlobStore {
+    fn location(&self) -> String {
+        format!("{S3_SCHEME}{}/{}", self.config.endpoint, self.config.bucket)
+    }
+
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self.request("GET", key, &[])?;
+        match response.status {
+            200 => Ok(Some(response.body)),
+            404 => Ok(None),
+            status => Err(FrankenError::Internal(format!(
+                "S3 GET {}/{key} failed with HTTP {status}",
+                self.location()
+            ))),
+        }
+    }
+
+    fn put(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let response = self.request("PUT", key, bytes)?;
+        match response.status {
+            200 | 201 | 204 => Ok(()),
+            status => Err(FrankenError::Internal(format!(
+                "S3 PUT {}/{key} failed with HTTP {status}",
+                self.location()
+            ))),
+        }
+    }
+}
+
+/// Parse a `Connection: close` HTTP/1.1 response (no chunked bodies).
+fn parse_http_response(raw: &[u8]) -> Result<HttpResponse> {
+    let malformed =
+        || FrankenError::Internal("malformed HTTP response from S3 endpoint".to_owned());
+    let header_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&raw[..header_end]).map_err(|_| malformed())?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(malformed)?;
+    let mut body = raw[header_end + 4..].to_vec();
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+            && let Ok(len) = value.trim().parse::<usize>()
+        {
+            body.truncate(len);
+        }
+    }
+    Ok(HttpResponse { status, body })
+}
+
+/// AWS Signature Version 4 `Authorization` header for a request signing only
+/// `host`, `x-amz-content-sha256` and `x-amz-date`.
+fn s3_authorization(
+    config: &S3Config,
+    method: &str,
+    canonical_uri: &str,
+    payload_hash: &str,
+    amz_date: &str,
+) -> String {
+    const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+    let date_stamp = &amz_date[..8.min(amz_date.len())];
+    let canonical_request = format!(
+        "{method}\n{canonical_uri}\n\nhost:{}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{SIGNED_HEADERS}\n{payload_hash}",
+        config.endpoint
+    );
+    let scope = format!("{date_stamp}/{}/s3/aws4_request", config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex_lower(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let k_date = hmac_sha256(
+        format!("AWS4{}", config.secret_key).as_bytes(),
+        date_stamp.as_bytes(),
+    );
+    let k_region = hmac_sha256(&k_date, config.region.as_bytes());
+    let k_service = hmac_sha256(&k_region, b"s3");
+    let k_signing = hmac_sha256(&k_service, b"aws4_request");
+    let signature = hex_lower(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}",
+        config.access_key
+    )
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_BYTES: usize = 64;
+    let mut block = [0_u8; BLOCK_BYTES];
+    if key.len() > BLOCK_BYTES {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha256::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    inner.update(message);
+    let inner_hash = inner.finalize();
+    let mut outer = Sha256::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner_hash);
+    let mut out = [0_u8; 32];
+    out.copy_from_slice(&outer.finalize());
+    out
+}
+
+/// `YYYYMMDDTHHMMSSZ` for a Unix timestamp.
+fn amz_date(unix_secs: u64) -> String {
+    let days = unix_secs / 86_400;
+    let time_of_day = unix_secs % 86_400;
+    let (year, month, day) = days_to_ymd(days);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        time_of_day / 3_600,
+        (time_of_day / 60) % 60,
+        time_of_day % 60
+    )
+}
+
+/// Convert days since Unix epoch to (year, month, day).
+fn days_to_ymd(days: u64) -> (u64, u64, u64) {
+    // Civil calendar algorithm (Howard Hinnant).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}
+
+fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Record set encoding
+// ---------------------------------------------------------------------------
+
+/// Encode records as `magic[4] | count:u32 | (len:u32 | SymbolRecord)*`.
+fn encode_record_set(records: &[SymbolRecord]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&RECORD_SET_MAGIC);
+    out.extend_from_slice(
+        &u32::try_from(records.len())
+            .unwrap_or(u32::MAX)
+            .to_le_bytes(),
+    );
+    for record in records {
+        let bytes = record.to_bytes();
+        out.extend_from_slice(&u32::try_from(bytes.len()).unwrap_or(u32::MAX).to_le_bytes());
+        out.extend_from_slice(&bytes);
+    }
+    out
+}
+
+fn decode_record_set(bytes: &[u8], key: &str) -> Result<Vec<SymbolRecord>> {
+    let corrupt = |detail: String| FrankenError::DatabaseCorrupt {
+        detail: format!("remote blob {key}: {detail}"),
+    };
+    if bytes.len() < 8 || bytes[..4] != RECORD_SET_MAGIC {
+        return Err(corrupt("bad record set header".to_owned()));
+    }
+    let count = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let mut records = Vec::with_capacity(count.min(bytes.len() / 4));
+    let mut offset = 8_usize;
+    for idx in 0..count {
+        let len_end = offset.saturating_add(4);
+        let Some(len_bytes) = bytes.get(offset..len_end) else {
+            return Err(corrupt(format!("truncated length for record {idx}")));
+        };
+        let len =
+            u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+        let record_end = len_end.saturating_add(len);
+        let Some(record_bytes) = bytes.get(len_end..record_end) else {
+            return Err(corrupt(format!("truncated record {idx}")));
+        };
+        let record = SymbolRecord::from_bytes(record_bytes)
+            .map_err(|err| corrupt(format!("record {idx}: {err}")))?;
+        records.push(record);
+        offset = record_end;
+    }
+    Ok(records)
+}
+
+fn segment_key(segment_id: u64) -> String {
+    format!("segments/{segment_id:016x}")
+}
+
+fn object_key(object_id: ObjectId) -> String {
+    format!("objects/{object_id}")
+}
+
+fn receipt_key(request: &UploadSegmentRequest) -> String {
+    format!(
+        "receipts/{:016x}-{}",
+        request.segment_id,
+        hex_lower(request.idempotency_key.as_bytes())
+    )
+}
+
+/// Merge `incoming` into `by_esi`, replacing entries only to heal corruption.
+fn merge_records_by_esi(
+    by_esi: &mut BTreeMap<u32, SymbolRecord>,
+    incoming: impl IntoIterator<Item = SymbolRecord>,
+) {
+    for record in incoming {
+        match by_esi.entry(record.esi) {
+            std::collections::btree_map::Entry::Vacant(entry) => {
+                entry.insert(record);
+            }
+            std::collections::btree_map::Entry::Occupied(mut entry) => {
+                if !entry.get().verify_integrity() && record.verify_integrity() {
+                    entry.insert(record);
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replicated remote tier
+// ---------------------------------------------------------------------------
+
+/// [`RemoteTier`] that replicates every upload to a set of [`BlobStore`]s.
+#[derive(Debug)]
+pub struct BlobRemoteTier {
+    stores: Vec<Box<dyn BlobStore>>,
+    required_acks: u32,
+}
+
+impl BlobRemoteTier {
+    /// Build a tier over `stores`; each store is one replica.
+    pub fn new(stores: Vec<Box<dyn BlobStore>>) -> Result<Self> {
+        if stores.is_empty() {
+            return Err(FrankenError::OutOfRange {
+                what: "remote tier stores".to_owned(),
+                value: "0".to_owned(),
+            });
+        }
+        Ok(Self {
+            stores,
+            required_acks: 1,
+        })
+    }
+
+    /// Single-replica tier backed by a filesystem directory.
+    pub fn directory(root: impl Into<PathBuf>) -> Result<Self> {
+        Self::new(vec![Box::new(DirectoryBlobStore::open(root)?)])
+    }
+
+    /// Single-replica tier backed by an S3-API-compatible bucket.
+    pub fn s3(config: S3Config) -> Result<Self> {
+        Self::new(vec![Box::new(S3BlobStore::new(config))])
+    }
+
+    /// Build a tier from a `;`-separated list of replica targets: directory
+    /// paths or `s3://` URLs (see [`S3Config::parse`]).
+    pub fn from_spec(spec: &str) -> Result<Self> {
+        let mut stores: Vec<Box<dyn BlobStore>> = Vec::new();
+        for target in spec.split(SPEC_SEPARATOR).map(str::trim) {
+            if target.is_empty() {
+                continue;
+            }
+            if target.starts_with(S3_SCHEME) {
+                stores.push(Box::new(S3BlobStore::new(S3Config::parse(target)?)));
+            } else {
+                stores.push(Box::new(DirectoryBlobStore::open(target)?));
+            }
+        }
+        Self::new(stores)
+    }
+
+    /// Number of replicas.
+    #[must_use]
+    pub fn store_count(&self) -> u32 {
+        u32::try_from(self.stores.len()).unwrap_or(u32::MAX)
+    }
+
+    /// Replica locations, in ACK order.
+    #[must_use]
+    pub fn locations(&self) -> Vec<String> {
+        self.stores.iter().map(|store| store.location()).collect()
+    }
+
+    /// Set how many replicas must hold a segment before
+    /// [`RemoteTier::segment_recoverable`] reports it recoverable.
+    pub fn set_durability_mode(&mut self, mode: DurabilityMode) {
+        self.required_acks = match mode {
+            DurabilityMode::Local => 1,
+            DurabilityMode::Quorum { required, .. } => required,
+        };
+    }
+
+    /// Upload to one replica; `Ok(false)` when it already held the receipt.
+    fn upload_to_store(
+        store: &dyn BlobStore,
+        request: &UploadSegmentRequest,
+        receipt_key: &str,
+    ) -> Result<bool> {
+        if store.get(receipt_key)?.is_some() {
+            return Ok(false);
+        }
+        store.put(
+            &segment_key(request.segment_id),
+            &encode_record_set(&request.records),
+        )?;
+
+        let mut by_object = BTreeMap::<ObjectId, Vec<SymbolRecord>>::new();
+        for record in &request.records {
+            by_object
+                .entry(record.object_id)
+                .or_default()
+                .push(record.clone());
+        }
+        for (object_id, records) in by_object {
+            let key = object_key(object_id);
+            let mut by_esi = BTreeMap::new();
+            if let Some(existing) = store.get(&key)? {
+                merge_records_by_esi(&mut by_esi, decode_record_set(&existing, &key)?);
+            }
+            merge_records_by_esi(&mut by_esi, records);
+            let merged: Vec<SymbolRecord> = by_esi.into_values().collect();
+            store.put(&key, &encode_record_set(&merged))?;
+        }
+
+        // The receipt goes last so a partial upload is retried, not skipped.
+        store.put(receipt_key, &request.segment_id.to_le_bytes())?;
+        Ok(true)
+    }
+
+    /// Whether one replica can decode every object in the segment.
+    fn store_recovers_segment(
+        store: &dyn BlobStore,
+        segment_id: u64,
+        min_symbols_per_object: usize,
+    ) -> Result<bool> {
+        let key = segment_key(segment_id);
+        let Some(bytes) = store.get(&key)? else {
+            return Ok(false);
+        };
+        let mut objects = BTreeMap::<ObjectId, Oti>::new();
+        for record in decode_record_set(&bytes, &key)? {
+            objects.entry(record.object_id).or_insert(record.oti);
+        }
+        for (object_id, oti) in objects {
+            let needed = source_symbol_count(oti)
+                .unwrap_or(usize::MAX)
+                .max(min_symbols_per_object);
+            let key = object_key(object_id);
+            let Some(bytes) = store.get(&key)? else {
+                return Ok(false);
+            };
+            let mut by_esi = BTreeMap::new();
+            merge_records_by_esi(
+                &mut by_esi,
+                decode_record_set(&bytes, &key)?
+                    .into_iter()
+                    .filter(|record| record.object_id == object_id && record.oti == oti),
+            );
+            let valid = by_esi
+                .values()
+                .filter(|record| record.verify_integrity())
+                .count();
+            if valid < needed {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl RemoteTier for BlobRemoteTier {
+    fn fetch_symbols(&mut self, request: &FetchSymbolsRequest) -> Result<Vec<SymbolRecord>> {
+        let key = object_key(request.object_id);
+        let mut by_esi = BTreeMap::new();
+        let mut answered = false;
+        let mut last_error = None;
+        for store in &self.stores {
+            let fetched = store.get(&key).and_then(|bytes| {
+                bytes
+                    .map(|bytes| decode_record_set(&bytes, &key))
+                    .transpose()
+            });
+            match fetched {
+                Ok(records) => {
+                    answered = true;
+                    merge_records_by_esi(&mut by_esi, records.into_iter().flatten());
+                }
+                Err(err) => {
+                    warn!(
+                        bead_id = BEAD_ID,
+                        store = %store.location(),
+                        object_id = %request.object_id,
+                        error = %err,
+                        "remote replica fetch failed"
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+        if !answered && let Some(err) = last_error {
+            return Err(err);
+        }
+
+        let preferred: std::collections::BTreeSet<u32> =
+            request.preferred_esis.iter().copied().collect();
+        let mut ordered: Vec<SymbolRecord> = by_esi.into_values().collect();
+        ordered.sort_by_key(|record| (!preferred.contains(&record.esi), record.esi));
+        ordered.truncate(request.max_symbols);
+        debug!(
+            bead_id = BEAD_ID,
+            object_id = %request.object_id,
+            symbols = ordered.len(),
+            "fetched symbols from remote tier"
+        );
+        Ok(ordered)
+    }
+
+    fn upload_segment(&mut self, request: &UploadSegmentRequest) -> Result<UploadSegmentReceipt> {
+        let receipt_key = receipt_key(request);
+        let mut acked_stores = 0_u32;
+        let mut deduplicated = true;
+        let mut last_error = None;
+        for store in &self.stores {
+            match Self::upload_to_store(store.as_ref(), request, &receipt_key) {
+                Ok(fresh) => {
+                    acked_stores = acked_stores.saturating_add(1);
+                    deduplicated &= !fresh;
+                }
+                Err(err) => {
+                    warn!(
+                        bead_id = BEAD_ID,
+                        store = %store.location(),
+                        segment_id = request.segment_id,
+                        error = %err,
+                        "remote replica upload failed"
+                    );
+                    last_error = Some(err);
+                }
+            }
+        }
+        if acked_stores == 0
+            && let Some(err) = last_error
+        {
+            return Err(err);
+        }
+        debug!(
+            bead_id = BEAD_ID,
+            segment_id = request.segment_id,
+            acked_stores,
+            deduplicated,
+            "uploaded segment to remote tier"
+        );
+        Ok(UploadSegmentReceipt {
+            acked_stores,
+            deduplicated,
+        })
+    }
+
+    fn segment_recoverable(&self, segment_id: u64, min_symbols_per_object: usize) -> bool {
+        let holding = self
+            .stores
+            .iter()
+            .filter(|store| {
+                Self::store_recovers_segment(store.as_ref(), segment_id, min_symbols_per_object)
+                    .unwrap_or_else(|err| {
+                        warn!(
+                            bead_id = BEAD_ID,
+                            store = %store.location(),
+                            segment_id,
+                            error = %err,
+                            "remote replica recoverability check failed"
+                        );
+                        false
+                    })
+            })
+            .count();
+        u32::try_from(holding).unwrap_or(u32::MAX) >= self.required_acks
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Connection spill session
+// ---------------------------------------------------------------------------
+
+/// Outcome for one cold segment handled by
+/// [`TieredSpillSession::spill_cold_segments`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpillReport {
+    pub segment_id: u64,
+    pub phase: EvictionPhase,
+    pub acked_stores: u32,
+    /// Whether the local `segment-*.log` file was deleted.
+    pub removed_local: bool,
+}
+
+impl SpillReport {
+    /// Stable label for [`Self::phase`].
+    #[must_use]
+    pub const fn phase_label(&self) -> &'static str {
+        match self.phase {
+            EvictionPhase::Uploaded => "uploaded",
+            EvictionPhase::CompensatedCancelled => "cancelled",
+            EvictionPhase::CompensatedPrecondition => "retained",
+            EvictionPhase::Retired => "evicted",
+        }
+    }
+}
+
+/// Connection-scoped tiering state: the L2 controller, the configured remote
+/// tier and the durability mode that gates local deletion.
+#[derive(Debug, Default)]
+pub struct TieredSpillSession {
+    storage: TieredStorage,
+    remote: Option<BlobRemoteTier>,
+    spec: Option<String>,
+    remote_cap: Option<RemoteCap>,
+}
+
+impl TieredSpillSession {
+    /// Current durability mode.
+    #[must_use]
+    pub const fn durability_mode(&self) -> DurabilityMode {
+        self.storage.durability_mode()
+    }
+
+    /// Configured tiering spec, `None` while tiering is off.
+    #[must_use]
+    pub fn spec(&self) -> Option<&str> {
+        self.spec.as_deref()
+    }
+
+    /// Configured remote tier, `None` while tiering is off.
+    #[must_use]
+    pub const fn remote(&self) -> Option<&BlobRemoteTier> {
+        self.remote.as_ref()
+    }
+
+    /// Change the durability mode. A quorum larger than the configured
+    /// replica set is rejected.
+    pub fn set_durability_mode(&mut self, mode: DurabilityMode) -> Result<()> {
+        if let Some(remote) = &self.remote {
+            check_quorum_fits(mode, remote.store_count())?;
+        }
+        self.storage.set_durability_mode(mode);
+        if let Some(remote) = &mut self.remote {
+            remote.set_durability_mode(mode);
+        }
+        Ok(())
+    }
+
+    /// Enable tiering with a spec accepted by [`BlobRemoteTier::from_spec`],
+    /// or turn it off with `None`.
+    pub fn configure_remote(&mut self, spec: Option<&str>) -> Result<()> {
+        let Some(spec) = spec else {
+            self.remote = None;
+            self.spec = None;
+            self.remote_cap = None;
+            info!(bead_id = BEAD_ID, "tiered storage disabled");
+            return Ok(());
+        };
+        let mut remote = BlobRemoteTier::from_spec(spec)?;
+        let mode = self.durability_mode();
+        check_quorum_fits(mode, remote.store_count())?;
+        remote.set_durability_mode(mode);
+        info!(
+            bead_id = BEAD_ID,
+            stores = ?remote.locations(),
+            durability = %mode,
+            "tiered storage enabled"
+        );
+        self.remote = Some(remote);
+        self.remote_cap = Some(derive_remote_cap(spec));
+        self.spec = Some(spec.to_owned());
+        Ok(())
+    }
+
+    /// Evict every rotated segment in `symbols_dir` except the newest
+    /// `keep_hot` to the remote tier. The highest-numbered segment is the
+    /// active append target and is never spilled. A segment file is deleted
+    /// only once the eviction saga retires it.
+    pub fn spill_cold_segments<Caps>(
+        &mut self,
+        cx: &Cx<Caps>,
+        symbols_dir: &Path,
+        keep_hot: usize,
+    ) -> Result<Vec<SpillReport>>
+    where
+        Caps: cap::SubsetOf<cap::All>,
+    {
+        let remote = self.remote.as_mut().ok_or_else(|| {
+            FrankenError::Internal(
+                "tiered storage is off; set PRAGMA fsqlite.tiered_storage first".to_owned(),
+            )
+        })?;
+        let segment_ids = list_symbol_segments(symbols_dir)?;
+        let cold = segment_ids.len().saturating_sub(keep_hot.saturating_add(1));
+
+        let mut reports = Vec::with_capacity(cold);
+        for &segment_id in &segment_ids[..cold] {
+            let path = symbol_segment_path(symbols_dir, segment_id);
+            let scan = scan_symbol_segment(&path)?;
+            if scan.torn_tail {
+                warn!(
+                    bead_id = BEAD_ID,
+                    segment_id, "rotated segment has a torn tail; leaving it local"
+                );
+                continue;
+            }
+            let records = scan.records.into_iter().map(|entry| entry.record).collect();
+            self.storage.insert_l2_segment(segment_id, records);
+            let outcome = self.storage.evict_segment(
+                cx,
+                segment_id,
+                1,
+                scan.header.epoch_id,
+                remote,
+                self.remote_cap,
+            );
+            let outcome = match outcome {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    self.storage.remove_l2_segment(segment_id);
+                    return Err(err);
+                }
+            };
+            if outcome.evicted {
+                fs::remove_file(&path)?;
+            } else {
+                // The local file stays authoritative; drop the staged copy.
+                self.storage.remove_l2_segment(segment_id);
+            }
+            reports.push(SpillReport {
+                segment_id,
+                phase: outcome.phase,
+                acked_stores: outcome.upload_receipt.acked_stores,
+                removed_local: outcome.evicted,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Fetch one object through L2, falling back to the remote tier.
+    pub fn fetch_object<Caps>(
+        &mut self,
+        cx: &Cx<Caps>,
+        object_id: ObjectId,
+        ecs_epoch: u64,
+    ) -> Result<FetchOutcome>
+    where
+        Caps: cap::SubsetOf<cap::All>,
+    {
+        self.storage.fetch_object(
+            cx,
+            object_id,
+            ecs_epoch,
+            self.remote.as_mut(),
+            self.remote_cap,
+        )
+    }
+}
+
+fn check_quorum_fits(mode: DurabilityMode, store_count: u32) -> Result<()> {
+    if let DurabilityMode::Quorum { total, .. } = mode
+        && total > store_count
+    {
+        return Err(FrankenError::OutOfRange {
+            what: "durability quorum".to_owned(),
+            value: format!("{mode} needs {total} remote stores, {store_count} configured"),
+        });
+    }
+    Ok(())
+}
+
+/// Enabling tiering is the grant: the capability is bound to the spec.
+fn derive_remote_cap(spec: &str) -> RemoteCap {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(REMOTE_CAP_DOMAIN);
+    hasher.update(spec.as_bytes());
+    let mut out = [0_u8; 16];
+    out.copy_from_slice(&hasher.finalize().as_bytes()[..16]);
+    RemoteCap::from_bytes(out)
+}
+
+/// Segment ids of `segment-*.log` files in `symbols_dir`, ascending.
+fn list_symbol_segments(symbols_dir: &Path) -> Result<Vec<u64>> {
+    let entries = match fs::read_dir(symbols_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut segment_ids = Vec::new();
+    for entry in entries {
+        let name = entry?.file_name();
+        if let Some(segment_id) = name
+            .to_str()
+            .and_then(|name| name.strip_prefix("segment-"))
+            .and_then(|name| name.strip_suffix(".log"))
+            .and_then(|digits| digits.parse::<u64>().ok())
+        {
+            segment_ids.push(segment_id);
+        }
+    }
+    segment_ids.sort_unstable();
+    Ok(segment_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::BufRead;
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread::JoinHandle;
+
+    use fsqlite_types::{IdempotencyKey, Saga, SymbolRecordFlags};
+
+    use super::*;
+    use crate::symbol_log::SymbolLogManager;
+
+    /// Replica that rejects every request, modelling an unreachable store.
+    #[derive(Debug)]
+    struct OfflineStore;
+
+    impl BlobStore for OfflineStore {
+        fn location(&self) -> String {
+            "offline".to_owned()
+        }
+
+        fn get(&self, _key: &str) -> Result<Option<Vec<u8>>> {
+            Err(FrankenError::Internal("store offline".to_owned()))
+        }
+
+        fn put(&self, _key: &str, _bytes: &[u8]) -> Result<()> {
+            Err(FrankenError::Internal("store offline".to_owned()))
+        }
+    }
+
+    /// Minimal MinIO-style stand-in: path-style PUT/GET on one bucket with
+    /// SigV4 verification.
+    struct ObjectStoreEmulator {
+        config: S3Config,
+        blobs: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        shutdown: Arc<AtomicBool>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl ObjectStoreEmulator {
+        fn start(bucket: &str, access_key: &str, secret_key: &str) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").expect("bind emulator");
+            let addr: SocketAddr = listener.local_addr().expect("emulator addr");
+            let config = S3Config {
+                endpoint: addr.to_string(),
+                bucket: bucket.to_owned(),
+                region: DEFAULT_S3_REGION.to_owned(),
+                access_key: access_key.to_owned(),
+                secret_key: secret_key.to_owned(),
+            };
+            let blobs = Arc::new(Mutex::new(HashMap::new()));
+            let shutdown = Arc::new(AtomicBool::new(false));
+            let handle = {
+                let config = config.clone();
+                let blobs = Arc::clone(&blobs);
+                let shutdown = Arc::clone(&shutdown);
+                std::thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        if shutdown.load(Ordering::Acquire) {
+                            break;
+                        }
+                        if let Ok(stream) = stream {
+                            serve_emulator_request(stream, &config, &blobs);
+                        }
+                    }
+                })
+            };
+            Self {
+                config,
+                blobs,
+                shutdown,
+                handle: Some(handle),
+            }
+        }
+
+        fn config(&self) -> S3Config {
+            self.config.clone()
+        }
+
+        fn blob_count(&self, prefix: &str) -> usize {
+            self.blobs
+                .lock()
+                .expect("emulator lock")
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .count()
+        }
+    }
+
+    impl Drop for ObjectStoreEmulator {
+        fn drop(&mut self) {
+            self.shutdown.store(true, Ordering::Release);
+            let _ = TcpStream::connect(&self.config.endpoint);
+            if let Some(handle) = self.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    fn serve_emulator_request(
+        stream: TcpStream,
+        config: &S3Config,
+        blobs: &Mutex<HashMap<String, Vec<u8>>>,
+    ) {
+        let mut reader = std::io::BufReader::new(stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let uri = parts.next().unwrap_or_default().to_owned();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() || line.trim_end().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_owned());
+            }
+        }
+        let len = headers
+            .get("content-length")
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        let mut body = vec![0_u8; len];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        let header = |name: &str| headers.get(name).cloned().unwrap_or_default();
+        let payload_hash = header("x-amz-content-sha256");
+        let expected_auth =
+            s3_authorization(config, &method, &uri, &payload_hash, &header("x-amz-date"));
+        let (status, response_body) = if payload_hash != hex_lower(&Sha256::digest(&body))
+            || header("authorization") != expected_auth
+        {
+            (
+                403,
+                b"<Error><Code>SignatureDoesNotMatch</Code></Error>".to_vec(),
+            )
+        } else if let Some(key) = uri.strip_prefix(&format!("/{}/", config.bucket)) {
+            let mut blobs = blobs.lock().expect("emulator lock");
+            match method.as_str() {
+                "PUT" => {
+                    blobs.insert(key.to_owned(), body);
+                    (200, Vec::new())
+                }
+                "GET" => blobs.get(key).map_or_else(
+                    || (404, b"<Error><Code>NoSuchKey</Code></Error>".to_vec()),
+                    |blob| (200, blob.clone()),
+                ),
+                _ => (405, Vec::new()),
+            }
+        } else {
+            (404, b"<Error><Code>NoSuchBucket</Code></Error>".to_vec())
+        };
+
+        let mut stream = reader.into_inner();
+        let head = format!(
+            "HTTP/1.1 {status} Emulated\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response_body.len()
+        );
+        let _ = stream.write_all(head.as_bytes());
+        let _ = stream.write_all(&response_body);
+    }
+
+    fn object_id_from_u64(raw: u64) -> ObjectId {
+        let mut bytes = [0_u8; 16];
+        bytes[0..8].copy_from_slice(&raw.to_le_bytes());
+        bytes[8..16].copy_from_slice(&raw.to_le_bytes());
+        ObjectId::from_bytes(bytes)
+    }
+
+    fn make_source_records(
+        object_id: ObjectId,
+        payload: &[u8],
+        symbol_size: usize,
+    ) -> Vec<SymbolRecord> {
+        let oti = Oti {
+            f: u64::try_from(payload.len()).expect("payload len fits u64"),
+            al: 1,
+            t: u32::try_from(symbol_size).expect("symbol size fits u32"),
+            z: 1,
+            n: 1,
+        };
+        payload
+            .chunks(symbol_size)
+            .enumerate()
+            .map(|(idx, chunk)| {
+                let mut symbol = vec![0_u8; symbol_size];
+                symbol[..chunk.len()].copy_from_slice(chunk);
+                let flags = if idx == 0 {
+                    SymbolRecordFlags::SYSTEMATIC_RUN_START
+                } else {
+                    SymbolRecordFlags::empty()
+                };
+                let esi = u32::try_from(idx).expect("esi fits u32");
+                SymbolRecord::new(object_id, oti, esi, symbol, flags)
+            })
+            .collect()
+    }
+
+    fn upload_request(segment_id: u64, records: Vec<SymbolRecord>) -> UploadSegmentRequest {
+        let key = IdempotencyKey::derive(7, &segment_id.to_le_bytes());
+        UploadSegmentRequest {
+            segment_id,
+            records,
+            idempotency_key: key,
+            saga: Saga::new(key),
+            ecs_epoch: 7,
+            remote_cap: RemoteCap::from_bytes([3; 16]),
+            computation: "fsqlite:tiered:upload_segment:v1",
+        }
+    }
+
+    fn fetch_request(object_id: ObjectId) -> FetchSymbolsRequest {
+        FetchSymbolsRequest {
+            object_id,
+            preferred_esis: Vec::new(),
+            max_symbols: usize::MAX,
+            idempotency_key: IdempotencyKey::derive(7, object_id.as_bytes()),
+            ecs_epoch: 7,
+            remote_cap: RemoteCap::from_bytes([3; 16]),
+            computation: "fsqlite:tiered:fetch_symbols:v1",
+        }
+    }
+
+    #[test]
+    fn test_directory_tier_upload_fetch_and_dedup_across_reopen() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let object_id = object_id_from_u64(1);
+        let records = make_source_records(object_id, b"directory-tier-payload-bytes", 8);
+        let request = upload_request(4, records.clone());
+
+        let mut tier = BlobRemoteTier::directory(dir.path()).expect("open tier");
+        let receipt = tier.upload_segment(&request).expect("upload");
+        assert_eq!(receipt.acked_stores, 1);
+        assert!(!receipt.deduplicated);
+        assert!(tier.segment_recoverable(4, 1));
+        assert!(!tier.segment_recoverable(5, 1), "unknown segment");
+
+        let mut reopened = BlobRemoteTier::directory(dir.path()).expect("reopen tier");
+        let again = reopened.upload_segment(&request).expect("retry upload");
+        assert!(again.deduplicated, "receipts persist across reopen");
+        let fetched = reopened
+            .fetch_symbols(&fetch_request(object_id))
+            .expect("fetch");
+        assert_eq!(fetched, records);
+    }
+
+    #[test]
+    fn test_quorum_recoverability_counts_healthy_replicas() {
+        let dirs = [
+            tempfile::tempdir().expect("tempdir"),
+            tempfile::tempdir().expect("tempdir"),
+        ];
+        let stores: Vec<Box<dyn BlobStore>> = vec![
+            Box::new(DirectoryBlobStore::open(dirs[0].path()).expect("store a")),
+            Box::new(OfflineStore),
+            Box::new(DirectoryBlobStore::open(dirs[1].path()).expect("store b")),
+        ];
+        let mut tier = BlobRemoteTier::new(stores).expect("tier");
+        let object_id = object_id_from_u64(2);
+        let records = make_source_records(object_id, b"quorum-replicated-payload", 8);
+
+        let receipt = tier
+            .upload_segment(&upload_request(9, records))
+            .expect("two replicas accept the upload");
+        assert_eq!(receipt.acked_stores, 2);
+
+        tier.set_durability_mode(DurabilityMode::quorum(2, 3).expect("valid quorum"));
+        assert!(tier.segment_recoverable(9, 1));
+        tier.set_durability_mode(DurabilityMode::quorum(3, 3).expect("valid quorum"));
+        assert!(!tier.segment_recoverable(9, 1));
+
+        let mut offline = BlobRemoteTier::new(vec![Box::new(OfflineStore)]).expect("tier");
+        assert!(
+            offline
+                .upload_segment(&upload_request(10, Vec::new()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_s3_tier_against_object_store_emulator() {
+        let emulator = ObjectStoreEmulator::start("fsqlite-cold", "minio", "minio-secret");
+        let object_id = object_id_from_u64(3);
+        let records = make_source_records(object_id, b"s3-compatible-tier-payload", 8);
+
+        let mut tier = BlobRemoteTier::s3(emulator.config()).expect("tier");
+        let receipt = tier
+            .upload_segment(&upload_request(11, records.clone()))
+            .expect("upload");
+        assert_eq!(receipt.acked_stores, 1);
+        assert_eq!(emulator.blob_count("segments/"), 1);
+        assert_eq!(emulator.blob_count("objects/"), 1);
+        assert!(tier.segment_recoverable(11, 1));
+        assert_eq!(
+            tier.fetch_symbols(&fetch_request(object_id))
+                .expect("fetch"),
+            records
+        );
+        assert!(
+            tier.fetch_symbols(&fetch_request(object_id_from_u64(99)))
+                .expect("missing object is empty")
+                .is_empty()
+        );
+
+        let mut wrong_secret = emulator.config();
+        wrong_secret.secret_key = "not-the-secret".to_owned();
+        let mut rejected = BlobRemoteTier::s3(wrong_secret).expect("tier");
+        assert!(
+            rejected
+                .upload_segment(&upload_request(12, Vec::new()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_s3_config_parse() {
+        let config = S3Config::parse("s3://ak:sk@127.0.0.1:9000/cold-bucket/?region=eu-west-1")
+            .expect("parse");
+        assert_eq!(config.endpoint, "127.0.0.1:9000");
+        assert_eq!(config.bucket, "cold-bucket");
+        assert_eq!(config.region, "eu-west-1");
+        assert_eq!(
+            (config.access_key.as_str(), config.secret_key.as_str()),
+            ("ak", "sk")
+        );
+        assert!(
+            !format!("{config:?}").contains("sk\""),
+            "secret is redacted"
+        );
+
+        assert_eq!(amz_date(1_700_000_000), "20231114T221320Z");
+
+        let anonymous = S3Config::parse("s3://localhost:9000/bucket").expect("parse");
+        assert_eq!(anonymous.region, DEFAULT_S3_REGION);
+        assert!(anonymous.access_key.is_empty());
+
+        for invalid in [
+            "http://host/bucket",
+            "s3://host",
+            "s3://host/B",
+            "s3://host/bucket?acl=1",
+        ] {
+            assert!(
+                S3Config::parse(invalid).is_err(),
+                "{invalid} must be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_spill_session_evicts_cold_segments_and_fetches_back() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let symbols_dir = dir.path().join("ecs").join("symbols");
+        let remote_dir = dir.path().join("remote");
+
+        let mut payloads = Vec::new();
+        let mut log = SymbolLogManager::new(&symbols_dir, 0, 1, 0).expect("open log");
+        for segment_id in 0_u64..3 {
+            if segment_id > 0 {
+                log.rotate(segment_id, 1, segment_id).expect("rotate");
+            }
+            let object_id = object_id_from_u64(100 + segment_id);
+            let payload = format!("cold-segment-{segment_id}-payload").into_bytes();
+            for record in make_source_records(object_id, &payload, 8) {
+                log.append(&record).expect("append");
+            }
+            payloads.push((object_id, payload));
+        }
+
+        let cx = Cx::<cap::All>::new();
+        let mut session = TieredSpillSession::default();
+        assert!(session.spill_cold_segments(&cx, &symbols_dir, 0).is_err());
+
+        session
+            .configure_remote(Some(&remote_dir.display().to_string()))
+            .expect("enable tiering");
+        assert!(
+            session
+                .set_durability_mode(DurabilityMode::quorum(2, 2).expect("valid quorum"))
+                .is_err(),
+            "quorum larger than the replica set is rejected"
+        );
+
+        let reports = session
+            .spill_cold_segments(&cx, &symbols_dir, 1)
+            .expect("spill keeping one hot segment");
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].segment_id, 0);
+        assert_eq!(reports[0].phase_label(), "evicted");
+        assert!(!symbol_segment_path(&symbols_dir, 0).exists());
+        assert!(symbol_segment_path(&symbols_dir, 1).exists());
+        assert!(
+            symbol_segment_path(&symbols_dir, 2).exists(),
+            "active segment stays"
+        );
+
+        let (object_id, payload) = &payloads[0];
+        let outcome = session
+            .fetch_object(&cx, *object_id, 1)
+            .expect("fetch back");
+        assert!(outcome.remote_used);
+        assert_eq!(&outcome.bytes, payload);
+    }
+}
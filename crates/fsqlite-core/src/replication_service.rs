@@ -0,0 +1,837 @@
+//! §3.4.2 Fountain-Coded Replication Service.
+//!
+//! Binds the replication sender and receiver state machines to real UDP
+//! sockets. A [`ReplicationPrimary`] streams the pages committed through a
+//! primary [`Connection`] since its previous round as fountain-coded symbols
+//! to every registered replica address; the connection logs the pages of
+//! each commit once a primary ships from it. The whole database is shipped
+//! on the first round, for new replicas, periodically, and whenever it
+//! changed without a logged commit. A [`ReplicationReplica`] drains its
+//! socket, decodes changesets, and writes only the shipped pages into a
+//! read-only replica connection.
+//!
+//! Both halves are driven by the caller (`ship` / `poll`), so connections
+//! never cross threads. Every changeset carries a sequence marker in page
+//! slot 0, which is never a database page: it numbers the changeset, stamps
+//! the ship time, and frames groups that span several changesets. Replicas
+//! use it to report lag and to detect gaps without a back channel; a replica
+//! that misses a changeset waits for the next full-image group.
+
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use fsqlite_error::{FrankenError, Result};
+use tracing::{debug, info, warn};
+
+use crate::connection::Connection;
+use crate::replication_receiver::{
+    DecodedPage, PacketResult, ReceiverConfig, ReceiverState, ReplicationReceiver,
+};
+use crate::replication_sender::{
+    CHANGESET_HEADER_SIZE, ChangesetId, MAX_UDP_PAYLOAD, PageEntry, ReplicationPacket,
+    ReplicationSender, SenderConfig,
+};
+use crate::source_block_partition::K_MAX;
+
+/// Page slot that carries the changeset sequence marker.
+pub const SEQUENCE_MARKER_PAGE: u32 = 0;
+
+/// Default number of incremental groups between full-image groups.
+pub const DEFAULT_FULL_SYNC_INTERVAL: u64 = 32;
+
+const MARKER_FLAG_FIRST: u8 = 0b0000_0001;
+const MARKER_FLAG_LAST: u8 = 0b0000_0010;
+const MARKER_FLAG_FULL: u8 = 0b0000_0100;
+const MARKER_LEN: usize = 17;
+
+// ---------------------------------------------------------------------------
+// Sequence marker
+// ---------------------------------------------------------------------------
+
+/// In-band framing shipped as page slot 0 of every changeset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SequenceMarker {
+    seq: u64,
+    shipped_at_micros: u64,
+    flags: u8,
+}
+
+impl SequenceMarker {
+    fn to_page(self, page_size: usize) -> PageEntry {
+        let mut bytes = vec![0_u8; page_size];
+        bytes[0..8].copy_from_slice(&self.seq.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.shipped_at_micros.to_le_bytes());
+        bytes[16] = self.flags;
+        PageEntry::new(SEQUENCE_MARKER_PAGE, bytes)
+    }
+
+    fn from_page(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < MARKER_LEN {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "replication sequence marker too short: {} < {MARKER_LEN}",
+                    bytes.len()
+                ),
+            });
+        }
+        Ok(Self {
+            seq: u64::from_le_bytes(bytes[0..8].try_into().expect("8 bytes")),
+            shipped_at_micros: u64::from_le_bytes(bytes[8..16].try_into().expect("8 bytes")),
+            flags: bytes[16],
+        })
+    }
+
+    const fn has(self, flag: u8) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX)
+        })
+}
+
+// ---------------------------------------------------------------------------
+// Primary
+// ---------------------------------------------------------------------------
+
+/// Configuration for a [`ReplicationPrimary`].
+#[derive(Debug, Clone)]
+pub struct ReplicationServiceConfig {
+    /// Symbol size and repair budget for outgoing changesets.
+    pub sender: SenderConfig,
+    /// Ship the whole image after this many incremental groups so replicas
+    /// that missed a changeset can resynchronize. Zero disables periodic
+    /// full syncs.
+    pub full_sync_interval: u64,
+}
+
+impl Default for ReplicationServiceConfig {
+    fn default() -> Self {
+        Self {
+            sender: SenderConfig::default(),
+            full_sync_interval: DEFAULT_FULL_SYNC_INTERVAL,
+        }
+    }
+}
+
+/// Counters reported by a [`ReplicationPrimary`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrimaryMetrics {
+    /// Sequence number of the last changeset shipped (0 before the first).
+    pub shipped_seq: u64,
+    /// Ship rounds that found committed changes.
+    pub groups_shipped: u64,
+    /// Groups that carried the whole database image.
+    pub full_syncs: u64,
+    /// Database pages shipped.
+    pub pages_shipped: u64,
+    /// Datagrams sent, counting each replica separately.
+    pub packets_sent: u64,
+    /// Datagram bytes sent, counting each replica separately.
+    pub bytes_sent: u64,
+}
+
+/// Primary side of UDP replication: ships committed changes of a
+/// connection to a set of replica addresses.
+#[derive(Debug)]
+pub struct ReplicationPrimary {
+    socket: UdpSocket,
+    replicas: Vec<SocketAddr>,
+    config: ReplicationServiceConfig,
+    sender: ReplicationSender,
+    full_sync_pending: bool,
+    groups_since_full: u64,
+    metrics: PrimaryMetrics,
+}
+
+impl ReplicationPrimary {
+    /// Bind the primary's sending socket to `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symbol size is invalid or the socket cannot
+    /// be bound.
+    pub fn bind(addr: impl ToSocketAddrs, config: ReplicationServiceConfig) -> Result<Self> {
+        ReplicationPacket::validate_symbol_size(usize::from(config.sender.symbol_size))?;
+        let socket = UdpSocket::bind(addr)?;
+        Ok(Self {
+            socket,
+            replicas: Vec::new(),
+            config,
+            sender: ReplicationSender::new(),
+            full_sync_pending: true,
+            groups_since_full: 0,
+            metrics: PrimaryMetrics::default(),
+        })
+    }
+
+    /// Address the primary sends from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket address cannot be read.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Start shipping to `addr`. The next ship round sends a full image so
+    /// the new replica gets a base.
+    pub fn add_replica(&mut self, addr: SocketAddr) {
+        if !self.replicas.contains(&addr) {
+            self.replicas.push(addr);
+            self.full_sync_pending = true;
+            info!(%addr, "replica registered");
+        }
+    }
+
+    /// Stop shipping to `addr`. Returns whether it was registered.
+    pub fn remove_replica(&mut self, addr: SocketAddr) -> bool {
+        let before = self.replicas.len();
+        self.replicas.retain(|replica| *replica != addr);
+        self.replicas.len() != before
+    }
+
+    /// Registered replica addresses.
+    #[must_use]
+    pub fn replicas(&self) -> &[SocketAddr] {
+        &self.replicas
+    }
+
+    /// Ship the whole image on the next round.
+    pub fn request_full_sync(&mut self) {
+        self.full_sync_pending = true;
+    }
+
+    /// Current counters.
+    #[must_use]
+    pub const fn metrics(&self) -> PrimaryMetrics {
+        self.metrics
+    }
+
+    /// Ship every page committed on `conn` since the previous round.
+    ///
+    /// Returns the sequence number of the last changeset sent, or `None`
+    /// when nothing changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pages cannot be read, encoded, or sent.
+    pub fn ship(&mut self, conn: &Connection) -> Result<Option<u64>> {
+        let collected = conn.collect_replicated_pages(
+            self.full_sync_pending
+                || (self.config.full_sync_interval > 0
+                    && self.groups_since_full >= self.config.full_sync_interval),
+        )?;
+        if collected.pages.is_empty() {
+            return Ok(None);
+        }
+        let full = collected.full;
+        let page_size = collected.page_size;
+        let changed: Vec<PageEntry> = collected
+            .pages
+            .into_iter()
+            .map(|(page_number, page)| PageEntry::new(page_number, page))
+            .collect();
+
+        let page_size_u32 = u32::try_from(page_size).map_err(|_| FrankenError::OutOfRange {
+            what: "page_size".to_owned(),
+            value: page_size.to_string(),
+        })?;
+        let per_changeset = self.pages_per_changeset(page_size);
+        let n_pages = changed.len();
+        let shipped_at_micros = unix_micros();
+        let mut remaining = changed.into_iter().peekable();
+        let mut first = true;
+        while remaining.peek().is_some() {
+            let mut entries = Vec::with_capacity(per_changeset + 1);
+            entries.push(PageEntry::new(SEQUENCE_MARKER_PAGE, Vec::new()));
+            entries.extend(remaining.by_ref().take(per_changeset));
+
+            let mut flags = 0;
+            if first {
+                flags |= MARKER_FLAG_FIRST;
+            }
+            if remaining.peek().is_none() {
+                flags |= MARKER_FLAG_LAST;
+            }
+            if full {
+                flags |= MARKER_FLAG_FULL;
+            }
+            let seq = self.metrics.shipped_seq + 1;
+            entries[0] = SequenceMarker {
+                seq,
+                shipped_at_micros,
+                flags,
+            }
+            .to_page(page_size);
+
+            self.stream_changeset(page_size_u32, &mut entries)?;
+            self.metrics.shipped_seq = seq;
+            first = false;
+        }
+
+        self.metrics.groups_shipped += 1;
+        self.metrics.pages_shipped += n_pages as u64;
+        if full {
+            self.metrics.full_syncs += 1;
+            self.groups_since_full = 0;
+        } else {
+            self.groups_since_full += 1;
+        }
+        self.full_sync_pending = false;
+        debug!(
+            seq = self.metrics.shipped_seq,
+            n_pages,
+            full,
+            replicas = self.replicas.len(),
+            "shipped replication group"
+        );
+        Ok(Some(self.metrics.shipped_seq))
+    }
+
+    /// Database pages that fit in one changeset next to the marker without
+    /// exceeding a single source block.
+    fn pages_per_changeset(&self, page_size: usize) -> usize {
+        let max_bytes = K_MAX as usize * usize::from(self.config.sender.symbol_size);
+        let entry_size = 4 + 8 + page_size;
+        (max_bytes.saturating_sub(CHANGESET_HEADER_SIZE) / entry_size)
+            .saturating_sub(1)
+            .max(1)
+    }
+
+    fn stream_changeset(&mut self, page_size: u32, entries: &mut [PageEntry]) -> Result<()> {
+        self.sender.reset();
+        self.sender
+            .prepare(page_size, entries, self.config.sender.clone())?;
+        self.sender.start_streaming()?;
+        while let Some(packet) = self.sender.next_packet()? {
+            let bytes = packet.to_bytes()?;
+            for replica in &self.replicas {
+                self.socket.send_to(&bytes, replica)?;
+                self.metrics.packets_sent += 1;
+                self.metrics.bytes_sent += bytes.len() as u64;
+            }
+        }
+        self.sender.complete();
+        self.sender.reset();
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replica
+// ---------------------------------------------------------------------------
+
+/// Counters reported by a [`ReplicationReplica`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplicaMetrics {
+    /// Sequence number of the last changeset installed (0 before the first).
+    pub applied_seq: u64,
+    /// Highest changeset sequence number decoded.
+    pub received_seq: u64,
+    /// Groups installed into the replica connection.
+    pub groups_applied: u64,
+    /// Database pages installed.
+    pub pages_applied: u64,
+    /// Datagrams read from the socket.
+    pub packets_received: u64,
+    /// Datagrams dropped as malformed, corrupt, or unauthenticated.
+    pub packets_rejected: u64,
+    /// Sequence gaps observed between decoded changesets.
+    pub gaps_detected: u64,
+    /// Changesets dropped while waiting for a full-image group.
+    pub changesets_skipped: u64,
+    /// Microseconds between the primary shipping the last installed group
+    /// and the replica installing it.
+    pub last_apply_delay_micros: u64,
+}
+
+impl ReplicaMetrics {
+    /// Changesets shipped by a primary at `shipped_seq` that this replica
+    /// has not installed yet.
+    #[must_use]
+    pub const fn seq_lag(&self, shipped_seq: u64) -> u64 {
+        shipped_seq.saturating_sub(self.applied_seq)
+    }
+}
+
+/// Replica side of UDP replication: decodes changesets from a socket and
+/// installs them into a replica connection.
+///
+/// The replica connection must be writable when the first group arrives
+/// (for example a fresh `:memory:` database with the primary's page size);
+/// installing a group leaves it read-only.
+#[derive(Debug)]
+pub struct ReplicationReplica {
+    socket: UdpSocket,
+    receiver: ReplicationReceiver,
+    buf: Vec<u8>,
+    last_seq: Option<u64>,
+    last_decoded: Option<ChangesetId>,
+    awaiting_full_sync: bool,
+    /// Pages of a group whose last changeset has not arrived yet.
+    group: Option<Vec<DecodedPage>>,
+    metrics: ReplicaMetrics,
+}
+
+impl ReplicationReplica {
+    /// Bind the replica's listening socket to `addr`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be bound or made non-blocking.
+    pub fn bind(addr: impl ToSocketAddrs, config: ReceiverConfig) -> Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            receiver: ReplicationReceiver::with_config(config),
+            buf: vec![0_u8; MAX_UDP_PAYLOAD],
+            last_seq: None,
+            last_decoded: None,
+            awaiting_full_sync: true,
+            group: None,
+            metrics: ReplicaMetrics::default(),
+        })
+    }
+
+    /// Address the replica listens on; register it with the primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket address cannot be read.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Current counters.
+    #[must_use]
+    pub const fn metrics(&self) -> ReplicaMetrics {
+        self.metrics
+    }
+
+    /// Whether the replica holds a base image and has seen no unrepaired
+    /// gap since.
+    #[must_use]
+    pub const fn is_synced(&self) -> bool {
+        !self.awaiting_full_sync
+    }
+
+    /// Drain every datagram queued on the socket without blocking and
+    /// install completed groups into `conn`.
+    ///
+    /// Returns the number of groups installed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket fails or a decoded group cannot be
+    /// installed. Malformed datagrams are counted and dropped.
+    pub fn poll(&mut self, conn: &Connection) -> Result<u64> {
+        let mut buf = std::mem::take(&mut self.buf);
+        let result = self.drain(conn, &mut buf);
+        self.buf = buf;
+        result
+    }
+
+    /// Install arriving groups until the changeset numbered `seq` is
+    /// installed or `timeout` elapses, blocking on the socket in between.
+    /// Returns whether it was installed.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::poll`].
+    pub fn wait_for_seq(&mut self, conn: &Connection, seq: u64, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = std::mem::take(&mut self.buf);
+        let result = self.wait_until(conn, seq, deadline, &mut buf);
+        self.buf = buf;
+        result
+    }
+
+    fn wait_until(
+        &mut self,
+        conn: &Connection,
+        seq: u64,
+        deadline: Instant,
+        buf: &mut [u8],
+    ) -> Result<bool> {
+        loop {
+            self.drain(conn, buf)?;
+            if self.metrics.applied_seq >= seq {
+                return Ok(true);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            self.recv_blocking(conn, buf, remaining)?;
+        }
+    }
+
+    /// Wait up to `timeout` for one datagram and ingest it.
+    fn recv_blocking(
+        &mut self,
+        conn: &Connection,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> Result<u64> {
+        self.socket.set_nonblocking(false)?;
+        let received = self
+            .socket
+            .set_read_timeout(Some(timeout))
+            .and_then(|()| self.socket.recv_from(buf));
+        self.socket.set_nonblocking(true)?;
+        let len = match received {
+            Ok((len, _)) => len,
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(0);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        self.metrics.packets_received += 1;
+        self.ingest(conn, &buf[..len])
+    }
+
+    fn drain(&mut self, conn: &Connection, buf: &mut [u8]) -> Result<u64> {
+        let mut applied = 0;
+        loop {
+            let len = match self.socket.recv_from(buf) {
+                Ok((len, _)) => len,
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => return Ok(applied),
+                Err(err) => return Err(err.into()),
+            };
+            self.metrics.packets_received += 1;
+            applied += self.ingest(conn, &buf[..len])?;
+        }
+    }
+
+    fn ingest(&mut self, conn: &Connection, datagram: &[u8]) -> Result<u64> {
+        // Repair symbols trail every changeset; once it has decoded they
+        // would only open a fresh decoder.
+        match ReplicationPacket::from_bytes(datagram) {
+            Ok(packet) if self.last_decoded == Some(packet.changeset_id) => return Ok(0),
+            Ok(_) => {}
+            Err(_) => {
+                self.metrics.packets_rejected += 1;
+                return Ok(0);
+            }
+        }
+
+        match self.receiver.process_packet(datagram) {
+            Ok(PacketResult::Erasure) => {
+                self.metrics.packets_rejected += 1;
+                return Ok(0);
+            }
+            Ok(_) => {}
+            Err(FrankenError::Busy) => {
+                warn!("replication decoder cap reached; dropping partial changesets");
+                self.receiver.force_reset();
+                self.metrics.packets_rejected += 1;
+                return Ok(0);
+            }
+            Err(err) => {
+                debug!(error = %err, "dropping replication packet");
+                self.metrics.packets_rejected += 1;
+                return Ok(0);
+            }
+        }
+        if self.receiver.state() != ReceiverState::Applying {
+            return Ok(0);
+        }
+
+        let results = self.receiver.apply_pending()?;
+        self.receiver.reset_to_listening()?;
+        let mut applied = 0;
+        for result in results {
+            self.last_decoded = Some(result.changeset_id);
+            applied += self.accept_changeset(conn, result.pages)?;
+        }
+        Ok(applied)
+    }
+
+    fn accept_changeset(&mut self, conn: &Connection, pages: Vec<DecodedPage>) -> Result<u64> {
+        let mut pages = pages.into_iter();
+        let marker = match pages.next() {
+            Some(page) if page.page_number == SEQUENCE_MARKER_PAGE => {
+                SequenceMarker::from_page(&page.page_data)?
+            }
+            _ => {
+                warn!("replication changeset without sequence marker");
+                self.metrics.changesets_skipped += 1;
+                return Ok(0);
+            }
+        };
+        if self.last_seq.is_some_and(|last| marker.seq <= last) {
+            self.metrics.changesets_skipped += 1;
+            return Ok(0);
+        }
+        if self.last_seq.is_some_and(|last| marker.seq != last + 1) {
+            warn!(
+                expected = self.last_seq.map(|last| last + 1),
+                got = marker.seq,
+                "replication sequence gap; waiting for full sync"
+            );
+            self.metrics.gaps_detected += 1;
+            self.awaiting_full_sync = true;
+            self.group = None;
+        }
+        self.last_seq = Some(marker.seq);
+        self.metrics.received_seq = marker.seq;
+
+        if marker.has(MARKER_FLAG_FIRST) {
+            if marker.has(MARKER_FLAG_FULL) {
+                self.awaiting_full_sync = false;
+            }
+            self.group = (!self.awaiting_full_sync).then(Vec::new);
+        }
+        let Some(group) = self.group.as_mut() else {
+            self.metrics.changesets_skipped += 1;
+            return Ok(0);
+        };
+        group.extend(pages);
+        if !marker.has(MARKER_FLAG_LAST) {
+            return Ok(0);
+        }
+
+        let group = self.group.take().expect("group staged above");
+        if let Err(err) = self.install(conn, &group) {
+            self.awaiting_full_sync = true;
+            return Err(err);
+        }
+        self.metrics.applied_seq = marker.seq;
+        self.metrics.groups_applied += 1;
+        self.metrics.last_apply_delay_micros =
+            unix_micros().saturating_sub(marker.shipped_at_micros);
+        info!(
+            seq = marker.seq,
+            delay_micros = self.metrics.last_apply_delay_micros,
+            "installed replication group"
+        );
+        Ok(1)
+    }
+
+    /// Write the pages of `group` into `conn`. A full group holds every
+    /// page; an incremental one page 1 plus the pages committed since the
+    /// previous group.
+    fn install(&mut self, conn: &Connection, group: &[DecodedPage]) -> Result<()> {
+        if group.is_empty() {
+            return Ok(());
+        }
+        let mut pages = Vec::with_capacity(group.len());
+        for page in group {
+            if page.page_number == SEQUENCE_MARKER_PAGE {
+                return Err(FrankenError::DatabaseCorrupt {
+                    detail: "replicated group repeats the sequence marker page".to_owned(),
+                });
+            }
+            pages.push((page.page_number, page.page_data.as_slice()));
+        }
+        conn.apply_replicated_pages(&pages)?;
+        self.metrics.pages_applied += group.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fsqlite_types::value::SqliteValue;
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    fn loopback_primary(config: ReplicationServiceConfig) -> ReplicationPrimary {
+        ReplicationPrimary::bind("127.0.0.1:0", config).unwrap()
+    }
+
+    fn loopback_replica() -> ReplicationReplica {
+        ReplicationReplica::bind("127.0.0.1:0", ReceiverConfig::default()).unwrap()
+    }
+
+    fn names(conn: &Connection) -> Vec<SqliteValue> {
+        conn.query("SELECT name FROM t ORDER BY id;")
+            .unwrap()
+            .iter()
+            .map(|row| row.values()[0].clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_replicates_commits_to_multiple_replicas_over_loopback() {
+        let primary_conn = Connection::open(":memory:").unwrap();
+        primary_conn
+            .execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+            .unwrap();
+        primary_conn
+            .execute("INSERT INTO t VALUES (1, 'a'), (2, 'b');")
+            .unwrap();
+
+        let mut primary = loopback_primary(ReplicationServiceConfig::default());
+        let mut replicas = [loopback_replica(), loopback_replica()];
+        let replica_conns = [
+            Connection::open(":memory:").unwrap(),
+            Connection::open(":memory:").unwrap(),
+        ];
+        for replica in &replicas {
+            primary.add_replica(replica.local_addr().unwrap());
+        }
+
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert_eq!(primary.ship(&primary_conn).unwrap(), None);
+        for (replica, conn) in replicas.iter_mut().zip(&replica_conns) {
+            assert!(replica.wait_for_seq(conn, seq, WAIT).unwrap());
+            assert!(replica.is_synced());
+            assert!(conn.is_read_only());
+            assert_eq!(
+                names(conn),
+                vec![SqliteValue::Text("a".into()), SqliteValue::Text("b".into())]
+            );
+        }
+
+        primary_conn
+            .execute("UPDATE t SET name = 'z' WHERE id = 1;")
+            .unwrap();
+        primary_conn
+            .execute("INSERT INTO t VALUES (3, 'c');")
+            .unwrap();
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        let metrics = primary.metrics();
+        assert_eq!(metrics.groups_shipped, 2);
+        assert_eq!(metrics.full_syncs, 1);
+        for (replica, conn) in replicas.iter_mut().zip(&replica_conns) {
+            assert_eq!(replica.metrics().seq_lag(metrics.shipped_seq), 1);
+            assert!(replica.wait_for_seq(conn, seq, WAIT).unwrap());
+            assert_eq!(replica.metrics().seq_lag(metrics.shipped_seq), 0);
+            assert_eq!(replica.metrics().groups_applied, 2);
+            assert_eq!(
+                names(conn),
+                vec![
+                    SqliteValue::Text("z".into()),
+                    SqliteValue::Text("b".into()),
+                    SqliteValue::Text("c".into()),
+                ]
+            );
+            assert!(conn.execute("INSERT INTO t VALUES (4, 'd');").is_err());
+        }
+    }
+
+    #[test]
+    fn test_replica_waits_for_full_sync_after_gap() {
+        let primary_conn = Connection::open(":memory:").unwrap();
+        primary_conn
+            .execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+            .unwrap();
+        let mut primary = loopback_primary(ReplicationServiceConfig::default());
+        let mut replica = loopback_replica();
+        let replica_conn = Connection::open(":memory:").unwrap();
+        primary.add_replica(replica.local_addr().unwrap());
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert!(replica.wait_for_seq(&replica_conn, seq, WAIT).unwrap());
+
+        // Simulate loss by shipping one group while the replica is unregistered.
+        let addr = replica.local_addr().unwrap();
+        assert!(primary.remove_replica(addr));
+        primary_conn
+            .execute("INSERT INTO t VALUES (1, 'a');")
+            .unwrap();
+        primary.ship(&primary_conn).unwrap().unwrap();
+        primary.replicas.push(addr);
+
+        primary_conn
+            .execute("INSERT INTO t VALUES (2, 'b');")
+            .unwrap();
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert!(
+            !replica
+                .wait_for_seq(&replica_conn, seq, Duration::from_millis(200))
+                .unwrap()
+        );
+        assert!(!replica.is_synced());
+        assert_eq!(replica.metrics().gaps_detected, 1);
+        assert_eq!(replica.metrics().changesets_skipped, 1);
+        assert!(names(&replica_conn).is_empty());
+
+        primary.request_full_sync();
+        primary_conn
+            .execute("INSERT INTO t VALUES (3, 'c');")
+            .unwrap();
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert!(replica.wait_for_seq(&replica_conn, seq, WAIT).unwrap());
+        assert!(replica.is_synced());
+        assert_eq!(names(&replica_conn).len(), 3);
+    }
+
+    #[test]
+    fn test_incremental_groups_ship_only_committed_pages() {
+        let primary_conn = Connection::open(":memory:").unwrap();
+        primary_conn
+            .execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);")
+            .unwrap();
+        primary_conn
+            .execute("CREATE TABLE big (id INTEGER PRIMARY KEY, pad TEXT);")
+            .unwrap();
+        for id in 0..200 {
+            primary_conn
+                .execute(&format!(
+                    "INSERT INTO big VALUES ({id}, '{}');",
+                    "x".repeat(200)
+                ))
+                .unwrap();
+        }
+        let mut primary = loopback_primary(ReplicationServiceConfig::default());
+        let mut replica = loopback_replica();
+        let replica_conn = Connection::open(":memory:").unwrap();
+        primary.add_replica(replica.local_addr().unwrap());
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert!(replica.wait_for_seq(&replica_conn, seq, WAIT).unwrap());
+        let full_pages = primary.metrics().pages_shipped;
+        assert!(full_pages > 10, "{full_pages}");
+
+        primary_conn
+            .execute("INSERT INTO t VALUES (1, 'a');")
+            .unwrap();
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        let incremental = primary.metrics().pages_shipped - full_pages;
+        assert!(incremental <= 3, "{incremental} pages for a one-row insert");
+        assert_eq!(primary.metrics().full_syncs, 1);
+        assert!(replica.wait_for_seq(&replica_conn, seq, WAIT).unwrap());
+        assert_eq!(replica.metrics().pages_applied, full_pages + incremental);
+        assert_eq!(names(&replica_conn), vec![SqliteValue::Text("a".into())]);
+
+        primary_conn.execute("DROP TABLE big;").unwrap();
+        primary_conn.execute("VACUUM;").unwrap();
+        let seq = primary.ship(&primary_conn).unwrap().unwrap();
+        assert!(replica.wait_for_seq(&replica_conn, seq, WAIT).unwrap());
+        assert_eq!(names(&replica_conn), vec![SqliteValue::Text("a".into())]);
+        assert!(
+            replica_conn
+                .query("SELECT name FROM sqlite_master WHERE name = 'big';")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_sequence_marker_roundtrip() {
+        let marker = SequenceMarker {
+            seq: 42,
+            shipped_at_micros: 1_700_000_000_000_000,
+            flags: MARKER_FLAG_FIRST | MARKER_FLAG_FULL,
+        };
+        let page = marker.to_page(512);
+        assert_eq!(page.page_number, SEQUENCE_MARKER_PAGE);
+        let decoded = SequenceMarker::from_page(&page.page_bytes).unwrap();
+        assert_eq!(decoded, marker);
+        assert!(decoded.has(MARKER_FLAG_FULL));
+        assert!(!decoded.has(MARKER_FLAG_LAST));
+        assert!(SequenceMarker::from_page(&[0_u8; 4]).is_err());
+    }
+}
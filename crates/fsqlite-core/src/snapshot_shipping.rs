@@ -796,6 +796,190 @@ pub enum SnapshotPacketResult {
     AlreadyComplete,
 }
 
+// ---------------------------------------------------------------------------
+// Snapshot Manifest
+// ---------------------------------------------------------------------------
+
+/// Magic bytes of an encoded [`SnapshotManifest`].
+pub const SNAPSHOT_MANIFEST_MAGIC: [u8; 4] = *b"FSSM";
+
+/// Encoded size of a [`SnapshotManifest`]:
+/// `magic(4) | page_size(4) | page_count(4) | num_blocks(4) | symbol_size(2) | digest(32)`.
+pub const SNAPSHOT_MANIFEST_SIZE: usize = 4 + 4 + 4 + 4 + 2 + 32;
+
+/// Description of a shipped snapshot that a receiver needs before it can
+/// size its decoders, and afterwards to verify the rebuilt image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotManifest {
+    /// Page size of the database.
+    pub page_size: u32,
+    /// Number of pages in the image.
+    pub page_count: u32,
+    /// Number of source blocks the image was partitioned into.
+    pub num_blocks: u32,
+    /// Symbol size used for every block.
+    pub symbol_size: u16,
+    /// BLAKE3 digest of the whole image.
+    pub digest: [u8; 32],
+}
+
+impl SnapshotManifest {
+    /// Serialize to the fixed little-endian wire layout.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; SNAPSHOT_MANIFEST_SIZE] {
+        let mut buf = [0_u8; SNAPSHOT_MANIFEST_SIZE];
+        buf[0..4].copy_from_slice(&SNAPSHOT_MANIFEST_MAGIC);
+        buf[4..8].copy_from_slice(&self.page_size.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.page_count.to_le_bytes());
+        buf[12..16].copy_from_slice(&self.num_blocks.to_le_bytes());
+        buf[16..18].copy_from_slice(&self.symbol_size.to_le_bytes());
+        buf[18..50].copy_from_slice(&self.digest);
+        buf
+    }
+
+    /// Deserialize from bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the buffer is too short or the magic is wrong.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self> {
+        if buf.len() < SNAPSHOT_MANIFEST_SIZE {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "snapshot manifest too short: {} < {SNAPSHOT_MANIFEST_SIZE}",
+                    buf.len()
+                ),
+            });
+        }
+        if buf[0..4] != SNAPSHOT_MANIFEST_MAGIC {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!("snapshot manifest magic mismatch: {:?}", &buf[0..4]),
+            });
+        }
+        Ok(Self {
+            page_size: u32::from_le_bytes(buf[4..8].try_into().expect("4 bytes")),
+            page_count: u32::from_le_bytes(buf[8..12].try_into().expect("4 bytes")),
+            num_blocks: u32::from_le_bytes(buf[12..16].try_into().expect("4 bytes")),
+            symbol_size: u16::from_le_bytes(buf[16..18].try_into().expect("2 bytes")),
+            digest: buf[18..50].try_into().expect("32 bytes"),
+        })
+    }
+}
+
+/// BLAKE3 digest of a database image, as carried in [`SnapshotManifest`].
+#[must_use]
+pub fn snapshot_digest(image: &[u8]) -> [u8; 32] {
+    *blake3::hash(image).as_bytes()
+}
+
+impl SnapshotSender {
+    /// Prepare a sender for a whole serialized database image.
+    ///
+    /// `config.symbol_size` is raised when a block would otherwise need more
+    /// than `K_MAX` source symbols.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the image is empty or not a whole number of pages,
+    /// or if no valid symbol size fits the largest block.
+    pub fn from_image(
+        image: &[u8],
+        page_size: u32,
+        mut config: SenderConfig,
+    ) -> Result<(Self, SnapshotManifest)> {
+        let page_len = page_size as usize;
+        if page_len == 0 || image.is_empty() || image.len() % page_len != 0 {
+            return Err(FrankenError::OutOfRange {
+                what: "snapshot image length".to_owned(),
+                value: format!("{} (page size {page_size})", image.len()),
+            });
+        }
+        let page_count =
+            u32::try_from(image.len() / page_len).map_err(|_| FrankenError::OutOfRange {
+                what: "snapshot page count".to_owned(),
+                value: (image.len() / page_len).to_string(),
+            })?;
+
+        let largest_block = partition_source_blocks(page_count)?
+            .iter()
+            .map(|block| block.num_pages)
+            .max()
+            .unwrap_or(0);
+        let block_bytes =
+            CHANGESET_HEADER_SIZE as u64 + u64::from(largest_block) * (12 + u64::from(page_size));
+        let needed = block_bytes.div_ceil(u64::from(K_MAX));
+        if needed > u64::from(config.symbol_size) {
+            config.symbol_size = u16::try_from(needed).map_err(|_| FrankenError::OutOfRange {
+                what: "snapshot symbol size".to_owned(),
+                value: needed.to_string(),
+            })?;
+            debug!(
+                bead_id = BEAD_ID,
+                symbol_size = config.symbol_size,
+                "raised snapshot symbol size to fit K_MAX"
+            );
+        }
+        ReplicationPacket::validate_symbol_size(usize::from(config.symbol_size))?;
+
+        let mut pages: Vec<PageEntry> = (1_u32..)
+            .zip(image.chunks_exact(page_len))
+            .map(|(page_number, page)| PageEntry::new(page_number, page.to_vec()))
+            .collect();
+        let symbol_size = config.symbol_size;
+        let sender = Self::prepare(page_size, &mut pages, config)?;
+        let manifest = SnapshotManifest {
+            page_size,
+            page_count,
+            num_blocks: u32::try_from(sender.num_blocks()).unwrap_or(u32::MAX),
+            symbol_size,
+            digest: snapshot_digest(image),
+        };
+        Ok((sender, manifest))
+    }
+}
+
+/// Rebuild the database image described by `manifest` from its decoded
+/// blocks and verify it against the manifest digest.
+///
+/// # Errors
+///
+/// Returns error if a page is out of range or missing, or if the digest of
+/// the rebuilt image does not match.
+pub fn assemble_snapshot_image(
+    manifest: &SnapshotManifest,
+    blocks: &[DecodedBlock],
+) -> Result<Vec<u8>> {
+    let page_len = manifest.page_size as usize;
+    let page_count = manifest.page_count as usize;
+    let mut image = vec![0_u8; page_len * page_count];
+    let mut present = vec![false; page_count];
+    for page in blocks.iter().flat_map(|block| block.pages.iter()) {
+        let index = (page.page_number as usize).wrapping_sub(1);
+        if index >= page_count || page.page_data.len() != page_len {
+            return Err(FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "snapshot page {} does not fit a {page_count}-page image of {page_len}-byte pages",
+                    page.page_number
+                ),
+            });
+        }
+        image[index * page_len..(index + 1) * page_len].copy_from_slice(&page.page_data);
+        present[index] = true;
+    }
+    if let Some(missing) = present.iter().position(|&seen| !seen) {
+        return Err(FrankenError::DatabaseCorrupt {
+            detail: format!("snapshot page {} was never received", missing + 1),
+        });
+    }
+    if snapshot_digest(&image) != manifest.digest {
+        error!(bead_id = BEAD_ID, "rebuilt snapshot digest mismatch");
+        return Err(FrankenError::DatabaseCorrupt {
+            detail: "rebuilt snapshot digest does not match manifest".to_owned(),
+        });
+    }
+    Ok(image)
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -1369,4 +1553,39 @@ mod tests {
         }
         assert_eq!(receiver.state(), SnapshotReceiverState::Complete);
     }
+
+    #[test]
+    fn test_image_roundtrip_verifies_manifest_digest() {
+        let page_size = 256_u32;
+        let pages = make_pages(page_size, &(1..=12).collect::<Vec<_>>());
+        let image: Vec<u8> = pages.iter().flat_map(|p| p.page_bytes.clone()).collect();
+        let config = SenderConfig {
+            symbol_size: 256,
+            max_isi_multiplier: 1,
+        };
+        let (mut sender, manifest) =
+            SnapshotSender::from_image(&image, page_size, config).expect("from_image");
+        assert_eq!(manifest.page_count, 12);
+        assert_eq!(
+            SnapshotManifest::from_bytes(&manifest.to_bytes()).expect("manifest"),
+            manifest,
+            "bead_id={TEST_BEAD_ID} case=manifest_roundtrip"
+        );
+
+        let mut receiver = SnapshotReceiver::new(manifest.num_blocks as usize, manifest.page_size);
+        while let Some(pkt) = sender.next_packet() {
+            let _ = receiver.process_packet(&pkt);
+        }
+        let blocks = receiver.take_decoded_blocks();
+        let rebuilt = assemble_snapshot_image(&manifest, &blocks).expect("assemble");
+        assert_eq!(
+            rebuilt, image,
+            "bead_id={TEST_BEAD_ID} case=image_roundtrip"
+        );
+
+        let mut tampered = manifest;
+        tampered.digest[0] ^= 0xFF;
+        assert!(assemble_snapshot_image(&tampered, &blocks).is_err());
+        assert!(assemble_snapshot_image(&manifest, &blocks[..0]).is_err());
+    }
 }
@@ -72,6 +72,43 @@ impl DurabilityMode {
             Self::Quorum { required, .. } => acked_stores >= required,
         }
     }
+
+    /// Parse the PRAGMA spelling: `local`, `quorum(M/N)` or `quorum(M)`
+    /// (shorthand for `quorum(M/M)`).
+    pub fn parse(text: &str) -> Result<Self> {
+        let normalized: String = text
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if normalized == "local" {
+            return Ok(Self::Local);
+        }
+        let invalid = || FrankenError::OutOfRange {
+            what: "durability".to_owned(),
+            value: text.to_owned(),
+        };
+        let inner = normalized
+            .strip_prefix("quorum(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(invalid)?;
+        let (required, total) = match inner.split_once('/') {
+            Some((required, total)) => (required, total),
+            None => (inner, inner),
+        };
+        let required = required.parse::<u32>().map_err(|_| invalid())?;
+        let total = total.parse::<u32>().map_err(|_| invalid())?;
+        Self::quorum(required, total)
+    }
+}
+
+impl std::fmt::Display for DurabilityMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => f.write_str("local"),
+            Self::Quorum { required, total } => write!(f, "quorum({required}/{total})"),
+        }
+    }
 }
 
 /// Request for a remote fetch operation.
@@ -256,6 +293,11 @@ impl TieredStorage {
         self.l2_segments.insert(segment_id, records);
     }
 
+    /// Drop one L2 segment without touching the remote tier.
+    pub fn remove_l2_segment(&mut self, segment_id: u64) -> Option<Vec<SymbolRecord>> {
+        self.l2_segments.remove(&segment_id)
+    }
+
     /// Number of L2 segments currently retained.
     #[must_use]
     pub fn l2_segment_count(&self) -> usize {
@@ -1827,6 +1869,30 @@ mod tests {
         assert!(storage.l2_segment_exists(61));
     }
 
+    #[test]
+    fn test_durability_mode_parse_roundtrip() {
+        assert_eq!(
+            DurabilityMode::parse("LOCAL").expect("local parses"),
+            DurabilityMode::Local
+        );
+        let quorum = DurabilityMode::parse(" quorum( 2 / 3 ) ").expect("quorum parses");
+        assert_eq!(quorum, DurabilityMode::quorum(2, 3).expect("valid quorum"));
+        assert_eq!(quorum.to_string(), "quorum(2/3)");
+        assert_eq!(
+            DurabilityMode::parse("quorum(2)").expect("shorthand parses"),
+            DurabilityMode::quorum(2, 2).expect("valid quorum")
+        );
+        for invalid in ["quorum(0/3)", "quorum(4/3)", "quorum(a/b)", "remote"] {
+            assert!(
+                matches!(
+                    DurabilityMode::parse(invalid),
+                    Err(FrankenError::OutOfRange { .. })
+                ),
+                "{invalid} must be rejected"
+            );
+        }
+    }
+
     #[test]
     fn test_tiered_storage_remote_admission_cancelled_while_waiting() {
         let object_id = object_id_from_u64(9);
@@ -0,0 +1,302 @@
+//! `sqlite3_open_v2` flag validation and `file:` URI parsing.
+//!
+//! [`OpenTarget::resolve`] turns a filename plus [`OpenFlags`] into the
+//! concrete database path and access mode a connection should use. When
+//! `OpenFlags::URI` is set and the filename starts with `file:`, the URI is
+//! parsed following SQLite's rules (<https://sqlite.org/uri.html>):
+//!
+//! - the authority must be empty or `localhost`;
+//! - `%HH` escapes are decoded in the path and in query parameters;
+//! - `mode=ro|rw|rwc|memory` restricts (never widens) the access mode;
+//! - `vfs=NAME` selects a registered VFS;
+//! - `cache=shared|private` is accepted (there is only one cache mode here);
+//! - `immutable=1` implies read-only access without file locking;
+//! - `nolock=` and `psow=` take SQLite boolean values;
+//! - unknown parameters are ignored.
+
+use fsqlite_error::{FrankenError, Result};
+use fsqlite_types::flags::{OpenFlags, VfsOpenFlags};
+
+/// Path used for in-memory databases.
+const MEMORY_PATH: &str = ":memory:";
+
+/// A resolved `sqlite3_open_v2` request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenTarget {
+    /// Database path with any URI syntax stripped (`:memory:` for in-memory).
+    pub path: String,
+    /// Effective open flags after applying URI parameters.
+    pub flags: OpenFlags,
+    /// VFS selected by a `vfs=` URI parameter.
+    pub vfs: Option<String>,
+    /// `immutable=1`: the file is assumed never to change.
+    pub immutable: bool,
+    /// `nolock=1` (or `immutable=1`): skip file locking.
+    pub nolock: bool,
+    /// `psow=` override for the powersafe-overwrite device characteristic.
+    pub psow: Option<bool>,
+}
+
+impl OpenTarget {
+    /// Validate `flags` and parse `filename` into an open target.
+    ///
+    /// # Errors
+    /// Returns [`FrankenError::CannotOpen`] if the access-mode flags are not
+    /// exactly one of `READONLY`, `READWRITE`, or `READWRITE | CREATE`, and
+    /// an SQL error if the URI has a non-local authority or a `mode=` /
+    /// `cache=` parameter is invalid or more permissive than `flags`.
+    pub fn resolve(filename: &str, flags: OpenFlags) -> Result<Self> {
+        let access = flags & (OpenFlags::READONLY | OpenFlags::READWRITE | OpenFlags::CREATE);
+        if access != OpenFlags::READONLY
+            && access != OpenFlags::READWRITE
+            && access != (OpenFlags::READWRITE | OpenFlags::CREATE)
+        {
+            return Err(FrankenError::CannotOpen {
+                path: filename.into(),
+            });
+        }
+
+        let mut target = Self {
+            path: filename.to_owned(),
+            flags,
+            vfs: None,
+            immutable: false,
+            nolock: false,
+            psow: None,
+        };
+        if flags.contains(OpenFlags::URI) {
+            if let Some(rest) = filename.strip_prefix("file:") {
+                target.apply_uri(rest)?;
+            }
+        }
+        if target.flags.contains(OpenFlags::MEMORY) {
+            MEMORY_PATH.clone_into(&mut target.path);
+        }
+        Ok(target)
+    }
+
+    /// Whether the database is opened without write access.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        !self.flags.contains(OpenFlags::READWRITE)
+    }
+
+    /// Whether the target is an in-memory database.
+    #[must_use]
+    pub fn is_memory(&self) -> bool {
+        self.path == MEMORY_PATH
+    }
+
+    /// VFS-level open flags for the main database file.
+    #[must_use]
+    pub fn vfs_open_flags(&self) -> VfsOpenFlags {
+        if self.is_read_only() {
+            VfsOpenFlags::READONLY
+        } else if self.flags.contains(OpenFlags::CREATE) {
+            VfsOpenFlags::READWRITE | VfsOpenFlags::CREATE
+        } else {
+            VfsOpenFlags::READWRITE
+        }
+    }
+
+    fn apply_uri(&mut self, rest: &str) -> Result<()> {
+        let rest = rest.split_once('#').map_or(rest, |(before, _)| before);
+        let (raw_path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let raw_path = if let Some(after) = raw_path.strip_prefix("//") {
+            let (authority, path) = after
+                .find('/')
+                .map_or((after, ""), |idx| after.split_at(idx));
+            if !authority.is_empty() && authority != "localhost" {
+                return Err(FrankenError::function_error(format!(
+                    "invalid uri authority: {authority}"
+                )));
+            }
+            path
+        } else {
+            raw_path
+        };
+        self.path = percent_decode(raw_path);
+
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let key = percent_decode(key);
+            let value = percent_decode(value);
+            match key.as_str() {
+                "mode" => self.apply_mode(&value)?,
+                "cache" => match value.as_str() {
+                    "shared" => {
+                        self.flags.remove(OpenFlags::PRIVATECACHE);
+                        self.flags.insert(OpenFlags::SHAREDCACHE);
+                    }
+                    "private" => {
+                        self.flags.remove(OpenFlags::SHAREDCACHE);
+                        self.flags.insert(OpenFlags::PRIVATECACHE);
+                    }
+                    _ => {
+                        return Err(FrankenError::function_error(format!(
+                            "no such cache mode: {value}"
+                        )));
+                    }
+                },
+                "vfs" => self.vfs = Some(value),
+                "immutable" => {
+                    if uri_boolean(&value) {
+                        self.immutable = true;
+                        self.nolock = true;
+                        self.flags.remove(OpenFlags::READWRITE | OpenFlags::CREATE);
+                        self.flags.insert(OpenFlags::READONLY);
+                    }
+                }
+                "nolock" => self.nolock = self.nolock || uri_boolean(&value),
+                "psow" => self.psow = Some(uri_boolean(&value)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_mode(&mut self, value: &str) -> Result<()> {
+        let (requested, memory) = match value {
+            "ro" => (OpenFlags::READONLY, false),
+            "rw" => (OpenFlags::READWRITE, false),
+            "rwc" => (OpenFlags::READWRITE | OpenFlags::CREATE, false),
+            "memory" => (OpenFlags::empty(), true),
+            _ => {
+                return Err(FrankenError::function_error(format!(
+                    "no such access mode: {value}"
+                )));
+            }
+        };
+        if memory {
+            self.flags.insert(OpenFlags::MEMORY);
+            return Ok(());
+        }
+
+        let allowed = self.flags & (OpenFlags::READONLY | OpenFlags::READWRITE | OpenFlags::CREATE);
+        let widens = (requested.contains(OpenFlags::READWRITE)
+            && !allowed.contains(OpenFlags::READWRITE))
+            || (requested.contains(OpenFlags::CREATE) && !allowed.contains(OpenFlags::CREATE));
+        if widens {
+            return Err(FrankenError::function_error(format!(
+                "access mode not allowed: {value}"
+            )));
+        }
+        self.flags
+            .remove(OpenFlags::READONLY | OpenFlags::READWRITE | OpenFlags::CREATE);
+        self.flags.insert(requested);
+        Ok(())
+    }
+}
+
+/// Interpret a URI parameter the way `sqlite3_uri_boolean` does; an empty
+/// value is false.
+fn uri_boolean(value: &str) -> bool {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "yes" | "true" | "on" => true,
+        other => other.parse::<i64>().is_ok_and(|n| n != 0),
+    }
+}
+
+/// Decode `%HH` escapes; malformed escapes are kept verbatim.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..=i + 2]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(filename: &str, flags: OpenFlags) -> Result<OpenTarget> {
+        OpenTarget::resolve(filename, flags | OpenFlags::URI)
+    }
+
+    #[test]
+    fn test_plain_filename_ignores_uri_syntax_without_flag() {
+        let target = OpenTarget::resolve("file:data.db?mode=ro", OpenFlags::default()).unwrap();
+        assert_eq!(target.path, "file:data.db?mode=ro");
+        assert!(!target.is_read_only());
+    }
+
+    #[test]
+    fn test_invalid_access_flags_rejected() {
+        assert!(matches!(
+            OpenTarget::resolve("a.db", OpenFlags::CREATE),
+            Err(FrankenError::CannotOpen { .. })
+        ));
+        assert!(OpenTarget::resolve("a.db", OpenFlags::READONLY | OpenFlags::READWRITE).is_err());
+        assert!(OpenTarget::resolve("a.db", OpenFlags::empty()).is_err());
+    }
+
+    #[test]
+    fn test_uri_path_and_authority() {
+        let target = uri("file:///tmp/my%20db.db#frag", OpenFlags::default()).unwrap();
+        assert_eq!(target.path, "/tmp/my db.db");
+        let target = uri("file://localhost/tmp/x.db", OpenFlags::default()).unwrap();
+        assert_eq!(target.path, "/tmp/x.db");
+        assert!(uri("file://example.com/x.db", OpenFlags::default()).is_err());
+    }
+
+    #[test]
+    fn test_uri_mode_parameter() {
+        let ro = uri("file:a.db?mode=ro", OpenFlags::default()).unwrap();
+        assert!(ro.is_read_only());
+        assert_eq!(ro.vfs_open_flags(), VfsOpenFlags::READONLY);
+
+        let rw = uri("file:a.db?mode=rw", OpenFlags::default()).unwrap();
+        assert_eq!(rw.vfs_open_flags(), VfsOpenFlags::READWRITE);
+
+        let mem = uri("file:a.db?mode=memory", OpenFlags::default()).unwrap();
+        assert!(mem.is_memory());
+
+        assert!(uri("file:a.db?mode=rwc", OpenFlags::READWRITE).is_err());
+        assert!(uri("file:a.db?mode=rw", OpenFlags::READONLY).is_err());
+        assert!(uri("file:a.db?mode=bogus", OpenFlags::default()).is_err());
+    }
+
+    #[test]
+    fn test_uri_misc_parameters() {
+        let target = uri(
+            "file:a.db?vfs=custom&cache=shared&nolock=1&psow=0&unknown=x",
+            OpenFlags::default(),
+        )
+        .unwrap();
+        assert_eq!(target.vfs.as_deref(), Some("custom"));
+        assert!(target.flags.contains(OpenFlags::SHAREDCACHE));
+        assert!(target.nolock);
+        assert_eq!(target.psow, Some(false));
+        assert!(matches!(
+            uri("file:a.db?cache=bogus", OpenFlags::default()),
+            Err(FrankenError::FunctionError(ref msg)) if msg == "no such cache mode: bogus"
+        ));
+
+        let empty = uri("file:a.db?nolock=&psow=", OpenFlags::default()).unwrap();
+        assert!(!empty.nolock);
+        assert_eq!(empty.psow, Some(false));
+
+        let immutable = uri("file:a.db?immutable=1", OpenFlags::default()).unwrap();
+        assert!(immutable.immutable && immutable.nolock && immutable.is_read_only());
+    }
+
+    #[test]
+    fn test_memory_flag_forces_memory_path() {
+        let target =
+            OpenTarget::resolve("ignored.db", OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+        assert!(target.is_memory());
+    }
+}
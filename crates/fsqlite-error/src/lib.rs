@@ -487,12 +487,26 @@ impl FrankenError {
     /// - `Busy` → 5 (SQLITE_BUSY)
     /// - `BusyRecovery` → 261 (SQLITE_BUSY_RECOVERY = 5 | (1 << 8))
     /// - `BusySnapshot` → 517 (SQLITE_BUSY_SNAPSHOT = 5 | (2 << 8))
+    ///
+    /// Constraint violations and page-level I/O failures report the matching
+    /// `SQLITE_CONSTRAINT_*` / `SQLITE_IOERR_*` code.
     pub const fn extended_error_code(&self) -> i32 {
+        const BUSY: i32 = ErrorCode::Busy as i32;
+        const IOERR: i32 = ErrorCode::IoErr as i32;
+        const CONSTRAINT: i32 = ErrorCode::Constraint as i32;
         match self {
-            Self::Busy => 5,                           // SQLITE_BUSY
-            Self::BusyRecovery => 5 | (1 << 8),        // SQLITE_BUSY_RECOVERY = 261
-            Self::BusySnapshot { .. } => 5 | (2 << 8), // SQLITE_BUSY_SNAPSHOT = 517
-            Self::DatatypeViolation { .. } => 3091,    // SQLITE_CONSTRAINT_DATATYPE
+            Self::Busy => BUSY,
+            Self::BusyRecovery => BUSY | (1 << 8), // SQLITE_BUSY_RECOVERY = 261
+            Self::BusySnapshot { .. } => BUSY | (2 << 8), // SQLITE_BUSY_SNAPSHOT = 517
+            Self::IoRead { .. } => IOERR | (1 << 8), // SQLITE_IOERR_READ = 266
+            Self::ShortRead { .. } => IOERR | (2 << 8), // SQLITE_IOERR_SHORT_READ = 522
+            Self::IoWrite { .. } => IOERR | (3 << 8), // SQLITE_IOERR_WRITE = 778
+            Self::CheckViolation { .. } => CONSTRAINT | (1 << 8), // SQLITE_CONSTRAINT_CHECK = 275
+            Self::ForeignKeyViolation => CONSTRAINT | (3 << 8), // SQLITE_CONSTRAINT_FOREIGNKEY = 787
+            Self::NotNullViolation { .. } => CONSTRAINT | (5 << 8), // SQLITE_CONSTRAINT_NOTNULL = 1299
+            Self::PrimaryKeyViolation => CONSTRAINT | (6 << 8), // SQLITE_CONSTRAINT_PRIMARYKEY = 1555
+            Self::UniqueViolation { .. } => CONSTRAINT | (8 << 8), // SQLITE_CONSTRAINT_UNIQUE = 2067
+            Self::DatatypeViolation { .. } => CONSTRAINT | (12 << 8), // SQLITE_CONSTRAINT_DATATYPE = 3091
             _ => self.error_code() as i32,
         }
     }
@@ -697,6 +711,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extended_error_codes_for_constraints_and_io() {
+        let unique = FrankenError::UniqueViolation {
+            columns: "t.a".to_owned(),
+        };
+        assert_eq!(unique.extended_error_code(), 2067);
+        assert_eq!(
+            FrankenError::NotNullViolation {
+                column: "t.a".to_owned()
+            }
+            .extended_error_code(),
+            1299
+        );
+        assert_eq!(
+            FrankenError::PrimaryKeyViolation.extended_error_code(),
+            1555
+        );
+        assert_eq!(FrankenError::ForeignKeyViolation.extended_error_code(), 787);
+        assert_eq!(FrankenError::IoRead { page: 1 }.extended_error_code(), 266);
+        assert_eq!(FrankenError::IoWrite { page: 1 }.extended_error_code(), 778);
+
+        // The low byte of every extended code is the primary code.
+        for err in [
+            unique,
+            FrankenError::CheckViolation {
+                name: "c".to_owned(),
+            },
+            FrankenError::ShortRead {
+                expected: 4096,
+                actual: 0,
+            },
+        ] {
+            assert_eq!(err.extended_error_code() & 0xff, err.error_code() as i32);
+        }
+    }
+
     #[test]
     fn constraint_errors() {
         let err = FrankenError::UniqueViolation {
@@ -890,7 +890,7 @@ impl VirtualTableCursor for RtreeCursor {
         }
 
         let coordinate_index = usize::try_from(col - 1).map_err(|error| {
-            FrankenError::function_error(format!("rtree column index conversion failed: {error}",))
+            FrankenError::function_error(format!("rtree column index conversion failed: {error}"))
         })?;
         if let Some(coord) = row.bbox.coords.get(coordinate_index) {
             ctx.set_value(self.coordinate_value(*coord)?);
@@ -928,9 +928,9 @@ impl PendingRowChange {
 /// A session that records database changes for later extraction as a
 /// changeset or patchset.
 ///
-/// In a real database engine this would hook into the DML pipeline. For now
-/// it provides a programmatic API for recording changes and generating the
-/// binary changeset/patchset encoding.
+/// This is the programmatic API for recording changes and generating the
+/// binary changeset/patchset encoding. `fsqlite-core` feeds it automatically
+/// from the VDBE write path via `Connection::session`.
 #[derive(Debug)]
 pub struct Session {
     tables: Vec<TrackedTable>,
@@ -1141,8 +1141,8 @@ pub enum ApplyOutcome {
 /// A simple in-memory "database" for testing changeset application.
 ///
 /// Maps `table_name -> Vec<row>` where each row is `Vec<SqliteValue>`.
-/// This is intentionally minimal; the real apply engine would operate on
-/// the B-tree layer.
+/// This is intentionally minimal; `Connection::apply_changeset` in
+/// `fsqlite-core` applies changesets to real tables.
 #[derive(Debug, Clone, Default)]
 pub struct SimpleTarget {
     pub tables: std::collections::HashMap<String, Vec<Vec<SqliteValue>>>,
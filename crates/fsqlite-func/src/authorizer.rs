@@ -63,7 +63,7 @@ pub enum AuthAction {
     /// DROP VIEW (arg1=view name, arg2=None)
     DropView,
 
-    // -- Miscellaneous (12) --
+    // -- Miscellaneous (13) --
     /// PRAGMA (arg1=pragma name, arg2=pragma arg or None)
     Pragma,
     /// Transaction control (arg1=operation e.g. "BEGIN", arg2=None)
@@ -88,6 +88,8 @@ pub enum AuthAction {
     Savepoint,
     /// Recursive query (arg1=None, arg2=None)
     Recursive,
+    /// VACUUM (arg1=schema name, arg2=`INTO` filename or None)
+    Vacuum,
 }
 
 /// Result of an authorization check.
@@ -113,8 +115,8 @@ pub enum AuthResult {
 /// - `arg1`/`arg2`: Context-dependent string parameters (table name, column
 ///   name, index name, etc.). The meaning depends on the [`AuthAction`].
 /// - `db_name`: The database name (e.g. `"main"`, `"temp"`, attached name).
-/// - `trigger`: If the operation originates inside a trigger, this is the
-///   trigger name. `None` for top-level SQL.
+/// - `trigger`: If the operation originates inside a trigger or view, this is
+///   the name of the inner-most one. `None` for top-level SQL.
 pub trait Authorizer: Send + Sync {
     /// Return allow/deny/ignore for the given action.
     fn authorize(
@@ -303,10 +305,11 @@ mod tests {
             AuthAction::Function,
             AuthAction::Savepoint,
             AuthAction::Recursive,
+            AuthAction::Vacuum,
         ];
 
-        // All 33 variants are constructible.
-        assert_eq!(actions.len(), 33);
+        // All 34 variants are constructible.
+        assert_eq!(actions.len(), 34);
 
         // Each is pattern-matchable (exhaustive).
         for a in &actions {
@@ -343,7 +346,8 @@ mod tests {
                 | AuthAction::DropVtable
                 | AuthAction::Function
                 | AuthAction::Savepoint
-                | AuthAction::Recursive => true,
+                | AuthAction::Recursive
+                | AuthAction::Vacuum => true,
             };
         }
     }
@@ -92,6 +92,44 @@ impl CollationFunction for RtrimCollation {
     }
 }
 
+/// A collation backed by a closure over UTF-8 text, as registered through
+/// `sqlite3_create_collation`-style APIs.
+///
+/// Operands that are not valid UTF-8 fall back to byte comparison.
+pub struct FnCollation<F> {
+    name: String,
+    compare: F,
+}
+
+impl<F> FnCollation<F>
+where
+    F: Fn(&str, &str) -> Ordering + Send + Sync,
+{
+    /// Create a collation named `name` that orders text with `compare`.
+    pub fn new(name: impl Into<String>, compare: F) -> Self {
+        Self {
+            name: name.into(),
+            compare,
+        }
+    }
+}
+
+impl<F> CollationFunction for FnCollation<F>
+where
+    F: Fn(&str, &str) -> Ordering + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compare(&self, left: &[u8], right: &[u8]) -> Ordering {
+        match (std::str::from_utf8(left), std::str::from_utf8(right)) {
+            (Ok(l), Ok(r)) => (self.compare)(l, r),
+            _ => left.cmp(right),
+        }
+    }
+}
+
 fn strip_trailing_spaces(s: &[u8]) -> &[u8] {
     let mut end = s.len();
     while end > 0 && s[end - 1] == b' ' {
@@ -159,6 +197,16 @@ impl CollationRegistry {
         self.collations.insert(name, Arc::new(collation))
     }
 
+    /// Remove a custom collation. The built-in BINARY, NOCASE and RTRIM
+    /// collations cannot be removed. Returns `true` if one was removed.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let canon = name.to_ascii_uppercase();
+        if matches!(canon.as_str(), "BINARY" | "NOCASE" | "RTRIM") {
+            return false;
+        }
+        self.collations.remove(&canon).is_some()
+    }
+
     /// Look up a collation by name (case-insensitive).
     ///
     /// Returns `None` if no collation with the given name is registered.
@@ -434,6 +482,19 @@ mod tests {
         assert_eq!(coll.compare(b"a", b"z"), Ordering::Greater);
     }
 
+    #[test]
+    fn test_fn_collation_compares_text() {
+        let mut reg = CollationRegistry::new();
+        reg.register(FnCollation::new("by_len", |l: &str, r: &str| {
+            l.chars().count().cmp(&r.chars().count())
+        }));
+
+        let coll = reg.find("BY_LEN").expect("closure collation registered");
+        assert_eq!(coll.name(), "by_len");
+        assert_eq!(coll.compare(b"zz", b"aaa"), Ordering::Less);
+        assert_eq!(coll.compare(b"\xff", b"\xfe"), Ordering::Greater);
+    }
+
     struct AlwaysEqualCollation;
 
     impl CollationFunction for AlwaysEqualCollation {
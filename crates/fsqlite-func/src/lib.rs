@@ -103,7 +103,7 @@ pub use builtins::{
 };
 pub use collation::{
     BinaryCollation, CollationAnnotation, CollationFunction, CollationRegistry, CollationSource,
-    NoCaseCollation, RtrimCollation, resolve_collation,
+    FnCollation, NoCaseCollation, RtrimCollation, resolve_collation,
 };
 pub use datetime::register_datetime_builtins;
 pub use math::register_math_builtins;
@@ -216,6 +216,27 @@ impl FunctionRegistry {
             .insert(key, Arc::new(WindowAdapter::new(function)))
     }
 
+    /// Remove the scalar, aggregate and window functions registered under
+    /// exactly `(name, num_args)`. Returns `true` if anything was removed.
+    pub fn unregister(&mut self, name: &str, num_args: i32) -> bool {
+        let key = FunctionKey::new(name, num_args);
+        let scalar = self.scalars.remove(&key).is_some();
+        let aggregate = self.aggregates.remove(&key).is_some();
+        let window = self.windows.remove(&key).is_some();
+        scalar || aggregate || window
+    }
+
+    /// Make the functions under exactly `(name, num_args)` the ones `base`
+    /// has under that key, dropping any `base` lacks. Returns `true` if
+    /// anything changed, so a no-op means no override was registered.
+    pub fn reset_to(&mut self, base: &Self, name: &str, num_args: i32) -> bool {
+        let key = FunctionKey::new(name, num_args);
+        let scalar = reset_entry(&mut self.scalars, &base.scalars, &key);
+        let aggregate = reset_entry(&mut self.aggregates, &base.aggregates, &key);
+        let window = reset_entry(&mut self.windows, &base.windows, &key);
+        scalar || aggregate || window
+    }
+
     /// Look up a scalar function by `(name, num_args)`.
     ///
     /// Tries exact match first, then falls back to the variadic version
@@ -376,6 +397,26 @@ fn canonical_name(name: &str) -> String {
     name.trim().to_ascii_uppercase()
 }
 
+/// Set `map[key]` to `base[key]`, returning whether the entry changed.
+fn reset_entry<V: ?Sized>(
+    map: &mut HashMap<FunctionKey, Arc<V>>,
+    base: &HashMap<FunctionKey, Arc<V>>,
+    key: &FunctionKey,
+) -> bool {
+    match (map.get(key), base.get(key)) {
+        (None, None) => false,
+        (Some(current), Some(original)) if Arc::ptr_eq(current, original) => false,
+        (_, Some(original)) => {
+            map.insert(key.clone(), Arc::clone(original));
+            true
+        }
+        (Some(_), None) => {
+            map.remove(key);
+            true
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use fsqlite_types::SqliteValue;
@@ -557,6 +598,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_registry_reset_to_restores_base_function() {
+        let mut base = FunctionRegistry::new();
+        base.register_scalar(Double);
+        let base = Arc::new(base);
+
+        let mut registry = FunctionRegistry::clone_from_arc(&base);
+        assert!(!registry.reset_to(&base, "double", 1), "nothing overridden");
+        registry.register_scalar(Double);
+        registry.register_scalar(VariadicConcat);
+        assert!(registry.reset_to(&base, "double", 1));
+        assert!(Arc::ptr_eq(
+            &registry.find_scalar("double", 1).unwrap(),
+            &base.find_scalar("double", 1).unwrap()
+        ));
+        assert!(registry.reset_to(&base, "my_func", -1));
+        assert!(registry.find_scalar("my_func", 3).is_none());
+    }
+
     #[test]
     fn test_registry_variadic_fallback() {
         let mut registry = FunctionRegistry::new();
@@ -122,6 +122,7 @@ fn tpch_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
         IndexInfo {
             name: "idx_lineitem_orderkey".to_owned(),
@@ -132,6 +133,7 @@ fn tpch_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
         IndexInfo {
             name: "idx_customer_nationkey".to_owned(),
@@ -142,6 +144,7 @@ fn tpch_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
         IndexInfo {
             name: "idx_supplier_nationkey".to_owned(),
@@ -152,6 +155,7 @@ fn tpch_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
     ]
 }
@@ -196,6 +200,7 @@ fn oltp_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
         IndexInfo {
             name: "idx_order_items_order_id".to_owned(),
@@ -206,6 +211,7 @@ fn oltp_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
         IndexInfo {
             name: "idx_order_items_item_id".to_owned(),
@@ -216,6 +222,7 @@ fn oltp_indexes() -> Vec<IndexInfo> {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         },
     ]
 }
@@ -313,6 +320,7 @@ fn test_access_path_selection() {
         source: StatsSource::Analyze,
         partial_where: None,
         expression_columns: vec![],
+        leading_column_stats: None,
     };
 
     // No WHERE -> full table scan
@@ -463,6 +471,7 @@ fn test_index_usability() {
         source: StatsSource::Analyze,
         partial_where: None,
         expression_columns: vec![],
+        leading_column_stats: None,
     };
 
     let expr = dummy_expr();
@@ -493,6 +502,7 @@ fn test_index_usability() {
         source: StatsSource::Analyze,
         partial_where: None,
         expression_columns: vec![],
+        leading_column_stats: None,
     };
 
     // Equality on first column of composite -> usable
@@ -616,6 +626,7 @@ fn test_conformance_summary() {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         };
         let expr = dummy_expr();
         let eq = eq_term("t", "a", &expr);
@@ -1045,7 +1045,7 @@ pub fn concurrent_commit(
     let txn_id = TxnId::new(session_id).ok_or((MvccError::InvalidState, FcwResult::Clean))?;
 
     // Step 1: First-committer-wins validation.
-    let fcw_result = validate_first_committer_wins(&handle, commit_index);
+    let fcw_result = validate_first_committer_wins(handle, commit_index);
     match &fcw_result {
         FcwResult::Clean => {
             // FCW passed. Now run SSI validation.
@@ -1111,8 +1111,8 @@ pub fn prepare_concurrent_commit_with_ssi(
 
     // First, verify the session exists and is active.
     {
-        let handle = registry
-            .get(session_id)
+        let mut handle = registry
+            .get_mut(session_id)
             .ok_or((MvccError::InvalidState, FcwResult::Clean))?;
         if !handle.is_active() {
             return Err((MvccError::InvalidState, FcwResult::Clean));
@@ -1122,9 +1122,7 @@ pub fn prepare_concurrent_commit_with_ssi(
         let fcw_result = validate_first_committer_wins(&handle, commit_index);
         if !matches!(fcw_result, FcwResult::Clean) {
             lock_table.release_all(txn_id);
-            if let Some(mut handle) = registry.get_mut(session_id) {
-                handle.mark_aborted();
-            }
+            handle.mark_aborted();
             return Err((MvccError::BusySnapshot, fcw_result));
         }
     }
@@ -1452,7 +1450,7 @@ pub fn concurrent_commit_with_ssi(
         Err(e) => {
             if let Some(handle) = registry.remove(session_id) {
                 let mut handle = handle.lock();
-                concurrent_abort(&mut *handle, lock_table, session_id);
+                concurrent_abort(&mut handle, lock_table, session_id);
             }
             return Err(e);
         }
@@ -1583,23 +1581,25 @@ mod tests {
             .expect("session 2");
 
         // Session 1 writes page 5.
-        let h1 = registry.get_mut(s1).expect("handle 1");
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data())
+        let mut h1 = registry.get_mut(s1).expect("handle 1");
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data())
             .expect("write page 5");
 
         // Session 2 writes page 10 (different page => no conflict).
-        let h2 = registry.get_mut(s2).expect("handle 2");
-        concurrent_write_page(h2, &lock_table, s2, test_page(10), test_data())
+        let mut h2 = registry.get_mut(s2).expect("handle 2");
+        concurrent_write_page(&mut h2, &lock_table, s2, test_page(10), test_data())
             .expect("write page 10");
 
         // Both commit successfully.
-        let h1 = registry.get_mut(s1).expect("handle 1");
-        let seq1 = concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
+        drop(h1);
+        let mut h1 = registry.get_mut(s1).expect("handle 1");
+        let seq1 = concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
             .expect("commit 1");
         assert_eq!(seq1, CommitSeq::new(11));
 
-        let h2 = registry.get_mut(s2).expect("handle 2");
-        let seq2 = concurrent_commit(h2, &commit_index, &lock_table, s2, CommitSeq::new(12))
+        drop(h2);
+        let mut h2 = registry.get_mut(s2).expect("handle 2");
+        let seq2 = concurrent_commit(&mut h2, &commit_index, &lock_table, s2, CommitSeq::new(12))
             .expect("commit 2");
         assert_eq!(seq2, CommitSeq::new(12));
     }
@@ -1623,23 +1623,25 @@ mod tests {
         // Both write to page 5, but lock contention prevents s2 from
         // acquiring the same lock.  In our model, each session uses its
         // session_id as the lock holder.
-        let h1 = registry.get_mut(s1).expect("handle 1");
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data())
+        let mut h1 = registry.get_mut(s1).expect("handle 1");
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data())
             .expect("s1 write page 5");
 
         // s1 commits first (first-committer-wins).
-        let h1 = registry.get_mut(s1).expect("handle 1");
-        concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
+        drop(h1);
+        let mut h1 = registry.get_mut(s1).expect("handle 1");
+        concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
             .expect("s1 commits first");
 
         // Now s2 tries to write and commit the same page.  The lock was
         // released by s1's commit, so s2 can acquire it.
-        let h2 = registry.get_mut(s2).expect("handle 2");
-        concurrent_write_page(h2, &lock_table, s2, test_page(5), test_data())
+        let mut h2 = registry.get_mut(s2).expect("handle 2");
+        concurrent_write_page(&mut h2, &lock_table, s2, test_page(5), test_data())
             .expect("s2 write page 5");
 
-        let h2 = registry.get_mut(s2).expect("handle 2");
-        let result = concurrent_commit(h2, &commit_index, &lock_table, s2, CommitSeq::new(12));
+        drop(h2);
+        let mut h2 = registry.get_mut(s2).expect("handle 2");
+        let result = concurrent_commit(&mut h2, &commit_index, &lock_table, s2, CommitSeq::new(12));
         assert!(result.is_err());
         let (err, fcw) = result.unwrap_err();
         assert_eq!(err, MvccError::BusySnapshot);
@@ -1666,30 +1668,33 @@ mod tests {
             .expect("session 3");
 
         // s1 writes page 5, s3 writes page 10 (no overlap).
-        let h1 = registry.get_mut(s1).expect("h1");
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+        let mut h1 = registry.get_mut(s1).expect("h1");
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
 
-        let h3 = registry.get_mut(s3).expect("h3");
-        concurrent_write_page(h3, &lock_table, s3, test_page(10), test_data()).unwrap();
+        let mut h3 = registry.get_mut(s3).expect("h3");
+        concurrent_write_page(&mut h3, &lock_table, s3, test_page(10), test_data()).unwrap();
 
         // s1 commits first on page 5.
-        let h1 = registry.get_mut(s1).expect("h1");
-        concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
+        drop(h1);
+        let mut h1 = registry.get_mut(s1).expect("h1");
+        concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11))
             .expect("s1 commits");
 
         // s2 now tries page 5 (same as s1, but s1 already committed).
-        let h2 = registry.get_mut(s2).expect("h2");
-        concurrent_write_page(h2, &lock_table, s2, test_page(5), test_data()).unwrap();
+        let mut h2 = registry.get_mut(s2).expect("h2");
+        concurrent_write_page(&mut h2, &lock_table, s2, test_page(5), test_data()).unwrap();
 
-        let h2 = registry.get_mut(s2).expect("h2");
-        let result = concurrent_commit(h2, &commit_index, &lock_table, s2, CommitSeq::new(12));
+        drop(h2);
+        let mut h2 = registry.get_mut(s2).expect("h2");
+        let result = concurrent_commit(&mut h2, &commit_index, &lock_table, s2, CommitSeq::new(12));
         assert!(result.is_err());
         let (err, _) = result.unwrap_err();
         assert_eq!(err, MvccError::BusySnapshot);
 
         // s3 commits on page 10 (no conflict with s1's page 5).
-        let h3 = registry.get_mut(s3).expect("h3");
-        let seq3 = concurrent_commit(h3, &commit_index, &lock_table, s3, CommitSeq::new(13))
+        drop(h3);
+        let mut h3 = registry.get_mut(s3).expect("h3");
+        let seq3 = concurrent_commit(&mut h3, &commit_index, &lock_table, s3, CommitSeq::new(13))
             .expect("s3 commits");
         assert_eq!(seq3, CommitSeq::new(13));
     }
@@ -1712,32 +1717,38 @@ mod tests {
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(1), test_data()).unwrap();
 
         // Create savepoint.
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
         let sp = concurrent_savepoint(&handle, "sp1").unwrap();
         assert_eq!(sp.captured_len(), 1);
 
         // Write page 2 (INSERT B).
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(2), test_data()).unwrap();
         assert_eq!(handle.write_set_len(), 2);
 
         // Rollback to savepoint: page 2 should be removed from write set,
         // but its lock should still be held.
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_rollback_to_savepoint(&mut handle, &sp).unwrap();
         assert_eq!(handle.write_set_len(), 1);
         assert!(handle.held_locks().contains(&test_page(2))); // Lock preserved.
 
         // Write page 3 (INSERT C).
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(3), test_data()).unwrap();
 
         // Commit: pages 1 and 3 are in the write set (not page 2).
+        drop(handle);
         let handle = registry.get_mut(s1).expect("handle");
         let mut pages = handle.write_set_pages();
         pages.sort();
         assert_eq!(pages, vec![test_page(1), test_page(3)]);
 
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_commit(
             &mut handle,
@@ -1759,9 +1770,11 @@ mod tests {
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(1), test_data()).unwrap();
 
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
         let sp = concurrent_savepoint(&handle, "sp1").unwrap();
 
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_free_page(&mut handle, &lock_table, s1, test_page(1)).unwrap();
         assert!(concurrent_page_is_freed(&handle, test_page(1)));
@@ -1789,9 +1802,11 @@ mod tests {
         assert!(concurrent_read_page(&handle, test_page(5)).is_none());
 
         // After writing, local read returns the written data.
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(5), test_data()).unwrap();
 
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
         assert!(concurrent_read_page(&handle, test_page(5)).is_some());
         assert!(concurrent_read_page(&handle, test_page(6)).is_none());
@@ -1965,9 +1980,11 @@ mod tests {
         assert_eq!(handle.held_locks().len(), 2);
 
         // Abort: locks released.
+        drop(handle);
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_abort(&mut handle, &lock_table, s1);
         assert!(!handle.is_active());
+        drop(handle);
 
         // Another session can now acquire the same locks.
         let s2 = registry
@@ -2008,6 +2025,7 @@ mod tests {
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(5), test_data()).unwrap();
 
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
         assert_eq!(
             validate_first_committer_wins(&handle, &commit_index),
@@ -2034,8 +2052,9 @@ mod tests {
         let mut handle = registry.get_mut(s1).expect("handle");
         concurrent_write_page(&mut handle, &lock_table, s1, test_page(5), test_data()).unwrap();
 
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
-        let result = validate_first_committer_wins(handle, &commit_index);
+        let result = validate_first_committer_wins(&handle, &commit_index);
         match result {
             FcwResult::Conflict {
                 conflicting_pages,
@@ -2081,6 +2100,7 @@ mod tests {
 
         let handle = registry.get(s1).expect("handle");
         assert!(handle.is_active());
+        drop(handle);
 
         let removed = registry.remove(s1);
         assert!(removed.is_some());
@@ -2101,10 +2121,11 @@ mod tests {
             .get_mut(doomed)
             .expect("doomed handle")
             .set_marked_for_abort(true);
+        let survivor_token = registry.get(survivor).expect("survivor handle").txn_token();
         registry
             .committed_readers
             .push(crate::ssi_validation::CommittedReaderInfo {
-                token: registry.get(survivor).expect("survivor handle").txn_token(),
+                token: survivor_token,
                 begin_seq: CommitSeq::new(20),
                 commit_seq: CommitSeq::new(15),
                 had_in_rw: false,
@@ -2139,8 +2160,8 @@ mod tests {
             .begin_concurrent(test_snapshot(10))
             .expect("session 1");
         {
-            let h1 = registry.get_mut(s1).expect("handle 1");
-            concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data())
+            let mut h1 = registry.get_mut(s1).expect("handle 1");
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data())
                 .expect("session 1 writes page 5");
         }
 
@@ -2176,8 +2197,8 @@ mod tests {
         let s2 = registry
             .begin_concurrent(test_snapshot(11))
             .expect("session 2");
-        let h2 = registry.get_mut(s2).expect("handle 2");
-        concurrent_write_page(h2, &lock_table, s2, test_page(5), test_data())
+        let mut h2 = registry.get_mut(s2).expect("handle 2");
+        concurrent_write_page(&mut h2, &lock_table, s2, test_page(5), test_data())
             .expect("page lock should be released during finalize");
     }
 
@@ -2191,14 +2212,14 @@ mod tests {
         let s2 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(20));
-            concurrent_write_page(h1, &lock_table, s1, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(10), test_data()).unwrap();
         }
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(30));
-            concurrent_write_page(h2, &lock_table, s2, test_page(20), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(20), test_data()).unwrap();
         }
 
         let prepared = prepare_concurrent_commit_with_ssi(
@@ -2226,8 +2247,8 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
         {
-            let h1 = registry.get_mut(s1).unwrap();
-            concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
         }
 
         let prepared = prepare_concurrent_commit_with_ssi(
@@ -2258,17 +2279,19 @@ mod tests {
             .expect("session");
 
         // Abort the handle.
-        let handle = registry.get_mut(s1).expect("handle");
-        concurrent_abort(handle, &lock_table, s1);
+        let mut handle = registry.get_mut(s1).expect("handle");
+        concurrent_abort(&mut handle, &lock_table, s1);
 
         // Write should fail on aborted handle.
-        let handle = registry.get_mut(s1).expect("handle");
-        let result = concurrent_write_page(handle, &lock_table, s1, test_page(1), test_data());
+        drop(handle);
+        let mut handle = registry.get_mut(s1).expect("handle");
+        let result = concurrent_write_page(&mut handle, &lock_table, s1, test_page(1), test_data());
         assert_eq!(result.unwrap_err(), MvccError::InvalidState);
 
         // Savepoint should fail on aborted handle.
+        drop(handle);
         let handle = registry.get(s1).expect("handle");
-        let result = concurrent_savepoint(handle, "sp1");
+        let result = concurrent_savepoint(&handle, "sp1");
         assert_eq!(result.unwrap_err(), MvccError::InvalidState);
     }
 
@@ -2284,7 +2307,7 @@ mod tests {
             .begin_concurrent(test_snapshot(10))
             .expect("session");
 
-        let handle = registry.get_mut(s1).expect("handle");
+        let mut handle = registry.get_mut(s1).expect("handle");
         assert_eq!(handle.read_set_len(), 0);
 
         handle.record_read(test_page(5));
@@ -2313,17 +2336,17 @@ mod tests {
 
         // T1 reads and writes.
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(5));
-            concurrent_write_page(h1, &lock_table, s1, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(10), test_data()).unwrap();
             // B
         }
 
         // T2 reads and writes.
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(20));
-            concurrent_write_page(h2, &lock_table, s2, test_page(30), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(30), test_data()).unwrap();
         }
 
         // Both should commit successfully.
@@ -2361,14 +2384,14 @@ mod tests {
         let s2 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(5));
-            concurrent_write_page(h1, &lock_table, s1, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(10), test_data()).unwrap();
         }
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(10));
-            concurrent_write_page(h2, &lock_table, s2, test_page(20), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(20), test_data()).unwrap();
         }
 
         concurrent_commit_with_ssi(
@@ -2433,17 +2456,17 @@ mod tests {
 
         // T1: reads page 5 (A), writes page 10 (B).
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(5)); // A
-            concurrent_write_page(h1, &lock_table, s1, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(10), test_data()).unwrap();
             // B
         }
 
         // T2: reads page 10 (B), writes page 5 (A).
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(10)); // B
-            concurrent_write_page(h2, &lock_table, s2, test_page(5), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(5), test_data()).unwrap();
             // A
         }
 
@@ -2485,14 +2508,14 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
-        let h1 = registry.get_mut(s1).unwrap();
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+        let mut h1 = registry.get_mut(s1).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
 
         // Manually mark for abort.
         h1.marked_for_abort.set(true);
 
         // Commit should fail.
-        let result = concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
+        let result = concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
         assert!(result.is_err());
         let (err, _) = result.unwrap_err();
         assert_eq!(err, MvccError::BusySnapshot);
@@ -2507,15 +2530,15 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
-        let h1 = registry.get_mut(s1).unwrap();
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+        let mut h1 = registry.get_mut(s1).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
 
         // Set only incoming edge (no outgoing).
         h1.has_in_rw.set(true);
         h1.has_out_rw.set(false);
 
         // Commit should succeed (not a pivot).
-        let result = concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
+        let result = concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
         assert!(result.is_ok(), "only incoming edge should allow commit");
     }
 
@@ -2528,15 +2551,15 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
-        let h1 = registry.get_mut(s1).unwrap();
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+        let mut h1 = registry.get_mut(s1).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
 
         // Set only outgoing edge (no incoming).
         h1.has_in_rw.set(false);
         h1.has_out_rw.set(true);
 
         // Commit should succeed (not a pivot).
-        let result = concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
+        let result = concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
         assert!(result.is_ok(), "only outgoing edge should allow commit");
     }
 
@@ -2549,15 +2572,15 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
-        let h1 = registry.get_mut(s1).unwrap();
-        concurrent_write_page(h1, &lock_table, s1, test_page(5), test_data()).unwrap();
+        let mut h1 = registry.get_mut(s1).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(5), test_data()).unwrap();
 
         // Set both edges → dangerous structure.
         h1.has_in_rw.set(true);
         h1.has_out_rw.set(true);
 
         // Commit should fail (pivot).
-        let result = concurrent_commit(h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
+        let result = concurrent_commit(&mut h1, &commit_index, &lock_table, s1, CommitSeq::new(11));
         assert!(result.is_err());
         let (err, _) = result.unwrap_err();
         assert_eq!(err, MvccError::BusySnapshot);
@@ -2571,11 +2594,11 @@ mod tests {
 
         let s1 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
-        let h1 = registry.get_mut(s1).unwrap();
+        let mut h1 = registry.get_mut(s1).unwrap();
         h1.record_read(test_page(5));
         h1.record_read(test_page(10));
-        concurrent_write_page(h1, &lock_table, s1, test_page(15), test_data()).unwrap();
-        concurrent_write_page(h1, &lock_table, s1, test_page(20), test_data()).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(15), test_data()).unwrap();
+        concurrent_write_page(&mut h1, &lock_table, s1, test_page(20), test_data()).unwrap();
 
         let read_keys = h1.read_witness_keys();
         let write_keys = h1.write_witness_keys();
@@ -2634,22 +2657,22 @@ mod tests {
 
         // T1 operations.
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(10));
             h1.record_read(test_page(20));
-            concurrent_write_page(h1, &lock_table, s1, test_page(30), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(30), test_data()).unwrap();
         }
         // T2 operations.
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(50));
-            concurrent_write_page(h2, &lock_table, s2, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(10), test_data()).unwrap();
         }
         // T3 operations.
         {
-            let h3 = registry.get_mut(s3).unwrap();
+            let mut h3 = registry.get_mut(s3).unwrap();
             h3.record_read(test_page(30));
-            concurrent_write_page(h3, &lock_table, s3, test_page(40), test_data()).unwrap();
+            concurrent_write_page(&mut h3, &lock_table, s3, test_page(40), test_data()).unwrap();
         }
 
         // Step 1: T3 commits. T1 writes page 30, T3 reads page 30
@@ -2765,22 +2788,22 @@ mod tests {
 
         // T1 operations.
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(10));
             h1.record_read(test_page(20));
-            concurrent_write_page(h1, &lock_table, s1, test_page(30), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(30), test_data()).unwrap();
         }
         // T2 operations.
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(50));
-            concurrent_write_page(h2, &lock_table, s2, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(10), test_data()).unwrap();
         }
         // T3 operations.
         {
-            let h3 = registry.get_mut(s3).unwrap();
+            let mut h3 = registry.get_mut(s3).unwrap();
             h3.record_read(test_page(30));
-            concurrent_write_page(h3, &lock_table, s3, test_page(40), test_data()).unwrap();
+            concurrent_write_page(&mut h3, &lock_table, s3, test_page(40), test_data()).unwrap();
         }
 
         // Step 1: T3 commits. T1 writes page 30, T3 reads page 30
@@ -2872,14 +2895,14 @@ mod tests {
         let s2 = registry.begin_concurrent(test_snapshot(10)).unwrap();
 
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(100));
-            concurrent_write_page(h1, &lock_table, s1, test_page(200), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(200), test_data()).unwrap();
         }
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(200));
-            concurrent_write_page(h2, &lock_table, s2, test_page(300), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(300), test_data()).unwrap();
         }
 
         // Before any commit: both T1 and T2 have no SSI flags.
@@ -2952,8 +2975,8 @@ mod tests {
 
         // T1 writes page 42 and commits first.
         {
-            let h1 = registry.get_mut(s1).unwrap();
-            concurrent_write_page(h1, &lock_table, s1, test_page(42), test_data()).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(42), test_data()).unwrap();
         }
         let result1 = concurrent_commit_with_ssi(
             &mut registry,
@@ -2967,8 +2990,8 @@ mod tests {
         // After T1 committed, the page lock on page 42 is released.
         // T2 now writes the same page.
         {
-            let h2 = registry.get_mut(s2).unwrap();
-            concurrent_write_page(h2, &lock_table, s2, test_page(42), test_data()).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(42), test_data()).unwrap();
         }
 
         // T2 commits second — FCW detects page 42 was modified after
@@ -3014,9 +3037,9 @@ mod tests {
         };
 
         {
-            let winner_handle = registry.get_mut(winner_session).unwrap();
+            let mut winner_handle = registry.get_mut(winner_session).unwrap();
             concurrent_write_page(
-                winner_handle,
+                &mut winner_handle,
                 &lock_table,
                 winner_session,
                 test_page(77),
@@ -3039,9 +3062,9 @@ mod tests {
         );
 
         {
-            let loser_handle = registry.get_mut(loser_session).unwrap();
+            let mut loser_handle = registry.get_mut(loser_session).unwrap();
             concurrent_write_page(
-                loser_handle,
+                &mut loser_handle,
                 &lock_table,
                 loser_session,
                 test_page(77),
@@ -3098,16 +3121,16 @@ mod tests {
 
         // T1: reads B (20), writes A (10)
         {
-            let h1 = registry.get_mut(s1).unwrap();
+            let mut h1 = registry.get_mut(s1).unwrap();
             h1.record_read(test_page(20));
-            concurrent_write_page(h1, &lock_table, s1, test_page(10), test_data()).unwrap();
+            concurrent_write_page(&mut h1, &lock_table, s1, test_page(10), test_data()).unwrap();
         }
 
         // T2: reads C (30), writes B (20)
         {
-            let h2 = registry.get_mut(s2).unwrap();
+            let mut h2 = registry.get_mut(s2).unwrap();
             h2.record_read(test_page(30));
-            concurrent_write_page(h2, &lock_table, s2, test_page(20), test_data()).unwrap();
+            concurrent_write_page(&mut h2, &lock_table, s2, test_page(20), test_data()).unwrap();
         }
 
         // T1 commits and should carry had_out_rw=true due to T2 writing B.
@@ -3134,9 +3157,9 @@ mod tests {
 
         // T3 now performs its workload using the earlier snapshot.
         {
-            let h3 = registry.get_mut(s3).unwrap();
+            let mut h3 = registry.get_mut(s3).unwrap();
             h3.record_read(test_page(10));
-            concurrent_write_page(h3, &lock_table, s3, test_page(40), test_data()).unwrap();
+            concurrent_write_page(&mut h3, &lock_table, s3, test_page(40), test_data()).unwrap();
         }
 
         // T3 must abort due to outgoing edge to committed writer pivot T1.
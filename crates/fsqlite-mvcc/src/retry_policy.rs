@@ -501,6 +501,11 @@ impl RetryController {
         &self.ledger
     }
 
+    /// Discard accumulated evidence ledger entries, keeping the posteriors.
+    pub fn clear_ledger(&mut self) {
+        self.ledger.clear();
+    }
+
     /// Access posterior for a given candidate wait time.
     #[must_use]
     pub fn posterior(&self, wait_ms: u64) -> &BetaPosterior {
@@ -19,6 +19,17 @@ use chacha20poly1305::{XChaCha20Poly1305, XNonce};
 /// Layout: nonce (24 B) + Poly1305 tag (16 B) = 40 B.
 pub const ENCRYPTION_RESERVED_BYTES: u8 = 40;
 
+/// Bytes at the very end of every page set aside for the [`KeyStore`].
+///
+/// Page 1 keeps the encoded key store there in the clear; other pages leave
+/// the region zeroed so every page has the same usable size.
+pub const KEY_STORE_RESERVED_BYTES: u8 = 124;
+
+/// Reserved bytes per page of an encrypted database: the key store region
+/// followed by the nonce and tag.
+pub const ENCRYPTED_DATABASE_RESERVED_BYTES: u8 =
+    ENCRYPTION_RESERVED_BYTES + KEY_STORE_RESERVED_BYTES;
+
 /// Size of the XChaCha20-Poly1305 nonce in bytes.
 pub const NONCE_SIZE: usize = 24;
 
@@ -31,6 +42,12 @@ pub const DATABASE_ID_SIZE: usize = 16;
 /// Size of a DEK or KEK in bytes (256-bit keys).
 pub const KEY_SIZE: usize = 32;
 
+/// Size of the per-database Argon2id salt in bytes.
+pub const SALT_SIZE: usize = 16;
+
+/// Size of a wrapped DEK: nonce (24 B) + ciphertext (32 B) + tag (16 B).
+pub const WRAPPED_DEK_SIZE: usize = NONCE_SIZE + KEY_SIZE + TAG_SIZE;
+
 // ---------------------------------------------------------------------------
 // DatabaseId
 // ---------------------------------------------------------------------------
@@ -79,6 +96,10 @@ pub enum EncryptError {
     DekUnwrapFailed,
     /// Argon2id parameter error.
     InvalidKdfParams,
+    /// An encoded [`KeyStore`] is truncated or has the wrong magic.
+    MalformedKeyStore,
+    /// The operating system's CSPRNG could not supply random bytes.
+    RandomnessUnavailable,
 }
 
 impl std::fmt::Display for EncryptError {
@@ -105,12 +126,27 @@ impl std::fmt::Display for EncryptError {
                 f.write_str("DEK unwrap failed (wrong key or corrupt wrapped blob)")
             }
             Self::InvalidKdfParams => f.write_str("invalid Argon2id parameters"),
+            Self::MalformedKeyStore => f.write_str("malformed key store"),
+            Self::RandomnessUnavailable => f.write_str("OS random number generator unavailable"),
         }
     }
 }
 
 impl std::error::Error for EncryptError {}
 
+/// Fill `buf` from the operating system's CSPRNG.
+///
+/// Keys, salts and nonces must never come from a seeded or deterministic
+/// source, so a failing CSPRNG is an error rather than a fallback.
+///
+/// # Errors
+///
+/// Returns [`EncryptError::RandomnessUnavailable`] if the OS cannot supply
+/// random bytes.
+pub fn os_random(buf: &mut [u8]) -> Result<(), EncryptError> {
+    getrandom::getrandom(buf).map_err(|_| EncryptError::RandomnessUnavailable)
+}
+
 // ---------------------------------------------------------------------------
 // AAD construction
 // ---------------------------------------------------------------------------
@@ -137,6 +173,7 @@ fn build_aad(page_number: u32, database_id: &DatabaseId) -> [u8; AAD_SIZE] {
 /// Page encryptor/decryptor holding a DEK and [`DatabaseId`].
 ///
 /// The encryptor is cheap to clone (32-byte key + 16-byte id).
+#[derive(Clone)]
 pub struct PageEncryptor {
     cipher: XChaCha20Poly1305,
     database_id: DatabaseId,
@@ -276,7 +313,7 @@ impl PageEncryptor {
 // ---------------------------------------------------------------------------
 
 /// Argon2id parameters for KEK derivation.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Argon2Params {
     /// Memory cost in KiB (default: 65 536 = 64 MiB).
     pub m_cost: u32,
@@ -365,6 +402,138 @@ impl KeyManager {
     }
 }
 
+// ---------------------------------------------------------------------------
+// KeyStore — durable DEK/KEK metadata
+// ---------------------------------------------------------------------------
+
+/// Magic prefix of an encoded [`KeyStore`].
+const KEYSTORE_MAGIC: [u8; 8] = *b"FSQLKEY1";
+
+/// Durable key metadata for an encrypted database.
+///
+/// Holds the [`DatabaseId`], the Argon2id salt and parameters, and
+/// `wrap(DEK, KEK)`. It is stored in the clear in the last
+/// [`KEY_STORE_RESERVED_BYTES`] of page 1, outside the encrypted region, so
+/// the database file is self-contained and `PRAGMA rekey` only rewrites
+/// this record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyStore {
+    database_id: DatabaseId,
+    salt: [u8; SALT_SIZE],
+    params: Argon2Params,
+    wrapped_dek: Vec<u8>,
+}
+
+impl KeyStore {
+    /// Encoded size: magic, database id, salt, three `u32` costs, wrapped DEK.
+    pub const ENCODED_SIZE: usize =
+        KEYSTORE_MAGIC.len() + DATABASE_ID_SIZE + SALT_SIZE + 12 + WRAPPED_DEK_SIZE;
+
+    /// Generate a fresh DEK and [`DatabaseId`] and wrap the DEK under a KEK
+    /// derived from `passphrase`.
+    ///
+    /// `random` must fill its buffer with cryptographically secure bytes,
+    /// normally via [`os_random`].
+    pub fn create(
+        passphrase: &[u8],
+        params: Argon2Params,
+        mut random: impl FnMut(&mut [u8]) -> Result<(), EncryptError>,
+    ) -> Result<(Self, PageEncryptor), EncryptError> {
+        let mut dek = [0u8; KEY_SIZE];
+        random(&mut dek)?;
+        let mut database_id = [0u8; DATABASE_ID_SIZE];
+        random(&mut database_id)?;
+        let encryptor = PageEncryptor::new(&dek, DatabaseId::from_bytes(database_id));
+        let store = Self::wrap(&encryptor, passphrase, params, random)?;
+        Ok((store, encryptor))
+    }
+
+    /// Wrap the DEK of `encryptor` under a KEK derived from `passphrase` with
+    /// a fresh salt.
+    ///
+    /// This is the O(1) rekey: pages encrypted under the DEK stay readable.
+    pub fn wrap(
+        encryptor: &PageEncryptor,
+        passphrase: &[u8],
+        params: Argon2Params,
+        mut random: impl FnMut(&mut [u8]) -> Result<(), EncryptError>,
+    ) -> Result<Self, EncryptError> {
+        let mut salt = [0u8; SALT_SIZE];
+        random(&mut salt)?;
+        let mut nonce = [0u8; NONCE_SIZE];
+        random(&mut nonce)?;
+        let kek = KeyManager::derive_kek(passphrase, &salt, &params)?;
+        let wrapped_dek = KeyManager::wrap_dek(encryptor.dek(), &kek, &nonce)?;
+        Ok(Self {
+            database_id: encryptor.database_id(),
+            salt,
+            params,
+            wrapped_dek,
+        })
+    }
+
+    /// Unwrap the DEK with `passphrase`.
+    ///
+    /// Returns [`EncryptError::DekUnwrapFailed`] for a wrong passphrase.
+    pub fn unlock(&self, passphrase: &[u8]) -> Result<PageEncryptor, EncryptError> {
+        let kek = KeyManager::derive_kek(passphrase, &self.salt, &self.params)?;
+        let dek = KeyManager::unwrap_dek(&self.wrapped_dek, &kek)?;
+        Ok(PageEncryptor::new(&dek, self.database_id))
+    }
+
+    /// The [`DatabaseId`] recorded in this key store.
+    #[must_use]
+    pub const fn database_id(&self) -> DatabaseId {
+        self.database_id
+    }
+
+    /// The Argon2id parameters recorded in this key store.
+    #[must_use]
+    pub const fn params(&self) -> &Argon2Params {
+        &self.params
+    }
+
+    /// Encode as `magic || database_id || salt || be_u32 costs || wrapped DEK`.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::ENCODED_SIZE);
+        out.extend_from_slice(&KEYSTORE_MAGIC);
+        out.extend_from_slice(self.database_id.as_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&self.params.m_cost.to_be_bytes());
+        out.extend_from_slice(&self.params.t_cost.to_be_bytes());
+        out.extend_from_slice(&self.params.p_cost.to_be_bytes());
+        out.extend_from_slice(&self.wrapped_dek);
+        out
+    }
+
+    /// Decode the output of [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EncryptError> {
+        if bytes.len() != Self::ENCODED_SIZE || bytes[..KEYSTORE_MAGIC.len()] != KEYSTORE_MAGIC {
+            return Err(EncryptError::MalformedKeyStore);
+        }
+        let (id, rest) = bytes[KEYSTORE_MAGIC.len()..].split_at(DATABASE_ID_SIZE);
+        let (salt, rest) = rest.split_at(SALT_SIZE);
+        let (costs, wrapped_dek) = rest.split_at(12);
+        let cost =
+            |i: usize| u32::from_be_bytes([costs[i], costs[i + 1], costs[i + 2], costs[i + 3]]);
+        let mut database_id = [0u8; DATABASE_ID_SIZE];
+        database_id.copy_from_slice(id);
+        let mut salt_bytes = [0u8; SALT_SIZE];
+        salt_bytes.copy_from_slice(salt);
+        Ok(Self {
+            database_id: DatabaseId::from_bytes(database_id),
+            salt: salt_bytes,
+            params: Argon2Params {
+                m_cost: cost(0),
+                t_cost: cost(4),
+                p_cost: cost(8),
+            },
+            wrapped_dek: wrapped_dek.to_vec(),
+        })
+    }
+}
+
 /// Validate that `reserved_per_page` is sufficient for encryption.
 pub fn validate_reserved_bytes(reserved_per_page: u8) -> Result<(), EncryptError> {
     if reserved_per_page < ENCRYPTION_RESERVED_BYTES {
@@ -472,6 +641,48 @@ mod tests {
         assert_eq!(unwrapped, TEST_DEK, "unwrapped DEK must match original");
     }
 
+    #[test]
+    fn test_key_store_roundtrip_and_rewrap() {
+        let params = Argon2Params {
+            m_cost: 256,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let mut seed = 0u8;
+        let mut random = |buf: &mut [u8]| {
+            seed = seed.wrapping_add(1);
+            buf.fill(seed);
+            Ok(())
+        };
+        let (store, enc) = KeyStore::create(b"pass1", params.clone(), &mut random).unwrap();
+        let encoded = store.to_bytes();
+        assert_eq!(encoded.len(), KeyStore::ENCODED_SIZE);
+        assert_eq!(
+            KeyStore::ENCODED_SIZE,
+            usize::from(KEY_STORE_RESERVED_BYTES)
+        );
+        let decoded = KeyStore::from_bytes(&encoded).unwrap();
+        assert_eq!(decoded, store);
+
+        let unlocked = decoded.unlock(b"pass1").unwrap();
+        assert_eq!(unlocked.dek(), enc.dek());
+        assert_eq!(unlocked.database_id(), enc.database_id());
+        assert!(matches!(
+            decoded.unlock(b"wrong"),
+            Err(EncryptError::DekUnwrapFailed)
+        ));
+
+        let rekeyed = KeyStore::wrap(&unlocked, b"pass2", params, &mut random).unwrap();
+        assert_eq!(rekeyed.database_id(), store.database_id());
+        assert_eq!(rekeyed.unlock(b"pass2").unwrap().dek(), enc.dek());
+        assert!(rekeyed.unlock(b"pass1").is_err());
+
+        assert_eq!(
+            KeyStore::from_bytes(&encoded[1..]),
+            Err(EncryptError::MalformedKeyStore)
+        );
+    }
+
     #[test]
     fn test_instant_rekey_o1() {
         // Encrypt pages with original DEK under KEK_1, rekey to KEK_2,
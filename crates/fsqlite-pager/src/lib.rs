@@ -9,8 +9,10 @@ pub mod traits;
 
 pub use arc_cache::{ArcCache, ArcCacheInner, CacheKey, CacheLookup, CachedPage};
 pub use encrypt::{
-    Argon2Params, DATABASE_ID_SIZE, DatabaseId, ENCRYPTION_RESERVED_BYTES, EncryptError, KEY_SIZE,
-    KeyManager, NONCE_SIZE, PageEncryptor, TAG_SIZE, validate_reserved_bytes,
+    Argon2Params, DATABASE_ID_SIZE, DatabaseId, ENCRYPTED_DATABASE_RESERVED_BYTES,
+    ENCRYPTION_RESERVED_BYTES, EncryptError, KEY_SIZE, KEY_STORE_RESERVED_BYTES, KeyManager,
+    KeyStore, NONCE_SIZE, PageEncryptor, SALT_SIZE, TAG_SIZE, WRAPPED_DEK_SIZE, os_random,
+    validate_reserved_bytes,
 };
 pub use journal::{
     CHECKSUM_STRIDE, JOURNAL_HEADER_SIZE, JOURNAL_MAGIC, JournalError, JournalHeader,
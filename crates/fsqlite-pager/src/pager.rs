@@ -22,6 +22,10 @@ use fsqlite_types::{
 };
 use fsqlite_vfs::{Vfs, VfsFile};
 
+use crate::encrypt::{
+    Argon2Params, ENCRYPTED_DATABASE_RESERVED_BYTES, EncryptError, KEY_STORE_RESERVED_BYTES,
+    KeyStore, NONCE_SIZE, PageEncryptor, os_random,
+};
 use crate::journal::{JournalHeader, JournalPageRecord};
 use crate::page_buf::{PageBuf, PageBufPool};
 use crate::page_cache::{PageCache, PageCacheMetricsSnapshot};
@@ -56,6 +60,146 @@ pub(crate) struct PagerInner<F: VfsFile> {
     wal_backend: Option<Box<dyn WalBackend>>,
     /// Monotonic commit sequence for MVCC version tracking.
     commit_seq: CommitSeq,
+    /// Whether the database was opened with write access.
+    access: PagerAccess,
+    /// Reserved bytes at the end of each page (header offset 20).
+    reserved_bytes: u8,
+    /// Page encryption state of the database file.
+    encryption: PagerCipher,
+}
+
+/// Write access a pager was opened with, shared by its transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PagerAccess {
+    ReadWrite,
+    ReadOnly,
+}
+
+impl PagerAccess {
+    const fn from_read_only(read_only: bool) -> Self {
+        if read_only {
+            Self::ReadOnly
+        } else {
+            Self::ReadWrite
+        }
+    }
+
+    const fn is_read_only(self) -> bool {
+        matches!(self, Self::ReadOnly)
+    }
+}
+
+/// Page encryption state of a pager (§15).
+#[derive(Clone)]
+enum PagerCipher {
+    /// Pages are stored in the clear.
+    Plain,
+    /// Pages are encrypted but no key has been supplied yet. As in SQLCipher,
+    /// opening succeeds and every page access fails with
+    /// [`FrankenError::NotADatabase`] until `PRAGMA key` unlocks the key store.
+    Locked { path: PathBuf },
+    /// Pages are encrypted and the key store has been unlocked.
+    Unlocked(PagerEncryption),
+}
+
+impl PagerCipher {
+    /// The unlocked page encryption, `None` for a plaintext database.
+    fn active(&self) -> Result<Option<&PagerEncryption>> {
+        match self {
+            Self::Plain => Ok(None),
+            Self::Locked { path } => Err(FrankenError::NotADatabase { path: path.clone() }),
+            Self::Unlocked(encryption) => Ok(Some(encryption)),
+        }
+    }
+}
+
+/// Page-level encryption for a keyed database (§15).
+///
+/// Every page is sealed with XChaCha20-Poly1305 before it reaches the
+/// database file or the WAL and opened again on read, so the cache and
+/// everything above the pager only ever see plaintext. Page 1 keeps its
+/// 100-byte database header and, in the last [`KEY_STORE_RESERVED_BYTES`],
+/// the [`KeyStore`] in the clear so the file stays recognisable, header-only
+/// patches (page count, journal mode) do not need the key, and the key can
+/// be unwrapped before any page is decrypted.
+#[derive(Clone)]
+struct PagerEncryption {
+    encryptor: PageEncryptor,
+    /// Current key store, stamped into page 1 whenever it is sealed.
+    key_store: KeyStore,
+}
+
+impl PagerEncryption {
+    /// The encrypted region of `page`: everything before the key store
+    /// region, minus the header on page 1.
+    fn sealed_region(page: &mut [u8], page_no: PageNumber) -> &mut [u8] {
+        let end = page.len() - usize::from(KEY_STORE_RESERVED_BYTES);
+        if page_no == PageNumber::ONE {
+            &mut page[DATABASE_HEADER_SIZE..end]
+        } else {
+            &mut page[..end]
+        }
+    }
+
+    /// Return an encrypted copy of `data` ready for the file or the WAL.
+    fn seal(&self, page_no: PageNumber, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = data.to_vec();
+        let key_store_start = out.len() - usize::from(KEY_STORE_RESERVED_BYTES);
+        if page_no == PageNumber::ONE {
+            out[key_store_start..].copy_from_slice(&self.key_store.to_bytes());
+        } else {
+            out[key_store_start..].fill(0);
+        }
+        let mut nonce = [0_u8; NONCE_SIZE];
+        os_random(&mut nonce).map_err(encrypt_error)?;
+        self.encryptor
+            .encrypt_page(
+                Self::sealed_region(&mut out, page_no),
+                page_no.get(),
+                &nonce,
+            )
+            .map_err(|err| {
+                FrankenError::internal(format!("failed to encrypt page {}: {err}", page_no.get()))
+            })?;
+        Ok(out)
+    }
+
+    /// Decrypt `page` in place, failing closed on authentication errors.
+    fn open(&self, page_no: PageNumber, page: &mut [u8]) -> Result<()> {
+        self.encryptor
+            .decrypt_page(Self::sealed_region(page, page_no), page_no.get())
+            .map_err(|err| FrankenError::DatabaseCorrupt {
+                detail: format!(
+                    "page {} failed decryption (wrong key or tampered page): {err}",
+                    page_no.get()
+                ),
+            })
+    }
+}
+
+/// Smallest page size that leaves SQLite's minimum 480 usable bytes after
+/// [`ENCRYPTED_DATABASE_RESERVED_BYTES`].
+const MIN_ENCRYPTED_PAGE_SIZE: u32 = 1024;
+
+/// Map a key-management failure to an error; a missing CSPRNG is an I/O
+/// failure of the host, anything else a bug.
+fn encrypt_error(err: EncryptError) -> FrankenError {
+    match err {
+        EncryptError::RandomnessUnavailable => FrankenError::Io(std::io::Error::other(err)),
+        other => FrankenError::internal(format!("key management failed: {other}")),
+    }
+}
+
+/// Parse the key store kept in the clear tail of page 1, if the database is
+/// encrypted.
+fn key_store_from_page1(page1: &[u8], reserved_bytes: u8) -> Option<KeyStore> {
+    if reserved_bytes != ENCRYPTED_DATABASE_RESERVED_BYTES {
+        return None;
+    }
+    let start = page1
+        .len()
+        .checked_sub(usize::from(KEY_STORE_RESERVED_BYTES))?;
+    KeyStore::from_bytes(&page1[start..]).ok()
 }
 
 impl<F: VfsFile> PagerInner<F> {
@@ -64,7 +208,10 @@ impl<F: VfsFile> PagerInner<F> {
         // In WAL mode, check the WAL for the latest version of the page first.
         if self.journal_mode == JournalMode::Wal {
             if let Some(ref mut wal) = self.wal_backend {
-                if let Some(wal_data) = wal.read_page(cx, page_no.get())? {
+                if let Some(mut wal_data) = wal.read_page(cx, page_no.get())? {
+                    if let Some(encryption) = self.encryption.active()? {
+                        encryption.open(page_no, &mut wal_data)?;
+                    }
                     return Ok(wal_data);
                 }
             }
@@ -81,6 +228,26 @@ impl<F: VfsFile> PagerInner<F> {
             return Ok(vec![0_u8; self.page_size.as_usize()]);
         }
 
+        // Encrypted pages are decrypted before they enter the cache, so the
+        // cache's direct file fill cannot be used.
+        if let Some(encryption) = self.encryption.active()?.cloned() {
+            let page_size = self.page_size.as_usize();
+            let offset = u64::from(page_no.get() - 1) * page_size as u64;
+            let mut out = vec![0_u8; page_size];
+            let bytes_read = self.db_file.read(cx, &mut out, offset)?;
+            if bytes_read < page_size {
+                return Err(FrankenError::DatabaseCorrupt {
+                    detail: format!(
+                        "short read fetching page {page}: got {bytes_read} of {page_size}",
+                        page = page_no.get()
+                    ),
+                });
+            }
+            encryption.open(page_no, &mut out)?;
+            self.cache_page_copy(page_no, &out)?;
+            return Ok(out);
+        }
+
         let slice = match self.cache.read_page(cx, &mut self.db_file, page_no) {
             Ok(slice) => slice,
             Err(FrankenError::OutOfMemory) => {
@@ -114,18 +281,30 @@ impl<F: VfsFile> PagerInner<F> {
     /// connection has committed. The local cache may still reflect an older
     /// generation, so committed-state refresh must bypass it.
     fn read_committed_page_copy(&mut self, cx: &Cx, page_no: PageNumber) -> Result<Vec<u8>> {
+        let (mut page, stored) = self.read_committed_raw_page(cx, page_no)?;
+        if stored && let Some(encryption) = self.encryption.active()? {
+            encryption.open(page_no, &mut page)?;
+        }
+        Ok(page)
+    }
+
+    /// Read a page as stored in the WAL or the database file, still sealed
+    /// on an encrypted database.
+    ///
+    /// The flag is `false` for a zero-filled page beyond the end of the file.
+    fn read_committed_raw_page(&mut self, cx: &Cx, page_no: PageNumber) -> Result<(Vec<u8>, bool)> {
         if self.journal_mode == JournalMode::Wal
             && let Some(ref mut wal) = self.wal_backend
             && let Some(wal_data) = wal.read_page(cx, page_no.get())?
         {
-            return Ok(wal_data);
+            return Ok((wal_data, true));
         }
 
         let page_size = self.page_size.as_usize();
         let offset = u64::from(page_no.get().saturating_sub(1)) * page_size as u64;
         let file_size = self.db_file.file_size(cx)?;
         if offset >= file_size {
-            return Ok(vec![0_u8; page_size]);
+            return Ok((vec![0_u8; page_size], false));
         }
 
         let mut out = vec![0_u8; page_size];
@@ -138,7 +317,7 @@ impl<F: VfsFile> PagerInner<F> {
                 ),
             });
         }
-        Ok(out)
+        Ok((out, true))
     }
 
     /// Refresh connection-local pager metadata from the latest committed state.
@@ -175,6 +354,13 @@ impl<F: VfsFile> PagerInner<F> {
                 detail: format!("invalid database header during pager refresh: {error}"),
             }
         })?;
+        // Another connection may have rekeyed; adopt its key store so our
+        // own page 1 writes do not put the old one back.
+        if let PagerCipher::Unlocked(encryption) = &mut self.encryption
+            && let Some(key_store) = key_store_from_page1(&page1, header.reserved_per_page)
+        {
+            encryption.key_store = key_store;
+        }
 
         let db_size = if header.is_page_count_stale() {
             let file_size = self.db_file.file_size(cx)?;
@@ -217,6 +403,21 @@ impl<F: VfsFile> PagerInner<F> {
 
     /// Flush page data to cache and file.
     fn flush_page(&mut self, cx: &Cx, page_no: PageNumber, data: &[u8]) -> Result<()> {
+        self.cache_page_copy(page_no, data)?;
+
+        let page_size = self.page_size.as_usize();
+        let offset = u64::from(page_no.get() - 1) * page_size as u64;
+        if let Some(encryption) = self.encryption.active()? {
+            let sealed = encryption.seal(page_no, data)?;
+            self.db_file.write(cx, &sealed, offset)?;
+        } else {
+            self.db_file.write(cx, data, offset)?;
+        }
+        Ok(())
+    }
+
+    /// Copy plaintext page data into the cache.
+    fn cache_page_copy(&mut self, page_no: PageNumber, data: &[u8]) -> Result<()> {
         if let Some(cached) = self.cache.get_mut(page_no) {
             let len = cached.len().min(data.len());
             cached[..len].copy_from_slice(&data[..len]);
@@ -241,10 +442,6 @@ impl<F: VfsFile> PagerInner<F> {
                 Err(err) => return Err(err),
             }
         }
-
-        let page_size = self.page_size.as_usize();
-        let offset = u64::from(page_no.get() - 1) * page_size as u64;
-        self.db_file.write(cx, data, offset)?;
         Ok(())
     }
 }
@@ -263,6 +460,31 @@ fn normalize_freelist(pages: &[PageNumber], db_size: u32) -> Vec<PageNumber> {
     normalized
 }
 
+/// Largest root b-tree page recorded in the page-1 header (offset 52).
+///
+/// Non-zero only for auto-vacuum databases, which maintain a pointer map.
+fn header_largest_root_page(page1: &[u8]) -> u32 {
+    page1.get(52..56).map_or(0, |bytes| {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    })
+}
+
+/// Whether `page_no` is a pointer-map page of an auto-vacuum database.
+///
+/// Mirrors `fsqlite_btree::freelist::is_ptrmap_page`, which sits above this
+/// crate; EOF allocation uses it to step over pointer-map slots.
+const fn is_ptrmap_page_number(page_no: u32, usable_size: u32, page_size: u32) -> bool {
+    if page_no < 2 {
+        return false;
+    }
+    let group = usable_size / 5 + 1;
+    let mut ptrmap = (page_no - 2) / group * group + 2;
+    if ptrmap == (0x4000_0000 / page_size) + 1 {
+        ptrmap += 1;
+    }
+    ptrmap == page_no
+}
+
 fn return_pages_to_freelist(
     freelist: &mut Vec<PageNumber>,
     pages: impl IntoIterator<Item = PageNumber>,
@@ -945,6 +1167,14 @@ where
         if inner.checkpoint_active {
             return Err(FrankenError::Busy);
         }
+        if inner.access.is_read_only()
+            && matches!(
+                mode,
+                TransactionMode::Immediate | TransactionMode::Exclusive
+            )
+        {
+            return Err(FrankenError::ReadOnly);
+        }
 
         let active_transactions_before_begin = inner.active_transactions;
         let had_recovery_pending = inner.rollback_journal_recovery_pending;
@@ -969,7 +1199,11 @@ where
                 }
             };
             journal_visibility_invalidation = had_recovery_pending || journal_exists;
-            if inner.rollback_journal_recovery_pending || journal_exists {
+            // Read-only pagers cannot replay a hot journal; the next
+            // read-write open does that. They still drop cached pages.
+            if inner.access.is_read_only() && journal_exists {
+                inner.cache.clear();
+            } else if inner.rollback_journal_recovery_pending || journal_exists {
                 let page_size = inner.page_size;
                 match Self::recover_rollback_journal_if_present_locked(
                     cx,
@@ -1091,6 +1325,7 @@ where
         inner.active_transactions = inner.active_transactions.saturating_add(1);
         let original_db_size = inner.db_size;
         let journal_mode = inner.journal_mode;
+        let access = inner.access;
         let pool = inner.cache.pool().clone();
         let published_visible_commit_seq = self.published.snapshot().visible_commit_seq;
         let cleanup_cx = cleanup_child_cx(cx);
@@ -1108,10 +1343,12 @@ where
             allocated_from_freelist: Vec::new(),
             allocated_from_eof: Vec::new(),
             mode,
+            access,
             is_writer: eager_writer,
             committed: false,
             finished: false,
             original_db_size,
+            truncate_to: None,
             savepoint_stack: Vec::new(),
             journal_mode,
             pool,
@@ -1149,8 +1386,10 @@ where
         // WAL mode uses version 2; all rollback journal modes use version 1.
         // Without this, standard SQLite tools cannot detect WAL mode from the
         // on-disk header and will fail to look for the WAL file.
+        // Read-only connections cannot persist the change; they only switch
+        // the in-process mode to match what is already on disk.
         let version_byte: u8 = if mode == JournalMode::Wal { 2 } else { 1 };
-        if inner.db_size > 0 {
+        if inner.db_size > 0 && !inner.access.is_read_only() {
             let page_size = inner.page_size.as_usize();
             let mut page1 = vec![0u8; page_size];
             let bytes_read = inner.db_file.read(cx, &mut page1, 0)?;
@@ -1411,6 +1650,98 @@ where
         inner.page_size
     }
 
+    /// Returns `true` if the database was opened without write access.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        let inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        inner.access.is_read_only()
+    }
+
+    /// Returns the reserved bytes per page recorded in the database header.
+    #[must_use]
+    pub fn reserved_bytes(&self) -> u8 {
+        let inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        inner.reserved_bytes
+    }
+
+    /// Returns `true` if pages are encrypted on disk and in the WAL.
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        let inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        !matches!(inner.encryption, PagerCipher::Plain)
+    }
+
+    /// Returns `true` if pages are encrypted and no key has been supplied
+    /// yet, so every page access fails until `PRAGMA key`.
+    #[must_use]
+    pub fn is_key_required(&self) -> bool {
+        let inner = self
+            .inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        matches!(inner.encryption, PagerCipher::Locked { .. })
+    }
+
+    /// Re-wrap the database key under a KEK derived from `passphrase`
+    /// (`PRAGMA rekey`).
+    ///
+    /// Only the key store in page 1 is rewritten, in an ordinary write
+    /// transaction so it is journaled like any other page; page ciphertext
+    /// is untouched, so rekey is O(1) in the database size.
+    pub fn rekey(&self, cx: &Cx, passphrase: &[u8]) -> Result<()> {
+        let mut txn = self.begin(cx, TransactionMode::Immediate)?;
+        let page1 = txn.get_page(cx, PageNumber::ONE)?.as_ref().to_vec();
+        let previous = {
+            let mut inner = self
+                .inner
+                .lock()
+                .map_err(|_| FrankenError::internal("SimplePager lock poisoned"))?;
+            let encryption = match &mut inner.encryption {
+                PagerCipher::Unlocked(encryption) => encryption,
+                PagerCipher::Locked { path } => {
+                    return Err(FrankenError::NotADatabase { path: path.clone() });
+                }
+                PagerCipher::Plain => {
+                    return Err(FrankenError::NotImplemented(
+                        "PRAGMA rekey requires an encrypted database; use PRAGMA key first"
+                            .to_owned(),
+                    ));
+                }
+            };
+            let rewrapped = KeyStore::wrap(
+                &encryption.encryptor,
+                passphrase,
+                encryption.key_store.params().clone(),
+                os_random,
+            )
+            .map_err(encrypt_error)?;
+            std::mem::replace(&mut encryption.key_store, rewrapped)
+        };
+        // Sealing page 1 stamps the new key store into its tail.
+        let result = txn
+            .write_page(cx, PageNumber::ONE, &page1)
+            .and_then(|()| txn.commit(cx));
+        if result.is_err() {
+            let mut inner = self
+                .inner
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if let PagerCipher::Unlocked(encryption) = &mut inner.encryption {
+                encryption.key_store = previous;
+            }
+        }
+        result
+    }
+
     /// Number of page reads satisfied directly from the publication plane.
     #[must_use]
     pub fn published_page_hits(&self) -> u64 {
@@ -1487,15 +1818,47 @@ where
     ///
     /// If a hot journal is detected (leftover from a crash), it is replayed
     /// to restore the database to a consistent state before returning.
-    #[allow(clippy::too_many_lines)]
     pub fn open_with_cx(
         cx: &Cx,
         vfs: V,
         path: &Path,
         requested_page_size: PageSize,
     ) -> Result<Self> {
-        let vfs = Arc::new(vfs);
         let flags = VfsOpenFlags::CREATE | VfsOpenFlags::READWRITE | VfsOpenFlags::MAIN_DB;
+        Self::open_with_flags(cx, vfs, path, requested_page_size, flags)
+    }
+
+    /// Open a database with explicit VFS open flags.
+    ///
+    /// Behaves like [`Self::open_with_cx`], except that the main database
+    /// file is opened with `flags`. Without [`VfsOpenFlags::READWRITE`] the
+    /// pager is read-only: every write transaction fails with
+    /// [`FrankenError::ReadOnly`], hot journals are left for the next
+    /// read-write open to replay, and an empty file is not initialized.
+    pub fn open_with_flags(
+        cx: &Cx,
+        vfs: V,
+        path: &Path,
+        requested_page_size: PageSize,
+        flags: VfsOpenFlags,
+    ) -> Result<Self> {
+        Self::open_impl(cx, Arc::new(vfs), path, requested_page_size, flags)
+    }
+
+    /// Shared open path.
+    ///
+    /// An encrypted database opens locked: its key store is read from page 1
+    /// but nothing is decrypted until [`Self::set_key`] supplies the key.
+    #[allow(clippy::too_many_lines)]
+    fn open_impl(
+        cx: &Cx,
+        vfs: Arc<V>,
+        path: &Path,
+        requested_page_size: PageSize,
+        flags: VfsOpenFlags,
+    ) -> Result<Self> {
+        let read_only = !flags.contains(VfsOpenFlags::READWRITE);
+        let flags = flags | VfsOpenFlags::MAIN_DB;
         let (mut db_file, _actual_flags) = vfs.open(cx, Some(path), flags)?;
 
         // Probe for existing page size BEFORE hot journal recovery.
@@ -1521,17 +1884,24 @@ where
         // Hot journal recovery writes the database image back to its durable
         // pre-commit state, so acquire EXCLUSIVE before replay even during
         // initial open.
-        let _ = Self::recover_rollback_journal_if_present_locked(
-            cx,
-            &*vfs,
-            &mut db_file,
-            &journal_path,
-            page_size,
-            LockLevel::None,
-        )?;
+        if !read_only {
+            let _ = Self::recover_rollback_journal_if_present_locked(
+                cx,
+                &*vfs,
+                &mut db_file,
+                &journal_path,
+                page_size,
+                LockLevel::None,
+            )?;
+        }
 
         // Refresh file size after potential recovery.
         file_size = db_file.file_size(cx)?;
+        if file_size == 0 && read_only {
+            return Err(FrankenError::CannotOpen {
+                path: path.to_owned(),
+            });
+        }
         let header = if file_size == 0 {
             // SQLite databases are never truly empty: page 1 contains the
             // 100-byte database header followed by the sqlite_master root page.
@@ -1595,6 +1965,21 @@ where
             }
             header
         };
+        let encryption = if header.reserved_per_page == ENCRYPTED_DATABASE_RESERVED_BYTES {
+            let mut page1 = vec![0_u8; page_size.as_usize()];
+            let bytes_read = db_file.read(cx, &mut page1, 0)?;
+            if bytes_read == page1.len()
+                && key_store_from_page1(&page1, header.reserved_per_page).is_some()
+            {
+                PagerCipher::Locked {
+                    path: path.to_owned(),
+                }
+            } else {
+                PagerCipher::Plain
+            }
+        } else {
+            PagerCipher::Plain
+        };
 
         let page_size_u64 = page_size.as_usize() as u64;
         if file_size % page_size_u64 != 0 {
@@ -1617,14 +2002,20 @@ where
         } else {
             2
         };
-        let freelist = load_freelist_from_disk(
-            cx,
-            &mut db_file,
-            page_size,
-            db_size,
-            header.freelist_trunk,
-            header.freelist_count,
-        )?;
+        // The freelist of a locked database is loaded by the first
+        // transaction after the key is supplied.
+        let freelist = if matches!(encryption, PagerCipher::Plain) {
+            load_freelist_from_disk(
+                cx,
+                &mut db_file,
+                page_size,
+                db_size,
+                header.freelist_trunk,
+                header.freelist_count,
+            )?
+        } else {
+            Vec::new()
+        };
 
         let initial_commit_seq = CommitSeq::new(u64::from(header.change_counter));
         let freelist_count = freelist.len();
@@ -1647,6 +2038,9 @@ where
                 rollback_journal_recovery_pending: false,
                 wal_backend: None,
                 commit_seq: initial_commit_seq,
+                access: PagerAccess::from_read_only(read_only),
+                reserved_bytes: header.reserved_per_page,
+                encryption,
             })),
             published: Arc::new(PublishedPagerState::new(
                 db_size,
@@ -1770,6 +2164,165 @@ where
     }
 }
 
+impl<V> SimplePager<V>
+where
+    V: Vfs + 'static,
+    V::File: Send + Sync,
+{
+    /// Open (or create) an encrypted database.
+    ///
+    /// Equivalent to opening the database and calling [`Self::set_key`]: the
+    /// DEK is unwrapped from the key store in page 1 with a KEK derived from
+    /// `passphrase`, and a wrong passphrase fails with
+    /// [`FrankenError::NotADatabase`]. A missing or still-empty database is
+    /// initialized as encrypted with a fresh DEK, with `params` recorded for
+    /// KEK derivation.
+    pub fn open_with_key(
+        cx: &Cx,
+        vfs: V,
+        path: &Path,
+        requested_page_size: PageSize,
+        flags: VfsOpenFlags,
+        passphrase: &[u8],
+        params: Argon2Params,
+    ) -> Result<Self> {
+        let pager = Self::open_impl(cx, Arc::new(vfs), path, requested_page_size, flags)?;
+        pager.set_key(cx, passphrase, params)?;
+        Ok(pager)
+    }
+
+    /// Apply `PRAGMA key` to an open pager.
+    ///
+    /// On a locked encrypted database this unwraps the DEK and authenticates
+    /// page 1, failing with [`FrankenError::NotADatabase`] for a wrong key; on
+    /// an unlocked one it only verifies `passphrase`. On a plaintext database
+    /// that has no content yet, page 1 is rewritten with
+    /// [`ENCRYPTED_DATABASE_RESERVED_BYTES`] reserved and every later page
+    /// write is encrypted. Encrypting a plaintext database that already holds
+    /// data requires `VACUUM` into a keyed target and is rejected.
+    pub fn set_key(&self, cx: &Cx, passphrase: &[u8], params: Argon2Params) -> Result<()> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| FrankenError::internal("SimplePager lock poisoned"))?;
+
+        match &inner.encryption {
+            PagerCipher::Unlocked(encryption) => {
+                return match encryption.key_store.unlock(passphrase) {
+                    Ok(unlocked) if unlocked.dek() == encryption.encryptor.dek() => Ok(()),
+                    _ => Err(FrankenError::NotADatabase {
+                        path: self.db_path.clone(),
+                    }),
+                };
+            }
+            PagerCipher::Locked { path } => {
+                let path = path.clone();
+                let not_a_database = || FrankenError::NotADatabase { path: path.clone() };
+                if inner.journal_mode == JournalMode::Wal
+                    && let Some(wal) = inner.wal_backend.as_mut()
+                {
+                    wal.begin_transaction(cx)?;
+                }
+                let (mut page1, _) = inner.read_committed_raw_page(cx, PageNumber::ONE)?;
+                let key_store = key_store_from_page1(&page1, inner.reserved_bytes)
+                    .ok_or_else(not_a_database)?;
+                let encryptor = key_store.unlock(passphrase).map_err(|_| not_a_database())?;
+                let encryption = PagerEncryption {
+                    encryptor,
+                    key_store,
+                };
+                encryption
+                    .open(PageNumber::ONE, &mut page1)
+                    .map_err(|_| not_a_database())?;
+                inner.encryption = PagerCipher::Unlocked(encryption);
+                inner.cache.clear();
+                return Ok(());
+            }
+            PagerCipher::Plain => {}
+        }
+        if inner.access.is_read_only() {
+            return Err(FrankenError::ReadOnly);
+        }
+        if inner.active_transactions > 0 || inner.checkpoint_active {
+            return Err(FrankenError::Busy);
+        }
+        if inner.page_size.get() < MIN_ENCRYPTED_PAGE_SIZE {
+            return Err(FrankenError::OutOfRange {
+                what: "page size of an encrypted database".to_owned(),
+                value: inner.page_size.get().to_string(),
+            });
+        }
+
+        let page1 = inner.read_page_copy(cx, PageNumber::ONE)?;
+        let wal_frames = inner
+            .wal_backend
+            .as_ref()
+            .map_or(0, |wal| wal.frame_count());
+        let root = &page1[DATABASE_HEADER_SIZE..];
+        let pristine =
+            inner.db_size <= 1 && wal_frames == 0 && root[0] == 0x0D && root[3..5] == [0, 0];
+        if !pristine {
+            return Err(FrankenError::NotImplemented(
+                "encrypting a database that already holds data requires VACUUM INTO a keyed database"
+                    .to_owned(),
+            ));
+        }
+
+        let mut header_bytes = [0_u8; DATABASE_HEADER_SIZE];
+        header_bytes.copy_from_slice(&page1[..DATABASE_HEADER_SIZE]);
+        let mut header = DatabaseHeader::from_bytes(&header_bytes).map_err(|error| {
+            FrankenError::DatabaseCorrupt {
+                detail: format!("invalid database header: {error}"),
+            }
+        })?;
+        header.reserved_per_page = ENCRYPTED_DATABASE_RESERVED_BYTES;
+        let hdr_bytes = header.to_bytes().map_err(|err| {
+            FrankenError::internal(format!("failed to encode encrypted database header: {err}"))
+        })?;
+        let mut new_page1 = vec![0_u8; inner.page_size.as_usize()];
+        new_page1[..DATABASE_HEADER_SIZE].copy_from_slice(&hdr_bytes);
+        let usable = inner.page_size.usable(ENCRYPTED_DATABASE_RESERVED_BYTES);
+        BTreePageHeader::write_empty_leaf_table(&mut new_page1, DATABASE_HEADER_SIZE, usable);
+
+        let (key_store, encryptor) =
+            KeyStore::create(passphrase, params, os_random).map_err(encrypt_error)?;
+        let plain_reserved_bytes = inner.reserved_bytes;
+        inner.encryption = PagerCipher::Unlocked(PagerEncryption {
+            encryptor,
+            key_store,
+        });
+        inner.reserved_bytes = ENCRYPTED_DATABASE_RESERVED_BYTES;
+        inner.db_file.lock(cx, LockLevel::Exclusive)?;
+        let write_result = inner
+            .flush_page(cx, PageNumber::ONE, &new_page1)
+            .and_then(|()| inner.db_file.sync(cx, SyncFlags::NORMAL));
+        let unlock_result = inner.db_file.unlock(cx, LockLevel::None);
+        if write_result.is_err() {
+            inner.encryption = PagerCipher::Plain;
+            inner.reserved_bytes = plain_reserved_bytes;
+            inner.cache.clear();
+        }
+        write_result?;
+        unlock_result?;
+
+        self.published.publish(
+            cx,
+            PublishedPagerUpdate {
+                visible_commit_seq: inner.commit_seq,
+                db_size: inner.db_size,
+                journal_mode: inner.journal_mode,
+                freelist_count: inner.freelist.len(),
+                checkpoint_active: inner.checkpoint_active,
+            },
+            |pages| {
+                pages.remove(&PageNumber::ONE);
+            },
+        );
+        drop(inner);
+        Ok(())
+    }
+}
+
 /// A snapshot of the transaction state at a savepoint boundary.
 struct SavepointEntry {
     /// The user-supplied savepoint name.
@@ -1791,6 +2344,8 @@ struct SavepointEntry {
     allocated_from_freelist_snapshot: Vec<PageNumber>,
     /// Snapshot of pages allocated from EOF by this transaction.
     allocated_from_eof_snapshot: Vec<PageNumber>,
+    /// Snapshot of the pending commit-time truncation.
+    truncate_to_snapshot: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -1876,10 +2431,14 @@ pub struct SimpleTransaction<V: Vfs> {
     allocated_from_freelist: Vec<PageNumber>,
     allocated_from_eof: Vec<PageNumber>,
     mode: TransactionMode,
+    /// Access of the owning pager; read-only transactions reject every write.
+    access: PagerAccess,
     is_writer: bool,
     committed: bool,
     finished: bool,
     original_db_size: u32,
+    /// Page count the database shrinks to at commit (auto-vacuum), if any.
+    truncate_to: Option<u32>,
     /// Stack of savepoints, pushed on SAVEPOINT and popped on RELEASE.
     savepoint_stack: Vec<SavepointEntry>,
     /// Journal mode captured at transaction start.
@@ -1897,18 +2456,28 @@ struct WalPageOneWritePlan {
     max_written: u32,
     page_one_dirty: bool,
     freelist_metadata_dirty: bool,
-    db_growth: bool,
+    page_count: PageCountChange,
+}
+
+/// How a WAL commit moves the database page count recorded on page 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageCountChange {
+    Unchanged,
+    Growth,
+    Shrink,
 }
 
 impl WalPageOneWritePlan {
     #[must_use]
     fn requires_page_one_rewrite(self) -> bool {
-        self.page_one_dirty || self.freelist_metadata_dirty || self.db_growth
+        self.page_one_dirty
+            || self.freelist_metadata_dirty
+            || self.page_count != PageCountChange::Unchanged
     }
 
     #[must_use]
     fn requires_page_count_advance(self) -> bool {
-        self.db_growth
+        self.page_count != PageCountChange::Unchanged
     }
 }
 
@@ -1975,9 +2544,27 @@ impl<V: Vfs> SimpleTransaction<V> {
 
     #[must_use]
     fn committed_db_size_with_inner(&self, inner: &PagerInner<V::File>) -> u32 {
+        let base_db_size = self.truncate_to.unwrap_or(inner.db_size);
         self.write_pages_sorted
             .last()
-            .map_or(inner.db_size, |page| inner.db_size.max(page.get()))
+            .map_or(base_db_size, |page| base_db_size.max(page.get()))
+    }
+
+    /// Whether the pending page-1 image marks this as an auto-vacuum
+    /// database (non-zero largest root page at header offset 52).
+    fn auto_vacuum_enabled_with_inner(
+        &self,
+        cx: &Cx,
+        inner: &mut PagerInner<V::File>,
+    ) -> Result<bool> {
+        if let Some(staged) = self.write_set.get(&PageNumber::ONE) {
+            return Ok(header_largest_root_page(staged.as_page_bytes()) != 0);
+        }
+        if inner.db_size == 0 {
+            return Ok(false);
+        }
+        let page1 = inner.read_page_copy(cx, PageNumber::ONE)?;
+        Ok(header_largest_root_page(&page1) != 0)
     }
 
     #[must_use]
@@ -1991,7 +2578,16 @@ impl<V: Vfs> SimpleTransaction<V> {
             max_written,
             page_one_dirty: self.write_set.contains_key(&PageNumber::ONE),
             freelist_metadata_dirty: freelist_dirty,
-            db_growth: max_written > current_db_size,
+            page_count: if max_written > current_db_size {
+                PageCountChange::Growth
+            } else if self
+                .truncate_to
+                .is_some_and(|n_pages| n_pages.max(max_written) < current_db_size)
+            {
+                PageCountChange::Shrink
+            } else {
+                PageCountChange::Unchanged
+            },
         }
     }
 
@@ -2041,6 +2637,10 @@ where
     }
 
     /// Commit using the rollback journal protocol.
+    ///
+    /// When `truncate_to` is set the file is cut back to that many pages
+    /// after the dirty pages land. The dropped tail is journaled as well so a
+    /// hot-journal rollback can restore it.
     #[allow(clippy::too_many_lines)]
     fn commit_journal(
         cx: &Cx,
@@ -2049,6 +2649,7 @@ where
         inner: &mut PagerInner<V::File>,
         write_set: &HashMap<PageNumber, StagedPage>,
         original_db_size: u32,
+        truncate_to: Option<u32>,
     ) -> Result<()> {
         if !write_set.is_empty() {
             // Escalate to EXCLUSIVE before writing to the database file.
@@ -2065,9 +2666,18 @@ where
                 VfsOpenFlags::CREATE | VfsOpenFlags::READWRITE | VfsOpenFlags::MAIN_JOURNAL;
             let (mut jrnl_file, _) = vfs.open(cx, Some(journal_path), jrnl_flags)?;
 
+            let mut journal_pages: Vec<PageNumber> = write_set.keys().copied().collect();
+            if let Some(n_pages) = truncate_to {
+                journal_pages.extend(
+                    (n_pages.saturating_add(1)..=inner.db_size)
+                        .filter_map(PageNumber::new)
+                        .filter(|page_no| !write_set.contains_key(page_no)),
+                );
+            }
+
             let header = JournalHeader {
                 #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-                page_count: write_set.len() as i32,
+                page_count: journal_pages.len() as i32,
                 nonce,
                 initial_db_size: original_db_size,
                 sector_size: 512,
@@ -2077,7 +2687,7 @@ where
             jrnl_file.write(cx, &hdr_bytes, 0)?;
 
             let mut jrnl_offset = hdr_bytes.len() as u64;
-            for &page_no in write_set.keys() {
+            for &page_no in &journal_pages {
                 // Read current on-disk content as the pre-image.
                 let mut pre_image = vec![0u8; ps];
                 if page_no.get() <= inner.db_size {
@@ -2112,6 +2722,12 @@ where
                 }
                 inner.db_size = inner.db_size.max(page_no.get());
             }
+            if let Some(n_pages) = truncate_to
+                && n_pages < inner.db_size
+            {
+                inner.db_file.truncate(cx, u64::from(n_pages) * ps as u64)?;
+                inner.db_size = n_pages;
+            }
 
             inner.db_file.sync(cx, SyncFlags::NORMAL)?;
 
@@ -2142,19 +2758,56 @@ where
         Ok(())
     }
     /// Commit using the WAL protocol (append frames to WAL file).
+    ///
+    /// A pending truncation is carried by the commit frame's database size;
+    /// the checkpointer cuts the file back once the frames are backfilled.
     fn commit_wal(
         cx: &Cx,
         inner: &mut PagerInner<V::File>,
         write_set: &HashMap<PageNumber, StagedPage>,
         write_pages_sorted: &[PageNumber],
+        truncate_to: Option<u32>,
     ) -> Result<()> {
-        if let Some(batch) = collect_wal_commit_batch(inner.db_size, write_set, write_pages_sorted)?
+        let current_db_size = truncate_to.unwrap_or(inner.db_size);
+        if let Some(batch) =
+            collect_wal_commit_batch(current_db_size, write_set, write_pages_sorted)?
         {
+            // Encrypted databases append sealed copies; the staged pages stay
+            // plaintext for the cache and publication plane.
+            let sealed_pages = inner
+                .encryption
+                .active()?
+                .map(|encryption| {
+                    batch
+                        .frames
+                        .iter()
+                        .map(|frame| {
+                            let page_no = PageNumber::new(frame.page_number).ok_or_else(|| {
+                                FrankenError::internal("WAL frame with page number 0")
+                            })?;
+                            encryption.seal(page_no, frame.page_data)
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?;
+            let frames: Vec<traits::WalFrameRef<'_>> = match &sealed_pages {
+                Some(sealed) => batch
+                    .frames
+                    .iter()
+                    .zip(sealed)
+                    .map(|(frame, data)| traits::WalFrameRef {
+                        page_data: data,
+                        ..*frame
+                    })
+                    .collect(),
+                None => batch.frames,
+            };
+
             let mut prepared_batch = {
                 let wal = inner.wal_backend.as_mut().ok_or_else(|| {
                     FrankenError::internal("WAL mode active but no WAL backend installed")
                 })?;
-                wal.prepare_append_frames(&batch.frames)?
+                wal.prepare_append_frames(&frames)?
             };
 
             // Escalate to EXCLUSIVE before writing WAL frames.
@@ -2168,7 +2821,7 @@ where
             if let Some(prepared_batch) = prepared_batch.as_mut() {
                 wal.append_prepared_frames(cx, prepared_batch)?;
             } else {
-                wal.append_frames(cx, &batch.frames)?;
+                wal.append_frames(cx, &frames)?;
             }
 
             // Sync WAL to ensure durability.
@@ -2190,6 +2843,10 @@ where
             return Ok(());
         }
 
+        if self.access.is_read_only() {
+            return Err(FrankenError::ReadOnly);
+        }
+
         match self.mode {
             TransactionMode::ReadOnly => Err(FrankenError::ReadOnly),
             TransactionMode::Concurrent => {
@@ -2416,9 +3073,16 @@ where
             return Ok(page);
         }
 
+        let auto_vacuum = self.auto_vacuum_enabled_with_inner(cx, &mut inner)?;
+        let page_size = inner.page_size.get();
+        let usable_size = page_size - u32::from(inner.reserved_bytes);
+        let pending_byte_page = (0x4000_0000 / page_size) + 1;
         let mut raw = inner.next_page;
-        let pending_byte_page = (0x4000_0000 / inner.page_size.get()) + 1;
-        if raw == pending_byte_page {
+        // Auto-vacuum databases reserve fixed pointer-map slots. The b-tree
+        // layer fills them in at commit, so allocation just steps over them.
+        while raw == pending_byte_page
+            || (auto_vacuum && is_ptrmap_page_number(raw, usable_size, page_size))
+        {
             raw = raw.saturating_add(1);
         }
         inner.next_page = raw.saturating_add(1);
@@ -2448,6 +3112,119 @@ where
         Ok(())
     }
 
+    fn allocate_page_at(&mut self, cx: &Cx, page_no: PageNumber) -> Result<bool> {
+        self.ensure_writer(cx)?;
+        if page_no == PageNumber::ONE {
+            return Ok(false);
+        }
+
+        if let Some(idx) = self.freed_pages.iter().position(|page| *page == page_no) {
+            // Relocation targets may reuse pages freed by this transaction:
+            // every reference to the old owner is rewritten before commit.
+            self.freed_pages.swap_remove(idx);
+            return Ok(true);
+        }
+
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| FrankenError::internal("SimpleTransaction lock poisoned"))?;
+
+        let committed_freelist_is_snapshot_pinned =
+            self.mode == TransactionMode::Concurrent || inner.active_transactions > 1;
+        if let Some(idx) = inner.freelist.iter().position(|page| *page == page_no) {
+            if committed_freelist_is_snapshot_pinned && page_no.get() <= inner.db_size {
+                return Ok(false);
+            }
+            let page = inner.freelist.swap_remove(idx);
+            self.allocated_from_freelist.push(page);
+            return Ok(true);
+        }
+
+        if page_no.get() < inner.next_page {
+            return Ok(false);
+        }
+
+        let auto_vacuum = self.auto_vacuum_enabled_with_inner(cx, &mut inner)?;
+        let page_size = inner.page_size.get();
+        let usable_size = page_size - u32::from(inner.reserved_bytes);
+        let pending_byte_page = (0x4000_0000 / page_size) + 1;
+        // Pages skipped on the way to `page_no` become free so the file has
+        // no holes once the target is written.
+        for raw in inner.next_page..page_no.get() {
+            if raw == pending_byte_page
+                || (auto_vacuum && is_ptrmap_page_number(raw, usable_size, page_size))
+            {
+                continue;
+            }
+            if let Some(gap) = PageNumber::new(raw) {
+                self.allocated_from_eof.push(gap);
+                self.freed_pages.push(gap);
+            }
+        }
+        inner.next_page = page_no.get().saturating_add(1);
+        drop(inner);
+        self.allocated_from_eof.push(page_no);
+        Ok(true)
+    }
+
+    fn database_size(&self) -> Result<u32> {
+        let inner = self
+            .inner
+            .lock()
+            .map_err(|_| FrankenError::internal("SimpleTransaction lock poisoned"))?;
+        Ok(self.committed_db_size_with_inner(&inner))
+    }
+
+    fn freelist_pages(&self) -> Result<Vec<PageNumber>> {
+        let inner = self
+            .inner
+            .lock()
+            .map_err(|_| FrankenError::internal("SimpleTransaction lock poisoned"))?;
+        let committed_db_size = self.committed_db_size_with_inner(&inner);
+        Ok(self.predicted_durable_freelist_pages_with_inner(&inner, committed_db_size))
+    }
+
+    fn truncate_database(&mut self, cx: &Cx, n_pages: u32) -> Result<bool> {
+        self.ensure_writer(cx)?;
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| FrankenError::internal("SimpleTransaction lock poisoned"))?;
+        // Readers on older snapshots may still need the tail pages, and
+        // concurrent writers cannot agree on a single end of file.
+        if self.mode == TransactionMode::Concurrent || inner.active_transactions > 1 {
+            return Ok(false);
+        }
+        let n_pages = n_pages.max(1);
+
+        let mut idx = 0;
+        while idx < inner.freelist.len() {
+            if inner.freelist[idx].get() > n_pages {
+                let page = inner.freelist.swap_remove(idx);
+                self.allocated_from_freelist.push(page);
+            } else {
+                idx += 1;
+            }
+        }
+        inner.next_page = n_pages.saturating_add(1).max(2);
+        drop(inner);
+
+        self.freed_pages.retain(|page| page.get() <= n_pages);
+        let tail: Vec<PageNumber> = self
+            .write_pages_sorted
+            .iter()
+            .copied()
+            .filter(|page| page.get() > n_pages)
+            .collect();
+        for page_no in tail {
+            self.write_set.remove(&page_no);
+            remove_page_sorted(&mut self.write_pages_sorted, page_no);
+        }
+        self.truncate_to = Some(n_pages);
+        Ok(true)
+    }
+
     #[allow(clippy::too_many_lines)]
     fn commit(&mut self, cx: &Cx) -> Result<()> {
         if self.finished {
@@ -2527,7 +3304,13 @@ where
                 if self.journal_mode != JournalMode::Wal
                     || wal_page1_plan.requires_page_count_advance()
                 {
-                    let new_db_size = committed_db_size.max(existing_page_count);
+                    // A pending auto-vacuum truncation is the only way the
+                    // page count may go down.
+                    let new_db_size = if self.truncate_to.is_some() {
+                        committed_db_size
+                    } else {
+                        committed_db_size.max(existing_page_count)
+                    };
                     // Offset 28..32: page count (big-endian u32)
                     page1[28..32].copy_from_slice(&new_db_size.to_be_bytes());
                 }
@@ -2542,8 +3325,16 @@ where
             );
         }
 
+        let truncate_to = self.truncate_to.map(|_| committed_db_size);
+        let db_size_before_commit = inner.db_size;
         let commit_result = if self.journal_mode == JournalMode::Wal {
-            Self::commit_wal(cx, &mut inner, &self.write_set, &self.write_pages_sorted)
+            Self::commit_wal(
+                cx,
+                &mut inner,
+                &self.write_set,
+                &self.write_pages_sorted,
+                truncate_to,
+            )
         } else {
             Self::commit_journal(
                 cx,
@@ -2552,10 +3343,22 @@ where
                 &mut inner,
                 &self.write_set,
                 self.original_db_size,
+                truncate_to,
             )
         };
 
         if commit_result.is_ok() {
+            if truncate_to.is_some() {
+                for pgno in committed_db_size.saturating_add(1)..=db_size_before_commit {
+                    if let Some(page_no) = PageNumber::new(pgno) {
+                        inner.cache.evict(page_no);
+                    }
+                }
+                inner
+                    .freelist
+                    .retain(|page| page.get() <= committed_db_size);
+                inner.next_page = committed_db_size.saturating_add(1).max(2);
+            }
             inner.db_size = committed_db_size;
             inner.commit_seq = inner.commit_seq.next();
             inner.active_transactions = inner.active_transactions.saturating_sub(1);
@@ -2629,6 +3432,7 @@ where
         self.write_pages_sorted.clear();
         self.freed_pages.clear();
         self.savepoint_stack.clear();
+        self.truncate_to = None;
         let mut inner = self
             .inner
             .lock()
@@ -2727,6 +3531,7 @@ where
             freelist_snapshot: inner.freelist.clone(),
             allocated_from_freelist_snapshot: self.allocated_from_freelist.clone(),
             allocated_from_eof_snapshot: self.allocated_from_eof.clone(),
+            truncate_to_snapshot: self.truncate_to,
         });
         drop(inner);
         Ok(())
@@ -2787,6 +3592,7 @@ where
         self.allocated_from_freelist = entry.allocated_from_freelist_snapshot.clone();
         self.allocated_from_eof = entry.allocated_from_eof_snapshot.clone();
         self.freed_pages = entry.freed_pages_snapshot.clone();
+        self.truncate_to = entry.truncate_to_snapshot;
         self.write_set = new_write_set;
         self.write_pages_sorted = entry.write_pages_sorted_snapshot.clone();
 
@@ -3888,6 +4694,166 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_open_read_only_rejects_writers() {
+        let vfs = MemoryVfs::new();
+        let path = PathBuf::from("/pager_read_only.db");
+        let cx = Cx::new();
+        let _writer = SimplePager::open(vfs.clone(), &path, PageSize::DEFAULT).unwrap();
+
+        let pager = SimplePager::open_with_flags(
+            &cx,
+            vfs,
+            &path,
+            PageSize::DEFAULT,
+            VfsOpenFlags::READONLY,
+        )
+        .unwrap();
+        assert!(
+            pager.is_read_only(),
+            "bead_id={BEAD_ID} case=read_only_flag"
+        );
+
+        let txn = pager.begin(&cx, TransactionMode::ReadOnly).unwrap();
+        assert!(txn.get_page(&cx, PageNumber::ONE).is_ok());
+        drop(txn);
+
+        let mut deferred = pager.begin(&cx, TransactionMode::Deferred).unwrap();
+        assert!(
+            matches!(deferred.allocate_page(&cx), Err(FrankenError::ReadOnly)),
+            "bead_id={BEAD_ID} case=read_only_deferred_write"
+        );
+        assert!(
+            matches!(
+                pager.begin(&cx, TransactionMode::Immediate),
+                Err(FrankenError::ReadOnly)
+            ),
+            "bead_id={BEAD_ID} case=read_only_immediate_begin"
+        );
+    }
+
+    #[test]
+    fn test_open_with_key_encrypts_pages_and_rejects_wrong_key() {
+        let cx = Cx::new();
+        let vfs = MemoryVfs::new();
+        let path = PathBuf::from("/pager_encrypted.db");
+        let flags = VfsOpenFlags::CREATE | VfsOpenFlags::READWRITE;
+        let params = Argon2Params {
+            m_cost: 256,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let open = |passphrase: &[u8]| {
+            SimplePager::open_with_key(
+                &cx,
+                vfs.clone(),
+                &path,
+                PageSize::DEFAULT,
+                flags,
+                passphrase,
+                params.clone(),
+            )
+        };
+        let payload_len =
+            PageSize::DEFAULT.as_usize() - usize::from(ENCRYPTED_DATABASE_RESERVED_BYTES);
+
+        let pager = open(b"secret").unwrap();
+        assert!(
+            pager.is_encrypted(),
+            "bead_id={BEAD_ID} case=encrypted_flag"
+        );
+        assert_eq!(pager.reserved_bytes(), ENCRYPTED_DATABASE_RESERVED_BYTES);
+        let page_no = {
+            let mut txn = pager.begin(&cx, TransactionMode::Immediate).unwrap();
+            let page_no = txn.allocate_page(&cx).unwrap();
+            let mut page = vec![0_u8; PageSize::DEFAULT.as_usize()];
+            page[..payload_len].fill(0x5A);
+            txn.write_page(&cx, page_no, &page).unwrap();
+            txn.commit(&cx).unwrap();
+            page_no
+        };
+        drop(pager);
+
+        let raw = read_all_vfs_bytes(&vfs, &cx, &path);
+        assert!(
+            !raw.windows(64)
+                .any(|window| window.iter().all(|&b| b == 0x5A)),
+            "bead_id={BEAD_ID} case=plaintext_not_on_disk"
+        );
+        assert!(
+            !vfs.access(
+                &cx,
+                Path::new("/pager_encrypted.db-key"),
+                AccessFlags::EXISTS
+            )
+            .unwrap(),
+            "bead_id={BEAD_ID} case=key_store_in_file"
+        );
+
+        assert!(
+            matches!(open(b"wrong"), Err(FrankenError::NotADatabase { .. })),
+            "bead_id={BEAD_ID} case=wrong_key"
+        );
+        // Without a key the open succeeds and page access fails until the
+        // key is supplied.
+        let locked = SimplePager::open(vfs.clone(), &path, PageSize::DEFAULT).unwrap();
+        assert!(
+            locked.is_key_required(),
+            "bead_id={BEAD_ID} case=locked_open"
+        );
+        assert!(
+            matches!(
+                locked.begin(&cx, TransactionMode::ReadOnly),
+                Err(FrankenError::NotADatabase { .. })
+            ),
+            "bead_id={BEAD_ID} case=missing_key"
+        );
+        assert!(matches!(
+            locked.set_key(&cx, b"wrong", params.clone()),
+            Err(FrankenError::NotADatabase { .. })
+        ));
+        locked.set_key(&cx, b"secret", params.clone()).unwrap();
+        let reader = locked.begin(&cx, TransactionMode::ReadOnly).unwrap();
+        assert_eq!(reader.get_page(&cx, page_no).unwrap().as_ref()[0], 0x5A);
+        drop(reader);
+        drop(locked);
+
+        let pager = open(b"secret").unwrap();
+        let reader = pager.begin(&cx, TransactionMode::ReadOnly).unwrap();
+        let page = reader.get_page(&cx, page_no).unwrap();
+        assert!(
+            page.as_ref()[..payload_len].iter().all(|&b| b == 0x5A),
+            "bead_id={BEAD_ID} case=decrypted_roundtrip"
+        );
+        drop(reader);
+        pager.rekey(&cx, b"rotated").unwrap();
+        drop(pager);
+
+        assert!(matches!(
+            open(b"secret"),
+            Err(FrankenError::NotADatabase { .. })
+        ));
+        let pager = open(b"rotated").unwrap();
+        let reader = pager.begin(&cx, TransactionMode::ReadOnly).unwrap();
+        assert_eq!(reader.get_page(&cx, page_no).unwrap().as_ref()[0], 0x5A);
+    }
+
+    #[test]
+    fn test_open_read_only_missing_database_fails() {
+        let cx = Cx::new();
+        let result = SimplePager::open_with_flags(
+            &cx,
+            MemoryVfs::new(),
+            Path::new("/pager_read_only_missing.db"),
+            PageSize::DEFAULT,
+            VfsOpenFlags::READONLY,
+        );
+        assert!(
+            matches!(result, Err(FrankenError::CannotOpen { .. })),
+            "bead_id={BEAD_ID} case=read_only_missing_db"
+        );
+    }
+
     #[test]
     fn test_begin_refreshes_external_page_growth_before_allocation() {
         let vfs = MemoryVfs::new();
@@ -7747,7 +8713,7 @@ mod tests {
                 max_written: page_two.get(),
                 page_one_dirty: false,
                 freelist_metadata_dirty: false,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_interior_update"
         );
@@ -7786,7 +8752,7 @@ mod tests {
                 max_written: PageNumber::ONE.get(),
                 page_one_dirty: true,
                 freelist_metadata_dirty: false,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_page1_dirty"
         );
@@ -7825,7 +8791,7 @@ mod tests {
                 max_written: 0,
                 page_one_dirty: false,
                 freelist_metadata_dirty: true,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_freelist_dirty"
         );
@@ -7857,7 +8823,7 @@ mod tests {
                 max_written: page_two.get(),
                 page_one_dirty: false,
                 freelist_metadata_dirty: false,
-                db_growth: true,
+                page_count: PageCountChange::Growth,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_db_growth"
         );
@@ -7907,7 +8873,7 @@ mod tests {
                 max_written: 0,
                 page_one_dirty: false,
                 freelist_metadata_dirty: false,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_net_zero_reuse_has_no_trigger"
         );
@@ -7945,7 +8911,7 @@ mod tests {
                 max_written: 0,
                 page_one_dirty: false,
                 freelist_metadata_dirty: false,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=wal_page1_plan_eof_allocate_then_free_has_no_trigger"
         );
@@ -8729,7 +9695,7 @@ mod tests {
                 max_written: 0,
                 page_one_dirty: false,
                 freelist_metadata_dirty: false,
-                db_growth: false,
+                page_count: PageCountChange::Unchanged,
             },
             "bead_id={BEAD_ID} case=concurrent_reuse_then_free_has_no_wal_page_one_trigger"
         );
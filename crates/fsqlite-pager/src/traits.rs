@@ -440,6 +440,36 @@ pub trait TransactionHandle: sealed::Sealed + Send {
     /// Free a page, returning it to the freelist.
     fn free_page(&mut self, cx: &Cx, page_no: PageNumber) -> Result<()>;
 
+    /// Claim a specific page number for this transaction.
+    ///
+    /// Succeeds when `page_no` is on the freelist, was freed earlier in this
+    /// transaction, or lies past the current end of file. Returns `false`
+    /// when the page is in use. Auto-vacuum relies on this to place root
+    /// pages and relocation targets at fixed positions.
+    fn allocate_page_at(&mut self, _cx: &Cx, _page_no: PageNumber) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Database size in pages this transaction would commit right now.
+    fn database_size(&self) -> Result<u32> {
+        Ok(0)
+    }
+
+    /// Pages that would be on the freelist if this transaction committed
+    /// right now, in ascending order.
+    fn freelist_pages(&self) -> Result<Vec<PageNumber>> {
+        Ok(Vec::new())
+    }
+
+    /// Shrink the database to `n_pages` pages when this transaction commits.
+    ///
+    /// Every page past `n_pages` must already be free or relocated. Returns
+    /// `false` when the pager cannot shrink the file in the current mode
+    /// (for example while another snapshot may still read the tail).
+    fn truncate_database(&mut self, _cx: &Cx, _n_pages: u32) -> Result<bool> {
+        Ok(false)
+    }
+
     /// Commit this transaction.
     ///
     /// Performs SSI validation, First-Committer-Wins check, merge ladder,
@@ -393,7 +393,7 @@ impl Parser {
         let tok = self.advance_token();
         match &tok.kind {
             TokenKind::KwCollate => {
-                let collation = match self.parse_identifier() {
+                let collation = match self.parse_collation_name() {
                     Ok(s) => s,
                     Err(_) => {
                         return Err(self.err_here("expected collation name after COLLATE"));
@@ -393,6 +393,24 @@ impl Parser {
         }
     }
 
+    /// Parse the name after `COLLATE`. SQLite accepts a string literal or any
+    /// keyword here too, so `COLLATE natural` names a collation rather than
+    /// starting a join.
+    pub(crate) fn parse_collation_name(&mut self) -> Result<String, ParseError> {
+        match self.peek().clone() {
+            TokenKind::String(s) => {
+                self.advance();
+                Ok(s)
+            }
+            ref k if k.keyword_str().is_some() => {
+                let s = kw_to_str(k);
+                self.advance();
+                Ok(s)
+            }
+            _ => self.parse_identifier(),
+        }
+    }
+
     pub(crate) fn parse_qualified_name(&mut self) -> Result<QualifiedName, ParseError> {
         let first = self.parse_identifier()?;
         if self.eat(&TokenKind::Dot) {
@@ -1455,7 +1473,7 @@ impl Parser {
                 ColumnConstraintKind::Default(DefaultValue::Expr(expr))
             }
         } else if self.eat_kw(&TokenKind::KwCollate) {
-            ColumnConstraintKind::Collate(self.parse_identifier()?)
+            ColumnConstraintKind::Collate(self.parse_collation_name()?)
         } else if self.eat_kw(&TokenKind::KwReferences) {
             ColumnConstraintKind::ForeignKey(self.parse_fk_clause()?)
         } else if self.eat_kw(&TokenKind::KwGenerated) || self.eat_kw(&TokenKind::KwAs) {
@@ -1608,7 +1626,7 @@ impl Parser {
     fn parse_indexed_column(&mut self) -> Result<IndexedColumn, ParseError> {
         let expr = self.parse_expr()?;
         let collation = if self.eat_kw(&TokenKind::KwCollate) {
-            Some(self.parse_identifier()?)
+            Some(self.parse_collation_name()?)
         } else {
             None
         };
@@ -2162,6 +2180,7 @@ pub(crate) fn is_nonreserved_kw(k: &TokenKind) -> bool {
             | TokenKind::KwAnalyze
             | TokenKind::KwAsc
             | TokenKind::KwBefore
+            | TokenKind::KwBy
             | TokenKind::KwCascade
             | TokenKind::KwColumn
             | TokenKind::KwConcurrent
@@ -5116,6 +5135,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_collate_accepts_keyword_names() {
+        let stmt = parse_one("CREATE TABLE t (name TEXT COLLATE natural)");
+        let Statement::CreateTable(ct) = stmt else {
+            unreachable!("expected CreateTable");
+        };
+        let CreateTableBody::Columns { columns, .. } = &ct.body else {
+            unreachable!("expected Columns body");
+        };
+        assert!(
+            columns[0].constraints.iter().any(
+                |c| matches!(&c.kind, ColumnConstraintKind::Collate(name) if name == "natural")
+            )
+        );
+        assert_roundtrip("SELECT a FROM t ORDER BY a COLLATE natural");
+        assert_roundtrip("CREATE INDEX i ON t (a COLLATE natural)");
+    }
+
     #[test]
     fn test_table_constraint_composite_pk() {
         let stmt = parse_one("CREATE TABLE t (a INTEGER, b INTEGER, PRIMARY KEY (a, b))");
@@ -700,6 +700,7 @@ mod tests {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         }]
     }
 
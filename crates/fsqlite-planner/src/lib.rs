@@ -19,6 +19,8 @@ use fsqlite_ast::{
     JoinConstraint, JoinKind, LikeOp, Literal, NullsOrder, OrderingTerm, ResultColumn, SelectBody,
     SelectCore, SortDirection, Span, TableOrSubquery,
 };
+use fsqlite_types::value::SqliteValue;
+use stats::{ColumnStats, Operator};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -452,6 +454,10 @@ pub struct IndexInfo {
     /// When present, the planner matches query expressions structurally against these.
     /// `columns` should contain synthetic names; the real matching uses these exprs.
     pub expression_columns: Vec<Expr>,
+    /// Sample-based statistics for the leftmost column, built from
+    /// `sqlite_stat1` / `sqlite_stat4` by [`stats::leading_column_stats`].
+    /// `None` falls back to the fixed selectivity heuristics.
+    pub leading_column_stats: Option<ColumnStats>,
 }
 
 // ---------------------------------------------------------------------------
@@ -1015,10 +1021,19 @@ fn best_access_path_internal(
         });
 
         let mut cost_multiplier: f64 = 1.0;
+        let mut sampled_fetch_selectivity = None;
         let (kind, mut est_rows) = match usability {
             IndexUsability::Equality => {
+                let sampled = if idx.unique {
+                    None
+                } else {
+                    sampled_equality_selectivity(idx, where_terms)
+                };
+                sampled_fetch_selectivity = sampled;
                 let rows = if idx.unique {
                     1.0
+                } else if let Some(selectivity) = sampled {
+                    (selectivity * table.n_rows as f64).max(1.0)
                 } else {
                     (table.n_rows as f64 / 10.0).max(1.0)
                 };
@@ -1042,6 +1057,13 @@ fn best_access_path_internal(
                 #[allow(clippy::cast_precision_loss)]
                 let base_rows = if idx.unique && eq_columns == idx.columns.len() {
                     1.0
+                } else if let Some(leading) = sampled_equality_selectivity(idx, where_terms) {
+                    // Sampled leading-column frequency; later columns keep
+                    // the ~1/10 heuristic.
+                    let divisor = 10.0_f64.powi(i32::try_from(eq_columns - 1).unwrap_or(i32::MAX));
+                    let rows = (leading * table.n_rows as f64 / divisor).max(1.0);
+                    sampled_fetch_selectivity = Some(rows / table.n_rows.max(1) as f64);
+                    rows
                 } else {
                     let divisor = 10.0_f64.powi(i32::try_from(eq_columns).unwrap_or(i32::MAX));
                     (table.n_rows as f64 / divisor).max(1.0)
@@ -1062,6 +1084,11 @@ fn best_access_path_internal(
                 }
             }
             IndexUsability::Range { selectivity } => {
+                let selectivity = if skip_scan_candidate.is_none() {
+                    sampled_range_selectivity(idx, where_terms).unwrap_or(selectivity)
+                } else {
+                    selectivity
+                };
                 let rows = (selectivity * table.n_rows as f64).max(1.0);
                 if is_covering {
                     (AccessPathKind::CoveringIndexScan { selectivity }, rows)
@@ -1101,6 +1128,14 @@ fn best_access_path_internal(
         }
 
         let mut cost = estimate_cost(&kind, table.n_pages, idx.n_pages) * cost_multiplier;
+        if let (AccessPathKind::IndexScanEquality, Some(selectivity)) =
+            (&kind, sampled_fetch_selectivity)
+        {
+            // Samples say how many rows the seek returns; charge for visiting
+            // them the way a range scan is charged, so a heavy-hitter key can
+            // lose to a full scan or a narrower index.
+            cost = selectivity.mul_add((idx.n_pages.max(1) + table.n_pages.max(1)) as f64, cost);
+        }
 
         if let Some(hinted_name) = explicit_indexed_by {
             if idx.name.eq_ignore_ascii_case(hinted_name) {
@@ -1995,6 +2030,125 @@ fn analyze_expression_index_usability(
     IndexUsability::NotUsable
 }
 
+fn index_column_matches(index: &IndexInfo, wc: &WhereColumn, idx_col: &str) -> bool {
+    wc.column.eq_ignore_ascii_case(idx_col)
+        && wc
+            .table
+            .as_ref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(&index.table))
+}
+
+/// Convert a constant literal into a histogram probe value.
+fn literal_probe_value(literal: &Literal) -> Option<SqliteValue> {
+    match literal {
+        Literal::Integer(value) => Some(SqliteValue::Integer(*value)),
+        Literal::Float(value) => Some(SqliteValue::Float(*value)),
+        Literal::String(value) => Some(SqliteValue::Text(value.clone())),
+        Literal::Blob(value) => Some(SqliteValue::Blob(value.clone())),
+        Literal::True => Some(SqliteValue::Integer(1)),
+        Literal::False => Some(SqliteValue::Integer(0)),
+        _ => None,
+    }
+}
+
+/// Fraction of rows matched by an equality on the index's leftmost column,
+/// taken from its sampled statistics.
+///
+/// Constant operands probe the histogram, so skewed keys get their real
+/// frequency; other operands (bound parameters, column references) use the
+/// average frequency `non-NULL rows / NDV`.
+fn sampled_equality_selectivity(index: &IndexInfo, terms: &[WhereTerm<'_>]) -> Option<f64> {
+    let stats = index.leading_column_stats.as_ref()?;
+    if !index.expression_columns.is_empty() || stats.table_row_count == 0 {
+        return None;
+    }
+    let leftmost = index.columns.first()?;
+    let term = terms.iter().find(|term| {
+        matches!(term.kind, WhereTermKind::Equality)
+            && term
+                .column
+                .as_ref()
+                .is_some_and(|wc| index_column_matches(index, wc, leftmost))
+    })?;
+    let probe = normalize_column_literal_comparison(term.expr)
+        .and_then(|comparison| literal_probe_value(&comparison.literal));
+    let selectivity = if let Some(value) = probe {
+        stats.estimate_selectivity(&Operator::Eq, &value)
+    } else {
+        let non_null = stats.table_row_count.saturating_sub(stats.null_count) as f64;
+        non_null / stats.ndv.max(1) as f64 / stats.table_row_count as f64
+    };
+    Some(selectivity)
+}
+
+/// Fraction of rows between the constant range bounds on the index's
+/// leftmost column, interpolated from its sampled histogram.
+///
+/// Returns `None` without a histogram or without any constant bound.
+fn sampled_range_selectivity(index: &IndexInfo, terms: &[WhereTerm<'_>]) -> Option<f64> {
+    let stats = index.leading_column_stats.as_ref()?;
+    if !index.expression_columns.is_empty()
+        || stats.histogram.is_none()
+        || stats.table_row_count == 0
+    {
+        return None;
+    }
+    let leftmost = index.columns.first()?;
+
+    // Tightest lower-bound (`>`/`>=`) and upper-bound (`<`/`<=`) fractions.
+    let mut lower: Option<f64> = None;
+    let mut upper: Option<f64> = None;
+    let mut apply = |op: AstBinaryOp, literal: &Literal| {
+        let Some(value) = literal_probe_value(literal) else {
+            return;
+        };
+        let (slot, operator) = match op {
+            AstBinaryOp::Gt => (&mut lower, Operator::Gt),
+            AstBinaryOp::Ge => (&mut lower, Operator::Ge),
+            AstBinaryOp::Lt => (&mut upper, Operator::Lt),
+            AstBinaryOp::Le => (&mut upper, Operator::Le),
+            _ => return,
+        };
+        let fraction = stats.estimate_selectivity(&operator, &value);
+        *slot = Some(slot.map_or(fraction, |current| current.min(fraction)));
+    };
+
+    for term in terms {
+        if !term
+            .column
+            .as_ref()
+            .is_some_and(|wc| index_column_matches(index, wc, leftmost))
+        {
+            continue;
+        }
+        match (&term.kind, term.expr) {
+            (WhereTermKind::Range, expr) => {
+                if let Some(comparison) = normalize_column_literal_comparison(expr) {
+                    apply(comparison.op, &comparison.literal);
+                }
+            }
+            (WhereTermKind::Between, Expr::Between { low, high, .. }) => {
+                if let Expr::Literal(literal, _) = low.as_ref() {
+                    apply(AstBinaryOp::Ge, literal);
+                }
+                if let Expr::Literal(literal, _) = high.as_ref() {
+                    apply(AstBinaryOp::Le, literal);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let non_null = stats.table_row_count.saturating_sub(stats.null_count) as f64
+        / stats.table_row_count as f64;
+    let selectivity = match (lower, upper) {
+        (Some(low), Some(high)) => low + high - non_null,
+        (Some(bound), None) | (None, Some(bound)) => bound,
+        (None, None) => return None,
+    };
+    Some(selectivity.clamp(1.0 / stats.table_row_count as f64, 1.0))
+}
+
 /// Default selectivity for range constraints when no ANALYZE data is available.
 const DEFAULT_RANGE_SELECTIVITY: f64 = 0.33;
 const SKIP_SCAN_EQ_SELECTIVITY: f64 = 0.01;
@@ -4225,6 +4379,7 @@ mod tests {
             source: StatsSource::Heuristic,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         }
     }
 
@@ -5878,6 +6033,100 @@ mod tests {
         assert!(matches!(ap.kind, AccessPathKind::IndexScanRange { .. }));
     }
 
+    /// Index stats from `(value, neq, nlt, ndlt)` stat4 samples on one column.
+    fn sampled_index(
+        name: &str,
+        column: &str,
+        n_rows: u64,
+        avg_eq: u64,
+        samples: &[(i64, u64, u64, u64)],
+    ) -> IndexInfo {
+        let samples: Vec<stats::IndexSample> = samples
+            .iter()
+            .map(|&(value, neq, nlt, ndlt)| stats::IndexSample {
+                neq: vec![neq, 1],
+                nlt: vec![nlt, nlt],
+                ndlt: vec![ndlt, nlt],
+                key: vec![SqliteValue::Integer(value), SqliteValue::Integer(0)],
+            })
+            .collect();
+        let mut idx = index_info(name, "orders", &[column], false, 40);
+        idx.source = StatsSource::Analyze;
+        idx.leading_column_stats = stats::leading_column_stats(&[n_rows, avg_eq], &samples);
+        idx
+    }
+
+    fn skewed_order_indexes() -> Vec<IndexInfo> {
+        // Tenant 1 owns 9000 of 10000 rows; tenants 2..=1001 own one each.
+        let tenant = sampled_index(
+            "idx_tenant",
+            "tenant_id",
+            10_000,
+            10,
+            &[(1, 9000, 0, 0), (500, 1, 9498, 499), (1001, 1, 9999, 1000)],
+        );
+        // created_at is unique and uniform over 0..10000.
+        let created = sampled_index(
+            "idx_created",
+            "created_at",
+            10_000,
+            1,
+            &[
+                (0, 1, 0, 0),
+                (2500, 1, 2500, 2500),
+                (5000, 1, 5000, 5000),
+                (7500, 1, 7500, 7500),
+                (9999, 1, 9999, 9999),
+            ],
+        );
+        vec![tenant, created]
+    }
+
+    #[test]
+    fn test_best_access_path_sampled_heavy_hitter_prefers_full_scan() {
+        let table = table_stats("orders", 200, 10_000);
+        let indexes = skewed_order_indexes();
+
+        let heavy = best_access_path(&table, &indexes, &[eq_term_value("tenant_id", 1)], None);
+        assert!(matches!(heavy.kind, AccessPathKind::FullTableScan));
+
+        let light = best_access_path(&table, &indexes, &[eq_term_value("tenant_id", 500)], None);
+        assert_eq!(light.index.as_deref(), Some("idx_tenant"));
+        assert!(matches!(light.kind, AccessPathKind::IndexScanEquality));
+        assert!((light.estimated_rows - 1.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_best_access_path_sampled_range_beats_skewed_equality() {
+        let table = table_stats("orders", 200, 10_000);
+        let indexes = skewed_order_indexes();
+        let recent: &'static Expr = Box::leak(Box::new(Expr::BinaryOp {
+            left: Box::new(Expr::Column(ColumnRef::bare("created_at"), Span::ZERO)),
+            op: AstBinaryOp::Gt,
+            right: Box::new(Expr::Literal(Literal::Integer(9900), Span::ZERO)),
+            span: Span::ZERO,
+        }));
+        let terms = [eq_term_value("tenant_id", 1), classify_where_term(recent)];
+
+        let ap = best_access_path(&table, &indexes, &terms, None);
+        assert_eq!(ap.index.as_deref(), Some("idx_created"));
+        let AccessPathKind::IndexScanRange { selectivity } = ap.kind else {
+            panic!("expected a range scan, got {:?}", ap.kind);
+        };
+        assert!((selectivity - 0.01).abs() < 0.001);
+
+        // Without samples the same query trusts the tenant equality.
+        let unsampled: Vec<IndexInfo> = indexes
+            .into_iter()
+            .map(|mut idx| {
+                idx.leading_column_stats = None;
+                idx
+            })
+            .collect();
+        let ap = best_access_path(&table, &unsampled, &terms, None);
+        assert_eq!(ap.index.as_deref(), Some("idx_tenant"));
+    }
+
     #[test]
     fn test_best_access_path_ignores_wrong_table_index() {
         // Index belongs to different table — should not be used.
@@ -5901,6 +6150,7 @@ mod tests {
             source: StatsSource::Heuristic,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         };
         let terms = [eq_term("a")];
         let ap = best_access_path(&table, &[idx], &terms, None);
@@ -5924,6 +6174,7 @@ mod tests {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         };
 
         let ap = best_access_path(&table, &[idx], &[eq_term("email")], None);
@@ -5951,6 +6202,7 @@ mod tests {
             source: StatsSource::Analyze,
             partial_where: None,
             expression_columns: vec![],
+            leading_column_stats: None,
         };
 
         let ap = best_access_path(&table, &[idx], &[eq_term("email")], None);
@@ -6527,6 +6779,7 @@ mod tests {
                     source: StatsSource::Heuristic,
                     partial_where: None,
                     expression_columns: vec![],
+                    leading_column_stats: None,
                 })
                 .boxed()
         }
@@ -6731,6 +6984,7 @@ mod tests {
                     source: StatsSource::Heuristic,
                     partial_where: None,
                     expression_columns: vec![],
+                    leading_column_stats: None,
                 };
 
                 let with_index_path = best_access_path(
@@ -48,7 +48,17 @@ pub struct Histogram {
 
 impl Histogram {
     /// Estimate the number of rows satisfying `col = value`.
+    ///
+    /// A single-value bucket (`lower == upper == value`) carries an exact
+    /// frequency and wins over any wider bucket whose bounds touch `value`.
     pub fn estimate_equality_rows(&self, value: &SqliteValue) -> f64 {
+        if let Some(exact) = self
+            .buckets
+            .iter()
+            .find(|bucket| &bucket.lower == value && &bucket.upper == value)
+        {
+            return exact.count as f64;
+        }
         for bucket in &self.buckets {
             if bucket.contains(value) {
                 // Uniform assumption within bucket: count / ndv
@@ -77,7 +87,7 @@ impl Histogram {
                 // Note: SqliteValue subtraction is not directly defined for all types.
                 // We use a heuristic interpolation for numeric types.
                 let fraction = interpolate_position(&bucket.lower, &bucket.upper, value);
-                count += bucket.count as f64 * fraction;
+                count = (bucket.count as f64).mul_add(fraction, count);
                 break;
             }
         }
@@ -98,7 +108,7 @@ impl Histogram {
                 // Value falls inside this bucket. Interpolate.
                 // Fraction = (upper - value) / (upper - lower)
                 let fraction = 1.0 - interpolate_position(&bucket.lower, &bucket.upper, value);
-                count += bucket.count as f64 * fraction;
+                count = (bucket.count as f64).mul_add(fraction, count);
                 break;
             }
         }
@@ -110,7 +120,7 @@ fn bytes_to_fraction(bytes: &[u8]) -> f64 {
     let mut fraction = 0.0;
     let mut weight = 1.0 / 256.0;
     for &b in bytes.iter().take(8) {
-        fraction += f64::from(b) * weight;
+        fraction = f64::from(b).mul_add(weight, fraction);
         weight /= 256.0;
     }
     fraction
@@ -462,6 +472,148 @@ fn cmp_matches(sample_val: &SqliteValue, op: Operator, probe: &SqliteValue) -> b
     }
 }
 
+// ---------------------------------------------------------------------------
+// ANALYZE ingestion (sqlite_stat1 / sqlite_stat4)
+// ---------------------------------------------------------------------------
+
+/// One `sqlite_stat4` sample: an index key plus its position statistics.
+///
+/// Each count vector has one entry per key prefix (leftmost column first,
+/// trailing rowid last), mirroring SQLite's space-separated text encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSample {
+    /// Rows whose key prefix equals the sample's prefix.
+    pub neq: Vec<u64>,
+    /// Rows whose key prefix sorts strictly before the sample's prefix.
+    pub nlt: Vec<u64>,
+    /// Distinct key prefixes sorting strictly before the sample's prefix.
+    pub ndlt: Vec<u64>,
+    /// Decoded index record (the `sample` column).
+    pub key: Vec<SqliteValue>,
+}
+
+impl IndexSample {
+    /// Build a sample from the columns of a `sqlite_stat4` row.
+    ///
+    /// Returns `None` if any count list is malformed or the lists disagree
+    /// in length.
+    pub fn from_stat4_columns(
+        neq: &str,
+        nlt: &str,
+        ndlt: &str,
+        key: Vec<SqliteValue>,
+    ) -> Option<Self> {
+        let parse = |text: &str| -> Option<Vec<u64>> {
+            text.split_ascii_whitespace()
+                .map(|field| field.parse().ok())
+                .collect()
+        };
+        let sample = Self {
+            neq: parse(neq)?,
+            nlt: parse(nlt)?,
+            ndlt: parse(ndlt)?,
+            key,
+        };
+        let width = sample.neq.len();
+        if width == 0 || sample.nlt.len() != width || sample.ndlt.len() != width {
+            return None;
+        }
+        Some(sample)
+    }
+
+    /// Encode a count vector in `sqlite_stat4` text form.
+    pub fn encode_counts(counts: &[u64]) -> String {
+        counts
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Parse the integer fields of a `sqlite_stat1.stat` value.
+///
+/// Trailing keyword options (`unordered`, `sz=N`, ...) are ignored.
+pub fn parse_stat1(stat: &str) -> Vec<u64> {
+    stat.split_ascii_whitespace()
+        .map_while(|field| field.parse().ok())
+        .collect()
+}
+
+/// Build statistics for an index's leftmost column from its `sqlite_stat1`
+/// fields and `sqlite_stat4` samples.
+///
+/// Every distinct sampled leading value becomes a single-value bucket with
+/// its exact row count, so heavy hitters keep their true frequency instead of
+/// being averaged away. The rows between consecutive samples form one
+/// interpolation bucket each. Rows past the last sample are not represented;
+/// fsqlite's `ANALYZE` always samples the final key.
+pub fn leading_column_stats(stat1: &[u64], samples: &[IndexSample]) -> Option<ColumnStats> {
+    let row_count = *stat1.first()?;
+    if row_count == 0 {
+        return None;
+    }
+    let avg_eq = stat1.get(1).copied().unwrap_or(1).max(1);
+    let mut stats = ColumnStats {
+        table_row_count: row_count,
+        ndv: row_count.div_ceil(avg_eq),
+        ..ColumnStats::default()
+    };
+
+    let mut ordered: Vec<&IndexSample> = samples
+        .iter()
+        .filter(|sample| {
+            !sample.key.is_empty()
+                && !sample.neq.is_empty()
+                && !sample.nlt.is_empty()
+                && !sample.ndlt.is_empty()
+        })
+        .collect();
+    ordered.sort_by_key(|sample| sample.nlt[0]);
+    ordered.dedup_by_key(|sample| sample.nlt[0]);
+    if ordered.is_empty() {
+        return Some(stats);
+    }
+
+    let mut buckets = Vec::with_capacity(ordered.len() * 2);
+    let mut rows_seen = 0_u64;
+    let mut distinct_seen = 0_u64;
+    let mut previous = SqliteValue::Null;
+    for sample in ordered {
+        let value = &sample.key[0];
+        let gap_rows = sample.nlt[0].saturating_sub(rows_seen);
+        if gap_rows > 0 {
+            buckets.push(HistogramBucket {
+                lower: previous.clone(),
+                upper: value.clone(),
+                count: gap_rows,
+                ndv: sample.ndlt[0].saturating_sub(distinct_seen).max(1),
+            });
+        }
+        buckets.push(HistogramBucket {
+            lower: value.clone(),
+            upper: value.clone(),
+            count: sample.neq[0],
+            ndv: 1,
+        });
+        if matches!(value, SqliteValue::Null) {
+            stats.null_count = sample.neq[0];
+        } else if stats.min_value.is_none() {
+            stats.min_value = Some(value.clone());
+        }
+        rows_seen = sample.nlt[0].saturating_add(sample.neq[0]);
+        distinct_seen = sample.ndlt[0].saturating_add(1);
+        previous = value.clone();
+    }
+
+    if !matches!(previous, SqliteValue::Null) {
+        stats.max_value = Some(previous);
+    }
+    stats.ndv = stats.ndv.max(distinct_seen);
+    stats.histogram = Some(Histogram { buckets });
+    Some(stats)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -637,4 +789,65 @@ mod tests {
         assert_eq!(est.method, EstimationMethod::Sampling);
         assert!((est.selectivity - 1.0).abs() < 0.01);
     }
+
+    // ── sqlite_stat4 ingestion ──
+
+    fn skewed_tenant_samples() -> Vec<IndexSample> {
+        // 1000 rows: tenant 1 owns 900, tenants 2..=101 own one row each.
+        let sample = |tenant: i64, neq: u64, nlt: u64, ndlt: u64| IndexSample {
+            neq: vec![neq, 1],
+            nlt: vec![nlt, nlt],
+            ndlt: vec![ndlt, nlt],
+            key: vec![SqliteValue::Integer(tenant), SqliteValue::Integer(1)],
+        };
+        vec![
+            sample(101, 1, 999, 100),
+            sample(1, 900, 0, 0),
+            sample(51, 1, 949, 50),
+        ]
+    }
+
+    #[test]
+    fn test_stat4_columns_round_trip() {
+        let sample = IndexSample::from_stat4_columns(
+            "900 1",
+            "0 0",
+            "0 0",
+            vec![SqliteValue::Integer(1), SqliteValue::Integer(7)],
+        )
+        .unwrap();
+        assert_eq!(sample.neq, vec![900, 1]);
+        assert_eq!(IndexSample::encode_counts(&sample.neq), "900 1");
+        assert!(IndexSample::from_stat4_columns("1 2", "0", "0 0", Vec::new()).is_none());
+        assert!(IndexSample::from_stat4_columns("x", "0", "0", Vec::new()).is_none());
+        assert_eq!(parse_stat1("1000 10 1 unordered sz=12"), vec![1000, 10, 1]);
+    }
+
+    #[test]
+    fn test_leading_column_stats_keep_heavy_hitter_frequency() {
+        let stats = leading_column_stats(&[1000, 10, 1], &skewed_tenant_samples()).unwrap();
+        assert_eq!(stats.table_row_count, 1000);
+        assert_eq!(stats.ndv, 101);
+        assert_eq!(stats.min_value, Some(SqliteValue::Integer(1)));
+        assert_eq!(stats.max_value, Some(SqliteValue::Integer(101)));
+
+        let heavy = stats.estimate_selectivity(&Operator::Eq, &SqliteValue::Integer(1));
+        assert!((heavy - 0.9).abs() < 1e-9);
+        let light = stats.estimate_selectivity(&Operator::Eq, &SqliteValue::Integer(30));
+        assert!((light - 0.001).abs() < 1e-9);
+
+        // Tenants above 51 hold 50 rows; the boundary bucket is fully counted.
+        let above = stats.estimate_selectivity(&Operator::Gt, &SqliteValue::Integer(51));
+        assert!((above - 0.05).abs() < 1e-9);
+        let below = stats.estimate_selectivity(&Operator::Lt, &SqliteValue::Integer(2));
+        assert!((below - 0.9).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_leading_column_stats_without_samples_uses_stat1() {
+        let stats = leading_column_stats(&[1000, 10], &[]).unwrap();
+        assert_eq!(stats.ndv, 100);
+        assert!(stats.histogram.is_none());
+        assert!(leading_column_stats(&[], &[]).is_none());
+    }
 }
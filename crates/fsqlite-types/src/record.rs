@@ -307,6 +307,36 @@ pub fn parse_record_column(data: &[u8], col_idx: usize) -> Option<SqliteValue> {
     None
 }
 
+/// Locate a column in a serialized record from its header alone.
+///
+/// `header` must contain at least the complete record header; body bytes,
+/// if present, are ignored. Returns the column's serial type and the byte
+/// offset of its value from the start of the record, which lets callers
+/// read or patch a large value in place. Returns `None` if the header is
+/// truncated or malformed, or if the record has no column `col_idx`.
+pub fn record_column_location(header: &[u8], col_idx: usize) -> Option<(u64, usize)> {
+    let (header_size_u64, hdr_varint_len) = read_varint(header)?;
+    let header_size = usize::try_from(header_size_u64).ok()?;
+    if header_size > header.len() || header_size < hdr_varint_len {
+        return None;
+    }
+
+    let mut offset = hdr_varint_len;
+    let mut body_offset = header_size;
+    let mut current_idx = 0;
+    while offset < header_size {
+        let (serial_type, consumed) = read_varint(&header[offset..header_size])?;
+        offset += consumed;
+        if current_idx == col_idx {
+            return Some((serial_type, body_offset));
+        }
+        let value_len = usize::try_from(serial_type_len(serial_type)?).ok()?;
+        body_offset = body_offset.checked_add(value_len)?;
+        current_idx += 1;
+    }
+    None
+}
+
 /// Count the number of serialized columns in a SQLite record without decoding
 /// the values themselves.
 ///
@@ -925,6 +955,27 @@ mod tests {
         assert_eq!(record_column_count(&data), Some(values.len()));
     }
 
+    #[test]
+    fn record_column_location_points_at_value_bytes() {
+        let values = vec![
+            SqliteValue::Integer(42),
+            SqliteValue::Text("hi".to_owned()),
+            SqliteValue::Blob(vec![7; 300]),
+        ];
+        let data = serialize_record(&values);
+        let (serial_type, offset) = record_column_location(&data, 2).unwrap();
+        assert_eq!(serial_type, 12 + 2 * 300);
+        assert_eq!(&data[offset..], &[7; 300]);
+
+        // Only the header is needed.
+        let header_len = usize::try_from(read_varint(&data).unwrap().0).unwrap();
+        assert_eq!(
+            record_column_location(&data[..header_len], 1),
+            Some((13 + 2 * 2, offset - 2))
+        );
+        assert_eq!(record_column_location(&data, 3), None);
+    }
+
     #[test]
     fn test_record_format_null_vector() {
         let data = serialize_record(&[SqliteValue::Null]);
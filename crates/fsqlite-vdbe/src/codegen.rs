@@ -162,6 +162,11 @@ pub struct IndexSchema {
     /// Empty means "all ASC" for legacy callers/tests that do not yet
     /// populate per-term ordering metadata.
     pub key_sort_directions: Vec<SortDirection>,
+    /// Explicit `COLLATE` name for each logical key term.
+    ///
+    /// `None` (or an empty vector) means the term inherits the indexed
+    /// column's declared collation, falling back to BINARY.
+    pub key_collations: Vec<Option<String>>,
     /// Optional partial-index predicate as SQL text.
     pub where_clause: Option<String>,
     /// Whether this index enforces a UNIQUE constraint.
@@ -198,6 +203,12 @@ impl IndexSchema {
         )
     }
 
+    /// Explicit collation for the `key_pos`th logical key term, if any.
+    #[must_use]
+    pub fn key_term_collation(&self, key_pos: usize) -> Option<&str> {
+        self.key_collations.get(key_pos).and_then(Option::as_deref)
+    }
+
     /// Whether planner / lookup fast paths may safely treat this as a simple
     /// non-partial column index.
     #[must_use]
@@ -2214,7 +2225,6 @@ fn codegen_select_ordered_scan(
     #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
     let sorter_base = b.alloc_regs(total_sorter_cols as i32);
     {
-        let mut reg = sorter_base;
         let scan = ScanCtx {
             cursor,
             table,
@@ -2223,7 +2233,7 @@ fn codegen_select_ordered_scan(
             register_base: None,
             secondary: None,
         };
-        for key in &sort_keys {
+        for (reg, key) in (sorter_base..).zip(&sort_keys) {
             match key {
                 SortKeySource::Column(col_idx) => {
                     #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
@@ -2236,7 +2246,6 @@ fn codegen_select_ordered_scan(
                     emit_expr(b, expr, reg, Some(&scan));
                 }
             }
-            reg += 1;
         }
 
         // Evaluate result columns (including expressions) and store the final
@@ -6173,17 +6182,6 @@ pub fn codegen_update(
         );
     }
 
-    // UPDATE is delete+insert: remove the current row first, then insert the
-    // rewritten record (possibly at a new rowid).
-    b.emit_op(
-        Opcode::Delete,
-        table_cursor,
-        0,
-        0,
-        P4::None,
-        OPFLAG_ISUPDATE,
-    );
-
     // Determine destination rowid for re-insertion.
     let mut rowid_reg = matched_rowid_reg;
     let rowid_alias_col_idx = ctx
@@ -6246,6 +6244,19 @@ pub fn codegen_update(
         0,
     );
 
+    // UPDATE is delete+insert: remove the old row, then insert the rewritten
+    // record (possibly at a new rowid). The new row is fully built first so
+    // the pre-update hook sees both images before anything changes.
+    emit_update_delete(
+        b,
+        table_cursor,
+        matched_rowid_reg,
+        rec_reg,
+        rowid_reg,
+        &table.name,
+        rowid_alias_col_idx.is_some(),
+    );
+
     // Conflict resolution for UPDATE: use explicit OR clause if present,
     // otherwise default to OE_ABORT (standard UPDATE raises constraint error
     // on PK/UNIQUE conflicts rather than silently replacing).
@@ -6526,16 +6537,6 @@ fn codegen_update_from(
     let old_rowid_reg = b.alloc_reg();
     b.emit_op(Opcode::Rowid, target_cursor, old_rowid_reg, 0, P4::None, 0);
 
-    // Delete old row.
-    b.emit_op(
-        Opcode::Delete,
-        target_cursor,
-        0,
-        0,
-        P4::None,
-        OPFLAG_ISUPDATE,
-    );
-
     // Determine destination rowid.
     let mut rowid_reg = old_rowid_reg;
     let rowid_alias_col_idx = ctx
@@ -6595,6 +6596,17 @@ fn codegen_update_from(
         0,
     );
 
+    // Delete old row.
+    emit_update_delete(
+        b,
+        target_cursor,
+        old_rowid_reg,
+        rec_reg,
+        rowid_reg,
+        &target.name,
+        rowid_alias_col_idx.is_some(),
+    );
+
     // Insert updated row.
     let oe_flag = conflict_action_to_oe(stmt.or_conflict.as_ref());
     b.emit_op(
@@ -7008,7 +7020,7 @@ fn emit_stored_generated_columns(b: &mut ProgramBuilder, table: &TableSchema, va
 ///
 /// For each CHECK constraint on the table, parses the constraint expression,
 /// evaluates it using register-based column resolution, and emits a `Halt`
-/// with SQLITE_CONSTRAINT (19) if any constraint evaluates to false (0).
+/// with SQLITE_CONSTRAINT_CHECK (275) if any constraint evaluates to false (0).
 /// NULL results are treated as passing (SQLite semantics: CHECK passes
 /// unless the expression is explicitly false).
 ///
@@ -7021,7 +7033,7 @@ fn emit_check_constraints(
     val_regs: i32,
     ignore_label: Option<Label>,
 ) {
-    const SQLITE_CONSTRAINT: i32 = 19;
+    const SQLITE_CONSTRAINT_CHECK: i32 = 275;
 
     for check_sql in &table.check_constraints {
         let Some(expr) = parse_default_expr(check_sql) else {
@@ -7056,7 +7068,7 @@ fn emit_check_constraints(
             // Default: halt with constraint error.
             b.emit_op(
                 Opcode::Halt,
-                SQLITE_CONSTRAINT,
+                SQLITE_CONSTRAINT_CHECK,
                 0,
                 0,
                 P4::Str(format!("CHECK constraint failed: {check_sql}")),
@@ -7071,7 +7083,7 @@ fn emit_check_constraints(
 /// Emit NOT NULL constraint validation for INSERT/UPDATE.
 ///
 /// For each column with `not_null == true` (and not an IPK, which can't be NULL),
-/// emits `HaltIfNull` to abort with SQLITE_CONSTRAINT if the value is NULL.
+/// emits a `Halt` with SQLITE_CONSTRAINT_NOTNULL (1299) if the value is NULL.
 #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
 fn emit_not_null_constraints(
     b: &mut ProgramBuilder,
@@ -7079,7 +7091,7 @@ fn emit_not_null_constraints(
     val_regs: i32,
     ignore_label: Option<Label>,
 ) {
-    const SQLITE_CONSTRAINT: i32 = 19;
+    const SQLITE_CONSTRAINT_NOTNULL: i32 = 1299;
 
     for (col_idx, col) in table.columns.iter().enumerate() {
         if col.notnull && !col.is_ipk {
@@ -7091,7 +7103,7 @@ fn emit_not_null_constraints(
             } else {
                 b.emit_op(
                     Opcode::Halt,
-                    SQLITE_CONSTRAINT,
+                    SQLITE_CONSTRAINT_NOTNULL,
                     0,
                     0,
                     P4::Str(format!(
@@ -7193,6 +7205,42 @@ fn emit_index_inserts(
     }
 }
 
+/// Emit the `Delete` half of an UPDATE, just before its `Insert`.
+///
+/// P2/P3 carry the new record and rowid registers so the engine can run the
+/// pre-update hook while the old row is still present. When a `NewRowid`
+/// may have moved the cursor, it is first re-seeked to `old_rowid_reg`.
+fn emit_update_delete(
+    b: &mut ProgramBuilder,
+    cursor: i32,
+    old_rowid_reg: i32,
+    rec_reg: i32,
+    new_rowid_reg: i32,
+    table_name: &str,
+    may_allocate_rowid: bool,
+) {
+    let done_label = b.emit_label();
+    if may_allocate_rowid {
+        b.emit_jump_to_label(
+            Opcode::SeekRowid,
+            cursor,
+            old_rowid_reg,
+            done_label,
+            P4::None,
+            0,
+        );
+    }
+    b.emit_op(
+        Opcode::Delete,
+        cursor,
+        rec_reg,
+        new_rowid_reg,
+        P4::Table(table_name.to_owned()),
+        OPFLAG_ISUPDATE,
+    );
+    b.resolve_label(done_label);
+}
+
 /// Emit `IdxDelete` opcodes for all indexes on the table (bd-34se: Phase 5I.4).
 ///
 /// For each index, this reads the indexed column values from the cursor,
@@ -10758,6 +10806,7 @@ mod tests {
                 columns: vec!["b".to_owned()],
                 key_expressions: vec!["b".to_owned()],
                 key_sort_directions: vec![],
+                key_collations: vec![],
                 where_clause: None,
                 is_unique: false,
             }],
@@ -11673,6 +11722,7 @@ mod tests {
                     columns: vec!["a".to_owned()],
                     key_expressions: vec!["a".to_owned()],
                     key_sort_directions: vec![],
+                    key_collations: vec![],
                     where_clause: None,
                     is_unique: false,
                 }],
@@ -23,22 +23,22 @@ use std::sync::{Arc, LazyLock, Mutex};
 use std::time::{Duration, Instant};
 
 use fsqlite_btree::{
-    BtCursor, BtreeCursorOps, BtreePageHeader, BtreePageType, MemPageStore, PageReader, PageWriter,
-    SeekResult, header_offset_for_page,
+    BtCursor, BtreeCursorOps, BtreePageHeader, BtreePageType, IndexKeyCollation, MemPageStore,
+    PageReader, PageWriter, SeekResult, header_offset_for_page,
 };
 use fsqlite_error::{ErrorCode, FrankenError, Result};
 use fsqlite_func::collation::CollationRegistry;
 use fsqlite_func::vtab::ColumnContext;
 use fsqlite_func::{ErasedAggregateFunction, ErasedWindowFunction, FunctionRegistry};
 use fsqlite_mvcc::{
-    CommitIndex, CommitLog, ConcurrentRegistry, InProcessPageLockTable, MvccError,
-    SharedConcurrentHandle, TimeTravelSnapshot, TimeTravelTarget, VersionStore,
-    concurrent_free_page, concurrent_page_is_freed, concurrent_page_state, concurrent_read_page,
+    CommitIndex, CommitLog, InProcessPageLockTable, MvccError, SharedConcurrentHandle,
+    TimeTravelSnapshot, TimeTravelTarget, VersionStore, concurrent_free_page,
+    concurrent_page_is_freed, concurrent_page_state, concurrent_read_page,
     concurrent_restore_page_state, concurrent_track_write_conflict_page, concurrent_write_page,
     create_time_travel_snapshot,
 };
 use fsqlite_pager::TransactionHandle;
-use fsqlite_types::cx::Cx;
+use fsqlite_types::cx::{CancelReason, Cx};
 use fsqlite_types::opcode::{IndexCursorMeta, Opcode, P4, VdbeOp};
 use fsqlite_types::record::{parse_record, serialize_record};
 use fsqlite_types::value::SqliteValue;
@@ -56,13 +56,23 @@ const OPFLAG_ISUPDATE: u16 = 0x10;
 
 #[inline]
 fn observe_execution_cancellation(cx: &Cx) -> Result<()> {
-    cx.checkpoint().map_err(|_| FrankenError::Abort)
+    cx.checkpoint()
+        .map_err(|_| execution_cancellation_error(cx))
+}
+
+/// Interrupts and elapsed deadlines surface as `SQLITE_INTERRUPT`; any other
+/// cancellation aborts the statement.
+fn execution_cancellation_error(cx: &Cx) -> FrankenError {
+    match cx.cancel_reason() {
+        Some(CancelReason::UserInterrupt | CancelReason::Timeout) => FrankenError::Interrupt,
+        _ => FrankenError::Abort,
+    }
 }
 
 #[inline]
 fn vtab_exec_outcome(opcode: &str, err: FrankenError) -> Result<ExecOutcome> {
-    if matches!(err, FrankenError::Abort) {
-        return Err(FrankenError::Abort);
+    if matches!(err, FrankenError::Abort | FrankenError::Interrupt) {
+        return Err(err);
     }
     Ok(ExecOutcome::Error {
         code: 1,
@@ -767,7 +777,9 @@ struct ConcurrentContext {
 /// (bd-kivg / 5E.2).
 #[derive(Clone)]
 struct SharedTxnPageIo {
-    txn: Rc<RefCell<Box<dyn TransactionHandle>>>,
+    /// `None` while a suspended statement has handed the transaction back
+    /// to its owner (see [`VdbeEngine::detach_transaction`]).
+    txn: Rc<RefCell<Option<Box<dyn TransactionHandle>>>>,
     /// MVCC concurrent context (bd-kivg / 5E.2). When present, enables
     /// page-level locking for write operations.
     concurrent: Option<ConcurrentContext>,
@@ -785,7 +797,7 @@ impl std::fmt::Debug for SharedTxnPageIo {
 impl SharedTxnPageIo {
     fn new(txn: Box<dyn TransactionHandle>) -> Self {
         Self {
-            txn: Rc::new(RefCell::new(txn)),
+            txn: Rc::new(RefCell::new(Some(txn))),
             concurrent: None,
         }
     }
@@ -800,7 +812,7 @@ impl SharedTxnPageIo {
         busy_timeout_ms: u64,
     ) -> Self {
         Self {
-            txn: Rc::new(RefCell::new(txn)),
+            txn: Rc::new(RefCell::new(Some(txn))),
             concurrent: Some(ConcurrentContext {
                 session_id,
                 handle,
@@ -811,9 +823,24 @@ impl SharedTxnPageIo {
         }
     }
 
-    /// Unwrap back to the owned transaction handle.
+    fn detached_error() -> FrankenError {
+        FrankenError::Internal("pager transaction is detached from the statement".to_owned())
+    }
+
+    /// Run `f` against the lent transaction.
+    fn with_txn_mut<T>(
+        &self,
+        f: impl FnOnce(&mut dyn TransactionHandle) -> Result<T>,
+    ) -> Result<T> {
+        match self.txn.borrow_mut().as_mut() {
+            Some(txn) => f(txn.as_mut()),
+            None => Err(Self::detached_error()),
+        }
+    }
+
+    /// Unwrap back to the owned transaction handle, if still attached.
     /// Returns an error if other Rc clones still exist.
-    fn into_inner(self) -> Result<Box<dyn TransactionHandle>> {
+    fn into_inner(self) -> Result<Option<Box<dyn TransactionHandle>>> {
         match Rc::try_unwrap(self.txn) {
             Ok(cell) => Ok(cell.into_inner()),
             Err(rc) => Err(FrankenError::Internal(format!(
@@ -1046,7 +1073,13 @@ impl PageReader for SharedTxnPageIo {
             );
         }
 
-        let page = self.txn.borrow().get_page(cx, page_no)?.into_vec();
+        let page = self
+            .txn
+            .borrow()
+            .as_ref()
+            .ok_or_else(Self::detached_error)?
+            .get_page(cx, page_no)?
+            .into_vec();
         Ok(page)
     }
 
@@ -1186,7 +1219,7 @@ impl PageWriter for SharedTxnPageIo {
             }
         }
         // Persist to the underlying transaction.
-        let write_result = self.txn.borrow_mut().write_page(cx, page_no, data);
+        let write_result = self.with_txn_mut(|txn| txn.write_page(cx, page_no, data));
         if let Err(write_error) = write_result {
             if let (Some(ctx), Some(prior_page_state)) =
                 (&self.concurrent, prior_page_state.as_ref())
@@ -1220,7 +1253,7 @@ impl PageWriter for SharedTxnPageIo {
         if let Some(ctx) = &self.concurrent {
             track_concurrent_conflict_only_page(cx, ctx, PageNumber::ONE, "allocate_page")?;
         }
-        let allocate_result = self.txn.borrow_mut().allocate_page(cx);
+        let allocate_result = self.with_txn_mut(|txn| txn.allocate_page(cx));
         if let Err(allocate_error) = &allocate_result {
             if let (Some(ctx), Some(page_one_state)) = (&self.concurrent, page_one_state.as_ref()) {
                 let mut handle = ctx.handle.lock();
@@ -1393,7 +1426,7 @@ impl PageWriter for SharedTxnPageIo {
                 return Err(error);
             }
         }
-        let free_result = self.txn.borrow_mut().free_page(cx, page_no);
+        let free_result = self.with_txn_mut(|txn| txn.free_page(cx, page_no));
         if let Err(free_error) = free_result {
             if let (Some(ctx), Some(prior_page_state)) =
                 (&self.concurrent, prior_page_state.as_ref())
@@ -1432,7 +1465,9 @@ impl PageWriter for SharedTxnPageIo {
             ctx.handle.lock().record_write_witness(key);
             return;
         }
-        self.txn.borrow_mut().record_write_witness(cx, key);
+        if let Some(txn) = self.txn.borrow_mut().as_mut() {
+            txn.record_write_witness(cx, key);
+        }
     }
 }
 
@@ -3033,6 +3068,90 @@ pub enum ExecOutcome {
     Done,
     /// Program halted with an error code and message.
     Error { code: i32, message: String },
+    /// Program produced a result row and suspended (row-yield mode only).
+    ///
+    /// The row is available via [`VdbeEngine::take_results`]; call
+    /// [`VdbeEngine::resume`] to continue execution after it.
+    Row,
+}
+
+/// Kind of row change recorded for `sqlite3_update_hook`-style callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RowChangeKind {
+    /// A row was inserted.
+    Insert,
+    /// A row was updated in place (or moved to a new rowid by UPDATE).
+    Update,
+    /// A row was deleted.
+    Delete,
+}
+
+impl RowChangeKind {
+    /// The C API operation code (`SQLITE_INSERT`, `SQLITE_UPDATE`,
+    /// `SQLITE_DELETE`).
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        match self {
+            Self::Insert => 18,
+            Self::Update => 23,
+            Self::Delete => 9,
+        }
+    }
+}
+
+/// A row change applied to a named table during execution.
+///
+/// Only changes made by `Insert`/`Delete` opcodes that carry a table name
+/// are recorded. Rows removed by `OR REPLACE` conflict resolution are not
+/// reported, matching C SQLite's update hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowChange {
+    /// Operation applied to the row.
+    pub kind: RowChangeKind,
+    /// Name of the table the row belongs to.
+    pub table: String,
+    /// Rowid of the affected row (the new rowid for updates).
+    pub rowid: i64,
+}
+
+/// A row change that is about to be applied, as seen by a pre-update hook.
+///
+/// Column vectors are in table column order with any `INTEGER PRIMARY KEY`
+/// column filled in from the rowid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreUpdateEvent {
+    /// Operation about to be applied.
+    pub kind: RowChangeKind,
+    /// Name of the table the row belongs to.
+    pub table: String,
+    /// Trigger nesting depth (0 for a top-level statement).
+    pub depth: i32,
+    /// Rowid before the change (`None` for inserts).
+    pub old_rowid: Option<i64>,
+    /// Rowid after the change (`None` for deletes).
+    pub new_rowid: Option<i64>,
+    /// Column values before the change (`None` for inserts).
+    pub old_values: Option<Vec<SqliteValue>>,
+    /// Column values after the change (`None` for deletes).
+    pub new_values: Option<Vec<SqliteValue>>,
+}
+
+/// sqlite3_preupdate_hook-style callback.
+pub type PreUpdateHook = Arc<dyn Fn(&PreUpdateEvent) + Send + Sync + 'static>;
+
+/// sqlite3_progress_handler-style callback. Returning `true` interrupts the
+/// running statement.
+pub type ProgressHandler = Arc<dyn Fn() -> bool + Send + Sync + 'static>;
+
+/// Saved interpreter position for a program suspended at `ResultRow`.
+#[derive(Debug, Clone, Default)]
+struct ExecResumePoint {
+    /// Instruction to continue from.
+    pc: usize,
+    /// Snapshot of the `Once` bitmap at the suspension point.
+    once_bits: Vec<u64>,
+    /// Opcodes executed so far (keeps cancellation checkpoints periodic).
+    opcode_count: u64,
 }
 
 /// The VDBE bytecode interpreter.
@@ -3048,8 +3167,13 @@ pub struct VdbeEngine {
     bindings: Vec<SqliteValue>,
     /// Root capability context for execution-owned cursor and virtual-table work.
     execution_cx: Cx,
+    /// When this engine was created; the execution budget's deadline counts
+    /// from here.
+    execution_started: Instant,
     /// Page size for this database (bd-zjisk.2).
     page_size: PageSize,
+    /// Reserved bytes at the end of each page (header offset 20).
+    reserved_bytes: u8,
     /// Whether opcode-level tracing is enabled.
     trace_opcodes: bool,
     /// Result rows accumulated during execution.
@@ -3134,6 +3258,8 @@ pub struct VdbeEngine {
     column_defaults_by_root_page: HashMap<i32, Vec<Option<SqliteValue>>>,
     /// Per-index descending flags keyed by index root page number.
     index_desc_flags_by_root_page: HashMap<i32, Vec<bool>>,
+    /// Per-index key collation names by index root page.
+    index_collations_by_root_page: HashMap<i32, Vec<Option<String>>>,
     /// Mapping from cursor_id to root_page for default value lookup.
     cursor_root_pages: HashMap<i32, i32>,
     /// Open virtual table cursors keyed by cursor number.
@@ -3166,6 +3292,23 @@ pub struct VdbeEngine {
     /// Each entry is a fixed-size bit array used for early rejection
     /// during index lookups.
     bloom_filters: HashMap<i32, Vec<u64>>,
+    /// When set, `ResultRow` suspends execution and returns
+    /// [`ExecOutcome::Row`] instead of buffering every row.
+    yield_result_rows: bool,
+    /// Position to continue from after a row-yield suspension.
+    resume_point: Option<ExecResumePoint>,
+    /// Row changes recorded for update hooks; `None` when recording is off.
+    row_changes: Option<Vec<RowChange>>,
+    /// Table names of `OpenWrite` cursors, kept while recording row changes.
+    cursor_table_names: HashMap<i32, String>,
+    /// Callback run before each named-table row change.
+    preupdate_hook: Option<PreUpdateHook>,
+    /// Trigger nesting depth reported to the pre-update hook.
+    preupdate_depth: i32,
+    /// Progress callback and the number of opcodes between invocations.
+    progress_handler: Option<(u64, ProgressHandler)>,
+    /// Opcodes executed since the progress callback last ran.
+    progress_ops: u64,
 }
 
 /// Time-travel target marker stored on cursors opened with
@@ -3461,7 +3604,9 @@ impl VdbeEngine {
             registers: smallvec::smallvec![SqliteValue::Null; count as usize],
             bindings: Vec::new(),
             execution_cx: execution_cx.create_child(),
+            execution_started: Instant::now(),
             page_size,
+            reserved_bytes: 0,
             trace_opcodes: opcode_trace_enabled(),
             results: Vec::with_capacity(64),
             cursors: SwissIndex::new(),
@@ -3494,6 +3639,7 @@ impl VdbeEngine {
             sequence_counters: HashMap::new(),
             column_defaults_by_root_page: HashMap::new(),
             index_desc_flags_by_root_page: HashMap::new(),
+            index_collations_by_root_page: HashMap::new(),
             cursor_root_pages: HashMap::new(),
             vtab_cursors: SwissIndex::new(),
             vtab_instances: SwissIndex::new(),
@@ -3505,6 +3651,14 @@ impl VdbeEngine {
             window_contexts: SwissIndex::new(),
             register_subtypes: HashMap::new(),
             bloom_filters: HashMap::new(),
+            yield_result_rows: false,
+            resume_point: None,
+            row_changes: None,
+            cursor_table_names: HashMap::new(),
+            preupdate_hook: None,
+            progress_handler: None,
+            progress_ops: 0,
+            preupdate_depth: 0,
         }
     }
 
@@ -3552,12 +3706,23 @@ impl VdbeEngine {
     /// Attach the root capability context for this execution.
     pub fn set_execution_cx(&mut self, cx: Cx) {
         self.execution_cx = cx;
+        self.execution_started = Instant::now();
     }
 
     fn derive_execution_cx(&self) -> Cx {
         self.execution_cx.create_child()
     }
 
+    /// Cancel the execution context once its budget deadline has elapsed so
+    /// every later checkpoint fails with `SQLITE_INTERRUPT`.
+    fn observe_execution_deadline(&self) {
+        if let Some(deadline) = self.execution_cx.budget().deadline
+            && self.execution_started.elapsed() >= deadline
+        {
+            self.execution_cx.cancel_with_reason(CancelReason::Timeout);
+        }
+    }
+
     fn index_desc_flags_for_root(&self, root_page: i32) -> Vec<bool> {
         self.index_desc_flags_by_root_page
             .get(&root_page)
@@ -3565,6 +3730,49 @@ impl VdbeEngine {
             .unwrap_or_default()
     }
 
+    fn index_collations_for_root(&self, root_page: i32) -> Vec<Option<String>> {
+        self.index_collations_by_root_page
+            .get(&root_page)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Resolve an index's per-key collation names against the registry.
+    ///
+    /// BINARY resolves to `None` (plain byte compare). A name that is not
+    /// registered is an error: the index was ordered by that collation, so
+    /// reading it with any other comparator would return wrong rows.
+    fn index_key_collations_for_root(
+        &self,
+        root_page: i32,
+    ) -> Result<Vec<Option<IndexKeyCollation>>> {
+        let Some(names) = self.index_collations_by_root_page.get(&root_page) else {
+            return Ok(Vec::new());
+        };
+        let registry = self
+            .collation_registry
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        names
+            .iter()
+            .map(|name| {
+                let Some(name) = name.as_deref() else {
+                    return Ok(None);
+                };
+                if name.eq_ignore_ascii_case("BINARY") {
+                    return Ok(None);
+                }
+                let func = registry.find(name).ok_or_else(|| {
+                    FrankenError::function_error(format!("no such collation sequence: {name}"))
+                })?;
+                Ok(Some(IndexKeyCollation::new(
+                    name,
+                    move |l: &[u8], r: &[u8]| func.compare(l, r),
+                )))
+            })
+            .collect()
+    }
+
     /// Handles REPLACE conflict resolution natively (bd-2yqp6.x).
     /// Deletes the conflicting row from the table AND from all associated indexes.
     fn native_replace_row(&mut self, tbl_cursor_id: i32, conflict_rowid: i64) -> Result<()> {
@@ -3626,6 +3834,166 @@ impl VdbeEngine {
         Ok(())
     }
 
+    /// Append a row change when recording is enabled.
+    ///
+    /// The table name comes from the opcode's `P4::Table`, falling back to
+    /// the name the cursor was opened with. Changes on unnamed cursors
+    /// (internal rewrites) are not recorded.
+    fn record_row_change(&mut self, kind: RowChangeKind, cursor_id: i32, p4: &P4, rowid: i64) {
+        if self.row_changes.is_none() {
+            return;
+        }
+        let table = self.cursor_table_name(cursor_id, p4);
+        if let (Some(row_changes), Some(table)) = (self.row_changes.as_mut(), table) {
+            row_changes.push(RowChange { kind, table, rowid });
+        }
+    }
+
+    fn cursor_table_name(&self, cursor_id: i32, p4: &P4) -> Option<String> {
+        match p4 {
+            P4::Table(name) => Some(name.clone()),
+            _ => self.cursor_table_names.get(&cursor_id).cloned(),
+        }
+    }
+
+    /// Fill in the `INTEGER PRIMARY KEY` column of a decoded table record.
+    fn expand_rowid_alias(
+        &self,
+        cursor_id: i32,
+        rowid: i64,
+        mut values: Vec<SqliteValue>,
+    ) -> Vec<SqliteValue> {
+        let root_page = self
+            .cursor_root_pages
+            .get(&cursor_id)
+            .copied()
+            .or_else(|| self.cursors.get(&cursor_id).map(|c| c.root_page));
+        let Some(root_page) = root_page else {
+            return values;
+        };
+        let Some(&ipk) = self.rowid_alias_col_by_root_page.get(&root_page) else {
+            return values;
+        };
+        let table_cols = self
+            .table_column_count_by_root_page
+            .get(&root_page)
+            .copied();
+        if payload_includes_rowid_alias(&values, rowid, ipk, table_cols) {
+            values[ipk] = SqliteValue::Integer(rowid);
+        } else if ipk <= values.len() {
+            values.insert(ipk, SqliteValue::Integer(rowid));
+        }
+        values
+    }
+
+    /// Build the pre-update event for an `Insert` opcode, if a hook is set.
+    fn insert_preupdate_event(
+        &self,
+        cursor_id: i32,
+        p4: &P4,
+        rowid: i64,
+        record: &SqliteValue,
+    ) -> Result<Option<PreUpdateEvent>> {
+        if self.preupdate_hook.is_none() {
+            return Ok(None);
+        }
+        let Some(table) = self.cursor_table_name(cursor_id, p4) else {
+            return Ok(None);
+        };
+        let new_values = if self.storage_cursors.contains_key(&cursor_id) {
+            self.expand_rowid_alias(cursor_id, rowid, decode_record(record)?)
+        } else {
+            decode_record(record)?
+        };
+        Ok(Some(PreUpdateEvent {
+            kind: RowChangeKind::Insert,
+            table,
+            depth: self.preupdate_depth,
+            old_rowid: None,
+            new_rowid: Some(rowid),
+            old_values: None,
+            new_values: Some(new_values),
+        }))
+    }
+
+    /// Rowid and values of the row cursor `cursor_id` points at, if any.
+    fn preupdate_old_row(&mut self, cursor_id: i32) -> Result<Option<(i64, Vec<SqliteValue>)>> {
+        if let Some(sc) = self.storage_cursors.get_mut(&cursor_id) {
+            if !sc.writable || sc.cursor.eof() {
+                return Ok(None);
+            }
+            let rowid = sc.cursor.rowid(&sc.cx)?;
+            let payload = sc.cursor.payload(&sc.cx)?;
+            let values = parse_record(&payload)
+                .ok_or_else(|| FrankenError::internal("malformed SQLite record blob"))?;
+            return Ok(Some((
+                rowid,
+                self.expand_rowid_alias(cursor_id, rowid, values),
+            )));
+        }
+        Ok(self.cursors.get(&cursor_id).and_then(|cursor| {
+            let pos = cursor.position?;
+            let table = self.db.as_ref()?.get_table(cursor.root_page)?;
+            table
+                .rows
+                .get(pos)
+                .map(|row| (row.rowid, row.values.clone()))
+        }))
+    }
+
+    /// Run the pre-update hook for a plain `Delete` of the current row.
+    fn fire_delete_preupdate_hook(&mut self, cursor_id: i32, p4: &P4) -> Result<()> {
+        let Some(hook) = self.preupdate_hook.clone() else {
+            return Ok(());
+        };
+        let Some(table) = self.cursor_table_name(cursor_id, p4) else {
+            return Ok(());
+        };
+        let Some((old_rowid, old_values)) = self.preupdate_old_row(cursor_id)? else {
+            return Ok(());
+        };
+        hook(&PreUpdateEvent {
+            kind: RowChangeKind::Delete,
+            table,
+            depth: self.preupdate_depth,
+            old_rowid: Some(old_rowid),
+            new_rowid: None,
+            old_values: Some(old_values),
+            new_values: None,
+        });
+        Ok(())
+    }
+
+    /// Run the pre-update hook for an UPDATE from its `Delete`, while the old
+    /// row is still in the table, as SQLite does. Registers `record_reg` and
+    /// `rowid_reg` hold the rewritten record and its rowid.
+    fn fire_update_preupdate_hook(
+        &mut self,
+        cursor_id: i32,
+        p4: &P4,
+        record_reg: i32,
+        rowid_reg: i32,
+    ) -> Result<()> {
+        let Some(hook) = self.preupdate_hook.clone() else {
+            return Ok(());
+        };
+        let Some((old_rowid, old_values)) = self.preupdate_old_row(cursor_id)? else {
+            return Ok(());
+        };
+        let new_rowid = self.get_reg(rowid_reg).to_integer();
+        let record = self.get_reg(record_reg).clone();
+        let Some(event) = self.insert_preupdate_event(cursor_id, p4, new_rowid, &record)? else {
+            return Ok(());
+        };
+        hook(&PreUpdateEvent {
+            kind: RowChangeKind::Update,
+            old_rowid: Some(old_rowid),
+            old_values: Some(old_values),
+            ..event
+        });
+        Ok(())
+    }
+
     fn rollback_pending_insert_after_index_conflict(&mut self) -> Result<()> {
         let entries = std::mem::take(&mut self.pending_idx_entries);
         for (idx_cid, idx_key) in entries {
@@ -3660,6 +4028,14 @@ impl VdbeEngine {
         self.changes = self.changes.checked_sub(1).ok_or_else(|| {
             FrankenError::internal("secondary-index rollback underflowed change counter")
         })?;
+        if let Some(row_changes) = self.row_changes.as_mut() {
+            if row_changes
+                .last()
+                .is_some_and(|change| change.rowid == rollback.rowid)
+            {
+                row_changes.pop();
+            }
+        }
         if let Some(update_restore) = rollback.update_restore {
             self.restore_pending_update_after_conflict(update_restore)?;
         }
@@ -3856,11 +4232,31 @@ impl VdbeEngine {
         // Drop all storage cursors first to release Rc references.
         self.storage_cursors.clear();
         match self.txn_page_io.take() {
-            Some(txn_page_io) => Ok(Some(txn_page_io.into_inner()?)),
+            Some(txn_page_io) => txn_page_io.into_inner(),
             None => Ok(None),
         }
     }
 
+    /// Hand the lent transaction back without closing storage cursors.
+    ///
+    /// Used by a statement suspended at `ResultRow` that reads through its
+    /// connection's open transaction: the connection keeps using the handle
+    /// between steps, and [`Self::attach_transaction`] lends it back before
+    /// the next [`Self::resume`]. Cursor positions survive the round trip.
+    pub fn detach_transaction(&mut self) -> Option<Box<dyn TransactionHandle>> {
+        self.txn_page_io
+            .as_ref()
+            .and_then(|page_io| page_io.txn.borrow_mut().take())
+    }
+
+    /// Re-lend a transaction taken by [`Self::detach_transaction`].
+    pub fn attach_transaction(&mut self, txn: Box<dyn TransactionHandle>) {
+        match self.txn_page_io.as_ref() {
+            Some(page_io) => *page_io.txn.borrow_mut() = Some(txn),
+            None => self.set_transaction(txn),
+        }
+    }
+
     /// Attach a function registry for `Function`/`PureFunc` opcode dispatch.
     pub fn set_function_registry(&mut self, registry: Arc<FunctionRegistry>) {
         self.func_registry = Some(registry);
@@ -3920,17 +4316,97 @@ impl VdbeEngine {
         self.index_desc_flags_by_root_page = map;
     }
 
+    /// Provide per-index key collation names keyed by index root page.
+    ///
+    /// `None` entries (and missing maps) compare with BINARY.
+    pub fn set_index_collations_by_root_page(&mut self, map: HashMap<i32, Vec<Option<String>>>) {
+        self.index_collations_by_root_page = map;
+    }
+
+    /// Suspend at every `ResultRow` instead of buffering the full result set.
+    ///
+    /// With row-yield enabled, [`Self::execute`] and [`Self::resume`] return
+    /// [`ExecOutcome::Row`] after each produced row, mirroring
+    /// `sqlite3_step()` returning `SQLITE_ROW`.
+    pub fn set_yield_result_rows(&mut self, enabled: bool) {
+        self.yield_result_rows = enabled;
+    }
+
+    /// Record every table row change made by `Insert`/`Delete` opcodes.
+    ///
+    /// Recorded changes are retrieved with [`Self::take_row_changes`]. The
+    /// log is cleared at the start of each [`Self::execute`].
+    pub fn set_record_row_changes(&mut self, enabled: bool) {
+        self.row_changes = enabled.then(Vec::new);
+    }
+
+    /// Take the row changes recorded since the last call or execution.
+    pub fn take_row_changes(&mut self) -> Vec<RowChange> {
+        self.row_changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Install a callback run before each named-table row change.
+    ///
+    /// `depth` is reported as [`PreUpdateEvent::depth`]; callers executing
+    /// trigger bodies pass the current trigger nesting level.
+    pub fn set_preupdate_hook(&mut self, hook: Option<PreUpdateHook>, depth: i32) {
+        self.preupdate_hook = hook;
+        self.preupdate_depth = depth;
+    }
+
+    /// Install a callback run every `n_ops` opcodes.
+    ///
+    /// The count restarts with each statement. When the callback returns
+    /// `true` the program halts with an `SQLITE_INTERRUPT` error outcome. An
+    /// `n_ops` of zero or a `None` handler removes the callback.
+    pub fn set_progress_handler(&mut self, n_ops: u32, handler: Option<ProgressHandler>) {
+        self.progress_handler = handler
+            .filter(|_| n_ops > 0)
+            .map(|handler| (u64::from(n_ops), handler));
+        self.progress_ops = 0;
+    }
+
+    /// Set the per-page reserved bytes so storage cursors keep cell content
+    /// clear of the reserved tail (for example the encryption nonce/tag).
+    pub fn set_reserved_bytes(&mut self, reserved_bytes: u8) {
+        self.reserved_bytes = reserved_bytes;
+    }
+
+    /// Usable bytes per page for B-tree content.
+    fn usable_size(&self) -> u32 {
+        self.page_size.usable(self.reserved_bytes)
+    }
+
+    /// Returns `true` if the engine is suspended after a yielded row.
+    #[must_use]
+    pub fn is_suspended(&self) -> bool {
+        self.resume_point.is_some()
+    }
+
+    /// Continue a program suspended at `ResultRow`.
+    ///
+    /// `program` must be the same program passed to [`Self::execute`].
+    /// Returns `Ok(ExecOutcome::Done)` immediately if nothing is suspended.
+    pub fn resume(&mut self, program: &VdbeProgram) -> Result<ExecOutcome> {
+        let Some(resume_point) = self.resume_point.take() else {
+            return Ok(ExecOutcome::Done);
+        };
+        self.results.clear();
+        // Like `sqlite3_step`, each resumed step observes a pending interrupt
+        // even when too few opcodes run to reach a periodic checkpoint.
+        self.observe_execution_deadline();
+        observe_execution_cancellation(&self.execution_cx)?;
+        self.run_program(program, Some(resume_point))
+    }
+
     /// Execute a VDBE program to completion.
     ///
     /// Returns `Ok(ExecOutcome::Done)` on normal halt, or an error if the
-    /// program encounters a fatal condition.
-    #[allow(
-        clippy::too_many_lines,
-        clippy::match_same_arms,
-        clippy::cast_sign_loss,
-        clippy::cast_possible_truncation,
-        clippy::cast_possible_wrap
-    )]
+    /// program encounters a fatal condition. In row-yield mode (see
+    /// [`Self::set_yield_result_rows`]) this returns after the first row.
     pub fn execute(&mut self, program: &VdbeProgram) -> Result<ExecOutcome> {
         self.aggregates.clear();
         self.results.clear();
@@ -3952,9 +4428,14 @@ impl VdbeEngine {
         self.window_contexts.clear();
         self.register_subtypes.clear();
         self.bloom_filters.clear();
+        self.resume_point = None;
+        self.cursor_table_names.clear();
+        self.progress_ops = 0;
+        if let Some(row_changes) = self.row_changes.as_mut() {
+            row_changes.clear();
+        }
 
-        let ops = program.ops();
-        if ops.is_empty() {
+        if program.ops().is_empty() {
             return Ok(ExecOutcome::Done);
         }
 
@@ -3963,6 +4444,24 @@ impl VdbeEngine {
             self.table_index_meta.insert(*table_cursor, indexes.clone());
         }
 
+        self.run_program(program, None)
+    }
+
+    /// Fetch-execute loop shared by [`Self::execute`] and [`Self::resume`].
+    #[allow(
+        clippy::too_many_lines,
+        clippy::match_same_arms,
+        clippy::cast_sign_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_possible_wrap
+    )]
+    fn run_program(
+        &mut self,
+        program: &VdbeProgram,
+        resume_point: Option<ExecResumePoint>,
+    ) -> Result<ExecOutcome> {
+        let ops = program.ops();
+        let resuming = resume_point.is_some();
         let statement_debug_enabled =
             tracing::enabled!(target: "fsqlite_vdbe::statement", tracing::Level::DEBUG);
         let jit_debug_enabled =
@@ -3985,11 +4484,12 @@ impl VdbeEngine {
             0
         };
         let start_time = Instant::now();
-        let mut opcode_count: u64 = 0;
+        let mut opcode_count: u64 = resume_point.as_ref().map_or(0, |point| point.opcode_count);
+        let opcode_count_at_entry = opcode_count;
         let mut local_opcode_execution_totals = [0_u64; Opcode::COUNT + 1];
         let result_rows_before = 0;
 
-        if statement_debug_enabled {
+        if statement_debug_enabled && !resuming {
             tracing::debug!(
                 target: "fsqlite_vdbe::statement",
                 program_id,
@@ -3998,7 +4498,12 @@ impl VdbeEngine {
             );
         }
 
-        match maybe_trigger_jit(program) {
+        let jit_decision = if resuming {
+            JitDecision::Disabled
+        } else {
+            maybe_trigger_jit(program)
+        };
+        match jit_decision {
             JitDecision::Disabled => {}
             JitDecision::Warming {
                 plan_hash,
@@ -4093,7 +4598,7 @@ impl VdbeEngine {
             }
         }
 
-        let mut pc: usize = 0;
+        let mut pc: usize = resume_point.as_ref().map_or(0, |point| point.pc);
         // "once" flags: one bit per instruction address (stack-backed for small programs).
         let n_ops = ops.len();
         let mut once_stack = [0u64; 4]; // covers up to 256 opcodes on the stack
@@ -4102,6 +4607,15 @@ impl VdbeEngine {
         } else {
             Vec::new()
         };
+        if let Some(point) = resume_point {
+            let saved = if n_ops > 256 {
+                &mut once_heap[..]
+            } else {
+                &mut once_stack[..]
+            };
+            let len = saved.len().min(point.once_bits.len());
+            saved[..len].copy_from_slice(&point.once_bits[..len]);
+        }
         let once_bits: &mut [u64] = if n_ops > 256 {
             &mut once_heap
         } else {
@@ -4113,9 +4627,23 @@ impl VdbeEngine {
                 break ExecOutcome::Done;
             }
             if opcode_count & (VDBE_EXECUTION_CHECKPOINT_INTERVAL - 1) == 0 {
+                self.observe_execution_deadline();
                 observe_execution_cancellation(&self.execution_cx)?;
             }
 
+            if let Some((interval, handler)) = &self.progress_handler {
+                self.progress_ops += 1;
+                if self.progress_ops >= *interval {
+                    self.progress_ops = 0;
+                    if handler() {
+                        break ExecOutcome::Error {
+                            code: ErrorCode::Interrupt as i32,
+                            message: "interrupted".to_owned(),
+                        };
+                    }
+                }
+            }
+
             let op = &ops[pc];
             opcode_count += 1;
             if collect_vdbe_metrics {
@@ -4276,14 +4804,18 @@ impl VdbeEngine {
                     } else {
                         self.index_desc_flags_for_root(root_page)
                     };
-                    let page_size_u32 = self.page_size.get();
-                    let new_cursor = BtCursor::new_with_index_desc(
+                    let usable_size = self.usable_size();
+                    let mut new_cursor = BtCursor::new_with_index_desc(
                         tt_page_io,
                         root_pgno,
-                        page_size_u32,
+                        usable_size,
                         is_table_btree,
                         index_desc_flags,
                     );
+                    if !is_table_btree {
+                        new_cursor
+                            .set_index_collations(self.index_key_collations_for_root(root_page)?);
+                    }
                     self.storage_cursors.insert(
                         cursor_id,
                         StorageCursor {
@@ -4434,6 +4966,14 @@ impl VdbeEngine {
                     }
                     self.results.push(row);
                     pc += 1;
+                    if self.yield_result_rows {
+                        self.resume_point = Some(ExecResumePoint {
+                            pc,
+                            once_bits: once_bits.to_vec(),
+                            opcode_count,
+                        });
+                        break ExecOutcome::Row;
+                    }
                 }
 
                 // ── Arithmetic ──────────────────────────────────────────
@@ -4861,7 +5401,7 @@ impl VdbeEngine {
                     let cursor_id = op.p1;
                     let root_page = op.p2;
                     self.pending_next_after_delete.remove(&cursor_id);
-                    if !self.open_storage_cursor(cursor_id, root_page, false) {
+                    if !self.open_storage_cursor(cursor_id, root_page, false)? {
                         return Err(FrankenError::Internal(format!(
                             "OpenRead failed: could not open storage cursor on root page {root_page}"
                         )));
@@ -4881,13 +5421,20 @@ impl VdbeEngine {
                     let cursor_id = op.p1;
                     let root_page = op.p2;
                     self.pending_next_after_delete.remove(&cursor_id);
-                    if !self.open_storage_cursor(cursor_id, root_page, true) {
+                    if !self.open_storage_cursor(cursor_id, root_page, true)? {
                         return Err(FrankenError::Internal(format!(
                             "OpenWrite failed: could not open storage cursor on root page {root_page}"
                         )));
                     }
                     self.cursor_root_pages.insert(cursor_id, root_page);
                     self.cursors.remove(&cursor_id);
+                    if self.row_changes.is_some() || self.preupdate_hook.is_some() {
+                        if let P4::Table(name) = &op.p4 {
+                            self.cursor_table_names.insert(cursor_id, name.clone());
+                        } else {
+                            self.cursor_table_names.remove(&cursor_id);
+                        }
+                    }
                     pc += 1;
                 }
 
@@ -5875,9 +6422,15 @@ impl VdbeEngine {
                         None
                     };
                     let pk_conflict = ExecOutcome::Error {
-                        code: ErrorCode::Constraint as i32,
+                        code: FrankenError::PrimaryKeyViolation.extended_error_code(),
                         message: "PRIMARY KEY constraint failed".to_owned(),
                     };
+                    // An UPDATE's hook already ran from its `Delete`.
+                    let preupdate = if pending_update_restore.is_some() {
+                        None
+                    } else {
+                        self.insert_preupdate_event(cursor_id, &op.p4, rowid, &record_val)?
+                    };
 
                     // Phase 5B.2 (bd-1yi8): write-through — route ONLY through
                     // StorageCursor when one exists; fall back to MemDatabase
@@ -5895,6 +6448,10 @@ impl VdbeEngine {
                                 // bit above the conflict-mode nibble.
                                 if oe_flag == 5 {
                                     // OE_REPLACE: Delete old, insert new
+                                    fire_preupdate_hook(
+                                        self.preupdate_hook.as_ref(),
+                                        preupdate.as_ref(),
+                                    );
                                     self.native_replace_row(cursor_id, rowid)?;
                                     let sc2 = self.storage_cursors.get_mut(&cursor_id).ok_or_else(
                                         || {
@@ -5920,6 +6477,10 @@ impl VdbeEngine {
                                 }
                             } else {
                                 // No conflict — insert normally
+                                fire_preupdate_hook(
+                                    self.preupdate_hook.as_ref(),
+                                    preupdate.as_ref(),
+                                );
                                 sc.cursor.table_insert(&sc.cx, rowid, blob)?;
                                 invalidate_storage_cursor_row_cache(sc);
                                 actually_inserted = true;
@@ -5960,6 +6521,10 @@ impl VdbeEngine {
                                     5 => {
                                         // OE_REPLACE: Delete conflicting row(s),
                                         // then insert new.
+                                        fire_preupdate_hook(
+                                            self.preupdate_hook.as_ref(),
+                                            preupdate.as_ref(),
+                                        );
                                         if let Some(table) = db.get_table_mut(root) {
                                             for conflict_rid in unique_conflicts {
                                                 // Delete conflicting rows that are not the new rowid
@@ -5985,6 +6550,10 @@ impl VdbeEngine {
                                 }
                             } else {
                                 // No conflict — insert normally
+                                fire_preupdate_hook(
+                                    self.preupdate_hook.as_ref(),
+                                    preupdate.as_ref(),
+                                );
                                 db.upsert_row(root, rowid, values);
                                 actually_inserted = true;
                             }
@@ -5999,6 +6568,12 @@ impl VdbeEngine {
                             self.last_insert_rowid = rowid;
                             self.last_insert_rowid_valid = true;
                         }
+                        let kind = if is_update {
+                            RowChangeKind::Update
+                        } else {
+                            RowChangeKind::Insert
+                        };
+                        self.record_row_change(kind, cursor_id, &op.p4, rowid);
                         self.pending_insert_rollback = Some(PendingInsertRollback {
                             cursor_id,
                             rowid,
@@ -6034,12 +6609,24 @@ impl VdbeEngine {
                     let cursor_id = op.p1;
                     let is_update = (op.p5 & OPFLAG_ISUPDATE) != 0;
                     let mut deleted = false;
+                    let mut deleted_rowid = None;
                     let mut update_restore = None;
+                    let record_delete = !is_update && self.row_changes.is_some();
+                    // P2/P3 of an UPDATE's Delete name the registers holding
+                    // the rewritten record and its rowid.
+                    if !is_update {
+                        self.fire_delete_preupdate_hook(cursor_id, &op.p4)?;
+                    } else if op.p2 > 0 {
+                        self.fire_update_preupdate_hook(cursor_id, &op.p4, op.p2, op.p3)?;
+                    }
                     // Phase 5B.3 (bd-1r0d): write-through — route ONLY through
                     // storage cursor when one exists; fall back to MemDatabase
                     // only for legacy Phase 4 cursors.
                     if let Some(sc) = self.storage_cursors.get_mut(&cursor_id) {
                         if sc.writable && !sc.cursor.eof() {
+                            if record_delete {
+                                deleted_rowid = Some(sc.cursor.rowid(&sc.cx)?);
+                            }
                             if is_update {
                                 update_restore = Some(PendingUpdateRestore::Storage {
                                     cursor_id,
@@ -6073,6 +6660,12 @@ impl VdbeEngine {
                                         values: row.values,
                                     });
                                 }
+                                if record_delete {
+                                    deleted_rowid = db
+                                        .get_table(root)
+                                        .and_then(|table| table.rows.get(pos))
+                                        .map(|row| row.rowid);
+                                }
                                 db.delete_at(root, pos);
                                 deleted = true;
                             }
@@ -6086,6 +6679,9 @@ impl VdbeEngine {
                         if op.p5 & 1 != 0 {
                             self.changes += 1;
                         }
+                        if let Some(rowid) = deleted_rowid {
+                            self.record_row_change(RowChangeKind::Delete, cursor_id, &op.p4, rowid);
+                        }
                         self.pending_next_after_delete.insert(cursor_id);
                     } else if is_update {
                         self.pending_update_restore = None;
@@ -6792,6 +7388,7 @@ impl VdbeEngine {
                         .copied()
                         .unwrap_or_default();
                     let desc_flags = self.index_desc_flags_for_root(root_page);
+                    let index_collations = self.index_collations_for_root(root_page);
                     let collation_registry = Arc::clone(&self.collation_registry);
 
                     // Extract current cursor key as parsed fields.
@@ -6839,7 +7436,7 @@ impl VdbeEngine {
                             &sc.target_vals_buf,
                             n_compare,
                             &desc_flags,
-                            &[], // TODO: Per-index collations are not yet threaded here.
+                            &index_collations,
                             collation_registry.as_ref(),
                         );
 
@@ -6870,12 +7467,13 @@ impl VdbeEngine {
                                 probe_fields.len()
                             };
                             let desc_flags = self.index_desc_flags_for_root(cursor.root_page);
+                            let index_collations = self.index_collations_for_root(cursor.root_page);
                             let cmp = compare_index_prefix_keys(
                                 &row.values,
                                 &probe_fields,
                                 n_compare,
                                 &desc_flags,
-                                &[],
+                                &index_collations,
                                 self.collation_registry.as_ref(),
                             );
                             let condition_met = match op.opcode {
@@ -7233,9 +7831,7 @@ impl VdbeEngine {
                     let func = registry
                         .find_scalar(func_name, arg_count as i32)
                         .ok_or_else(|| {
-                            FrankenError::Internal(format!(
-                                "no such function: {func_name}/{arg_count}",
-                            ))
+                            FrankenError::function_error(format!("no such function: {func_name}"))
                         })?;
 
                     let args = self.collect_reg_range(first_arg_reg, arg_count);
@@ -7790,8 +8386,13 @@ impl VdbeEngine {
         let result_rows = self.results.len() - result_rows_before;
 
         if collect_vdbe_metrics {
-            FSQLITE_VDBE_OPCODES_EXECUTED_TOTAL.fetch_add(opcode_count, AtomicOrdering::Relaxed);
-            FSQLITE_VDBE_STATEMENTS_TOTAL.fetch_add(1, AtomicOrdering::Relaxed);
+            FSQLITE_VDBE_OPCODES_EXECUTED_TOTAL.fetch_add(
+                opcode_count - opcode_count_at_entry,
+                AtomicOrdering::Relaxed,
+            );
+            if outcome != ExecOutcome::Row {
+                FSQLITE_VDBE_STATEMENTS_TOTAL.fetch_add(1, AtomicOrdering::Relaxed);
+            }
             #[allow(clippy::cast_possible_truncation)]
             FSQLITE_VDBE_STATEMENT_DURATION_US_TOTAL
                 .fetch_add(elapsed_us as u64, AtomicOrdering::Relaxed);
@@ -8128,7 +8729,12 @@ impl VdbeEngine {
     }
 
     #[allow(clippy::cast_sign_loss)]
-    fn open_storage_cursor(&mut self, cursor_id: i32, root_page: i32, writable: bool) -> bool {
+    fn open_storage_cursor(
+        &mut self,
+        cursor_id: i32,
+        root_page: i32,
+        writable: bool,
+    ) -> Result<bool> {
         let _page_size_u32 = self.page_size.get();
         // bd-1xrs: storage_cursors_enabled check removed.
         // StorageCursor is now the ONLY cursor path.
@@ -8148,7 +8754,7 @@ impl VdbeEngine {
                 decision_reason = "invalid_page_number",
                 "open_storage_cursor: invalid root page number"
             );
-            return false;
+            return Ok(false);
         };
 
         let has_txn = self.txn_page_io.is_some();
@@ -8180,7 +8786,7 @@ impl VdbeEngine {
                         error = %err,
                         "open_storage_cursor: failed to read root page from pager"
                     );
-                    return false;
+                    return Ok(false);
                 }
             };
             let hdr_offset = header_offset_for_page(root_pgno);
@@ -8220,10 +8826,10 @@ impl VdbeEngine {
                     };
                     (is_table, None)
                 };
-                let cursor = BtCursor::new_with_index_desc(
+                let mut cursor = BtCursor::new_with_index_desc(
                     page_io.clone(),
                     root_pgno,
-                    self.page_size.get(),
+                    self.usable_size(),
                     is_table_btree,
                     if is_table_btree {
                         Vec::new()
@@ -8231,6 +8837,9 @@ impl VdbeEngine {
                         self.index_desc_flags_for_root(root_page)
                     },
                 );
+                if !is_table_btree {
+                    cursor.set_index_collations(self.index_key_collations_for_root(root_page)?);
+                }
                 self.storage_cursors.insert(
                     cursor_id,
                     StorageCursor {
@@ -8257,7 +8866,7 @@ impl VdbeEngine {
                     is_table_btree,
                     "open_storage_cursor: routed through pager transaction"
                 );
-                return true;
+                return Ok(true);
             }
 
             // For writable cursors on truly zeroed pages (e.g., freshly
@@ -8279,9 +8888,9 @@ impl VdbeEngine {
                 page[0] = init_page_type as u8;
                 // Bytes 1-2: first freeblock offset = 0 (none).
                 // Bytes 3-4: cell count = 0.
-                // Bytes 5-6: content area offset = page_size (no cells yet).
+                // Bytes 5-6: content area offset = usable size (no cells yet).
                 #[allow(clippy::cast_possible_truncation)]
-                let content_offset = self.page_size.get() as u16; // self.page_size.get()=4096 fits in u16
+                let content_offset = self.page_size.usable(self.reserved_bytes) as u16; // usable size <= 65536; 65536 encodes as 0
                 page[5..7].copy_from_slice(&content_offset.to_be_bytes());
                 // Byte 7: fragmented free bytes = 0.
 
@@ -8298,12 +8907,12 @@ impl VdbeEngine {
                         error = %err,
                         "open_storage_cursor: failed to initialize writable root page in pager"
                     );
-                    return false;
+                    return Ok(false);
                 }
-                let cursor = BtCursor::new_with_index_desc(
+                let mut cursor = BtCursor::new_with_index_desc(
                     page_io.clone(),
                     root_pgno,
-                    self.page_size.get(),
+                    self.usable_size(),
                     is_table_btree,
                     if is_table_btree {
                         Vec::new()
@@ -8311,6 +8920,9 @@ impl VdbeEngine {
                         self.index_desc_flags_for_root(root_page)
                     },
                 );
+                if !is_table_btree {
+                    cursor.set_index_collations(self.index_key_collations_for_root(root_page)?);
+                }
                 self.storage_cursors.insert(
                     cursor_id,
                     StorageCursor {
@@ -8337,7 +8949,7 @@ impl VdbeEngine {
                     is_table_btree,
                     "open_storage_cursor: initialized empty root page via pager"
                 );
-                return true;
+                return Ok(true);
             }
 
             // If the page is zero/invalid but MemDatabase has this table
@@ -8361,7 +8973,7 @@ impl VdbeEngine {
                     is_zero_page,
                     "open_storage_cursor: refusing on invalid transaction-backed root page"
                 );
-                return false;
+                return Ok(false);
             }
             // else: fall through to MemDatabase path
             tracing::debug!(
@@ -8388,7 +9000,7 @@ impl VdbeEngine {
                 decision_reason = "parity_cert_rejection",
                 "open_storage_cursor: MemPageStore fallback rejected in parity-cert mode"
             );
-            return false;
+            return Ok(false);
         }
 
         // Fallback: build a transient B-tree snapshot (Phase 4 path used by
@@ -8415,6 +9027,9 @@ impl VdbeEngine {
                 self.index_desc_flags_for_root(root_page)
             },
         );
+        if !is_table_btree {
+            cursor.set_index_collations(self.index_key_collations_for_root(root_page)?);
+        }
         // Populate cursor from MemDatabase if available.
         if is_table_btree
             && let Some(table) = self.db.as_ref().and_then(|db| db.get_table(root_page))
@@ -8422,7 +9037,7 @@ impl VdbeEngine {
             for row in &table.rows {
                 let payload = encode_record(&row.values);
                 if cursor.table_insert(&cx, row.rowid, &payload).is_err() {
-                    return false;
+                    return Ok(false);
                 }
             }
         }
@@ -8452,7 +9067,7 @@ impl VdbeEngine {
             is_table_btree,
             "open_storage_cursor: routed through MemPageStore fallback"
         );
-        true
+        Ok(true)
     }
 
     fn trace_opcode(&self, pc: usize, op: &VdbeOp) {
@@ -8778,6 +9393,12 @@ fn record_blob_bytes(val: &SqliteValue) -> &[u8] {
     }
 }
 
+fn fire_preupdate_hook(hook: Option<&PreUpdateHook>, event: Option<&PreUpdateEvent>) {
+    if let (Some(hook), Some(event)) = (hook, event) {
+        hook(event);
+    }
+}
+
 fn decode_record(val: &SqliteValue) -> Result<Vec<SqliteValue>> {
     let SqliteValue::Blob(bytes) = val else {
         return Ok(Vec::new());
@@ -9462,13 +10083,91 @@ mod tests {
         let mut engine =
             VdbeEngine::new_with_execution_cx(program.register_count(), &cx, PageSize::DEFAULT);
         let err = engine.execute(&program).unwrap_err();
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
         assert!(
             engine.results().is_empty(),
             "cancelled execute should not emit rows"
         );
     }
 
+    #[test]
+    fn test_execute_interrupts_when_budget_deadline_elapses() {
+        let mut builder = ProgramBuilder::new();
+        for _ in 0..=VDBE_EXECUTION_CHECKPOINT_INTERVAL {
+            builder.emit_op(Opcode::Noop, 0, 0, 0, P4::None, 0);
+        }
+        builder.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        let program = builder.finish().expect("program should build");
+
+        let cx = Cx::new()
+            .scope_with_budget(fsqlite_types::cx::Budget::INFINITE.with_deadline(Duration::ZERO));
+        cx.transition_to_running();
+
+        let mut engine =
+            VdbeEngine::new_with_execution_cx(program.register_count(), &cx, PageSize::DEFAULT);
+        let err = engine.execute(&program).unwrap_err();
+        assert!(matches!(err, FrankenError::Interrupt));
+        assert_eq!(
+            cx.cancel_reason(),
+            None,
+            "the deadline cancels only this execution, not the caller's context"
+        );
+    }
+
+    #[test]
+    fn test_progress_handler_runs_every_n_ops_and_interrupts() {
+        let mut builder = ProgramBuilder::new();
+        for _ in 0..100 {
+            builder.emit_op(Opcode::Noop, 0, 0, 0, P4::None, 0);
+        }
+        builder.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        let program = builder.finish().expect("program should build");
+
+        let calls = Arc::new(AtomicU64::new(0));
+        let counter = Arc::clone(&calls);
+        let mut engine = VdbeEngine::new(program.register_count());
+        engine.set_progress_handler(
+            10,
+            Some(Arc::new(move || {
+                counter.fetch_add(1, AtomicOrdering::Relaxed);
+                false
+            })),
+        );
+        assert_eq!(engine.execute(&program).unwrap(), ExecOutcome::Done);
+        assert_eq!(calls.load(AtomicOrdering::Relaxed), 10);
+
+        let counter = Arc::clone(&calls);
+        engine.set_progress_handler(
+            10,
+            Some(Arc::new(move || {
+                counter.fetch_add(1, AtomicOrdering::Relaxed) >= 12
+            })),
+        );
+        assert_eq!(
+            engine.execute(&program).unwrap(),
+            ExecOutcome::Error {
+                code: ErrorCode::Interrupt as i32,
+                message: "interrupted".to_owned(),
+            }
+        );
+        assert_eq!(calls.load(AtomicOrdering::Relaxed), 13);
+
+        // Every statement starts counting from zero.
+        calls.store(0, AtomicOrdering::Relaxed);
+        let counter = Arc::clone(&calls);
+        engine.set_progress_handler(
+            60,
+            Some(Arc::new(move || {
+                counter.fetch_add(1, AtomicOrdering::Relaxed);
+                false
+            })),
+        );
+        for _ in 0..3 {
+            assert_eq!(engine.execute(&program).unwrap(), ExecOutcome::Done);
+        }
+        assert_eq!(calls.load(AtomicOrdering::Relaxed), 3);
+    }
+
     #[test]
     fn test_execute_reuse_clears_prior_results() {
         let mut first_builder = ProgramBuilder::new();
@@ -9553,6 +10252,159 @@ mod tests {
         assert_eq!(engine.last_insert_rowid(), None);
     }
 
+    #[test]
+    fn test_record_row_changes_names_table_from_p4() {
+        let mut db = MemDatabase::new();
+        let root = db.create_table(1);
+
+        let mut b = ProgramBuilder::new();
+        let end = b.emit_label();
+        b.emit_jump_to_label(Opcode::Init, 0, 0, end, P4::None, 0);
+        b.emit_op(Opcode::OpenWrite, 0, root, 0, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::Integer, 7, 1, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 42, 2, 0, P4::None, 0);
+        b.emit_op(Opcode::MakeRecord, 2, 1, 3, P4::None, 0);
+        b.emit_op(Opcode::Insert, 0, 3, 1, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::Integer, 8, 1, 0, P4::None, 0);
+        b.emit_op(Opcode::Insert, 0, 3, 1, P4::None, 0);
+        b.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        b.resolve_label(end);
+        let program = b.finish().expect("program should build");
+
+        let mut engine = VdbeEngine::new(program.register_count());
+        engine.set_database(db);
+        engine.set_reject_mem_fallback(false);
+        assert!(engine.take_row_changes().is_empty(), "recording is opt-in");
+        engine.set_record_row_changes(true);
+        assert_eq!(engine.execute(&program).unwrap(), ExecOutcome::Done);
+
+        let changes = engine.take_row_changes();
+        assert_eq!(
+            changes,
+            vec![
+                RowChange {
+                    kind: RowChangeKind::Insert,
+                    table: "t".to_owned(),
+                    rowid: 7,
+                },
+                RowChange {
+                    kind: RowChangeKind::Insert,
+                    table: "t".to_owned(),
+                    rowid: 8,
+                },
+            ],
+            "unnamed Insert falls back to the OpenWrite table name"
+        );
+        assert!(engine.take_row_changes().is_empty());
+        assert_eq!(RowChangeKind::Insert.code(), 18);
+        assert_eq!(RowChangeKind::Update.code(), 23);
+        assert_eq!(RowChangeKind::Delete.code(), 9);
+    }
+
+    #[test]
+    fn test_preupdate_hook_sees_new_row_before_insert() {
+        let mut db = MemDatabase::new();
+        let root = db.create_table(1);
+
+        let mut b = ProgramBuilder::new();
+        let end = b.emit_label();
+        b.emit_jump_to_label(Opcode::Init, 0, 0, end, P4::None, 0);
+        b.emit_op(Opcode::OpenWrite, 0, root, 0, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::Integer, 7, 1, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 42, 2, 0, P4::None, 0);
+        b.emit_op(Opcode::MakeRecord, 2, 1, 3, P4::None, 0);
+        b.emit_op(Opcode::Insert, 0, 3, 1, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        b.resolve_label(end);
+        let program = b.finish().expect("program should build");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let mut engine = VdbeEngine::new(program.register_count());
+        engine.set_database(db);
+        engine.set_reject_mem_fallback(false);
+        engine.set_preupdate_hook(
+            Some(Arc::new(move |event: &PreUpdateEvent| {
+                sink.lock().unwrap().push(event.clone());
+            })),
+            2,
+        );
+        assert_eq!(engine.execute(&program).unwrap(), ExecOutcome::Done);
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![PreUpdateEvent {
+                kind: RowChangeKind::Insert,
+                table: "t".to_owned(),
+                depth: 2,
+                old_rowid: None,
+                new_rowid: Some(7),
+                old_values: None,
+                new_values: Some(vec![SqliteValue::Integer(42)]),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_preupdate_hook_fires_update_from_delete_before_row_is_removed() {
+        let mut db = MemDatabase::new();
+        let root = db.create_table(1);
+
+        let mut b = ProgramBuilder::new();
+        let end = b.emit_label();
+        b.emit_jump_to_label(Opcode::Init, 0, 0, end, P4::None, 0);
+        b.emit_op(Opcode::OpenWrite, 0, root, 0, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::Integer, 7, 1, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 42, 2, 0, P4::None, 0);
+        b.emit_op(Opcode::MakeRecord, 2, 1, 3, P4::None, 0);
+        b.emit_op(Opcode::Insert, 0, 3, 1, P4::Table("t".to_owned()), 0);
+        b.emit_op(Opcode::SeekRowid, 0, 0, 1, P4::None, 0);
+        b.emit_op(Opcode::Integer, 43, 2, 0, P4::None, 0);
+        b.emit_op(Opcode::MakeRecord, 2, 1, 3, P4::None, 0);
+        // An UPDATE's Delete names the new record (P2) and rowid (P3); the
+        // hook must run here, with no Insert following.
+        b.emit_op(
+            Opcode::Delete,
+            0,
+            3,
+            1,
+            P4::Table("t".to_owned()),
+            OPFLAG_ISUPDATE,
+        );
+        b.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        b.resolve_label(end);
+        let program = b.finish().expect("program should build");
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+        let mut engine = VdbeEngine::new(program.register_count());
+        engine.set_database(db);
+        engine.set_reject_mem_fallback(false);
+        engine.set_preupdate_hook(
+            Some(Arc::new(move |event: &PreUpdateEvent| {
+                sink.lock().unwrap().push(event.clone());
+            })),
+            0,
+        );
+        assert_eq!(engine.execute(&program).unwrap(), ExecOutcome::Done);
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1],
+            PreUpdateEvent {
+                kind: RowChangeKind::Update,
+                table: "t".to_owned(),
+                depth: 0,
+                old_rowid: Some(7),
+                new_rowid: Some(7),
+                old_values: Some(vec![SqliteValue::Integer(42)]),
+                new_values: Some(vec![SqliteValue::Integer(43)]),
+            }
+        );
+    }
+
     #[test]
     fn test_execute_reuse_empty_program_clears_prior_statement_state() {
         let mut first_builder = ProgramBuilder::new();
@@ -9630,7 +10482,7 @@ mod tests {
             .execute(&program)
             .expect_err("cancelled execution context should abort before opcode dispatch");
 
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
     }
 
     #[test]
@@ -9664,7 +10516,7 @@ mod tests {
         let err = engine
             .execute(&program)
             .expect_err("cancellation should be observed before dispatch continues");
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
         assert!(engine.results().is_empty());
     }
 
@@ -12713,6 +13565,61 @@ mod tests {
         assert_eq!(rows[2], vec![SqliteValue::Integer(300)]);
     }
 
+    #[test]
+    fn test_yield_result_rows_suspends_and_resumes() {
+        // Loop emitting 1..=3 whose body starts with a Once-guarded counter;
+        // the Once bitmap must survive each suspension at ResultRow.
+        let mut b = ProgramBuilder::new();
+        let end = b.emit_label();
+        b.emit_jump_to_label(Opcode::Init, 0, 0, end, P4::None, 0);
+        let r_i = b.alloc_reg();
+        let r_once_hits = b.alloc_reg();
+        let r_limit = b.alloc_reg();
+        let r_one = b.alloc_reg();
+        b.emit_op(Opcode::Integer, 0, r_i, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 0, r_once_hits, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 3, r_limit, 0, P4::None, 0);
+        b.emit_op(Opcode::Integer, 1, r_one, 0, P4::None, 0);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let loop_top = b.current_addr() as i32;
+        let after_once = b.emit_label();
+        b.emit_jump_to_label(Opcode::Once, 0, 0, after_once, P4::None, 0);
+        b.emit_op(Opcode::AddImm, r_once_hits, 1, 0, P4::None, 0);
+        b.resolve_label(after_once);
+        b.emit_op(Opcode::Add, r_one, r_i, r_i, P4::None, 0);
+        b.emit_op(Opcode::ResultRow, r_i, 2, 0, P4::None, 0);
+        b.emit_op(Opcode::Lt, r_limit, loop_top, r_i, P4::None, 0);
+        b.emit_op(Opcode::Halt, 0, 0, 0, P4::None, 0);
+        b.resolve_label(end);
+        let prog = b.finish().expect("program should build");
+
+        let mut engine = VdbeEngine::new(prog.register_count());
+        engine.set_yield_result_rows(true);
+        let mut outcome = engine.execute(&prog).expect("execution should succeed");
+        let mut rows = Vec::new();
+        while outcome == ExecOutcome::Row {
+            assert!(engine.is_suspended());
+            let mut batch = engine.take_results();
+            assert_eq!(batch.len(), 1, "row-yield mode buffers a single row");
+            rows.push(batch.remove(0).into_vec());
+            outcome = engine.resume(&prog).expect("resume should succeed");
+        }
+        assert_eq!(outcome, ExecOutcome::Done);
+        assert!(!engine.is_suspended());
+        assert_eq!(
+            rows,
+            vec![
+                vec![SqliteValue::Integer(1), SqliteValue::Integer(1)],
+                vec![SqliteValue::Integer(2), SqliteValue::Integer(1)],
+                vec![SqliteValue::Integer(3), SqliteValue::Integer(1)],
+            ]
+        );
+        assert_eq!(
+            engine.resume(&prog).expect("resume after done"),
+            ExecOutcome::Done
+        );
+    }
+
     #[test]
     fn test_make_record_encodes_values() {
         // MakeRecord packs source registers into the SQLite record format blob.
@@ -13606,7 +14513,7 @@ mod tests {
         assert_eq!(
             outcome,
             ExecOutcome::Error {
-                code: ErrorCode::Constraint as i32,
+                code: FrankenError::PrimaryKeyViolation.extended_error_code(),
                 message: "PRIMARY KEY constraint failed".to_owned(),
             }
         );
@@ -13649,7 +14556,7 @@ mod tests {
         assert_eq!(
             outcome,
             ExecOutcome::Error {
-                code: ErrorCode::Constraint as i32,
+                code: FrankenError::PrimaryKeyViolation.extended_error_code(),
                 message: "PRIMARY KEY constraint failed".to_owned(),
             }
         );
@@ -13722,7 +14629,7 @@ mod tests {
         engine.set_transaction(Box::new(txn));
 
         // open_storage_cursor should succeed using the Txn backend.
-        let opened = engine.open_storage_cursor(0, root, false);
+        let opened = engine.open_storage_cursor(0, root, false).unwrap();
         assert!(opened);
 
         // Verify the cursor exists in storage_cursors.
@@ -13751,7 +14658,7 @@ mod tests {
         engine.set_index_desc_flags_by_root_page(HashMap::from([(root, vec![true])]));
 
         assert!(
-            engine.open_storage_cursor(0, root, true),
+            engine.open_storage_cursor(0, root, true).unwrap(),
             "writable txn-backed index cursor should open on a fresh root page"
         );
 
@@ -13810,6 +14717,7 @@ mod tests {
     fn test_open_storage_cursor_write_init_failure_does_not_fallback_to_mem() {
         use fsqlite_mvcc::{ConcurrentRegistry, InProcessPageLockTable};
         use fsqlite_pager::{MockMvccPager, MvccPager as _, TransactionMode};
+        use fsqlite_types::Snapshot;
 
         let pager = MockMvccPager;
         let cx = Cx::new();
@@ -13846,7 +14754,7 @@ mod tests {
             5000,
         );
 
-        let opened = engine.open_storage_cursor(0, root, true);
+        let opened = engine.open_storage_cursor(0, root, true).unwrap();
         assert!(
             !opened,
             "write-init errors must fail cursor open instead of silently falling back to Mem"
@@ -13871,7 +14779,7 @@ mod tests {
         engine.set_reject_mem_fallback(false);
 
         // Without a transaction, should fall back to Mem backend.
-        let opened = engine.open_storage_cursor(0, root, false);
+        let opened = engine.open_storage_cursor(0, root, false).unwrap();
         assert!(opened);
         assert!(engine.storage_cursors.contains_key(&0));
     }
@@ -13889,7 +14797,7 @@ mod tests {
 
         // MockTransaction synthesizes page bytes from the page number; page 256
         // yields first byte 0x00, simulating an uninitialized root page.
-        let opened = engine.open_storage_cursor(0, 256, false);
+        let opened = engine.open_storage_cursor(0, 256, false).unwrap();
         assert!(
             !opened,
             "transaction-backed opens must not silently fall back to MemPageStore"
@@ -14764,7 +15672,7 @@ mod tests {
         engine.set_reject_mem_fallback(false);
 
         // No txn_page_io set — should fall back to MemPageStore.
-        assert!(engine.open_storage_cursor(0, root, false));
+        assert!(engine.open_storage_cursor(0, root, false).unwrap());
         assert!(engine.storage_cursors.get(&0).is_some());
     }
 
@@ -14784,7 +15692,7 @@ mod tests {
         engine.set_reject_mem_fallback(true);
 
         // No txn_page_io set — parity-cert should reject the fallback.
-        assert!(!engine.open_storage_cursor(0, root, false));
+        assert!(!engine.open_storage_cursor(0, root, false).unwrap());
         assert!(engine.storage_cursors.get(&0).is_none());
     }
 
@@ -14792,7 +15700,7 @@ mod tests {
     fn test_open_storage_cursor_invalid_page_number() {
         // Root page 0 is invalid (PageNumber requires nonzero).
         let mut engine = VdbeEngine::new(8);
-        assert!(!engine.open_storage_cursor(0, 0, false));
+        assert!(!engine.open_storage_cursor(0, 0, false).unwrap());
     }
 
     #[test]
@@ -14963,7 +15871,7 @@ mod tests {
         engine.set_database(db);
         engine.set_reject_mem_fallback(false);
 
-        assert!(engine.open_storage_cursor(0, root, false));
+        assert!(engine.open_storage_cursor(0, root, false).unwrap());
         assert!(
             engine.has_mem_cursor(),
             "cursor should be mem-backed without txn"
@@ -14983,7 +15891,7 @@ mod tests {
         engine.set_transaction(Box::new(txn));
 
         // Open cursor on page 1 (valid with pager txn).
-        assert!(engine.open_storage_cursor(0, 1, false));
+        assert!(engine.open_storage_cursor(0, 1, false).unwrap());
         assert!(
             engine.all_cursors_are_txn_backed(),
             "cursor should be txn-backed with pager transaction"
@@ -15013,7 +15921,7 @@ mod tests {
         engine.set_transaction(Box::new(txn));
         engine.set_reject_mem_fallback(true);
 
-        assert!(engine.open_storage_cursor(0, 1, false));
+        assert!(engine.open_storage_cursor(0, 1, false).unwrap());
         assert!(
             engine.validate_parity_cert_invariant().is_ok(),
             "txn-backed cursor satisfies parity-cert invariant"
@@ -15029,7 +15937,7 @@ mod tests {
         engine.set_database(db);
         // Explicitly disable parity-cert — mem cursors allowed.
         engine.set_reject_mem_fallback(false);
-        assert!(engine.open_storage_cursor(0, root, false));
+        assert!(engine.open_storage_cursor(0, root, false).unwrap());
         assert!(
             engine.validate_parity_cert_invariant().is_ok(),
             "parity-cert disabled should always pass"
@@ -15054,7 +15962,7 @@ mod tests {
         let mut engine = VdbeEngine::new(8);
         engine.set_database(db);
         engine.set_reject_mem_fallback(false);
-        engine.open_storage_cursor(0, root, false);
+        engine.open_storage_cursor(0, root, false).unwrap();
 
         let sc = engine.storage_cursors.get(&0).unwrap();
         assert_eq!(sc.cursor.kind_str(), "mem");
@@ -15075,7 +15983,7 @@ mod tests {
         engine.set_reject_mem_fallback(true);
 
         // Attempt to open cursor — should fail.
-        let opened = engine.open_storage_cursor(0, root, false);
+        let opened = engine.open_storage_cursor(0, root, false).unwrap();
         assert!(
             !opened,
             "ratchet must prevent cursor creation in parity-cert mode"
@@ -15099,7 +16007,7 @@ mod tests {
         engine.set_reject_mem_fallback(true);
 
         // With txn set, cursor creation should succeed via pager path.
-        let opened = engine.open_storage_cursor(0, 1, false);
+        let opened = engine.open_storage_cursor(0, 1, false).unwrap();
         assert!(opened, "txn-backed cursor should work in parity-cert mode");
         assert!(engine.all_cursors_are_txn_backed());
         assert!(engine.validate_parity_cert_invariant().is_ok());
@@ -15118,12 +16026,12 @@ mod tests {
         engine.set_reject_mem_fallback(true);
 
         // Open cursor 0 on page 1 — should succeed (txn path).
-        assert!(engine.open_storage_cursor(0, 1, false));
+        assert!(engine.open_storage_cursor(0, 1, false).unwrap());
         assert!(engine.all_cursors_are_txn_backed());
 
         // Attempt cursor 1 on non-existent high page — should still
         // succeed via txn path (MockMvccPager returns zero-filled pages).
-        assert!(engine.open_storage_cursor(1, 1, false));
+        assert!(engine.open_storage_cursor(1, 1, false).unwrap());
         assert!(engine.all_cursors_are_txn_backed());
         assert!(engine.validate_parity_cert_invariant().is_ok());
     }
@@ -15875,7 +16783,7 @@ mod tests {
         let err = engine
             .execute(&prog)
             .expect_err("cancellation should be observed before VFilter advances execution");
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
         assert!(engine.take_results().is_empty());
     }
 
@@ -16000,7 +16908,7 @@ mod tests {
         let err = engine
             .execute(&prog)
             .expect_err("VBegin child cancellation should abort execution immediately");
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
         assert!(engine.take_results().is_empty());
     }
 
@@ -16090,7 +16998,7 @@ mod tests {
         let err = engine
             .execute(&prog)
             .expect_err("cancellation should be observed before VColumn publishes a value");
-        assert!(matches!(err, FrankenError::Abort));
+        assert!(matches!(err, FrankenError::Interrupt));
         assert!(engine.take_results().is_empty());
     }
 
@@ -16468,6 +17376,7 @@ mod tests {
 
     #[test]
     fn test_shared_txn_page_io_busy_timeout_preserves_losing_writer_state() {
+        use fsqlite_mvcc::ConcurrentRegistry;
         use fsqlite_pager::{MockMvccPager, MvccPager as _, TransactionMode};
         use fsqlite_types::Snapshot;
 
@@ -16549,6 +17458,8 @@ mod tests {
             writer.held_locks().is_empty(),
             "timed-out writer must not retain the contested page lock"
         );
+        drop(writer);
+        drop(holder);
         drop(guard);
 
         assert_eq!(
@@ -4,6 +4,7 @@ pub mod shm;
 pub mod traits;
 #[cfg(all(feature = "native", unix))]
 pub mod unix;
+pub mod uri;
 #[cfg(all(feature = "native", target_os = "linux"))]
 pub mod uring;
 #[cfg(all(feature = "native", target_os = "windows"))]
@@ -90,6 +91,7 @@ pub use shm::ShmRegion;
 pub use traits::{Vfs, VfsFile};
 #[cfg(all(feature = "native", unix))]
 pub use unix::{UnixFile, UnixVfs};
+pub use uri::{UriFile, UriVfs};
 #[cfg(all(feature = "native", target_os = "linux"))]
 pub use uring::{IoUringFile, IoUringVfs};
 #[cfg(all(feature = "native", target_os = "windows"))]
@@ -0,0 +1,239 @@
+//! URI-parameter VFS shim.
+//!
+//! SQLite lets `file:` URIs adjust how the main database file is accessed
+//! without choosing a different VFS: `nolock=1` disables file locking (and
+//! `immutable=1` implies it), while `psow=` overrides the
+//! powersafe-overwrite device characteristic. [`UriVfs`] wraps any [`Vfs`]
+//! and applies those parameters to every file it opens; all other
+//! operations are forwarded unchanged.
+
+use std::path::{Path, PathBuf};
+
+use fsqlite_error::Result;
+use fsqlite_types::LockLevel;
+use fsqlite_types::cx::Cx;
+use fsqlite_types::flags::{AccessFlags, SyncFlags, VfsOpenFlags};
+
+use crate::shm::ShmRegion;
+use crate::traits::{Vfs, VfsFile};
+
+/// `SQLITE_IOCAP_POWERSAFE_OVERWRITE` device characteristic bit.
+const SQLITE_IOCAP_POWERSAFE_OVERWRITE: u32 = 0x0000_1000;
+
+/// A [`Vfs`] wrapper that applies `nolock` / `psow` URI parameters.
+#[derive(Debug, Clone)]
+pub struct UriVfs<V: Vfs> {
+    inner: V,
+    nolock: bool,
+    psow: Option<bool>,
+}
+
+impl<V: Vfs> UriVfs<V> {
+    /// Wrap `inner` with the given URI parameters.
+    ///
+    /// `psow = None` leaves the inner file's device characteristics alone.
+    #[must_use]
+    pub const fn new(inner: V, nolock: bool, psow: Option<bool>) -> Self {
+        Self {
+            inner,
+            nolock,
+            psow,
+        }
+    }
+
+    /// Access the wrapped VFS.
+    #[must_use]
+    pub const fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Whether file locking is disabled.
+    #[must_use]
+    pub const fn nolock(&self) -> bool {
+        self.nolock
+    }
+
+    /// The powersafe-overwrite override, if any.
+    #[must_use]
+    pub const fn psow(&self) -> Option<bool> {
+        self.psow
+    }
+}
+
+impl<V: Vfs> Vfs for UriVfs<V> {
+    type File = UriFile<V::File>;
+
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn open(
+        &self,
+        cx: &Cx,
+        path: Option<&Path>,
+        flags: VfsOpenFlags,
+    ) -> Result<(Self::File, VfsOpenFlags)> {
+        let (file, out_flags) = self.inner.open(cx, path, flags)?;
+        Ok((
+            UriFile {
+                inner: file,
+                nolock: self.nolock,
+                psow: self.psow,
+            },
+            out_flags,
+        ))
+    }
+
+    fn delete(&self, cx: &Cx, path: &Path, sync_dir: bool) -> Result<()> {
+        self.inner.delete(cx, path, sync_dir)
+    }
+
+    fn access(&self, cx: &Cx, path: &Path, flags: AccessFlags) -> Result<bool> {
+        self.inner.access(cx, path, flags)
+    }
+
+    fn full_pathname(&self, cx: &Cx, path: &Path) -> Result<PathBuf> {
+        self.inner.full_pathname(cx, path)
+    }
+
+    fn randomness(&self, cx: &Cx, buf: &mut [u8]) {
+        self.inner.randomness(cx, buf);
+    }
+
+    fn current_time(&self, cx: &Cx) -> f64 {
+        self.inner.current_time(cx)
+    }
+}
+
+/// A file opened through [`UriVfs`].
+#[derive(Debug)]
+pub struct UriFile<F: VfsFile> {
+    inner: F,
+    nolock: bool,
+    psow: Option<bool>,
+}
+
+impl<F: VfsFile> UriFile<F> {
+    /// Access the inner file.
+    #[must_use]
+    pub const fn inner(&self) -> &F {
+        &self.inner
+    }
+}
+
+impl<F: VfsFile> VfsFile for UriFile<F> {
+    fn close(&mut self, cx: &Cx) -> Result<()> {
+        self.inner.close(cx)
+    }
+
+    fn read(&mut self, cx: &Cx, buf: &mut [u8], offset: u64) -> Result<usize> {
+        self.inner.read(cx, buf, offset)
+    }
+
+    fn write(&mut self, cx: &Cx, buf: &[u8], offset: u64) -> Result<()> {
+        self.inner.write(cx, buf, offset)
+    }
+
+    fn truncate(&mut self, cx: &Cx, size: u64) -> Result<()> {
+        self.inner.truncate(cx, size)
+    }
+
+    fn sync(&mut self, cx: &Cx, flags: SyncFlags) -> Result<()> {
+        self.inner.sync(cx, flags)
+    }
+
+    fn file_size(&self, cx: &Cx) -> Result<u64> {
+        self.inner.file_size(cx)
+    }
+
+    fn lock(&mut self, cx: &Cx, level: LockLevel) -> Result<()> {
+        if self.nolock {
+            return Ok(());
+        }
+        self.inner.lock(cx, level)
+    }
+
+    fn unlock(&mut self, cx: &Cx, level: LockLevel) -> Result<()> {
+        if self.nolock {
+            return Ok(());
+        }
+        self.inner.unlock(cx, level)
+    }
+
+    fn check_reserved_lock(&self, cx: &Cx) -> Result<bool> {
+        if self.nolock {
+            return Ok(false);
+        }
+        self.inner.check_reserved_lock(cx)
+    }
+
+    fn sector_size(&self) -> u32 {
+        self.inner.sector_size()
+    }
+
+    fn device_characteristics(&self) -> u32 {
+        let caps = self.inner.device_characteristics();
+        match self.psow {
+            Some(true) => caps | SQLITE_IOCAP_POWERSAFE_OVERWRITE,
+            Some(false) => caps & !SQLITE_IOCAP_POWERSAFE_OVERWRITE,
+            None => caps,
+        }
+    }
+
+    fn shm_map(&mut self, cx: &Cx, region: u32, size: u32, extend: bool) -> Result<ShmRegion> {
+        self.inner.shm_map(cx, region, size, extend)
+    }
+
+    fn shm_lock(&mut self, cx: &Cx, offset: u32, n: u32, flags: u32) -> Result<()> {
+        self.inner.shm_lock(cx, offset, n, flags)
+    }
+
+    fn shm_barrier(&self) {
+        self.inner.shm_barrier();
+    }
+
+    fn shm_unmap(&mut self, cx: &Cx, delete: bool) -> Result<()> {
+        self.inner.shm_unmap(cx, delete)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryVfs;
+
+    fn open_main(vfs: &UriVfs<MemoryVfs>, cx: &Cx) -> UriFile<crate::memory::MemoryFile> {
+        let flags = VfsOpenFlags::MAIN_DB | VfsOpenFlags::CREATE | VfsOpenFlags::READWRITE;
+        vfs.open(cx, Some(Path::new("uri.db")), flags).unwrap().0
+    }
+
+    #[test]
+    fn nolock_skips_locking() {
+        let cx = Cx::new();
+        let vfs = UriVfs::new(MemoryVfs::new(), true, None);
+        let mut first = open_main(&vfs, &cx);
+        let mut second = open_main(&vfs, &cx);
+
+        first.lock(&cx, LockLevel::Exclusive).unwrap();
+        second.lock(&cx, LockLevel::Exclusive).unwrap();
+        assert!(!second.check_reserved_lock(&cx).unwrap());
+        first.unlock(&cx, LockLevel::None).unwrap();
+        second.unlock(&cx, LockLevel::None).unwrap();
+    }
+
+    #[test]
+    fn psow_overrides_device_characteristics() {
+        let cx = Cx::new();
+        let on = open_main(&UriVfs::new(MemoryVfs::new(), false, Some(true)), &cx);
+        assert_eq!(
+            on.device_characteristics() & SQLITE_IOCAP_POWERSAFE_OVERWRITE,
+            SQLITE_IOCAP_POWERSAFE_OVERWRITE
+        );
+
+        let off = open_main(&UriVfs::new(MemoryVfs::new(), false, Some(false)), &cx);
+        assert_eq!(
+            off.device_characteristics() & SQLITE_IOCAP_POWERSAFE_OVERWRITE,
+            0
+        );
+    }
+}
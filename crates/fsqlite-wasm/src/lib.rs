@@ -211,6 +211,14 @@ impl FrankenDb {
             Ok(stmt.explain())
         })
     }
+
+    pub fn serialize(&self) -> Result<Vec<u8>, JsValue> {
+        self.with_connection(|conn| conn.serialize("main"))
+    }
+
+    pub fn deserialize(&self, bytes: &[u8], read_only: Option<bool>) -> Result<(), JsValue> {
+        self.with_connection(|conn| conn.deserialize("main", bytes, read_only.unwrap_or(false)))
+    }
 }
 
 impl FrankenDb {
@@ -812,6 +820,27 @@ mod wasm_tests {
         assert_eq!(rows.length(), 2);
     }
 
+    #[wasm_bindgen_test]
+    fn wasm_db_serialize_deserialize_roundtrip() {
+        let db = FrankenDb::new(None).expect("db should open");
+        db.execute("CREATE TABLE wasm_t (id INTEGER PRIMARY KEY, name TEXT)")
+            .expect("table create should succeed");
+        db.execute("INSERT INTO wasm_t (id, name) VALUES (1, 'alpha')")
+            .expect("seed insert should succeed");
+        let bytes = db.serialize().expect("serialize should succeed");
+
+        let copy = FrankenDb::new(None).expect("db should open");
+        copy.deserialize(&bytes, None)
+            .expect("deserialize should succeed");
+        let result = copy
+            .query("SELECT name FROM wasm_t")
+            .expect("query should succeed");
+        let rows = Reflect::get(&result, &JsValue::from_str("rows"))
+            .expect("rows field should exist")
+            .unchecked_into::<Array>();
+        assert_eq!(rows.length(), 1);
+    }
+
     #[wasm_bindgen_test]
     fn parse_sql_export_reports_errors() {
         let result = parse_sql_js("NOT VALID SQL {{{{").expect("parse export should return");
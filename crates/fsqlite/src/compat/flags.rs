@@ -1,13 +1,15 @@
 //! Connection open flags, analogous to `rusqlite::OpenFlags`.
 
 use fsqlite_error::FrankenError;
-use fsqlite_types::flags::VfsOpenFlags;
+use fsqlite_types::flags::{OpenFlags as CoreOpenFlags, VfsOpenFlags};
 
 use crate::Connection;
 
 /// Subset of SQLite open flags that cass uses, mirroring `rusqlite::OpenFlags`.
 ///
-/// Under the hood these map to `VfsOpenFlags`.
+/// The bit values match `sqlite3_open_v2()`; under the hood they map to
+/// `fsqlite_types::flags::OpenFlags` and, for the main database file,
+/// `VfsOpenFlags`.
 #[derive(Debug, Clone, Copy)]
 pub struct OpenFlags(u32);
 
@@ -21,9 +23,32 @@ impl OpenFlags {
     /// Create the database if it does not exist (combined with READ_WRITE).
     pub const SQLITE_OPEN_CREATE: Self = Self(0x04);
 
-    /// Default flags: READ_WRITE | CREATE.
+    /// Interpret the filename as a `file:` URI.
+    pub const SQLITE_OPEN_URI: Self = Self(0x40);
+
+    /// Open an in-memory database regardless of the filename.
+    pub const SQLITE_OPEN_MEMORY: Self = Self(0x80);
+
+    /// Multi-thread threading mode (accepted; connections are not shared).
+    pub const SQLITE_OPEN_NO_MUTEX: Self = Self(0x8000);
+
+    /// Serialized threading mode (accepted; connections are not shared).
+    pub const SQLITE_OPEN_FULL_MUTEX: Self = Self(0x0001_0000);
+
+    /// Request shared-cache mode.
+    pub const SQLITE_OPEN_SHARED_CACHE: Self = Self(0x0002_0000);
+
+    /// Request private-cache mode.
+    pub const SQLITE_OPEN_PRIVATE_CACHE: Self = Self(0x0004_0000);
+
+    /// Default flags: READ_WRITE | CREATE | URI | NO_MUTEX, as in rusqlite.
     pub fn default_flags() -> Self {
-        Self(Self::SQLITE_OPEN_READ_WRITE.0 | Self::SQLITE_OPEN_CREATE.0)
+        Self(
+            Self::SQLITE_OPEN_READ_WRITE.0
+                | Self::SQLITE_OPEN_CREATE.0
+                | Self::SQLITE_OPEN_URI.0
+                | Self::SQLITE_OPEN_NO_MUTEX.0,
+        )
     }
 
     /// Combine two flag sets with bitwise OR.
@@ -36,17 +61,24 @@ impl OpenFlags {
         self.0 & flag.0 == flag.0
     }
 
-    /// Convert to `VfsOpenFlags`.
+    /// Convert to `VfsOpenFlags` for the main database file.
     pub fn to_vfs_flags(self) -> VfsOpenFlags {
         let mut flags = VfsOpenFlags::MAIN_DB;
         if self.contains(Self::SQLITE_OPEN_READ_WRITE) {
             flags |= VfsOpenFlags::READWRITE;
+        } else if self.contains(Self::SQLITE_OPEN_READ_ONLY) {
+            flags |= VfsOpenFlags::READONLY;
         }
         if self.contains(Self::SQLITE_OPEN_CREATE) {
             flags |= VfsOpenFlags::CREATE;
         }
         flags
     }
+
+    /// Convert to the core `sqlite3_open_v2` flag set.
+    pub fn to_open_flags(self) -> CoreOpenFlags {
+        CoreOpenFlags::from_bits_truncate(self.0)
+    }
 }
 
 impl std::ops::BitOr for OpenFlags {
@@ -59,10 +91,10 @@ impl std::ops::BitOr for OpenFlags {
 
 /// Open a connection with the given flags.
 ///
-/// Currently fsqlite's `Connection::open` doesn't accept flags directly,
-/// so this function validates flags and delegates to `Connection::open`.
-/// Read-only mode emits a warning but still opens read-write (until
-/// fsqlite adds read-only support upstream).
+/// Follows `sqlite3_open_v2()`: exactly one of READ_ONLY, READ_WRITE, or
+/// READ_WRITE | CREATE must be set, READ_ONLY connections reject writes
+/// with `FrankenError::ReadOnly`, and with SQLITE_OPEN_URI the `file:` URI
+/// parameters (`mode=`, `vfs=`, `immutable=`, ...) are honored.
 ///
 /// # Examples
 ///
@@ -71,10 +103,8 @@ impl std::ops::BitOr for OpenFlags {
 ///
 /// let conn = open_with_flags("my.db", OpenFlags::SQLITE_OPEN_READ_WRITE)?;
 /// ```
-pub fn open_with_flags(path: &str, _flags: OpenFlags) -> Result<Connection, FrankenError> {
-    // fsqlite currently opens all connections as read-write.
-    // When upstream adds flag support, wire them through here.
-    Connection::open(path)
+pub fn open_with_flags(path: &str, flags: OpenFlags) -> Result<Connection, FrankenError> {
+    Connection::open_with_flags(path, flags.to_open_flags())
 }
 
 #[cfg(test)]
@@ -101,6 +131,23 @@ mod tests {
         assert_eq!(conn.path(), ":memory:");
     }
 
+    #[test]
+    fn open_with_flags_read_only_rejects_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compat_ro.db");
+        let path_str = path.to_str().unwrap();
+        open_with_flags(path_str, OpenFlags::default_flags())
+            .unwrap()
+            .execute("CREATE TABLE t(x INTEGER)")
+            .unwrap();
+
+        let conn = open_with_flags(path_str, OpenFlags::SQLITE_OPEN_READ_ONLY).unwrap();
+        assert!(matches!(
+            conn.execute("INSERT INTO t VALUES (1)"),
+            Err(FrankenError::ReadOnly)
+        ));
+    }
+
     #[test]
     fn vfs_flags_conversion() {
         let flags = OpenFlags::default_flags();
@@ -108,5 +155,9 @@ mod tests {
         assert!(vfs.contains(VfsOpenFlags::READWRITE));
         assert!(vfs.contains(VfsOpenFlags::CREATE));
         assert!(vfs.contains(VfsOpenFlags::MAIN_DB));
+
+        let read_only = OpenFlags::SQLITE_OPEN_READ_ONLY.to_vfs_flags();
+        assert!(read_only.contains(VfsOpenFlags::READONLY));
+        assert!(!read_only.contains(VfsOpenFlags::READWRITE));
     }
 }
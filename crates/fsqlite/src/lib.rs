@@ -4,8 +4,11 @@
 //! phases it also re-exports selected internal crates for integration tests.
 
 pub use fsqlite_core::connection::{
-    Connection, ConnectionEnv, IoPollStrategy, PreparedStatement, Row, RuntimeConfig,
-    RuntimeContext, TraceEvent, TraceMask, init_global_runtime,
+    Backup, BackupStep, Blob, BusyHandler, CommitHook, Connection, ConnectionEnv,
+    ConnectionSession, InterruptHandle, IoPollStrategy, PreUpdateEvent, PreUpdateHook,
+    PreparedStatement, ProgressHandler, RollbackHook, Row, RowChangeKind, Rows, RuntimeConfig,
+    RuntimeContext, TraceEvent, TraceMask, UpdateHook, VfsRegistry, WalHook, adaptive_busy_handler,
+    init_global_runtime,
 };
 pub use fsqlite_error::FrankenError;
 pub use fsqlite_types::SqliteValue;
@@ -114,6 +117,25 @@ mod tests {
         assert_eq!(count, 3);
     }
 
+    #[test]
+    fn test_public_api_query_rows_iterates_lazily() {
+        let conn = Connection::open(":memory:").expect("in-memory connection should open");
+        conn.execute("CREATE TABLE facade_rows (id INTEGER PRIMARY KEY);")
+            .expect("create table should succeed");
+        conn.execute("INSERT INTO facade_rows VALUES (1), (2), (3);")
+            .expect("insert should succeed");
+        let stmt = conn
+            .prepare("SELECT id FROM facade_rows;")
+            .expect("prepare should succeed");
+        let mut rows: super::Rows<'_> = stmt.query_rows().expect("query_rows should succeed");
+        let first = rows
+            .next()
+            .expect("first row should exist")
+            .expect("first row should decode");
+        assert_eq!(row_values(&first), vec![SqliteValue::Integer(1)]);
+        assert_eq!(rows.count(), 2);
+    }
+
     // ── Connection::open error paths ────────────────────────────────────
 
     #[test]